@@ -1,4 +1,4 @@
-use opencan_core::CANNetwork;
+use opencan_core::{CANMessage, CANNetwork};
 
 pub struct Analyze<'n> {
     net: &'n CANNetwork,
@@ -12,12 +12,8 @@ impl<'n> Analyze<'n> {
     //https://electronics.stackexchange.com/questions/422998/how-to-calculate-bus-load-of-can-bus
     pub fn print_bus_load(&self) {
         let mut frames: u32 = 0;
-        //for 29 bit id, we need something in the .yml or some other settings file to specify longer msg ids
-        //i thought about reading the msg id with some function from compose, but that would require the existence of a msg with the highest id
-        // im forcing it to 11 here
 
-        let Some(cap) = self.net.bitrate() else
-        {
+        let Some(cap) = self.net.bitrate() else {
             eprintln!("No bitrate specified in network!");
             std::process::exit(-1);
         };
@@ -25,18 +21,11 @@ impl<'n> Analyze<'n> {
         let mut bits_sent = 0;
 
         for msg in self.net.iter_messages() {
-            let id_len = 11;
             if let Some(cycletime) = msg.cycletime {
                 let tx_per_sec = 1000 / cycletime;
                 frames += tx_per_sec;
-                if id_len == 11 {
-                    let frame_bytes = msg.length;
-
-                    // Analysing Real-Time Communications: Controller Area Network (CAN)
-                    // Tindell Equation:
-                    let bits_this_frame = ((34 + 8 * frame_bytes) / 5) + 47 + 8 * frame_bytes;
-                    bits_sent += tx_per_sec * bits_this_frame
-                }
+
+                bits_sent += tx_per_sec * Self::frame_bits(msg);
             }
         }
 
@@ -47,9 +36,22 @@ impl<'n> Analyze<'n> {
 
         println!("Busload at {busload:.2}%")
     }
-}
 
-// fn twenty_nine_bit_id(tbit:u8){
-//     let Cm;
-//     Cm = (((52 + 8*8)/5) + 65 + 8*8)*tbit;
-// }
+    /// Worst-case number of bits on the wire for one frame of this message,
+    /// including stuff bits and interframe space.
+    ///
+    /// CAN FD frames are counted as if their whole payload were sent at the
+    /// nominal bitrate, which overestimates load for frames using bit rate
+    /// switching.
+    fn frame_bits(msg: &CANMessage) -> u32 {
+        let frame_bytes = msg.length;
+
+        // Analysing Real-Time Communications: Controller Area Network (CAN)
+        // Tindell Equation:
+        if msg.id.is_extended() {
+            ((54 + 8 * frame_bytes) / 5) + 67 + 8 * frame_bytes
+        } else {
+            ((34 + 8 * frame_bytes) / 5) + 47 + 8 * frame_bytes
+        }
+    }
+}
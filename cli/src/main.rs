@@ -1,77 +1,246 @@
-use std::fs::{create_dir, write};
+use std::fs::{create_dir, read_to_string, write};
 use std::path::Path;
+use std::process::ExitCode;
 
-use anyhow::{anyhow, Result};
+use anyhow::{anyhow, Context, Result};
 use clap::Parser;
 use opencan_analyze::Analyze;
 use opencan_codegen::{Codegen, CodegenOutput};
+use opencan_compose::ComposeError;
+use opencan_core::{
+    translation::{ArxmlImporter, DbcExporter, DbcImporter, ImportMode, KcdExporter, KcdImporter},
+    CANNetwork, CANSystem, Compatibility, TranslationFromOpencan, TranslationToOpencan,
+};
 
 #[derive(clap::Parser)]
 struct PrimaryArgs {
     #[clap(subcommand)]
     subcommand: Command,
+
+    /// Skip the parts of an input .dbc/.kcd/.arxml file that can't be
+    /// imported, with a warning, rather than failing
+    #[clap(long, global = true)]
+    lenient: bool,
 }
 
 #[derive(clap::Subcommand)]
 enum Command {
     /// Analyze
     Analyze {
-        /// Input .yml file
+        /// Input .yml, composed .json, or .dbc/.kcd/.arxml file
         in_file: String,
+        /// Bus to analyze, for files describing several buses
+        #[clap(long)]
+        bus: Option<String>,
     },
     /// Compose a CAN network using a definitions file
     Compose(opencan_compose::Args),
     Codegen {
-        /// Input .yml file
+        /// Input .yml, composed .json, or .dbc/.kcd/.arxml file
         in_file: String,
         /// Output directory (created if it doesn't exist yet)
         output_path: String,
+        /// Bus to generate code for, for files describing several buses
+        #[clap(long)]
+        bus: Option<String>,
         /// Codegen arguments
         #[clap(flatten)]
         cg_args: opencan_codegen::Args,
     },
+    /// Compare two versions of a network, classifying each change as
+    /// wire-compatible or wire-breaking
+    Diff {
+        /// Old .yml or composed .json file
+        old_file: String,
+        /// New .yml or composed .json file
+        new_file: String,
+        /// Print changes as JSON
+        #[clap(long)]
+        json: bool,
+        /// Bus to compare, for files describing several buses
+        #[clap(long)]
+        bus: Option<String>,
+    },
+    /// Export a composed CAN network to other formats
+    Export {
+        /// Input .yml, composed .json, or .dbc/.kcd/.arxml file
+        in_file: String,
+        /// Output .dbc file
+        #[clap(long, required_unless_present = "kcd")]
+        dbc: Option<String>,
+        /// Output .kcd file
+        #[clap(long)]
+        kcd: Option<String>,
+        /// Bus to export, for files describing several buses
+        #[clap(long)]
+        bus: Option<String>,
+    },
+    /// Print the JSON Schema for .yml definitions files, for editors to
+    /// complete and check them with
+    Schema,
 }
 
-fn main() -> Result<()> {
+fn main() -> ExitCode {
     let args = PrimaryArgs::parse();
 
-    // for now, we call compose every time we call codegen because we don't
-    // have reliable serialization/deserialization of the network from core.
-    //
-    // now:
+    match run(args) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            // errors in definitions files are shown in the definitions
+            match e.downcast_ref::<ComposeError>() {
+                Some(compose) => eprintln!("{compose}"),
+                None => eprintln!("Error: {e:?}"),
+            }
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn run(args: PrimaryArgs) -> Result<()> {
+    // Commands other than compose take either a .yml definitions file, which
+    // is composed on the fly, or a network already composed to .json:
     // 1. codegen <- compose <- yml
-    //
-    // later:
-    // 1. (compose <- yml) -> network.json
-    // 2. codegen <- network.json
+    // 2. (compose <- yml) -> network.json, then codegen <- network.json
+    let mode = if args.lenient {
+        ImportMode::Lenient
+    } else {
+        ImportMode::Strict
+    };
+
     match args.subcommand {
-        Command::Analyze { in_file } => {
-            let net = opencan_compose::compose(opencan_compose::Args {
-                in_file,
-                dump_json: false,
-                dump_python: false,
-            })?;
+        Command::Analyze { in_file, bus } => {
+            let net = load_network(in_file, bus, mode)?;
             let ana = Analyze::new(&net);
             ana.print_bus_load();
             Ok(())
         }
-        Command::Compose(a) => opencan_compose::compose(a).map(|_| ()),
+        Command::Compose(a) => {
+            let input = read_to_string(&a.in_file).context("Failed to read input file")?;
+
+            if a.bus.is_none() && opencan_compose::describes_system(&input) {
+                opencan_compose::compose_system(a).map(|_| ())
+            } else {
+                opencan_compose::compose(a).map(|_| ())
+            }
+        }
         Command::Codegen {
             cg_args,
             in_file,
             output_path,
+            bus,
         } => {
-            let net = opencan_compose::compose(opencan_compose::Args {
-                in_file,
-                dump_json: false,
-                dump_python: false,
-            })?;
+            let net = load_network(in_file, bus, mode)?;
             let gen = Codegen::new(cg_args, &net)?;
             let out = gen.network_to_c();
             save_codegen_files(&out, output_path)?;
             Ok(())
         }
+        Command::Diff {
+            old_file,
+            new_file,
+            json,
+            bus,
+        } => {
+            let old = load_network(old_file, bus.clone(), mode)?;
+            let new = load_network(new_file, bus, mode)?;
+            let changes = old.diff(&new);
+
+            if json {
+                println!("{}", serde_json::to_string_pretty(&changes)?);
+            } else {
+                for change in &changes {
+                    println!("{change}");
+                }
+
+                let breaking = changes
+                    .iter()
+                    .filter(|c| c.compatibility == Compatibility::Breaking)
+                    .count();
+                println!("{} change(s), {breaking} breaking", changes.len());
+            }
+            Ok(())
+        }
+        Command::Export {
+            in_file,
+            dbc,
+            kcd,
+            bus,
+        } => {
+            let net = load_network(in_file, bus, mode)?;
+            if let Some(dbc) = dbc {
+                write(&dbc, DbcExporter::translate(&net))
+                    .context(format!("Failed to write DBC file {dbc}"))?;
+            }
+            if let Some(kcd) = kcd {
+                write(&kcd, KcdExporter::translate(&net))
+                    .context(format!("Failed to write KCD file {kcd}"))?;
+            }
+            Ok(())
+        }
+        Command::Schema => {
+            let schema = opencan_compose::schema();
+            println!("{}", serde_json::to_string_pretty(&schema)?);
+            Ok(())
+        }
+    }
+}
+
+/// Load a network from a composed .json file, import it from a .dbc, .kcd or
+/// .arxml file, or compose it from a .yml file.
+///
+/// With `bus`, the file describes several buses and that one is loaded.
+/// Imports are made in `mode`.
+fn load_network(in_file: String, bus: Option<String>, mode: ImportMode) -> Result<CANNetwork> {
+    let extension = Path::new(&in_file).extension().and_then(|e| e.to_str());
+
+    let import: Option<fn(String, ImportMode) -> Result<CANNetwork>> = match extension {
+        Some("dbc") => Some(import_network::<DbcImporter>),
+        Some("kcd") => Some(import_network::<KcdImporter>),
+        Some("arxml") => Some(import_network::<ArxmlImporter>),
+        _ => None,
+    };
+
+    if let Some(import) = import {
+        let input = read_to_string(&in_file).context("Failed to read input file")?;
+        return import(input, mode).context(format!("Failed to import {in_file}"));
+    }
+
+    if extension == Some("json") {
+        let input = read_to_string(&in_file).context("Failed to read input file")?;
+
+        let Some(bus) = bus else {
+            return serde_json::from_str(&input)
+                .context(format!("Failed to load composed network {in_file}"));
+        };
+
+        let system: CANSystem = serde_json::from_str(&input)
+            .context(format!("Failed to load composed system {in_file}"))?;
+        return system
+            .into_bus(&bus)
+            .context(format!("Bus `{bus}` does not exist in {in_file}"));
+    }
+
+    opencan_compose::compose(opencan_compose::Args {
+        in_file,
+        dump_json: false,
+        dump_python: false,
+        deny_warnings: false,
+        bus,
+    })
+}
+
+/// Import a network from another format, printing anything skipped over in
+/// lenient `mode` as a warning.
+fn import_network<T>(input: String, mode: ImportMode) -> Result<CANNetwork>
+where
+    T: TranslationToOpencan,
+    T::Error: Send + Sync + 'static,
+{
+    let mut net = CANNetwork::new();
+    for warning in T::import_network(input, &mut net, mode)? {
+        eprintln!("warning: {warning}");
     }
+    Ok(net)
 }
 
 /// Save output files from codegen to given path.
@@ -0,0 +1,63 @@
+use indoc::formatdoc;
+use opencan_core::{AttributeValue, Attributes};
+
+use crate::Codegen;
+use crate::CodegenOutput;
+
+impl<'n> Codegen<'n> {
+    pub fn attributes_h(&self) -> String {
+        let mut defs = String::new();
+
+        defs += &Self::attribute_defines("CAN_NET_ATTR", self.net.attributes());
+
+        let node = self.net.node_by_name(&self.args.node).unwrap();
+        defs += &Self::attribute_defines(&format!("CAN_NODE_{}_ATTR", node.name), &node.attributes);
+
+        for msg in [&self.sorted_rx_messages, &self.sorted_tx_messages]
+            .into_iter()
+            .flatten()
+        {
+            defs +=
+                &Self::attribute_defines(&format!("CAN_MSG_{}_ATTR", msg.name), &msg.attributes);
+
+            for sigbit in &msg.signals {
+                defs += &Self::attribute_defines(
+                    &format!("CAN_SIG_{}_ATTR", sigbit.sig.name),
+                    &sigbit.sig.attributes,
+                );
+            }
+        }
+
+        formatdoc! {"
+            {greet}
+
+            #ifndef OPENCAN_ATTRIBUTES_H
+            #define OPENCAN_ATTRIBUTES_H
+
+            {defs}
+            #endif
+            ",
+            greet = self.internal_prelude_greeting(CodegenOutput::ATTRIBUTES_H_NAME),
+        }
+    }
+
+    /// One `#define` per attribute. Strings and enum variants become string
+    /// literals; numbers are written as-is.
+    fn attribute_defines(prefix: &str, attributes: &Attributes) -> String {
+        let mut defs = String::new();
+
+        for (name, value) in attributes {
+            let value = match value {
+                AttributeValue::String(s) | AttributeValue::Enum(s) => {
+                    format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""))
+                }
+                AttributeValue::Int(i) => format!("{i}"),
+                AttributeValue::Float(f) => format!("{f:?}"),
+            };
+
+            defs += &format!("#define {prefix}_{name} {value}\n");
+        }
+
+        defs
+    }
+}
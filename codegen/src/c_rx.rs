@@ -20,10 +20,13 @@ impl<'n> Codegen<'n> {
 
                 /*** Message ID ***/
                 #define CAN_MSG_{}_ID 0x{:X}U
+                #define CAN_MSG_{}_ID_EXTENDED {}
                 ",
                 msg.name,
                 msg.name,
-                msg.id,
+                msg.id.raw(),
+                msg.name,
+                msg.id.is_extended(),
             };
 
             // Is this a raw message?
@@ -101,14 +104,14 @@ impl<'n> Codegen<'n> {
             /* Primary Rx Handler Function */
             /*********************************************************/
 
-            void {rx_handler}(uint32_t id, uint8_t * data, uint8_t len);
+            void {rx_handler}(uint32_t id, bool extended, uint8_t * data, uint8_t len);
 
             /*********************************************************/
             /* ID-to-Rx-Function Lookup */
             /*********************************************************/
 
             typedef bool (*{rx_fn_ptr})(const uint8_t * data, uint_fast8_t len);
-            {rx_fn_ptr} {rx_fn_name}(uint32_t id);
+            {rx_fn_ptr} {rx_fn_name}(uint32_t id, bool extended);
 
             {messages}
 
@@ -178,8 +181,8 @@ impl<'n> Codegen<'n> {
             messages += &formatdoc! {"
                 /*** Message Structs ***/
 
-                static {mstruct_raw_name} {global_ident_raw};
-                static {mstruct_name} {global_ident};
+                static {mstruct_raw_name} {global_ident_raw} = {raw_init};
+                static {mstruct_name} {global_ident} = {init};
 
                 /*** Accounting Data ***/
 
@@ -195,8 +198,10 @@ impl<'n> Codegen<'n> {
                 ",
                 mstruct_raw_name = msg.raw_struct_ty(),
                 global_ident_raw = msg.global_raw_struct_ident(),
+                raw_init = msg.raw_struct_initializer(),
                 mstruct_name = msg.struct_ty(),
                 global_ident = msg.global_struct_ident(),
+                init = msg.struct_initializer(),
                 timestamp = msg.rx_timestamp_ident(),
                 getters = msg.getter_fn_defs(),
                 rx_def = msg.rx_fn_def(),
@@ -228,8 +233,8 @@ impl<'n> Codegen<'n> {
             /* Primary Rx Handler Function */
             /*********************************************************/
 
-            void {rx_handler}(const uint32_t id, uint8_t * const data, const uint8_t len) {{
-                const {rx_fn_ptr} rx_fn = {id_to_rx_name}(id);
+            void {rx_handler}(const uint32_t id, const bool extended, uint8_t * const data, const uint8_t len) {{
+                const {rx_fn_ptr} rx_fn = {id_to_rx_name}(id, extended);
 
                 if (rx_fn) {{
                     rx_fn(data, len);
@@ -270,31 +275,49 @@ impl<'n> Codegen<'n> {
     }
 
     /// Message ID to decode function pointer mapping.
-    // todo: extended vs standard IDs?
+    ///
+    /// Standard and extended IDs are looked up separately, since the same
+    /// raw value can be used by both on one bus.
     fn rx_id_to_decode_fn(&self) -> String {
-        let mut cases = String::new();
+        let mut std_cases = String::new();
+        let mut ext_cases = String::new();
 
         for msg in &self.sorted_rx_messages {
-            cases += &formatdoc! {"
+            let cases = if msg.id.is_extended() {
+                &mut ext_cases
+            } else {
+                &mut std_cases
+            };
+
+            *cases += &formatdoc! {"
                 case 0x{:X}U: return {};
                 ",
-                msg.id,
+                msg.id.raw(),
                 msg.rx_fn_name()
             };
         }
 
         formatdoc! {"
-            {dec_ptr} {name}(const uint32_t id)
+            {dec_ptr} {name}(const uint32_t id, const bool extended)
             {{
+                if (extended) {{
+                    switch (id) {{
+            {ext_cases}
+                        default:
+                            return NULL;
+                    }}
+                }}
+
                 switch (id) {{
-            {cases}
+            {std_cases}
                     default:
                         return NULL;
                 }}
             }}",
             dec_ptr = Self::RX_FN_PTR_TYPEDEF,
             name = Self::ID_TO_RX_FN_NAME,
-            cases = cases.trim().indent(8),
+            ext_cases = ext_cases.trim().indent(12),
+            std_cases = std_cases.trim().indent(8),
         }
     }
 }
@@ -19,11 +19,14 @@ impl<'n> Codegen<'n> {
 
                 /*** Message ID ***/
                 #define CAN_MSG_{}_ID 0x{:X}U
+                #define CAN_MSG_{}_ID_EXTENDED {}
 
                 ",
                 msg.name,
                 msg.name,
-                msg.id,
+                msg.id.raw(),
+                msg.name,
+                msg.id.is_extended(),
             };
 
             // Is this a raw message?
@@ -9,6 +9,7 @@ use textwrap::indent;
 pub mod message;
 use message::MessageCodegen;
 
+pub mod attributes;
 pub mod c_rx;
 pub mod c_tx;
 pub mod message_ok;
@@ -29,8 +30,10 @@ pub struct Args {
 
 #[non_exhaustive]
 pub struct CodegenOutput {
+    pub attributes_h: String,
     pub callbacks_h: String,
     pub templates_h: String,
+    pub value_tables_h: String,
     pub rx_c: String,
     pub rx_h: String,
     pub tx_c: String,
@@ -61,9 +64,9 @@ impl Indent for &str {
 }
 
 impl<'n> Codegen<'n> {
-    const RX_FN_PTR_TYPEDEF: &str = "rx_fn_ptr";
-    const ID_TO_RX_FN_NAME: &str = "CANRX_id_to_rx_fn";
-    const RX_HANDLER_FN_NAME: &str = "CANRX_handle_rx";
+    const RX_FN_PTR_TYPEDEF: &'static str = "rx_fn_ptr";
+    const ID_TO_RX_FN_NAME: &'static str = "CANRX_id_to_rx_fn";
+    const RX_HANDLER_FN_NAME: &'static str = "CANRX_handle_rx";
 
     pub fn new(args: Args, net: &'n CANNetwork) -> Result<Self> {
         net.node_by_name(&args.node)
@@ -94,8 +97,10 @@ impl<'n> Codegen<'n> {
 
     pub fn network_to_c(self) -> CodegenOutput {
         CodegenOutput {
+            attributes_h: self.attributes_h(),
             callbacks_h: self.callbacks_h(),
             templates_h: self.templates_h(),
+            value_tables_h: self.value_tables_h(),
             rx_c: self.rx_c(),
             rx_h: self.rx_h(),
             tx_c: self.tx_c(),
@@ -167,12 +172,54 @@ impl<'n> Codegen<'n> {
 
             {std_incl}
 
+            #include \"{value_tables_h}\"
+
             {templates}
 
             #endif
             ",
             greet = self.internal_prelude_greeting(CodegenOutput::TEMPLATES_H_NAME),
             std_incl = Self::common_std_includes(),
+            value_tables_h = CodegenOutput::VALUE_TABLES_H_NAME,
+        }
+    }
+
+    fn value_tables_h(&self) -> String {
+        // one enum per value table, shared by every signal that uses it
+        let mut tables: Vec<_> = self.net.iter_value_tables().collect();
+        tables.sort_by(|a, b| a.name.cmp(&b.name));
+
+        let mut enums = String::new();
+        for table in tables {
+            let mut values: Vec<_> = table.values.iter().collect();
+            values.sort_by_key(|v| v.1);
+
+            let mut inner = String::new();
+            for (name, val) in values {
+                inner += &format!("CAN_VT_{}_{name} = {val},\n", table.name.to_uppercase());
+            }
+
+            enums += &formatdoc! {"
+                enum CAN_VT_{} {{
+                {}
+                }};
+
+                ",
+                table.name,
+                inner.trim().indent(4)
+            };
+        }
+
+        formatdoc! {"
+            {greet}
+
+            #ifndef OPENCAN_VALUE_TABLES_H
+            #define OPENCAN_VALUE_TABLES_H
+
+            {enums}
+            #endif
+            ",
+            greet = self.internal_prelude_greeting(CodegenOutput::VALUE_TABLES_H_NAME),
         }
     }
 
@@ -185,7 +232,7 @@ impl<'n> Codegen<'n> {
 
             {std_incl}
 
-            void CAN_callback_enqueue_tx_message(const uint8_t *data, uint8_t len, uint32_t id);
+            void CAN_callback_enqueue_tx_message(const uint8_t *data, uint8_t len, uint32_t id, bool extended, bool fd, bool brs);
             uint64_t CAN_callback_get_system_time(void);
 
             #endif
@@ -199,7 +246,8 @@ impl<'n> Codegen<'n> {
         formatdoc! {"
             #include <stdbool.h>
             #include <stddef.h>
-            #include <stdint.h>",
+            #include <stdint.h>
+            #include <string.h>",
         }
     }
 
@@ -225,8 +273,10 @@ impl<'n> Codegen<'n> {
 }
 
 impl CodegenOutput {
+    const ATTRIBUTES_H_NAME: &str = "opencan_attributes.h";
     const CALLBACKS_H_NAME: &str = "opencan_callbacks.h";
     const TEMPLATES_H_NAME: &str = "opencan_templates.h";
+    const VALUE_TABLES_H_NAME: &str = "opencan_value_tables.h";
     const RX_C_NAME: &str = "opencan_rx.c";
     const RX_H_NAME: &str = "opencan_rx.h";
     const TX_C_NAME: &str = "opencan_tx.c";
@@ -242,8 +292,10 @@ impl CodegenOutput {
 
     pub fn as_list_h(&self) -> Vec<(&str, &str)> {
         vec![
+            (Self::ATTRIBUTES_H_NAME, &self.attributes_h),
             (Self::CALLBACKS_H_NAME, &self.callbacks_h),
             (Self::TEMPLATES_H_NAME, &self.templates_h),
+            (Self::VALUE_TABLES_H_NAME, &self.value_tables_h),
             (Self::RX_H_NAME, &self.rx_h),
             (Self::TX_H_NAME, &self.tx_h),
         ]
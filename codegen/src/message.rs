@@ -1,5 +1,5 @@
 use indoc::formatdoc;
-use opencan_core::{CANMessage, CANMessageKind};
+use opencan_core::{CANMessage, CANMessageKind, CANSignal, CANSignalWithPosition};
 
 use crate::{signal::*, Indent};
 
@@ -10,6 +10,8 @@ pub trait MessageCodegen {
     fn struct_def(&self) -> String;
     /// Identifier for the global unpacked + decoded data struct of type [`.struct_ty()`](Self::struct_ty()).
     fn global_struct_ident(&self) -> String;
+    /// Initializer for the decoded data struct, holding each signal's initial value.
+    fn struct_initializer(&self) -> String;
 
     /// C type for this message's unpacked raw data struct.
     fn raw_struct_ty(&self) -> String;
@@ -17,6 +19,8 @@ pub trait MessageCodegen {
     fn raw_struct_def(&self) -> String;
     /// Identifier for the global unpacked raw data struct of type [`.raw_struct_ty()`](Self::raw_struct_ty()).
     fn global_raw_struct_ident(&self) -> String;
+    /// Initializer for the raw data struct, holding each signal's initial value.
+    fn raw_struct_initializer(&self) -> String;
 
     /// Name of the RX handler function for this message.
     fn rx_fn_name(&self) -> String;
@@ -56,6 +60,22 @@ pub trait MessageCodegen {
 
     /// Fix up signal name within structs for template-derived messages.
     fn normalize_struct_signal_name(&self, name: &str) -> String;
+
+    /// Path to a signal's member within this message's structs. Template
+    /// signals of instances with extra signals live in the `base` member.
+    fn struct_signal_field(&self, name: &str) -> String;
+
+    /// Whether this is a template instance with extra signals of its own,
+    /// which wraps its template's structs in structs of its own.
+    fn extends_template(&self) -> bool;
+
+    /// Whether a signal was added by a template instance rather than coming
+    /// from its template.
+    fn is_instance_signal(&self, sig: &CANSignal) -> bool;
+
+    /// C condition for a multiplexed signal to be present, in terms of the
+    /// raw struct `raw`. `None` if the signal is always present.
+    fn mux_condition(&self, sig: &CANSignal, raw: &str) -> Option<String>;
 }
 
 impl MessageCodegen for CANMessage {
@@ -70,6 +90,9 @@ impl MessageCodegen for CANMessage {
             CANMessageKind::Template => {
                 format!("struct CAN_TMessage_{}", self.name)
             }
+            CANMessageKind::FromTemplate(_) if self.extends_template() => {
+                format!("struct CAN_Message_{}", self.name)
+            }
             CANMessageKind::FromTemplate(t) => {
                 format!("struct CAN_TMessage_{t}")
             }
@@ -77,16 +100,26 @@ impl MessageCodegen for CANMessage {
     }
 
     fn struct_def(&self) -> String {
-        if let CANMessageKind::FromTemplate(t) = self.kind() {
-            return format!(
-                "/*  Decoded struct `{}` provided by template `{t}`  */",
-                self.struct_ty()
-            );
-        }
-
         let mut inner = String::new(); // struct contents
 
-        for sigbit in &self.signals {
+        let signals = match self.kind() {
+            CANMessageKind::FromTemplate(t) if self.extends_template() => {
+                inner += &format!(
+                    "\n/* Signals from template `{t}` */\nstruct CAN_TMessage_{t} base;\n"
+                );
+
+                self.instance_signals()
+            }
+            CANMessageKind::FromTemplate(t) => {
+                return format!(
+                    "/*  Decoded struct `{}` provided by template `{t}`  */",
+                    self.struct_ty()
+                );
+            }
+            _ => &self.signals,
+        };
+
+        for sigbit in signals {
             inner += "\n";
             inner += &formatdoc! {"
                 /**
@@ -94,14 +127,16 @@ impl MessageCodegen for CANMessage {
                  *
                  * ----> Description: {desc}
                  * ----> Start bit: {start}
-                 * ----> Width: {width}
+                 * ----> Width: {width}{physical}{mux}
                  */
                 _Atomic {sigty} {name};
                 ",
-                name = sigbit.sig.name,
+                name = self.struct_signal_field(&sigbit.sig.name),
                 desc = sigbit.sig.description.as_ref().unwrap_or(&"(None)".into()),
                 start = sigbit.start(),
                 width = sigbit.sig.width,
+                physical = physical_doc(&sigbit.sig),
+                mux = mux_doc(self, &sigbit.sig),
                 sigty = self.sig_ty_decoded(&sigbit.sig),
             };
         }
@@ -119,6 +154,12 @@ impl MessageCodegen for CANMessage {
         format!("CANRX_Message_{}", self.name)
     }
 
+    fn struct_initializer(&self) -> String {
+        initializer(self, |sig, raw| {
+            self.decoding_expression(sig, &self.raw_value_literal(sig, raw))
+        })
+    }
+
     fn raw_struct_ty(&self) -> String {
         match self.kind() {
             CANMessageKind::Raw => {
@@ -130,6 +171,9 @@ impl MessageCodegen for CANMessage {
             CANMessageKind::Template => {
                 format!("struct CAN_TMessageRaw_{}", self.name)
             }
+            CANMessageKind::FromTemplate(_) if self.extends_template() => {
+                format!("struct CAN_MessageRaw_{}", self.name)
+            }
             CANMessageKind::FromTemplate(t) => {
                 format!("struct CAN_TMessageRaw_{t}")
             }
@@ -137,16 +181,26 @@ impl MessageCodegen for CANMessage {
     }
 
     fn raw_struct_def(&self) -> String {
-        if let CANMessageKind::FromTemplate(t) = self.kind() {
-            return format!(
-                "/*  Raw struct `{}` provided by template `{t}`  */",
-                self.raw_struct_ty()
-            );
-        }
-
         let mut inner = String::new(); // struct contents
 
-        for sigbit in &self.signals {
+        let signals = match self.kind() {
+            CANMessageKind::FromTemplate(t) if self.extends_template() => {
+                inner += &format!(
+                    "\n/* Raw signals from template `{t}` */\nstruct CAN_TMessageRaw_{t} base;\n"
+                );
+
+                self.instance_signals()
+            }
+            CANMessageKind::FromTemplate(t) => {
+                return format!(
+                    "/*  Raw struct `{}` provided by template `{t}`  */",
+                    self.raw_struct_ty()
+                );
+            }
+            _ => &self.signals,
+        };
+
+        for sigbit in signals {
             inner += "\n";
             inner += &formatdoc! {"
                 /**
@@ -154,14 +208,16 @@ impl MessageCodegen for CANMessage {
                  *
                  * ----> Description: {desc}
                  * ----> Start bit: {start}
-                 * ----> Width: {width}
+                 * ----> Width: {width}{raw_values}{mux}
                  */
                 _Atomic {sigty} {name};
                 ",
-                name = sigbit.sig.name,
+                name = self.struct_signal_field(&sigbit.sig.name),
                 desc = sigbit.sig.description.as_ref().unwrap_or(&"(None)".into()),
                 start = sigbit.start(),
                 width = sigbit.sig.width,
+                raw_values = raw_values_doc(&sigbit.sig),
+                mux = mux_doc(self, &sigbit.sig),
                 sigty = self.sig_ty_raw(&sigbit.sig),
             };
         }
@@ -179,6 +235,10 @@ impl MessageCodegen for CANMessage {
         format!("CANRX_MessageRaw_{}", self.name)
     }
 
+    fn raw_struct_initializer(&self) -> String {
+        initializer(self, |sig, raw| self.raw_value_literal(sig, raw))
+    }
+
     fn rx_fn_name(&self) -> String {
         format!("CANRX_doRx_{}", self.name)
     }
@@ -263,7 +323,6 @@ impl MessageCodegen for CANMessage {
                 sig.name, bit, sig.width
             );
 
-            // todo: assumes big-endian.
             // step through each of the bit-byte boundaries
 
             // say the signal is 3 bits wide and starts at position 6.
@@ -275,42 +334,23 @@ impl MessageCodegen for CANMessage {
             //                               ^----------------------- mask of bits to select from this byte
             //                                     ^------^---------- ending offset of range within this byte
             //                                                  ^---- current offset within the signal
-
-            assert!(
-                sigbit.end() < u8::MAX.into(),
-                "Too many bits for me :P, please fix for CAN FD"
-            );
+            //
+            // big-endian signals work the same way, except that the signal
+            // continues in the previous byte rather than the next one.
 
             let unpack_ty = self.sig_ty_raw_before_sign_extension(sig);
 
             let unpack_var = format!("unpack__{sig_name}");
             unpack += &format!("{unpack_ty} {unpack_var} = 0;\n");
 
-            let mut pos = bit;
-            let sig_end = sigbit.end();
-            while pos <= sig_end {
-                let byte = pos / 8;
-
-                let end_of_this_byte = ((byte + 1) * 8) - 1;
-                let end_pos = end_of_this_byte.min(sig_end); // either end of this byte or final end of signal
-                let end_pos_within_byte = end_pos % 8;
-
-                let num_bits_from_this_byte = end_pos - pos + 1;
-                let mask_shift = end_pos_within_byte + 1 - num_bits_from_this_byte;
-
-                let mask: u8 = if num_bits_from_this_byte == 8 {
-                    0xFF
-                } else {
-                    !(!0 << num_bits_from_this_byte)
-                };
-                let mask = format!("0x{mask:02x}");
-
+            for chunk in byte_chunks(sigbit) {
                 unpack += &formatdoc! {"
                     {unpack_var} |= ({unpack_ty})((data[{byte}U] & ({mask}U << {mask_shift}U)) >> {mask_shift}U) << {sig_pos}U;\n",
-                    sig_pos = pos - bit
+                    byte = chunk.byte,
+                    mask = chunk.mask(),
+                    mask_shift = chunk.shift,
+                    sig_pos = chunk.sig_pos,
                 };
-
-                pos = end_pos + 1;
             }
 
             unpack += "\n";
@@ -319,8 +359,20 @@ impl MessageCodegen for CANMessage {
         let unpack = unpack.trim();
 
         /* sign extension + populate raw struct */
-        // The sign extensions go before the raw struct, but we'll build both in parallel.
-        let mut raw_struct = format!("const {} raw = {{", self.raw_struct_ty());
+        // The sign extensions (and type punning of float signals) go before the raw
+        // struct, but we'll build both in parallel.
+        //
+        // Multiplexed messages only update the signals in the received group,
+        // so they start from the last received values and assign each signal.
+        let mut raw_struct = if self.is_multiplexed() {
+            format!(
+                "{} raw = {};\n",
+                self.raw_struct_ty(),
+                self.global_raw_struct_ident()
+            )
+        } else {
+            format!("const {} raw = {{", self.raw_struct_ty())
+        };
         let mut sign_extensions = String::new();
         for sigbit in &self.signals {
             let sig = &sigbit.sig;
@@ -343,13 +395,34 @@ impl MessageCodegen for CANMessage {
                 };
 
                 extended
+            } else if self.sig_needs_type_punning(sig) {
+                let punned = format!("unpack_pun__{sig_name}");
+
+                sign_extensions += &formatdoc! {"
+                    {ty} {punned};
+                    memcpy(&{punned}, &unpack__{sig_name}, sizeof {punned});
+                    ",
+                    ty = self.sig_ty_raw(sig),
+                };
+
+                punned
             } else {
                 format!("unpack__{sig_name}")
             };
 
-            raw_struct += &format!("\n    .{sig_name} = {unpacked_val},");
+            let field = self.struct_signal_field(&sig.name);
+            if self.is_multiplexed() {
+                raw_struct += &maybe_conditional(
+                    self.mux_condition(sig, "raw"),
+                    &format!("raw.{field} = {unpacked_val};\n"),
+                );
+            } else {
+                raw_struct += &format!("\n    .{field} = {unpacked_val},");
+            }
+        }
+        if !self.is_multiplexed() {
+            raw_struct += "\n};";
         }
-        raw_struct += "\n};";
 
         let raw_struct = raw_struct.trim();
         let sign_extensions = sign_extensions.trim();
@@ -361,22 +434,29 @@ impl MessageCodegen for CANMessage {
         // todo for later bounds checks: a facility for signals being strictly enumerated?
         // todo  -> that is, ensure a signal can only be one of its enumerated values
 
-        let decode_start = format!("{} dec = {{0}};", self.struct_ty());
+        let decode_start = if self.is_multiplexed() {
+            format!("{} dec = {};", self.struct_ty(), self.global_struct_ident())
+        } else {
+            format!("{} dec = {{0}};", self.struct_ty())
+        };
 
         let mut decode = String::new();
 
         for sigbit in &self.signals {
             let sig = &sigbit.sig;
-            let sig_name = self.normalize_struct_signal_name(&sig.name);
-
-            decode += &formatdoc! {"
-                // Decode `{name}`
-                dec.{name} = {};
+            let field = self.struct_signal_field(&sig.name);
 
-                ",
-                self.decoding_expression(sig, &format!("raw.{sig_name}")),
-                name = sig_name,
-            };
+            decode += &maybe_conditional(
+                self.mux_condition(sig, "raw"),
+                &formatdoc! {"
+                    // Decode `{name}`
+                    dec.{field} = {};
+                    ",
+                    self.decoding_expression(sig, &format!("raw.{field}")),
+                    name = self.normalize_struct_signal_name(&sig.name),
+                },
+            );
+            decode += "\n";
         }
 
         let decode = decode.trim();
@@ -412,7 +492,7 @@ impl MessageCodegen for CANMessage {
 
             {unpack}
 
-            /* --- Perform sign extension --- */
+            /* --- Perform sign extension and type punning --- */
 
             {sign_extensions}
 
@@ -504,18 +584,22 @@ impl MessageCodegen for CANMessage {
                     /* Call user-provided populate function */
 
                     // User will provide raw data and length.
-                    uint8_t data[8] = {{0}};
+                    uint8_t data[{max_len}] = {{0}};
                     uint8_t len = 0;
                     {pop_fn}(data, &len);
 
                     /* ------- Send message ------- */
-                    CAN_callback_enqueue_tx_message(data, len, 0x{id:X}U);
+                    CAN_callback_enqueue_tx_message(data, len, 0x{id:X}U, {extended}, {fd}, {brs});
 
                     return true;
                 }}",
                 fn_name = self.tx_fn_name(),
+                max_len = self.max_length(),
                 pop_fn = self.tx_populate_fn_name(),
-                id = self.id,
+                id = self.id.raw(),
+                extended = self.id.is_extended(),
+                fd = self.fd,
+                brs = self.brs,
             };
         }
 
@@ -526,15 +610,15 @@ impl MessageCodegen for CANMessage {
 
         for sigbit in &self.signals {
             let sig = &sigbit.sig;
-            let sig_name = self.normalize_struct_signal_name(&sig.name);
+            let field = self.struct_signal_field(&sig.name);
 
             encode += &formatdoc! {"
                 // Encode `{name}`
-                raw.{name} = {};
+                raw.{field} = {};
 
                 ",
-                self.encoding_expression(sig, &format!("dec.{sig_name}")),
-                name = sig_name,
+                self.encoding_expression(sig, &format!("dec.{field}")),
+                name = self.normalize_struct_signal_name(&sig.name),
             };
         }
 
@@ -563,42 +647,41 @@ impl MessageCodegen for CANMessage {
                 sig_name, bit, sig.width
             );
 
-            assert!(
-                sigbit.end() < u8::MAX.into(),
-                "Too many bits for me :P, please fix for CAN FD"
-            );
-
-            let mut pos = bit;
-            let sig_end = sigbit.end();
-            while pos <= sig_end {
-                let byte = pos / 8;
+            // float signals are packed from their bits
+            let mut pack_sig = String::new();
+            let packed = if self.sig_needs_type_punning(sig) {
+                let punned = format!("pack_pun__{sig_name}");
 
-                let end_of_this_byte = ((byte + 1) * 8) - 1;
-                let end_pos = end_of_this_byte.min(sig_end); // either end of this byte or final end of signal
-                let end_pos_within_byte = end_pos % 8;
+                pack_sig += &formatdoc! {"
+                    {ty} {punned};
+                    memcpy(&{punned}, &raw.{field}, sizeof {punned});
+                    ",
+                    ty = self.sig_ty_raw_before_sign_extension(sig),
+                    field = self.struct_signal_field(&sig.name),
+                };
 
-                let num_bits_from_this_byte = end_pos - pos + 1;
-                let mask_shift = end_pos_within_byte + 1 - num_bits_from_this_byte;
+                punned
+            } else {
+                format!("raw.{}", self.struct_signal_field(&sig.name))
+            };
 
-                let mask: u8 = if num_bits_from_this_byte == 8 {
-                    0xFF
-                } else {
-                    !(!0 << num_bits_from_this_byte)
-                };
+            for chunk in byte_chunks(sigbit) {
                 let mask = format!(
-                    "(({})0x{mask:02x}U)",
-                    self.sig_ty_raw_before_sign_extension(sig)
+                    "(({}){}U)",
+                    self.sig_ty_raw_before_sign_extension(sig),
+                    chunk.mask()
                 );
 
-                pack += &formatdoc! {"
-                    data[{byte}U] |= ((raw.{name} & ({mask} << {sig_pos}U)) >> {sig_pos}U) << {mask_shift}U;\n",
-                    name = sig_name,
-                    sig_pos = pos - bit
+                pack_sig += &formatdoc! {"
+                    data[{byte}U] |= (({packed} & ({mask} << {sig_pos}U)) >> {sig_pos}U) << {mask_shift}U;\n",
+                    byte = chunk.byte,
+                    mask_shift = chunk.shift,
+                    sig_pos = chunk.sig_pos,
                 };
-
-                pos = end_pos + 1;
             }
 
+            // only pack multiplexed signals in the group selected by the user
+            pack += &maybe_conditional(self.mux_condition(sig, "raw"), &pack_sig);
             pack += "\n";
         }
 
@@ -609,9 +692,9 @@ impl MessageCodegen for CANMessage {
             bool {fn_name}(void)\n{{
                 /* Call user-provided populate function */
 
-                // Struct is zeroed. If the user doesn't populate a signal value,
-                // its value will be as if the user had said `m->signal = 0`.
-                {dec_ty} dec = {{0}};
+                // Struct starts with each signal's initial value, or zero if it has
+                // none. If the user doesn't populate a signal value, that's what is sent.
+            {dec}
                 {pop_fn}(&dec); // calls into user code!
 
                 /* ------- Encode signals ------- */
@@ -625,16 +708,19 @@ impl MessageCodegen for CANMessage {
             {pack}
 
                 /* ------- Send message ------- */
-                CAN_callback_enqueue_tx_message(data, {length}, 0x{id:X}U);
+                CAN_callback_enqueue_tx_message(data, {length}, 0x{id:X}U, {extended}, {fd}, {brs});
 
                 return true;
             }}",
             fn_name = self.tx_fn_name(),
-            dec_ty = self.struct_ty(),
+            dec = format!("{} dec = {};", self.struct_ty(), self.struct_initializer()).indent(4),
             pop_fn = self.tx_populate_fn_name(),
             raw_ty = self.raw_struct_ty(),
             length = self.length,
-            id = self.id,
+            id = self.id.raw(),
+            extended = self.id.is_extended(),
+            fd = self.fd,
+            brs = self.brs,
         }
     }
 
@@ -650,13 +736,10 @@ impl MessageCodegen for CANMessage {
                 )
             }
             CANMessageKind::FromTemplate(_) => {
-                // strip the node name
-                // todo: assumes node name is present in the message and is actually the prefix
-                let stripped = self
-                    .name
-                    .strip_prefix(self.tx_node.as_ref().unwrap())
-                    .unwrap();
-                format!("CANTX_populateTemplate{stripped}")
+                // instances are named with the same prefix as their signals
+                let prefix = self.instance_prefix().unwrap_or_default();
+                let stripped = self.name.strip_prefix(prefix).unwrap_or(&self.name);
+                format!("CANTX_populateTemplate_{stripped}")
             }
         }
     }
@@ -708,13 +791,22 @@ impl MessageCodegen for CANMessage {
             getters += &formatdoc! {"
                 {sigty_dec} {fn_name}(void);
                 {sigty_raw} {fn_name_raw}(void);
-
                 ",
                 sigty_dec = self.sig_ty_decoded(sig),
                 sigty_raw = self.sig_ty_raw(sig),
                 fn_name = self.getter_fn_name(sig),
                 fn_name_raw = self.raw_getter_fn_name(sig),
+            };
+
+            if sig.sna_value.is_some() {
+                getters += &format!("bool {}(void);\n", self.sna_check_fn_name(sig));
             }
+
+            if sig.error_value.is_some() {
+                getters += &format!("bool {}(void);\n", self.error_check_fn_name(sig));
+            }
+
+            getters += "\n";
         }
 
         getters.trim().into()
@@ -735,13 +827,35 @@ impl MessageCodegen for CANMessage {
                 }}
 
                 ",
-                name = self.normalize_struct_signal_name(&sig.name),
+                name = self.struct_signal_field(&sig.name),
                 sigty_dec = self.sig_ty_decoded(sig),
                 sigty_raw = self.sig_ty_raw(sig),
                 global_decoded = self.global_struct_ident(),
                 global_raw = self.global_raw_struct_ident(),
                 fn_name = self.getter_fn_name(sig),
                 fn_name_raw = self.raw_getter_fn_name(sig),
+            };
+
+            let checks = [
+                (sig.sna_value, self.sna_check_fn_name(sig)),
+                (sig.error_value, self.error_check_fn_name(sig)),
+            ];
+
+            for (value, fn_name) in checks {
+                let Some(value) = value else {
+                    continue;
+                };
+
+                getters += &formatdoc! {"
+                    bool {fn_name}(void) {{
+                        return {global_raw}.{name} == {value};
+                    }}
+
+                    ",
+                    name = self.struct_signal_field(&sig.name),
+                    global_raw = self.global_raw_struct_ident(),
+                    value = self.raw_value_literal(sig, value),
+                };
             }
         }
 
@@ -749,14 +863,20 @@ impl MessageCodegen for CANMessage {
     }
 
     fn signal_enums(&self) -> String {
-        if let CANMessageKind::FromTemplate(t) = self.kind() {
-            return format!("/*  Signal enums provied by template `{t}`  */");
-        }
-
         let mut out = String::new();
         let mut some = false;
 
-        for sigbit in &self.signals {
+        let signals = match self.kind() {
+            CANMessageKind::FromTemplate(t) => {
+                out += &format!("/*  Signal enums provied by template `{t}`  */\n\n");
+                some = true;
+
+                self.instance_signals()
+            }
+            _ => &self.signals,
+        };
+
+        for sigbit in signals {
             if let Some(e) = self.c_enum(&sigbit.sig) {
                 out += &format!("{e}\n\n");
                 some = true;
@@ -772,10 +892,201 @@ impl MessageCodegen for CANMessage {
 
     fn normalize_struct_signal_name(&self, name: &str) -> String {
         if matches!(self.kind(), CANMessageKind::FromTemplate(_)) {
-            let prefix = format!("{}_", self.tx_node.as_ref().unwrap());
-            name.strip_prefix(&prefix).unwrap().into()
+            let prefix = self.instance_prefix().unwrap_or_default();
+            name.strip_prefix(prefix).unwrap_or(name).into()
         } else {
             name.into()
         }
     }
+
+    fn struct_signal_field(&self, name: &str) -> String {
+        let field = self.normalize_struct_signal_name(name);
+
+        if self.extends_template() && self.instance_signals().iter().all(|s| s.sig.name != name) {
+            format!("base.{field}")
+        } else {
+            field
+        }
+    }
+
+    fn extends_template(&self) -> bool {
+        matches!(self.kind(), CANMessageKind::FromTemplate(_))
+            && !self.instance_signals().is_empty()
+    }
+
+    fn is_instance_signal(&self, sig: &CANSignal) -> bool {
+        self.instance_signals()
+            .iter()
+            .any(|s| s.sig.name == sig.name)
+    }
+
+    fn mux_condition(&self, sig: &CANSignal, raw: &str) -> Option<String> {
+        let chain = self.mux_chain(sig);
+        if chain.is_empty() {
+            return None;
+        }
+
+        // outermost multiplexor first
+        let conds: Vec<String> = chain
+            .iter()
+            .rev()
+            .map(|mux| {
+                let multiplexor = self.struct_signal_field(&mux.multiplexor);
+                let values: Vec<String> = mux
+                    .values
+                    .iter()
+                    .map(|v| format!("{raw}.{multiplexor} == {v}U"))
+                    .collect();
+
+                if values.len() == 1 {
+                    values[0].clone()
+                } else {
+                    format!("({})", values.join(" || "))
+                }
+            })
+            .collect();
+
+        Some(conds.join(" && "))
+    }
+}
+
+/// Extra struct member doc lines describing a signal's unit and physical range.
+fn physical_doc(sig: &CANSignal) -> String {
+    let mut doc = String::new();
+
+    if let Some(unit) = &sig.unit {
+        doc += &format!("\n * ----> Unit: {unit}");
+    }
+
+    if let Some(min) = sig.min {
+        doc += &format!("\n * ----> Minimum: {min}");
+    }
+
+    if let Some(max) = sig.max {
+        doc += &format!("\n * ----> Maximum: {max}");
+    }
+
+    doc
+}
+
+/// Extra raw struct member doc lines giving a signal's initial, SNA and
+/// error values.
+fn raw_values_doc(sig: &CANSignal) -> String {
+    let values = [
+        ("Initial value", sig.initial_value),
+        ("SNA value", sig.sna_value),
+        ("Error value", sig.error_value),
+    ];
+
+    values
+        .into_iter()
+        .filter_map(|(what, value)| Some(format!("\n * ----> {what}: {}", value?)))
+        .collect()
+}
+
+/// C initializer for one of a message's structs, with `value` for each
+/// signal that has an initial value. Other signals are zeroed.
+fn initializer(msg: &CANMessage, value: impl Fn(&CANSignal, u64) -> String) -> String {
+    let members: Vec<String> = msg
+        .signals
+        .iter()
+        .filter_map(|s| {
+            let initial = s.sig.initial_value?;
+            Some(format!(
+                "\n    .{} = {},",
+                msg.struct_signal_field(&s.sig.name),
+                value(&s.sig, initial)
+            ))
+        })
+        .collect();
+
+    if members.is_empty() {
+        "{0}".into()
+    } else {
+        format!("{{{}\n}}", members.concat())
+    }
+}
+
+/// Extra struct member doc lines describing a signal's multiplexing.
+fn mux_doc(msg: &CANMessage, sig: &CANSignal) -> String {
+    let mut doc = String::new();
+
+    if msg.is_multiplexor(&sig.name) {
+        doc += "\n * ----> Multiplexor: selects the signal group; holds the last group received";
+    }
+
+    if let Some(mux) = &sig.mux {
+        let values: Vec<String> = mux.values.iter().map(|v| v.to_string()).collect();
+        doc += &format!(
+            "\n * ----> Multiplexed: present when `{}` is {}",
+            msg.normalize_struct_signal_name(&mux.multiplexor),
+            values.join(" or ")
+        );
+    }
+
+    doc
+}
+
+/// Wrap `body` in `if (cond) { ... }` if there is a condition.
+fn maybe_conditional(cond: Option<String>, body: &str) -> String {
+    match cond {
+        Some(cond) => formatdoc! {"
+            if ({cond}) {{
+            {}
+            }}
+            ",
+            body.trim().indent(4)
+        },
+        None => body.into(),
+    }
+}
+
+/// A contiguous run of a signal's bits within a single data byte.
+struct ByteChunk {
+    /// Index of the data byte.
+    byte: u32,
+    /// Position of the run's lowest bit within the data byte.
+    shift: u32,
+    /// Number of bits in the run.
+    len: u32,
+    /// Position of the run's lowest bit within the signal.
+    sig_pos: u32,
+}
+
+impl ByteChunk {
+    /// Mask of `len` bits as a hex literal (without suffix).
+    fn mask(&self) -> String {
+        let mask: u8 = if self.len == 8 {
+            0xFF
+        } else {
+            !(!0 << self.len)
+        };
+
+        format!("0x{mask:02x}")
+    }
+}
+
+/// Split a signal into runs of bits that share a data byte, starting from
+/// the signal's least significant bit.
+///
+/// Within a byte, signal bits always ascend with message bits, regardless
+/// of byte order - only the order of the bytes differs.
+fn byte_chunks(sigbit: &CANSignalWithPosition) -> Vec<ByteChunk> {
+    let mut chunks: Vec<ByteChunk> = Vec::new();
+
+    for (sig_pos, pos) in (0..).zip(sigbit.bit_positions()) {
+        let byte = pos / 8;
+
+        match chunks.last_mut() {
+            Some(c) if c.byte == byte && c.shift + c.len == pos % 8 => c.len += 1,
+            _ => chunks.push(ByteChunk {
+                byte,
+                shift: pos % 8,
+                len: 1,
+                sig_pos,
+            }),
+        }
+    }
+
+    chunks
 }
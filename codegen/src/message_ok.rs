@@ -64,7 +64,7 @@ impl MessageStatusCodegen for Codegen<'_> {
 
                 return false;
             }}\n\n",
-            decl = self.message_ok_fn_decl(&message),
+            decl = self.message_ok_fn_decl(message),
         };
 
         check.trim().into()
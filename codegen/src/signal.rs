@@ -1,7 +1,7 @@
 use std::fmt::Display;
 
 use indoc::formatdoc;
-use opencan_core::{CANMessage, CANMessageKind, CANSignal};
+use opencan_core::{CANMessage, CANMessageKind, CANSignal, SignalValueType};
 
 use crate::{message::MessageCodegen, Indent};
 
@@ -16,6 +16,7 @@ pub enum CSignalTy {
     U64,
     I64,
     Float,
+    Double,
     Enum(String),
 }
 
@@ -35,6 +36,7 @@ impl Display for CSignalTy {
                 Self::U64 => "uint64_t",
                 Self::I64 => "int64_t",
                 Self::Float => "float", // todo: use a typedef?
+                Self::Double => "double",
                 Self::Enum(s) => s,
             }
         )
@@ -60,6 +62,9 @@ pub trait SignalCodegen {
     /// Signals that are twos-complement and whose width is not an even power
     /// of two need sign extension.
     fn sig_needs_sign_extension(&self, sig: &CANSignal) -> bool;
+    /// Whether this signal's unpacked bits need to be reinterpreted as its
+    /// raw type, as for IEEE-754 float and double signals.
+    fn sig_needs_type_punning(&self, sig: &CANSignal) -> bool;
 
     /// C enumeration for this signal's enumerated values, if any.
     fn c_enum(&self, sig: &CANSignal) -> Option<String>;
@@ -69,6 +74,15 @@ pub trait SignalCodegen {
     /// Name of the C getter function for this signal's raw value.
     fn raw_getter_fn_name(&self, sig: &CANSignal) -> String;
 
+    /// Name of the C function telling whether this signal holds its SNA value.
+    fn sna_check_fn_name(&self, sig: &CANSignal) -> String;
+    /// Name of the C function telling whether this signal holds its error value.
+    fn error_check_fn_name(&self, sig: &CANSignal) -> String;
+
+    /// C literal for a raw value of this signal, as held by its raw type
+    /// (i.e. sign-extended or reinterpreted as a float).
+    fn raw_value_literal(&self, sig: &CANSignal, raw: u64) -> String;
+
     /// Conversion expression from raw signal to decoded signal.
     fn decoding_expression(&self, sig: &CANSignal, raw_rvalue: &str) -> String;
     /// Conversion expression from decoded signal to raw signal.
@@ -83,12 +97,18 @@ impl SignalCodegen for CANMessage {
             CSignalTy::I16 | CSignalTy::U16 => CSignalTy::U16,
             CSignalTy::I32 | CSignalTy::U32 => CSignalTy::U32,
             CSignalTy::I64 | CSignalTy::U64 => CSignalTy::U64,
+            CSignalTy::Float => CSignalTy::U32,
+            CSignalTy::Double => CSignalTy::U64,
             t => panic!("Unexpected raw type {t} for signal {}", sig.name),
         }
     }
 
     fn sig_ty_raw(&self, sig: &CANSignal) -> CSignalTy {
-        if sig.twos_complement {
+        if sig.value_type == SignalValueType::Float {
+            CSignalTy::Float
+        } else if sig.value_type == SignalValueType::Double {
+            CSignalTy::Double
+        } else if sig.twos_complement {
             match sig.width {
                 1 => panic!(
                     "Signal `{}` has width 1 but also twos_complement=true",
@@ -122,16 +142,25 @@ impl SignalCodegen for CANMessage {
         matches!(
             self.sig_ty_raw(sig),
             CSignalTy::I8 | CSignalTy::I16 | CSignalTy::I32 | CSignalTy::I64
-        ) && !sig.width.is_power_of_two()
+        ) && !matches!(sig.width, 8 | 16 | 32 | 64)
+    }
+
+    fn sig_needs_type_punning(&self, sig: &CANSignal) -> bool {
+        sig.value_type != SignalValueType::Integer
     }
 
     fn sig_ty_decoded(&self, sig: &CANSignal) -> CSignalTy {
         // todo: support for both enumerated and continuous decoded getters
-        if !sig.enumerated_values.is_empty() {
+        if let Some(table) = &sig.value_table {
+            CSignalTy::Enum(format!("enum CAN_VT_{table}"))
+        } else if !sig.enumerated_values.is_empty() {
             // CSignalTy::Enum(format!("enum CAN_{}", sig.name))
             let name = match self.kind() {
                 CANMessageKind::Raw => panic!("Raw message should not have signals"),
                 CANMessageKind::Independent => format!("enum CAN_{}", sig.name),
+                CANMessageKind::FromTemplate(_) if self.is_instance_signal(sig) => {
+                    format!("enum CAN_{}", sig.name)
+                }
                 CANMessageKind::Template => format!("enum CAN_T_{}_{}", self.name, sig.name),
                 CANMessageKind::FromTemplate(t) => format!(
                     "enum CAN_T_{t}_{}",
@@ -152,6 +181,8 @@ impl SignalCodegen for CANMessage {
             // for now, if the signal has no offset or scale, then return its raw type, else float.
             //
             self.sig_ty_raw(sig)
+        } else if sig.value_type == SignalValueType::Double {
+            CSignalTy::Double
         } else {
             CSignalTy::Float
         }
@@ -162,14 +193,20 @@ impl SignalCodegen for CANMessage {
             return None; // decoded type is not an enum
         };
 
+        if sig.value_table.is_some() {
+            return None; // shared enum, emitted with the network's value tables
+        }
+
         // sort enumerated values since they're in random order in the map
         let mut evs: Vec<_> = sig.enumerated_values.iter().collect();
         evs.sort_by_key(|ev| ev.1);
 
         // choose prefix
+        let instance_signal = self.is_instance_signal(sig);
         let prefix = match self.kind() {
             CANMessageKind::Raw => panic!("Raw message should not have enums"),
             CANMessageKind::Independent => "CAN".into(),
+            CANMessageKind::FromTemplate(_) if instance_signal => "CAN".into(),
             CANMessageKind::Template => format!("CAN_T_{}", self.name.to_uppercase()),
             CANMessageKind::FromTemplate(t) => format!("CAN_T_{}", t.to_uppercase()),
         };
@@ -179,7 +216,11 @@ impl SignalCodegen for CANMessage {
         for e in evs {
             inner += &format!(
                 "{prefix}_{}_{} = {},\n",
-                self.normalize_struct_signal_name(&sig.name).to_uppercase(),
+                if instance_signal {
+                    sig.name.to_uppercase()
+                } else {
+                    self.normalize_struct_signal_name(&sig.name).to_uppercase()
+                },
                 e.0,
                 e.1
             );
@@ -202,47 +243,80 @@ impl SignalCodegen for CANMessage {
         format!("CANRX_getRaw_{}", sig.name)
     }
 
+    fn sna_check_fn_name(&self, sig: &CANSignal) -> String {
+        format!("CANRX_isSNA_{}", sig.name)
+    }
+
+    fn error_check_fn_name(&self, sig: &CANSignal) -> String {
+        format!("CANRX_isError_{}", sig.name)
+    }
+
+    fn raw_value_literal(&self, sig: &CANSignal, raw: u64) -> String {
+        match self.sig_ty_raw(sig) {
+            CSignalTy::Float => format!("{:?}f", f32::from_bits(raw as u32)),
+            CSignalTy::Double => format!("{:?}", f64::from_bits(raw)),
+            CSignalTy::Bool => (raw != 0).to_string(),
+            CSignalTy::I8 | CSignalTy::I16 | CSignalTy::I32 | CSignalTy::I64 => {
+                // sign-extend from the signal's width
+                let shift = u64::BITS - sig.width;
+                let value = ((raw << shift) as i64) >> shift;
+
+                if value == i64::MIN {
+                    "INT64_MIN".into()
+                } else {
+                    format!("{value}LL")
+                }
+            }
+            _ => format!("{raw}ULL"),
+        }
+    }
+
     fn decoding_expression(&self, sig: &CANSignal, raw_rvalue: &str) -> String {
         // Currently, signals are either their raw type if they have no scale
-        // or offset, or they're CSignalTy::Float if they have a scale or offset.
+        // or offset, or they're CSignalTy::Float (Double for double signals)
+        // if they have a scale or offset.
         //
         // We're not accounting for enumerated values yet, which we may or may not
         // do at all in this function.
 
-        if matches!(self.sig_ty_decoded(sig), CSignalTy::Float) {
-            let scale = sig.scale.map_or("".into(), |s| format!(" * {s:?}f"));
-            let offset = sig.offset.map_or("".into(), |o| format!(" + {o:?}f"));
+        if sig.scale.is_some() || sig.offset.is_some() {
+            let float_ty = self.sig_ty_decoded(sig);
+            let suffix = float_literal_suffix(&float_ty);
+            let scale = sig.scale.map_or("".into(), |s| format!(" * {s:?}{suffix}"));
+            let offset = sig
+                .offset
+                .map_or("".into(), |o| format!(" + {o:?}{suffix}"));
 
-            format!(
-                "(({float_ty})({raw_rvalue}){scale}){offset}",
-                float_ty = CSignalTy::Float
-            )
+            format!("(({float_ty})({raw_rvalue}){scale}){offset}")
         } else {
             // Just copy the raw signal.
-
-            // For now, these should be None according to the logic in .c_ty_decoded()
-            assert!(sig.offset.is_none());
-            assert!(sig.scale.is_none());
-
             raw_rvalue.into()
         }
     }
 
     // Similar logic and notes as above
     fn encoding_expression(&self, sig: &CANSignal, dec_rvalue: &str) -> String {
-        if matches!(self.sig_ty_decoded(sig), CSignalTy::Float) {
-            let scale = sig.scale.map_or("".into(), |s| format!(" / {s:?}f"));
-            let offset = sig.offset.map_or("".into(), |o| format!(" - {o:?}f"));
+        if sig.scale.is_some() || sig.offset.is_some() {
+            let suffix = float_literal_suffix(&self.sig_ty_decoded(sig));
+            let scale = sig.scale.map_or("".into(), |s| format!(" / {s:?}{suffix}"));
+            let offset = sig
+                .offset
+                .map_or("".into(), |o| format!(" - {o:?}{suffix}"));
 
             format!(
                 "({raw_ty})((({dec_rvalue}){scale}){offset})",
                 raw_ty = self.sig_ty_raw(sig)
             )
         } else {
-            assert!(sig.offset.is_none());
-            assert!(sig.scale.is_none());
-
             dec_rvalue.into()
         }
     }
 }
+
+/// Suffix for floating point literals of the given type.
+fn float_literal_suffix(ty: &CSignalTy) -> &'static str {
+    match ty {
+        CSignalTy::Double => "",
+        _ => "f",
+    }
+}
@@ -0,0 +1,118 @@
+use anyhow::Result;
+use opencan_codegen::{Args, Codegen};
+use opencan_core::AttributeValue;
+
+const TEST_DESC: &str = "
+attributes:
+  BusName: Powertrain
+
+message_templates:
+- Heartbeat:
+    attributes:
+      Priority: 1
+      Owner: Platform
+    signals:
+    - alive:
+        width: 1
+
+nodes:
+- TESTTX:
+    description: Test transmitter
+    attributes:
+      Domain: { enum: Chassis }
+    messages:
+    - TestMessage:
+        id: 0x10
+        description: Test message
+        attributes:
+          Priority: 3
+        signals:
+        - voltage:
+            width: 8
+            attributes:
+              Accuracy: 0.5
+              Supplier: 'ACME \"Power\"'
+    - Heartbeat:
+        id: 0x11
+        from_template: Heartbeat
+        attributes:
+          Priority: 2
+- TESTRX:
+    rx:
+      - TESTTX_TestMessage
+      - TESTTX_Heartbeat
+";
+
+#[test]
+fn attributes_composed() -> Result<()> {
+    let net = opencan_compose::compose_str(TEST_DESC, "")?;
+
+    assert_eq!(
+        net.node_by_name("TESTTX").unwrap().description.as_deref(),
+        Some("Test transmitter")
+    );
+    assert_eq!(
+        net.message_by_name("TESTTX_TestMessage")
+            .unwrap()
+            .description
+            .as_deref(),
+        Some("Test message")
+    );
+    assert_eq!(
+        net.message_by_name("TESTTX_Heartbeat").unwrap().description,
+        None
+    );
+
+    assert_eq!(
+        net.attributes()["BusName"],
+        AttributeValue::String("Powertrain".into())
+    );
+
+    let node = net.node_by_name("TESTTX").unwrap();
+    assert_eq!(
+        node.attributes["Domain"],
+        AttributeValue::Enum("Chassis".into())
+    );
+
+    let msg = net.message_by_name("TESTTX_TestMessage").unwrap();
+    assert_eq!(msg.attributes["Priority"], AttributeValue::Int(3));
+    assert_eq!(
+        msg["TESTTX_voltage"].attributes["Accuracy"],
+        AttributeValue::Float(0.5)
+    );
+
+    // instances override template attributes and keep the rest
+    let msg = net.message_by_name("TESTTX_Heartbeat").unwrap();
+    assert_eq!(msg.attributes["Priority"], AttributeValue::Int(2));
+    assert_eq!(
+        msg.attributes["Owner"],
+        AttributeValue::String("Platform".into())
+    );
+
+    assert!(net.validate().is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn attributes_defined() -> Result<()> {
+    let net = opencan_compose::compose_str(TEST_DESC, "")?;
+    let args = Args {
+        node: "TESTRX".into(),
+        tx_stubs: false,
+        rx_callback_stubs: false,
+    };
+    let attributes_h = Codegen::new(args, &net)?.network_to_c().attributes_h;
+
+    assert!(attributes_h.contains("#define CAN_NET_ATTR_BusName \"Powertrain\"\n"));
+    assert!(attributes_h.contains("#define CAN_MSG_TESTTX_TestMessage_ATTR_Priority 3\n"));
+    assert!(attributes_h.contains("#define CAN_MSG_TESTTX_Heartbeat_ATTR_Owner \"Platform\"\n"));
+    assert!(attributes_h.contains("#define CAN_SIG_TESTTX_voltage_ATTR_Accuracy 0.5\n"));
+    assert!(attributes_h
+        .contains("#define CAN_SIG_TESTTX_voltage_ATTR_Supplier \"ACME \\\"Power\\\"\"\n"));
+
+    // only this node's attributes
+    assert!(!attributes_h.contains("CAN_NODE_TESTTX"));
+
+    Ok(())
+}
@@ -0,0 +1,132 @@
+use std::fs;
+
+use anyhow::Result;
+use opencan_core::{CANId, Severity};
+use tempfile::tempdir;
+use testutil::decoders::*;
+
+const TEST_DESC: &str = "
+buses:
+- chassis:
+    bitrate: 500000
+    nodes:
+    - ABS:
+        messages:
+        - Speed:
+            id: 0x100
+            signals:
+            - speed:
+                width: 16
+                scale: 0.01
+        rx:
+          - DBW_Command
+    - GW: {}
+- dbw:
+    nodes:
+    - DBW:
+        messages:
+        - Command:
+            id: 0x100
+            signals:
+            - enable:
+                width: 1
+        rx:
+          - ABS_Speed
+    - GW: {}
+
+gateways:
+- GW:
+  - message: ABS_Speed
+    from: chassis
+    to: dbw
+    id: 0x210
+  - message: DBW_Command
+    from: dbw
+    to: chassis
+    id: 0x101
+";
+
+#[test]
+fn buses_composed() -> Result<()> {
+    assert!(opencan_compose::describes_system(TEST_DESC));
+
+    let system = opencan_compose::compose_system_str(TEST_DESC, "")?;
+    assert_eq!(system.buses_by_node("GW"), ["chassis", "dbw"]);
+    assert_eq!(system.iter_routes().count(), 2);
+
+    let dbw = system.bus_by_name("dbw").unwrap();
+    let speed = dbw.message_by_name("ABS_Speed").unwrap();
+    assert_eq!(speed.id, CANId::from(0x210));
+    assert_eq!(speed.tx_node(), Some("GW"));
+
+    let chassis = system.bus_by_name("chassis").unwrap();
+    assert_eq!(chassis.bitrate(), Some(500000));
+    assert_eq!(
+        chassis.message_by_name("DBW_Command").unwrap().id,
+        CANId::from(0x101)
+    );
+
+    // the gateway receives what it routes
+    let rx = chassis.rx_messages_by_node("GW").unwrap();
+    assert!(rx.iter().any(|m| m.name == "ABS_Speed"));
+
+    assert_eq!(system.validate(), []);
+
+    Ok(())
+}
+
+#[test]
+fn buses_bad_route() {
+    let desc = TEST_DESC.replace("id: 0x101", "id: 0x100");
+    assert!(opencan_compose::compose_system_str(&desc, "").is_err());
+
+    let desc = TEST_DESC.replace("to: chassis", "to: body");
+    assert!(opencan_compose::compose_system_str(&desc, "").is_err());
+}
+
+#[test]
+fn buses_gateway_codegen() -> Result<()> {
+    let system = opencan_compose::compose_system_str(TEST_DESC, "")?;
+
+    let dbw = system.bus_by_name("dbw").unwrap();
+    let decoder = CodegenDecoder::new(dbw, "DBW")?;
+    let v = decoder.decode_message("ABS_Speed", &[0x10, 0x27])?;
+    assert_eq!(v[0].2, SignalValue::Float(100.0));
+
+    CodegenDecoder::new(dbw, "GW")?;
+    CodegenDecoder::new(system.bus_by_name("chassis").unwrap(), "GW")?;
+
+    Ok(())
+}
+
+/// Problems on one bus don't stop another from being composed on its own.
+#[test]
+fn buses_validate_chosen_bus() -> Result<()> {
+    let desc = TEST_DESC.replace(
+        "\ngateways:",
+        "- body:\n    nodes:\n    - int: {}\n\ngateways:",
+    );
+
+    let system = opencan_compose::compose_system_str(&desc, "")?;
+    assert!(system
+        .validate()
+        .iter()
+        .any(|f| f.severity() == Severity::Error));
+    assert_eq!(system.validate_bus("dbw"), []);
+
+    let dir = tempdir()?;
+    let path = dir.path().join("system.yml");
+    fs::write(&path, desc)?;
+    let args = |bus: &str| opencan_compose::Args {
+        in_file: path.to_str().unwrap().into(),
+        dump_json: false,
+        dump_python: false,
+        deny_warnings: false,
+        bus: Some(bus.into()),
+    };
+
+    assert!(opencan_compose::compose(args("dbw")).is_ok());
+    assert!(opencan_compose::compose(args("body")).is_err());
+
+    Ok(())
+}
@@ -0,0 +1,98 @@
+use anyhow::Result;
+use testutil::decoders::*;
+
+const TEST_DESC: &str = "
+nodes:
+- TESTTX:
+    messages:
+    - TestMessage:
+        id: 0x10
+        signals:
+        - sigA:
+            width: 12
+            byte_order: big_endian
+        - sigB:
+            width: 12
+            byte_order: big_endian
+            twos_complement: true
+        - sigC:
+            width: 4
+- TESTRX:
+    rx:
+      - TESTTX_TestMessage
+";
+
+#[test]
+fn big_endian_decode() -> Result<()> {
+    let net = opencan_compose::compose_str(TEST_DESC, "")?;
+    let decoder = CodegenDecoder::new(&net, "TESTRX")?;
+
+    let v = decoder.decode_message("TESTTX_TestMessage", &[0x12, 0x3F, 0xFE, 0x05])?;
+
+    assert_eq!(v.len(), 3);
+
+    let (sig, raw, _dec) = &v[0];
+    assert_eq!(sig, "TESTTX_sigA");
+    assert_eq!(*raw, SignalValue::U16(0x123));
+
+    let (sig, raw, _dec) = &v[1];
+    assert_eq!(sig, "TESTTX_sigB");
+    assert_eq!(*raw, SignalValue::I16(-2));
+
+    let (sig, raw, _dec) = &v[2];
+    assert_eq!(sig, "TESTTX_sigC");
+    assert_eq!(*raw, SignalValue::U8(0x5));
+
+    Ok(())
+}
+
+#[test]
+fn big_endian_compare_cantools() -> Result<()> {
+    let net = opencan_compose::compose_str(TEST_DESC, "")?;
+    let cantools = CantoolsDecoder::new(&net)?;
+    let opencan = CodegenDecoder::new(&net, "TESTRX")?;
+
+    let data = &[0xDE, 0xAD, 0xBE, 0xEF];
+
+    assert_eq!(
+        cantools.decode_message("TESTTX_TestMessage", data)?,
+        opencan.decode_message("TESTTX_TestMessage", data)?
+    );
+
+    Ok(())
+}
+
+/// Signed signals narrower than their C type are sign-extended, including
+/// those with a power-of-two width.
+#[test]
+fn narrow_signed_decode() -> Result<()> {
+    let desc = "
+nodes:
+- TESTTX:
+    messages:
+    - TestMessage:
+        id: 0x10
+        signals:
+        - sigA:
+            width: 2
+            twos_complement: true
+        - sigB:
+            width: 4
+            twos_complement: true
+        - sigC:
+            width: 8
+            twos_complement: true
+- TESTRX:
+    rx:
+      - TESTTX_TestMessage
+";
+    let net = opencan_compose::compose_str(desc, "")?;
+    let decoder = CodegenDecoder::new(&net, "TESTRX")?;
+
+    let v = decoder.decode_message("TESTTX_TestMessage", &[0x33, 0xFE])?;
+    assert_eq!(v[0].1, SignalValue::I8(-1));
+    assert_eq!(v[1].1, SignalValue::I8(-4));
+    assert_eq!(v[2].1, SignalValue::I8(-8));
+
+    Ok(())
+}
@@ -0,0 +1,73 @@
+use anyhow::Result;
+use testutil::decoders::*;
+
+const TEST_DESC: &str = "
+nodes:
+- TESTTX:
+    messages:
+    - TestMessage:
+        id: 0x10
+        fd: true
+        brs: true
+        signals:
+        - sigA:
+            width: 8
+        - sigB:
+            start_bit: 64
+            width: 16
+        - sigC:
+            start_bit: 128
+            width: 32
+    - RawMessage:
+        id: 0x11
+        fd: true
+- TESTRX:
+    rx:
+      - TESTTX_TestMessage
+";
+
+#[test]
+fn fd_message_length() -> Result<()> {
+    let net = opencan_compose::compose_str(TEST_DESC, "")?;
+    let msg = net.message_by_name("TESTTX_TestMessage").unwrap();
+
+    // 20 bytes used, which is a valid FD length
+    assert!(msg.fd);
+    assert!(msg.brs);
+    assert_eq!(msg.length, 20);
+
+    Ok(())
+}
+
+#[test]
+fn fd_decode() -> Result<()> {
+    let net = opencan_compose::compose_str(TEST_DESC, "")?;
+    let decoder = CodegenDecoder::new(&net, "TESTRX")?;
+
+    let mut data = [0u8; 20];
+    data[0] = 0x12;
+    data[8..10].copy_from_slice(&0xBEEFu16.to_le_bytes());
+    data[16..20].copy_from_slice(&0xDEADBEEFu32.to_le_bytes());
+
+    let v = decoder.decode_message("TESTTX_TestMessage", &data)?;
+
+    assert_eq!(v.len(), 3);
+    assert_eq!(v[0].1, SignalValue::U8(0x12));
+    assert_eq!(v[1].1, SignalValue::U16(0xBEEF));
+    assert_eq!(v[2].1, SignalValue::U32(0xDEADBEEF));
+
+    // classic-length frames are rejected
+    assert!(decoder
+        .decode_message("TESTTX_TestMessage", &data[..8])
+        .is_err());
+
+    Ok(())
+}
+
+#[test]
+fn fd_tx_compiles() -> Result<()> {
+    let net = opencan_compose::compose_str(TEST_DESC, "")?;
+    CodegenDecoder::new(&net, "TESTTX")?;
+
+    Ok(())
+}
@@ -22,13 +22,20 @@ fn message_id_lookup() -> Result<()> {
                 signals:
                   - testSignal2:
                       width: 1
+            - Message3:
+                id: 0x10
+                extended: true
+                signals:
+                  - testSignal3:
+                      width: 1
         - TESTRX:
             rx:
               - TESTTX_Message1
               - TESTTX_Message2
+              - TESTTX_Message3
     "};
 
-    let net = opencan_compose::compose_str(&desc)?;
+    let net = opencan_compose::compose_str(&desc, "")?;
 
     // Do codegen
     let dec = CodegenDecoder::new(&net, "TESTRX")?;
@@ -36,11 +43,17 @@ fn message_id_lookup() -> Result<()> {
     // Look up symbols
     let msg1_decode: Symbol<DecodeFn> = unsafe { dec.lib.get(b"CANRX_doRx_TESTTX_Message1")? };
     let msg2_decode: Symbol<DecodeFn> = unsafe { dec.lib.get(b"CANRX_doRx_TESTTX_Message2")? };
-    let lookup: Symbol<fn(u32) -> Option<DecodeFn>> = unsafe { dec.lib.get(b"CANRX_id_to_rx_fn")? };
+    let msg3_decode: Symbol<DecodeFn> = unsafe { dec.lib.get(b"CANRX_doRx_TESTTX_Message3")? };
+    let lookup: Symbol<fn(u32, bool) -> Option<DecodeFn>> =
+        unsafe { dec.lib.get(b"CANRX_id_to_rx_fn")? };
 
-    assert_eq!(lookup(0x10), Some(*msg1_decode));
-    assert_eq!(lookup(0x11), Some(*msg2_decode));
-    assert_eq!(lookup(0x99), None);
+    // compare as addresses; both sides come from the same loaded library
+    let lookup_addr = |id, extended| lookup(id, extended).map(|f| f as usize);
+    assert_eq!(lookup_addr(0x10, false), Some(*msg1_decode as usize));
+    assert_eq!(lookup_addr(0x11, false), Some(*msg2_decode as usize));
+    assert_eq!(lookup_addr(0x10, true), Some(*msg3_decode as usize));
+    assert_eq!(lookup_addr(0x11, true), None);
+    assert_eq!(lookup_addr(0x99, false), None);
     Ok(())
 }
 
@@ -48,7 +61,7 @@ fn message_id_lookup() -> Result<()> {
 fn basic_compare_decoders() -> Result<()> {
     let desc = include_str!("../../compose/gadgets/can.yml");
 
-    let net = opencan_compose::compose_str(desc)?;
+    let net = opencan_compose::compose_str(desc, "")?;
     let cantools = CantoolsDecoder::new(&net)?;
     let opencan = CodegenDecoder::new(&net, "TEST")?;
 
@@ -0,0 +1,103 @@
+use anyhow::Result;
+use opencan_compose::{ComposeError, Location};
+
+const TEST_DESC: &str = "
+nodes:
+- TEST:
+    messages:
+    - Status:
+        id: 0x10
+        from_template: Missing
+    - Voltage:
+        id: 0x11
+        signals:
+        - voltage:
+            width: 70
+    - Current:
+        id: 0x12
+        signals:
+        - current:
+            width: 8
+- TESTRX:
+    rx:
+    - TEST_Current
+    - TEST_Nothing
+";
+
+fn compose_errors(input: &str) -> ComposeError {
+    let err = opencan_compose::compose_str(input, "test.yml").unwrap_err();
+    err.downcast::<ComposeError>().unwrap()
+}
+
+fn line_col(loc: &Option<Location>) -> (usize, usize) {
+    let loc = loc.as_ref().unwrap();
+    assert_eq!(loc.file, "test.yml");
+    (loc.line, loc.column)
+}
+
+#[test]
+fn diagnostics_collected() -> Result<()> {
+    let err = compose_errors(TEST_DESC);
+
+    // both broken messages are reported; rx isn't reached
+    assert_eq!(err.diagnostics.len(), 2);
+
+    let status = &err.diagnostics[0];
+    assert_eq!(status.causes[0], "Could not build message `TEST_Status`");
+    assert_eq!(line_col(&status.location), (5, 7));
+
+    // errors in a signal point at the signal
+    let voltage = &err.diagnostics[1];
+    assert_eq!(line_col(&voltage.location), (11, 11));
+    assert_eq!(voltage.location.as_ref().unwrap().len, "voltage".len());
+
+    let rendered = err.to_string();
+    assert!(rendered.contains(" --> test.yml:5:7"), "{rendered}");
+    assert!(
+        rendered.contains("  |       ^^^^^^ No template named `Missing`"),
+        "{rendered}"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn diagnostics_rx() -> Result<()> {
+    let input = TEST_DESC
+        .replace("from_template: Missing", "cycletime: 10")
+        .replace("width: 70", "width: 16");
+    let err = compose_errors(&input);
+
+    assert_eq!(err.diagnostics.len(), 1);
+    assert_eq!(line_col(&err.diagnostics[0].location), (21, 7));
+
+    Ok(())
+}
+
+#[test]
+fn diagnostics_parse() -> Result<()> {
+    let err = compose_errors("nodes:\n- TEST:\n    messages:\n    - Status:\n        idd: 1\n");
+
+    assert_eq!(err.diagnostics.len(), 1);
+    assert_eq!(line_col(&err.diagnostics[0].location), (5, 9));
+
+    Ok(())
+}
+
+#[test]
+fn diagnostics_system() -> Result<()> {
+    let input = "
+buses:
+- main:
+    nodes:
+    - TEST:
+        rx:
+        - TEST_Nothing
+";
+    let err = opencan_compose::compose_system_str(input, "test.yml").unwrap_err();
+    let err = err.downcast::<ComposeError>().unwrap();
+
+    assert_eq!(line_col(&err.diagnostics[0].location), (7, 11));
+
+    Ok(())
+}
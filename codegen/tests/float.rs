@@ -0,0 +1,86 @@
+use anyhow::Result;
+use opencan_core::{RawValue, SignalValueType};
+use testutil::decoders::*;
+
+const TEST_DESC: &str = "
+nodes:
+- TESTTX:
+    messages:
+    - TestMessage:
+        id: 0x10
+        fd: true
+        signals:
+        - accel:
+            value_type: float
+        - heading:
+            value_type: float
+            byte_order: big_endian
+            start_bit: 39
+        - lat:
+            value_type: double
+            scale: 0.5
+- TESTRX:
+    rx:
+      - TESTTX_TestMessage
+";
+
+fn test_data() -> [u8; 16] {
+    let mut data = [0u8; 16];
+    data[0..4].copy_from_slice(&(-9.81f32).to_le_bytes());
+    data[4..8].copy_from_slice(&271.5f32.to_be_bytes());
+    data[8..16].copy_from_slice(&94.5f64.to_le_bytes());
+    data
+}
+
+#[test]
+fn float_signals_composed() -> Result<()> {
+    let net = opencan_compose::compose_str(TEST_DESC, "")?;
+    let msg = net.message_by_name("TESTTX_TestMessage").unwrap();
+
+    assert_eq!(msg["TESTTX_accel"].value_type, SignalValueType::Float);
+    assert_eq!(msg["TESTTX_accel"].width, 32);
+    assert_eq!(msg["TESTTX_lat"].value_type, SignalValueType::Double);
+    assert_eq!(msg["TESTTX_lat"].width, 64);
+    assert_eq!(msg.length, 16);
+
+    Ok(())
+}
+
+#[test]
+fn float_decode() -> Result<()> {
+    let net = opencan_compose::compose_str(TEST_DESC, "")?;
+    let decoder = CodegenDecoder::new(&net, "TESTRX")?;
+
+    let v = decoder.decode_message("TESTTX_TestMessage", &test_data())?;
+
+    // sorted by name
+    assert_eq!(v[0].0, "TESTTX_accel");
+    assert_eq!(v[0].1, SignalValue::Float(-9.81));
+    assert_eq!(v[0].2, SignalValue::Float(-9.81));
+    assert_eq!(v[1].1, SignalValue::Float(271.5));
+    assert_eq!(v[2].1, SignalValue::Double(94.5));
+    assert_eq!(v[2].2, SignalValue::Double(47.25));
+
+    Ok(())
+}
+
+#[test]
+fn float_core_codec_matches() -> Result<()> {
+    let net = opencan_compose::compose_str(TEST_DESC, "")?;
+    let msg = net.message_by_name("TESTTX_TestMessage").unwrap();
+
+    let v = msg.decode(&test_data())?;
+    assert_eq!(v[0].raw, RawValue::Float((-9.81f32).into()));
+    assert_eq!(v[1].raw, RawValue::Float(271.5));
+    assert_eq!(v[2].physical, 47.25);
+
+    Ok(())
+}
+
+#[test]
+fn float_tx_compiles() -> Result<()> {
+    let net = opencan_compose::compose_str(TEST_DESC, "")?;
+    CodegenDecoder::new(&net, "TESTTX")?;
+
+    Ok(())
+}
@@ -0,0 +1,234 @@
+use std::{fs, path::Path};
+
+use anyhow::Result;
+use tempfile::tempdir;
+use testutil::decoders::*;
+
+const COMMON: &str = "
+value_tables:
+- OnOff:
+  - OFF
+  - ON
+
+message_templates:
+- Status:
+    cycletime: 100
+    signals:
+    - power:
+        value_table: OnOff
+";
+
+const BATTERY: &str = "
+include:
+- ../common/defs.yml
+
+nodes:
+- BMS:
+    messages:
+    - Status:
+        id: 0x10
+        from_template: Status
+    - Voltage:
+        id: 0x11
+        signals:
+        - voltage:
+            width: 16
+- HEATER:
+    rx:
+    - BMS_Voltage
+";
+
+const CHARGER: &str = "
+include:
+- ../common/defs.yml
+
+nodes:
+- CHARGER:
+    messages:
+    - Status:
+        id: 0x30
+        from_template: Status
+";
+
+const TOP: &str = "
+include:
+- common/defs.yml
+- path: nodes/battery.yml
+  namespace: FRONT
+- nodes/charger.yml
+
+nodes:
+- VCU:
+    messages:
+    - Command:
+        id: 0x20
+        from_template: Status
+    rx:
+    - FRONT_BMS_Status
+    - CHARGER_Status
+";
+
+#[test]
+fn includes_namespaced() -> Result<()> {
+    let dir = tempdir()?;
+    fs::create_dir(dir.path().join("common"))?;
+    fs::create_dir(dir.path().join("nodes"))?;
+    fs::write(dir.path().join("common/defs.yml"), COMMON)?;
+    fs::write(dir.path().join("nodes/battery.yml"), BATTERY)?;
+    fs::write(dir.path().join("nodes/charger.yml"), CHARGER)?;
+
+    let top = dir.path().join("top.yml");
+    let net = opencan_compose::compose_str(TOP, top.to_str().unwrap())?;
+
+    // the namespace covers the file's nodes and everything it includes
+    assert!(net.node_by_name("FRONT_BMS").is_some());
+    assert!(net.node_by_name("BMS").is_none());
+    assert!(net.template_message_by_name("FRONT_Status").is_some());
+    assert!(net.value_table_by_name("FRONT_OnOff").is_some());
+
+    let status = net.message_by_name("FRONT_BMS_Status").unwrap();
+    assert_eq!(status.cycletime, Some(100));
+
+    // rx within the namespace follows the rename
+    let rx = net.rx_messages_by_node("FRONT_HEATER").unwrap();
+    assert_eq!(rx[0].name, "FRONT_BMS_Voltage");
+
+    // common definitions included twice without a namespace are merged once
+    assert!(net.template_message_by_name("Status").is_some());
+    assert!(net.message_by_name("CHARGER_Status").is_some());
+    assert!(net.message_by_name("VCU_Command").is_some());
+
+    let decoder = CodegenDecoder::new(&net, "VCU")?;
+    let v = decoder.decode_message("FRONT_BMS_Status", &[1])?;
+    assert_eq!(v[0].0, "FRONT_BMS_power");
+
+    Ok(())
+}
+
+/// Rx entries move with the node whose name prefixes them most closely.
+#[test]
+fn includes_namespaced_overlapping_nodes() -> Result<()> {
+    let dir = tempdir()?;
+    let top = dir.path().join("top.yml");
+
+    let cases = [
+        ("{node}_", "A_B", "A_B_Status"),
+        ("{node}_{node}_", "A_A", "A_A_A_A_Status"),
+    ];
+    for (naming, sender, msg) in cases {
+        let nodes = format!(
+            "
+naming: {{ prefix: \"{naming}\" }}
+nodes:
+- A:
+    messages:
+    - Status:
+        id: 0x10
+- A_A:
+    messages:
+    - Status:
+        id: 0x11
+- A_B:
+    messages:
+    - Status:
+        id: 0x12
+- C:
+    rx:
+    - {msg}
+"
+        );
+        fs::write(dir.path().join("nodes.yml"), nodes)?;
+
+        let desc = "include: [{ path: nodes.yml, namespace: FRONT }]";
+        let net = opencan_compose::compose_str(desc, top.to_str().unwrap())?;
+
+        let rx = net.rx_messages_by_node("FRONT_C").unwrap();
+        assert_eq!(rx.len(), 1);
+        assert_eq!(rx[0].tx_node(), Some(format!("FRONT_{sender}").as_str()));
+    }
+
+    Ok(())
+}
+
+#[test]
+fn include_cycle() -> Result<()> {
+    let dir = tempdir()?;
+    fs::write(dir.path().join("a.yml"), "include: [b.yml]")?;
+    fs::write(dir.path().join("b.yml"), "include: [a.yml]")?;
+
+    let top = dir.path().join("top.yml");
+    let err = opencan_compose::compose_str("include: [a.yml]", top.to_str().unwrap()).unwrap_err();
+
+    let chain = format!("{err:#}");
+    assert!(chain.contains("Include cycle"), "{chain}");
+    assert!(
+        chain.contains("a.yml` -> `") && chain.contains("b.yml` -> `"),
+        "{chain}"
+    );
+
+    Ok(())
+}
+
+const MESSY_DBC: &str = r#"VERSION ""
+
+NS_ :
+
+BS_:
+
+BU_: TX
+
+BO_ 32 Status: 1 TX
+ SG_ bogus ??? nonsense
+ SG_ level : 0|8@1+ (1,0) [0|0] "" Vector__XXX
+"#;
+
+#[test]
+fn include_lenient() -> Result<()> {
+    let dir = tempdir()?;
+    fs::write(dir.path().join("messy.dbc"), MESSY_DBC)?;
+    fs::write(
+        dir.path().join("bus.yml"),
+        "include: [{ path: messy.dbc, lenient: true }]",
+    )?;
+    let top = dir.path().join("top.yml");
+    let top = top.to_str().unwrap();
+
+    let err = opencan_compose::compose_str("include: [messy.dbc]", top).unwrap_err();
+    assert!(format!("{err:#}").contains("line 10"), "{err:#}");
+
+    // the bad line is skipped, directly and through another definitions file
+    for desc in [
+        "include: [{ path: messy.dbc, lenient: true }]",
+        "include: [bus.yml]",
+    ] {
+        let net = opencan_compose::compose_str(desc, top)?;
+        assert!(net
+            .message_by_name("Status")
+            .unwrap()
+            .get_sig("level")
+            .is_some());
+    }
+
+    Ok(())
+}
+
+/// Includes resolve from the including file, even when it's given by a path
+/// relative to the working directory.
+#[test]
+fn includes_relative_path() -> Result<()> {
+    let dir = tempfile::tempdir_in(".")?;
+    let specs = dir.path().join("specs");
+    fs::create_dir(&specs)?;
+    fs::write(specs.join("common.yml"), COMMON)?;
+    fs::write(specs.join("legacy.dbc"), MESSY_DBC)?;
+
+    let top = Path::new(dir.path().file_name().unwrap()).join("specs/top.yml");
+    assert!(top.is_relative());
+
+    let desc = "include: [common.yml, { path: legacy.dbc, lenient: true }]";
+    let net = opencan_compose::compose_str(desc, top.to_str().unwrap())?;
+    assert!(net.template_message_by_name("Status").is_some());
+    assert!(net.message_by_name("Status").is_some());
+
+    Ok(())
+}
@@ -0,0 +1,61 @@
+use anyhow::Result;
+use testutil::decoders::*;
+
+const TEST_DESC: &str = "
+nodes:
+- TESTTX:
+    messages:
+    - TestMessage:
+        id: 0x10
+        signals:
+        - mode:
+            width: 8
+        - voltage:
+            width: 16
+            mux: { signal: mode, values: [0] }
+        - current:
+            width: 16
+            mux: { signal: mode, values: [1, 2] }
+        - temp:
+            width: 8
+            mux: { signal: mode, values: [1] }
+- TESTRX:
+    rx:
+      - TESTTX_TestMessage
+";
+
+fn raw_value<'v>(v: &'v [(String, SignalValue, SignalValue)], name: &str) -> &'v SignalValue {
+    &v.iter().find(|(n, ..)| n == name).unwrap().1
+}
+
+#[test]
+fn mux_decode_active_group() -> Result<()> {
+    let net = opencan_compose::compose_str(TEST_DESC, "")?;
+    let decoder = CodegenDecoder::new(&net, "TESTRX")?;
+
+    let v = decoder.decode_message("TESTTX_TestMessage", &[0x00, 0x34, 0x12, 0x00])?;
+    assert_eq!(raw_value(&v, "TESTTX_mode"), &SignalValue::U8(0));
+    assert_eq!(raw_value(&v, "TESTTX_voltage"), &SignalValue::U16(0x1234));
+    assert_eq!(raw_value(&v, "TESTTX_current"), &SignalValue::U16(0));
+
+    // signals outside the received group keep their last values
+    let v = decoder.decode_message("TESTTX_TestMessage", &[0x01, 0xCD, 0xAB, 0x05])?;
+    assert_eq!(raw_value(&v, "TESTTX_mode"), &SignalValue::U8(1));
+    assert_eq!(raw_value(&v, "TESTTX_voltage"), &SignalValue::U16(0x1234));
+    assert_eq!(raw_value(&v, "TESTTX_current"), &SignalValue::U16(0xABCD));
+    assert_eq!(raw_value(&v, "TESTTX_temp"), &SignalValue::U8(5));
+
+    let v = decoder.decode_message("TESTTX_TestMessage", &[0x02, 0x01, 0x00, 0x07])?;
+    assert_eq!(raw_value(&v, "TESTTX_current"), &SignalValue::U16(1));
+    assert_eq!(raw_value(&v, "TESTTX_temp"), &SignalValue::U8(5));
+
+    Ok(())
+}
+
+#[test]
+fn mux_tx_compiles() -> Result<()> {
+    let net = opencan_compose::compose_str(TEST_DESC, "")?;
+    CodegenDecoder::new(&net, "TESTTX")?;
+
+    Ok(())
+}
@@ -0,0 +1,178 @@
+use std::fs;
+
+use anyhow::Result;
+use opencan_codegen::{Args, Codegen};
+use opencan_compose::ComposeError;
+use opencan_core::{translation::DbcExporter, CANNaming, CANNetwork, TranslationFromOpencan};
+use tempfile::tempdir;
+use testutil::decoders::*;
+
+const TEST_DESC: &str = "
+message_templates:
+- Heartbeat:
+    signals:
+    - counter:
+        width: 4
+
+nodes:
+- BATT:
+    messages:
+    - Heartbeat:
+        id: 0x10
+        from_template: Heartbeat
+        signals:
+        - state:
+            width: 1
+    - Voltage:
+        id: 0x11
+        signals:
+        - voltage:
+            width: 12
+- PDU:
+    rx: \"*\"
+";
+
+fn compose(naming: &str) -> Result<CANNetwork> {
+    opencan_compose::compose_str(&format!("naming: {naming}\n{TEST_DESC}"), "")
+}
+
+fn args(node: &str) -> Args {
+    Args {
+        node: node.into(),
+        tx_stubs: false,
+        rx_callback_stubs: false,
+    }
+}
+
+#[test]
+fn naming_separator() -> Result<()> {
+    let net = compose("{ separator: __ }")?;
+    assert_eq!(net.naming().format(), "{node}__");
+
+    let heartbeat = net.message_by_name("BATT__Heartbeat").unwrap();
+    assert_eq!(heartbeat.instance_prefix(), Some("BATT__"));
+    assert!(heartbeat.get_sig("BATT__counter").is_some());
+    assert!(heartbeat.get_sig("BATT__state").is_some());
+    assert_eq!(
+        net.message_by_name("BATT__Voltage").unwrap()["BATT__voltage"].width,
+        12
+    );
+
+    // template instances are populated by their name under the node
+    let c = Codegen::new(args("BATT"), &net)?.network_to_c();
+    assert!(c.tx_h.contains("CANTX_populateTemplate_Heartbeat("));
+    assert!(c.tx_h.contains("CANTX_populate_BATT__Voltage("));
+
+    let decoder = CodegenDecoder::new(&net, "PDU")?;
+    let v = decoder.decode_message("BATT__Heartbeat", &[0x13])?;
+    assert_eq!(v[0].0, "BATT__counter");
+    assert_eq!(v[0].2, SignalValue::U8(3));
+    assert_eq!(v[1].0, "BATT__state");
+
+    CodegenDecoder::new(&net, "BATT")?;
+
+    Ok(())
+}
+
+#[test]
+fn naming_none() -> Result<()> {
+    let net = compose("none")?;
+    assert_eq!(net.naming(), &CANNaming::unprefixed());
+
+    let heartbeat = net.message_by_name("Heartbeat").unwrap();
+    assert_eq!(heartbeat.instance_prefix(), Some(""));
+    assert!(heartbeat.get_sig("state").is_some());
+    assert!(net.message_by_name("Voltage").is_some());
+
+    let c = Codegen::new(args("BATT"), &net)?.network_to_c();
+    assert!(c.tx_h.contains("CANTX_populateTemplate_Heartbeat("));
+    assert!(c.tx_h.contains("CANTX_populate_Voltage("));
+
+    let decoder = CodegenDecoder::new(&net, "PDU")?;
+    let v = decoder.decode_message("Heartbeat", &[0x13])?;
+    assert_eq!(v[0].0, "counter");
+    assert_eq!(v[1].0, "state");
+
+    CodegenDecoder::new(&net, "BATT")?;
+
+    Ok(())
+}
+
+#[test]
+fn naming_prefix() -> Result<()> {
+    let net = compose("{ prefix: \"ECU_{node}_\" }")?;
+
+    let heartbeat = net.message_by_name("ECU_BATT_Heartbeat").unwrap();
+    assert!(heartbeat.get_sig("ECU_BATT_counter").is_some());
+
+    let c = Codegen::new(args("BATT"), &net)?.network_to_c();
+    assert!(c.tx_h.contains("CANTX_populateTemplate_Heartbeat("));
+
+    Ok(())
+}
+
+#[test]
+fn naming_invalid() {
+    let err = compose("{ separator: \"-\" }").unwrap_err();
+    let err = err.downcast::<ComposeError>().unwrap();
+
+    let diagnostic = &err.diagnostics[0];
+    assert_eq!(diagnostic.causes[0], "Invalid naming for network");
+    assert!(diagnostic.causes[1].contains("invalid character `-`"));
+    assert_eq!(diagnostic.location.as_ref().unwrap().line, 1);
+}
+
+/// Definitions use the naming of an included DBC, unless they give their
+/// own.
+#[test]
+fn naming_from_dbc() -> Result<()> {
+    let mut dbc_net = CANNetwork::new();
+    dbc_net.set_naming(CANNaming::unprefixed());
+
+    let dir = tempdir()?;
+    fs::write(dir.path().join("bus.dbc"), DbcExporter::translate(&dbc_net))?;
+    let top = dir.path().join("top.yml");
+    let top = top.to_str().unwrap();
+
+    let desc = format!("include: [bus.dbc]\n{TEST_DESC}");
+    let net = opencan_compose::compose_str(&desc, top)?;
+    assert!(net.message_by_name("Heartbeat").is_some());
+
+    let desc = format!("include: [bus.dbc]\nnaming: {{ separator: _ }}\n{TEST_DESC}");
+    let net = opencan_compose::compose_str(&desc, top)?;
+    assert!(net.message_by_name("BATT_Heartbeat").is_some());
+
+    Ok(())
+}
+
+/// Names in a namespaced include move with their node under its naming.
+#[test]
+fn naming_namespaced() -> Result<()> {
+    let battery = "
+naming: { separator: __ }
+nodes:
+- BMS:
+    messages:
+    - Voltage:
+        id: 0x10
+- HEATER:
+    rx: [BMS__Voltage]
+";
+
+    let dir = tempdir()?;
+    fs::write(dir.path().join("battery.yml"), battery)?;
+    let top = dir.path().join("top.yml");
+
+    let desc = "include: [{ path: battery.yml, namespace: FRONT }]";
+    let net = opencan_compose::compose_str(desc, top.to_str().unwrap())?;
+
+    let rx = net.rx_messages_by_node("FRONT_HEATER").unwrap();
+    assert_eq!(rx[0].name, "FRONT_BMS__Voltage");
+
+    // a different naming in the including file conflicts
+    let desc = format!("naming: none\n{desc}");
+    let err = opencan_compose::compose_str(&desc, top.to_str().unwrap()).unwrap_err();
+    assert!(format!("{err:#}").contains("Conflicting naming prefixes"));
+
+    Ok(())
+}
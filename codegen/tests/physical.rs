@@ -0,0 +1,54 @@
+use anyhow::Result;
+use opencan_codegen::{Args, Codegen};
+
+const TEST_DESC: &str = "
+nodes:
+- TESTTX:
+    messages:
+    - TestMessage:
+        id: 0x10
+        signals:
+        - voltage:
+            width: 8
+            scale: 0.1
+            unit: V
+            min: 0
+            max: 25.5
+        - current:
+            width: 8
+            twos_complement: true
+            unit: A
+- TESTRX:
+    rx:
+      - TESTTX_TestMessage
+";
+
+#[test]
+fn units_and_ranges_kept() -> Result<()> {
+    let net = opencan_compose::compose_str(TEST_DESC, "")?;
+    let msg = net.message_by_name("TESTTX_TestMessage").unwrap();
+
+    assert_eq!(msg["TESTTX_voltage"].unit.as_deref(), Some("V"));
+    assert_eq!(msg["TESTTX_voltage"].min, Some(0.));
+    assert_eq!(msg["TESTTX_voltage"].max, Some(25.5));
+    assert_eq!(msg["TESTTX_current"].unit.as_deref(), Some("A"));
+    assert_eq!(msg["TESTTX_current"].max, None);
+
+    Ok(())
+}
+
+#[test]
+fn units_and_ranges_documented() -> Result<()> {
+    let net = opencan_compose::compose_str(TEST_DESC, "")?;
+    let args = Args {
+        node: "TESTRX".into(),
+        tx_stubs: false,
+        rx_callback_stubs: false,
+    };
+    let rx_h = Codegen::new(args, &net)?.network_to_c().rx_h;
+
+    assert!(rx_h.contains("* ----> Unit: V\n     * ----> Minimum: 0\n     * ----> Maximum: 25.5\n"));
+    assert!(rx_h.contains("* ----> Unit: A\n     */"));
+
+    Ok(())
+}
@@ -0,0 +1,111 @@
+use anyhow::Result;
+use opencan_compose::ComposeError;
+use opencan_core::CANNetwork;
+
+const TEST_DESC: &str = "
+message_templates:
+- NodeStatus:
+    signals:
+    - ok:
+        width: 1
+
+nodes:
+- STEER:
+    messages:
+    - Status:
+        id: 0x10
+        from_template: NodeStatus
+    - Angle:
+        id: 0x11
+- ENC:
+    messages:
+    - LeftData:
+        id: 0x20
+    - RightData:
+        id: 0x21
+    - Debug:
+        id: 0x22
+- DBW:
+    messages:
+    - Status:
+        id: 0x30
+        from_template: NodeStatus
+    rx:
+    - from: STEER
+    - ENC_*
+    - template: NodeStatus
+    - exclude:
+      - ENC_Debug
+      - regex: ^STEER_A
+- LOGGER:
+    messages:
+    - Log:
+        id: 0x40
+    rx: \"*\"
+";
+
+fn rx(net: &CANNetwork, node: &str) -> Vec<String> {
+    let mut names: Vec<_> = net
+        .rx_messages_by_node(node)
+        .unwrap()
+        .into_iter()
+        .map(|m| m.name.clone())
+        .collect();
+    names.sort();
+    names
+}
+
+#[test]
+fn rx_selection() -> Result<()> {
+    let net = opencan_compose::compose_str(TEST_DESC, "")?;
+
+    // DBW's own status is an instance of the template, but isn't received
+    assert_eq!(
+        rx(&net, "DBW"),
+        ["ENC_LeftData", "ENC_RightData", "STEER_Status"]
+    );
+
+    // everything but the node's own messages
+    assert_eq!(
+        rx(&net, "LOGGER"),
+        [
+            "DBW_Status",
+            "ENC_Debug",
+            "ENC_LeftData",
+            "ENC_RightData",
+            "STEER_Angle",
+            "STEER_Status"
+        ]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn rx_selection_errors() -> Result<()> {
+    let input = TEST_DESC
+        .replace("- ENC_*", "- BRAKE_*")
+        .replace("- from: STEER", "- from: DBW");
+
+    let err = opencan_compose::compose_str(&input, "test.yml").unwrap_err();
+    let err = err.downcast::<ComposeError>().unwrap();
+
+    // each entry that selects nothing is reported where it's written
+    assert_eq!(err.diagnostics.len(), 2);
+
+    let causes = &err.diagnostics[0].causes;
+    assert!(
+        causes[1].contains("`from: DBW` selects no messages"),
+        "{causes:?}"
+    );
+    assert_eq!(err.diagnostics[0].location.as_ref().unwrap().line, 30);
+
+    let causes = &err.diagnostics[1].causes;
+    assert!(
+        causes[1].contains("`BRAKE_*` selects no messages"),
+        "{causes:?}"
+    );
+    assert_eq!(err.diagnostics[1].location.as_ref().unwrap().line, 31);
+
+    Ok(())
+}
@@ -0,0 +1,115 @@
+use anyhow::Result;
+use libloading::Symbol;
+use opencan_codegen::{Args, Codegen};
+use testutil::{decoders::*, util::*};
+
+const TEST_DESC: &str = "
+nodes:
+- TESTTX:
+    messages:
+    - Status:
+        id: 0x10
+        signals:
+        - level:
+            width: 8
+            scale: 0.5
+            initial_value: 200
+            sna_value: 255
+        - trim:
+            width: 4
+            twos_complement: true
+            initial_value: 14
+            error_value: 8
+        - state:
+            enumerated_values:
+            - OFF
+            - ON
+            - FAULT
+            - UNKNOWN
+            initial_value: 1
+            sna_value: 3
+            error_value: 2
+- TESTRX:
+    rx:
+      - TESTTX_Status
+";
+
+/// Stand-in for the user's TX callback that keeps the last frame sent.
+const TX_CAPTURE: &str = "
+#include <stdbool.h>
+#include <stdint.h>
+#include <string.h>
+
+uint8_t last_tx[8];
+
+void CAN_callback_enqueue_tx_message(const uint8_t * const data, const uint8_t len, const uint32_t id, const bool extended, const bool fd, const bool brs) {
+    (void)id;
+    (void)extended;
+    (void)fd;
+    (void)brs;
+    memcpy(last_tx, data, len);
+}
+
+uint64_t CAN_callback_get_system_time(void) {
+    return 0;
+}
+";
+
+#[test]
+fn signal_values_rx() -> Result<()> {
+    let net = opencan_compose::compose_str(TEST_DESC, "")?;
+    let decoder = CodegenDecoder::new(&net, "TESTRX")?;
+
+    let get = |name: &str| -> Result<bool> {
+        let f: Symbol<fn() -> bool> = unsafe { decoder.lib.get(name.as_bytes())? };
+        Ok(f())
+    };
+
+    // nothing received yet: signals hold their initial values
+    let level: Symbol<fn() -> f32> = unsafe { decoder.lib.get(b"CANRX_get_TESTTX_level")? };
+    assert_eq!(level(), 100.0);
+    let trim: Symbol<fn() -> i8> = unsafe { decoder.lib.get(b"CANRX_getRaw_TESTTX_trim")? };
+    assert_eq!(trim(), -2);
+    assert!(!get("CANRX_isSNA_TESTTX_level")?);
+
+    let v = decoder.decode_message("TESTTX_Status", &[0xFF, 0b11_1000])?;
+    assert_eq!(v[0].0, "TESTTX_level");
+    assert_eq!(v[0].1, SignalValue::U8(255));
+    assert_eq!(v[2].2, SignalValue::I8(-8));
+    assert!(get("CANRX_isSNA_TESTTX_level")?);
+    assert!(get("CANRX_isError_TESTTX_trim")?);
+    assert!(get("CANRX_isSNA_TESTTX_state")?);
+    assert!(!get("CANRX_isError_TESTTX_state")?);
+
+    decoder.decode_message("TESTTX_Status", &[0x10, 0b10_0001])?;
+    assert!(!get("CANRX_isSNA_TESTTX_level")?);
+    assert!(!get("CANRX_isError_TESTTX_trim")?);
+    assert!(get("CANRX_isError_TESTTX_state")?);
+
+    Ok(())
+}
+
+#[test]
+fn signal_values_tx() -> Result<()> {
+    let net = opencan_compose::compose_str(TEST_DESC, "")?;
+    let args = Args {
+        node: "TESTTX".into(),
+        tx_stubs: true,
+        rx_callback_stubs: true,
+    };
+    let c = Codegen::new(args, &net)?.network_to_c();
+
+    // no checks for a node that only transmits the signals
+    assert!(!c.rx_h.contains("CANRX_isSNA_TESTTX_level"));
+
+    let lib = c_strings_to_so([c.as_list(), vec![("tx_capture.c", TX_CAPTURE)]].concat())?;
+
+    // the populate stub sets nothing, so the initial values are sent
+    let tx: Symbol<fn() -> bool> = unsafe { lib.get(b"CANTX_doTx_TESTTX_Status")? };
+    assert!(tx());
+
+    let last_tx: Symbol<*const [u8; 2]> = unsafe { lib.get(b"last_tx")? };
+    assert_eq!(unsafe { **last_tx }, [200, 0b01_1110]);
+
+    Ok(())
+}
@@ -0,0 +1,132 @@
+use anyhow::Result;
+use opencan_codegen::{Args, Codegen};
+use opencan_core::CANSignal;
+use testutil::decoders::*;
+
+const TEST_DESC: &str = "
+message_templates:
+- Heartbeat:
+    cycletime: 100
+    signals:
+    - counter:
+        width: 4
+- Power:
+    extends: Heartbeat
+    signals:
+    - voltage:
+        width: 8
+        scale: 0.1
+
+nodes:
+- BATT:
+    messages:
+    - Power:
+        id: 0x10
+        from_template: Power
+        signals:
+        - state:
+            enumerated_values:
+            - OFF
+            - ON
+        overrides:
+        - voltage:
+            scale: 0.5
+- PDU:
+    messages:
+    - Power:
+        id: 0x11
+        from_template: Power
+- TESTRX:
+    rx:
+      - BATT_Power
+      - PDU_Power
+";
+
+fn args(node: &str) -> Args {
+    Args {
+        node: node.into(),
+        tx_stubs: false,
+        rx_callback_stubs: false,
+    }
+}
+
+#[test]
+fn templates_composed() -> Result<()> {
+    let net = opencan_compose::compose_str(TEST_DESC, "")?;
+
+    let template = net.template_message_by_name("Power").unwrap();
+    assert_eq!(template.cycletime, Some(100));
+    assert_eq!(template.signals[0].sig.name, "counter");
+    assert_eq!(template.signals[1].start(), 4);
+
+    let batt = net.message_by_name("BATT_Power").unwrap();
+    assert_eq!(batt.signals.len(), 3);
+    assert_eq!(batt.instance_signals()[0].sig.name, "BATT_state");
+    assert_eq!(batt.instance_signals()[0].start(), 12);
+    assert_eq!(batt["BATT_voltage"].scale, Some(0.5));
+
+    let pdu = net.message_by_name("PDU_Power").unwrap();
+    assert!(pdu.instance_signals().is_empty());
+    assert_eq!(pdu["PDU_voltage"].scale, Some(0.1));
+
+    Ok(())
+}
+
+#[test]
+fn templates_instance_structs() -> Result<()> {
+    let net = opencan_compose::compose_str(TEST_DESC, "")?;
+    let c = Codegen::new(args("TESTRX"), &net)?.network_to_c();
+
+    // the instance with extra signals wraps the template's struct
+    assert!(c.rx_h.contains("struct CAN_Message_BATT_Power {"));
+    assert!(c.rx_h.contains("struct CAN_TMessage_Power base;"));
+    assert!(c.rx_h.contains("enum CAN_BATT_state {"));
+    assert!(c.rx_h.contains("CAN_BATT_STATE_ON = 1,"));
+
+    // while the plain instance uses the template's struct directly
+    assert!(!c.rx_h.contains("struct CAN_Message_PDU_Power {"));
+
+    Ok(())
+}
+
+/// Instance signals may be added or renamed without the instance's prefix.
+#[test]
+fn templates_unprefixed_instance_signals() -> Result<()> {
+    let mut net = opencan_compose::compose_str(TEST_DESC, "")?;
+
+    let extra = CANSignal::builder().name("extra").width(1).build()?;
+    let pdu = net.remove_msg("PDU_Power")?;
+    net.insert_msg(pdu.with_instance_signals([(None, extra)])?)?;
+    net.set_message_rx_by_node("PDU_Power", "TESTRX")?;
+    net.edit_signal("BATT_Power", "BATT_state", |s| s.name = "state".into())?;
+
+    let c = Codegen::new(args("TESTRX"), &net)?.network_to_c();
+    assert!(c.rx_h.contains("struct CAN_Message_PDU_Power {"));
+    assert!(c.rx_h.contains("bool CANRX_get_extra(void);"));
+    assert!(c.rx_h.contains("enum CAN_state CANRX_get_state(void);"));
+
+    Ok(())
+}
+
+#[test]
+fn templates_decode() -> Result<()> {
+    let net = opencan_compose::compose_str(TEST_DESC, "")?;
+    let decoder = CodegenDecoder::new(&net, "TESTRX")?;
+
+    let v = decoder.decode_message("BATT_Power", &[0x13, 0x10])?;
+    assert_eq!(v[0].0, "BATT_counter");
+    assert_eq!(v[0].2, SignalValue::U8(3));
+    assert_eq!(v[1].0, "BATT_state");
+    assert_eq!(v[1].2, SignalValue::I32(1));
+    assert_eq!(v[2].0, "BATT_voltage");
+    assert_eq!(v[2].2, SignalValue::Float(0.5));
+
+    let v = decoder.decode_message("PDU_Power", &[0x13, 0x00])?;
+    assert_eq!(v[1].0, "PDU_voltage");
+    assert_eq!(v[1].2, SignalValue::Float(0.1));
+
+    CodegenDecoder::new(&net, "BATT")?;
+    CodegenDecoder::new(&net, "PDU")?;
+
+    Ok(())
+}
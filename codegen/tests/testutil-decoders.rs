@@ -18,7 +18,7 @@ nodes:
 
 #[test]
 fn test_decode_with_trait() -> Result<()> {
-    let net = opencan_compose::compose_str(TEST_DESC)?;
+    let net = opencan_compose::compose_str(TEST_DESC, "")?;
     let decoder = CodegenDecoder::new(&net, "TESTRX")?;
 
     let v = decoder.decode_message("TESTTX_TestMessage", &[0xFA])?;
@@ -34,7 +34,7 @@ fn test_decode_with_trait() -> Result<()> {
 
 #[test]
 fn test_decode_with_trait_cantools() -> Result<()> {
-    let net = opencan_compose::compose_str(TEST_DESC)?;
+    let net = opencan_compose::compose_str(TEST_DESC, "")?;
     let decoder = CantoolsDecoder::new(&net)?;
 
     let v = decoder.decode_message("TESTTX_TestMessage", &[0xFA])?;
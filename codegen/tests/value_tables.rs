@@ -0,0 +1,119 @@
+use anyhow::Result;
+use opencan_codegen::{Args, Codegen};
+use testutil::decoders::*;
+
+const TEST_DESC: &str = "
+value_tables:
+- OnOff:
+  - OFF
+  - ON
+- Gear:
+  - PARK: 1
+  - DRIVE: 4
+
+message_templates:
+- Status:
+    signals:
+    - power:
+        value_table: OnOff
+
+nodes:
+- TESTTX:
+    messages:
+    - Lights:
+        id: 0x10
+        signals:
+        - headlights:
+            value_table: OnOff
+        - fog:
+            value_table: OnOff
+        - gear:
+            value_table: Gear
+    - Status:
+        id: 0x11
+        from_template: Status
+- TESTRX:
+    rx:
+      - TESTTX_Lights
+      - TESTTX_Status
+";
+
+fn args(node: &str) -> Args {
+    Args {
+        node: node.into(),
+        tx_stubs: false,
+        rx_callback_stubs: false,
+    }
+}
+
+#[test]
+fn value_tables_composed() -> Result<()> {
+    let net = opencan_compose::compose_str(TEST_DESC, "")?;
+    let msg = net.message_by_name("TESTTX_Lights").unwrap();
+
+    assert_eq!(msg["TESTTX_fog"].value_table.as_deref(), Some("OnOff"));
+    assert_eq!(msg["TESTTX_fog"].width, 1);
+    assert_eq!(msg["TESTTX_gear"].width, 3);
+    assert_eq!(
+        msg["TESTTX_gear"].enumerated_values.get_by_left("DRIVE"),
+        Some(&4)
+    );
+
+    Ok(())
+}
+
+#[test]
+fn value_tables_shared_enum() -> Result<()> {
+    let net = opencan_compose::compose_str(TEST_DESC, "")?;
+    let c = Codegen::new(args("TESTRX"), &net)?.network_to_c();
+
+    assert_eq!(c.value_tables_h.matches("enum CAN_VT_OnOff {").count(), 1);
+    assert!(c
+        .value_tables_h
+        .contains("    CAN_VT_GEAR_PARK = 1,\n    CAN_VT_GEAR_DRIVE = 4,\n"));
+    assert!(c
+        .templates_h
+        .contains("#include \"opencan_value_tables.h\""));
+
+    // signals use the shared enum instead of their own
+    assert!(c
+        .rx_h
+        .contains("enum CAN_VT_OnOff CANRX_get_TESTTX_headlights(void);"));
+    assert!(c
+        .rx_h
+        .contains("enum CAN_VT_OnOff CANRX_get_TESTTX_fog(void);"));
+    assert!(c
+        .rx_h
+        .contains("enum CAN_VT_OnOff CANRX_get_TESTTX_power(void);"));
+    assert!(!c.rx_h.contains("enum CAN_TESTTX_fog"));
+
+    Ok(())
+}
+
+#[test]
+fn value_tables_decode() -> Result<()> {
+    let net = opencan_compose::compose_str(TEST_DESC, "")?;
+    let decoder = CodegenDecoder::new(&net, "TESTRX")?;
+
+    let v = decoder.decode_message("TESTTX_Lights", &[0b10001])?;
+    assert_eq!(v[0].0, "TESTTX_fog");
+    assert_eq!(v[0].2, SignalValue::I32(0));
+    assert_eq!(v[1].2, SignalValue::I32(4));
+    assert_eq!(v[2].2, SignalValue::I32(1));
+
+    CodegenDecoder::new(&net, "TESTTX")?;
+
+    Ok(())
+}
+
+#[test]
+fn value_tables_invalid_name() {
+    let desc = "
+value_tables:
+- On-Off:
+  - OFF
+  - ON
+";
+    let err = opencan_compose::compose_str(desc, "").unwrap_err();
+    assert!(format!("{err:#}").contains("invalid character `-`"));
+}
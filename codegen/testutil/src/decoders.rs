@@ -1,4 +1,7 @@
-use std::{collections::HashMap, ffi::c_float};
+use std::{
+    collections::HashMap,
+    ffi::{c_double, c_float},
+};
 
 use anyhow::{anyhow, Context, Result};
 use float_cmp::approx_eq;
@@ -23,6 +26,7 @@ pub enum SignalValue {
     U64(u64),
     I64(i64),
     Float(c_float),
+    Double(c_double),
 }
 
 impl PartialEq for SignalValue {
@@ -38,6 +42,7 @@ impl PartialEq for SignalValue {
             (Self::U64(l0), Self::U64(r0)) => l0 == r0,
             (Self::I64(l0), Self::I64(r0)) => l0 == r0,
             (Self::Float(l0), Self::Float(r0)) => approx_eq!(f32, *l0, *r0, ulps = 2),
+            (Self::Double(l0), Self::Double(r0)) => approx_eq!(f64, *l0, *r0, ulps = 2),
             _ => false,
         }
     }
@@ -117,6 +122,8 @@ impl Decoder for CodegenDecoder<'_> {
                 CodegenCSignalTy::I32 => codegen_get_raw!(I32, i32),
                 CodegenCSignalTy::U64 => codegen_get_raw!(U64, u64),
                 CodegenCSignalTy::I64 => codegen_get_raw!(I64, i64),
+                CodegenCSignalTy::Float => codegen_get_raw!(Float, c_float),
+                CodegenCSignalTy::Double => codegen_get_raw!(Double, c_double),
                 t => panic!("Unexpected signal type `{t}` for raw codegen decode"),
             };
 
@@ -141,6 +148,7 @@ impl Decoder for CodegenDecoder<'_> {
                 CodegenCSignalTy::U64 => codegen_get_dec!(U64, u64),
                 CodegenCSignalTy::I64 => codegen_get_dec!(I64, i64),
                 CodegenCSignalTy::Float => codegen_get_dec!(Float, c_float),
+                CodegenCSignalTy::Double => codegen_get_dec!(Double, c_double),
                 CodegenCSignalTy::Enum(_) => codegen_get_dec!(I32, i32), // todo enum better handling?
             };
 
@@ -222,6 +230,8 @@ impl Decoder for CantoolsDecoder<'_> {
                     CodegenCSignalTy::I32 => cantools_get_raw!(I32),
                     CodegenCSignalTy::U64 => cantools_get_raw!(U64),
                     CodegenCSignalTy::I64 => cantools_get_raw!(I64),
+                    CodegenCSignalTy::Float => cantools_get_raw!(Float),
+                    CodegenCSignalTy::Double => cantools_get_raw!(Double),
                     t => panic!("Unexpected signal type `{t}` for raw cantools decode"),
                 };
 
@@ -253,6 +263,7 @@ impl Decoder for CantoolsDecoder<'_> {
                     CodegenCSignalTy::U64 => cantools_get_dec!(U64),
                     CodegenCSignalTy::I64 => cantools_get_dec!(I64),
                     CodegenCSignalTy::Float => cantools_get_dec!(Float),
+                    CodegenCSignalTy::Double => cantools_get_dec!(Double),
                     CodegenCSignalTy::Enum(_) => cantools_get_dec!(I32), // todo enum better handling?
                 };
 
@@ -1,9 +1,13 @@
+#include <stdbool.h>
 #include <stdint.h>
 
-void CAN_callback_enqueue_tx_message(const uint8_t * const data, const uint8_t len, const uint32_t id) {
+void CAN_callback_enqueue_tx_message(const uint8_t * const data, const uint8_t len, const uint32_t id, const bool extended, const bool fd, const bool brs) {
     (void)data;
     (void)len;
     (void)id;
+    (void)extended;
+    (void)fd;
+    (void)brs;
 }
 
 uint64_t CAN_callback_get_system_time(void) {
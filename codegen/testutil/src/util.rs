@@ -61,7 +61,7 @@ pub fn c_strings_to_so<'a>(files: impl IntoIterator<Item = (&'a str, &'a str)>)
 
     for (name, content) in files {
         let path = dir.join(name);
-        std::fs::write(&dir.join(name), content)?;
+        std::fs::write(&path, content)?;
         sources.push(path);
     }
 
@@ -0,0 +1,295 @@
+//! Errors from composing, located in the definitions files they come from.
+
+use std::fmt::{self, Display, Formatter};
+
+/// Everything that went wrong composing a network or system.
+///
+/// Independent problems (e.g. two broken messages) are all reported, in the
+/// order they were found.
+#[derive(Debug, Clone)]
+pub struct ComposeError {
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+/// A single problem found while composing.
+#[derive(Debug, Clone)]
+pub struct Diagnostic {
+    /// What went wrong, from the outermost context (e.g. the message) to the
+    /// root cause (e.g. a signal that doesn't fit).
+    pub causes: Vec<String>,
+
+    /// Where the problem is, if it could be found.
+    pub location: Option<Location>,
+}
+
+/// A position in a definitions file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Location {
+    /// Path of the file, as given to compose or in an `include:`.
+    pub file: String,
+
+    /// 1-based line number.
+    pub line: usize,
+
+    /// 1-based column of the start of the offending key.
+    pub column: usize,
+
+    /// Length of the offending key (at least 1).
+    pub len: usize,
+
+    /// Text of the line, to show the problem in context.
+    pub source_line: String,
+}
+
+impl std::error::Error for ComposeError {}
+
+impl From<Diagnostic> for ComposeError {
+    fn from(diagnostic: Diagnostic) -> Self {
+        Self {
+            diagnostics: vec![diagnostic],
+        }
+    }
+}
+
+impl Display for ComposeError {
+    /// Renders each diagnostic as an annotated snippet of the definitions,
+    /// like:
+    ///
+    /// ```text
+    /// error: Could not build message `TEST_Status`
+    ///  --> test.yml:9:11
+    ///   |
+    /// 9 |         - Status:
+    ///   |           ^^^^^^ No template named `Foo` in network.
+    /// ```
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        for diagnostic in &self.diagnostics {
+            writeln!(f, "{diagnostic}")?;
+        }
+
+        write!(
+            f,
+            "Failed to compose network with {} error(s).",
+            self.diagnostics.len()
+        )
+    }
+}
+
+impl Display for Diagnostic {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let (headline, rest) = self
+            .causes
+            .split_first()
+            .map_or(("unknown error", &[][..]), |(h, r)| (h.as_str(), r));
+
+        writeln!(f, "error: {headline}")?;
+
+        let Some(loc) = &self.location else {
+            for cause in rest {
+                writeln!(f, "  = {cause}")?;
+            }
+            return Ok(());
+        };
+
+        // the root cause labels the snippet, anything between is a note
+        let (label, notes) = match rest.split_last() {
+            Some((label, notes)) => (label.as_str(), notes),
+            None => ("", rest),
+        };
+
+        let line = loc.line.to_string();
+        let gutter = " ".repeat(line.len());
+
+        writeln!(f, "{gutter}--> {}:{}:{}", loc.file, loc.line, loc.column)?;
+        writeln!(f, "{gutter} |")?;
+        writeln!(f, "{line} | {}", loc.source_line)?;
+        writeln!(
+            f,
+            "{gutter} | {}{} {label}",
+            " ".repeat(loc.column.saturating_sub(1)),
+            "^".repeat(loc.len)
+        )?;
+
+        for note in notes {
+            writeln!(f, "{gutter} = {note}")?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Diagnostic {
+    /// Make a diagnostic from an error, at `location`.
+    pub(crate) fn new(e: &anyhow::Error, location: Option<Location>) -> Self {
+        Self {
+            causes: e.chain().map(|c| c.to_string()).collect(),
+            location,
+        }
+    }
+
+    /// Make a diagnostic from a YAML syntax or format error in `text`.
+    pub(crate) fn from_yaml(e: &serde_yaml::Error, file: &str, text: &str) -> Self {
+        // errors at the end of the input can be on a line past the last one
+        let location = e.location().map(|l| Location {
+            file: display_file(file),
+            line: l.line(),
+            column: l.column(),
+            len: 1,
+            source_line: text.lines().nth(l.line() - 1).unwrap_or_default().into(),
+        });
+
+        Self {
+            causes: vec!["Failed to parse specifications.".into(), e.to_string()],
+            location,
+        }
+    }
+}
+
+/// Context for an error in a single signal (listed under `section` of its
+/// message or template), so the signal itself can be located.
+#[derive(Debug)]
+pub(crate) struct SignalContext {
+    pub section: &'static str,
+    pub name: String,
+    pub what: &'static str,
+}
+
+impl Display for SignalContext {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{} `{}`.", self.what, self.name)
+    }
+}
+
+/// A definitions file, as needed to locate things in it.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Source {
+    /// Path of the file (may be empty for definitions given as a string).
+    pub path: String,
+
+    /// Contents of the file.
+    pub text: String,
+
+    /// Keys leading to the part of the file the definitions are in, e.g. a
+    /// bus in a file describing several.
+    pub prefix: Vec<String>,
+}
+
+/// The file a definition merged in from an include came from, and its name
+/// there (before any namespace).
+#[derive(Debug, Clone)]
+pub(crate) struct Origin {
+    pub path: String,
+    pub name: String,
+}
+
+impl Source {
+    /// Find the definition reached through `keys` from the top of the file.
+    ///
+    /// Each key is looked for among the block-style mapping keys or sequence
+    /// items directly under the previous one. If a key can't be found (e.g.
+    /// it's in a flow-style `{ ... }` mapping), the last one that was found
+    /// is used instead.
+    pub(crate) fn locate(&self, keys: &[&str]) -> Option<Location> {
+        let lines: Vec<&str> = self.text.lines().collect();
+
+        let mut found = None;
+        let mut start = 0;
+        let mut parent_col = None;
+
+        for key in self
+            .prefix
+            .iter()
+            .map(String::as_str)
+            .chain(keys.iter().copied())
+        {
+            let Some((idx, col)) = find_key(&lines[start..], parent_col, key) else {
+                break;
+            };
+
+            found = Some((start + idx, col, key.len()));
+            start += idx + 1;
+            parent_col = Some(col);
+        }
+
+        let (idx, col, len) = found?;
+
+        Some(Location {
+            file: display_file(&self.path),
+            line: idx + 1,
+            column: col + 1,
+            len: len.max(1),
+            source_line: lines[idx].into(),
+        })
+    }
+}
+
+/// Find `key` among the entries of the block starting at `lines[0]`, whose
+/// parent key is at column `parent_col`. Returns the line index and column.
+fn find_key(lines: &[&str], parent_col: Option<usize>, key: &str) -> Option<(usize, usize)> {
+    let mut entry_col = None;
+
+    for (idx, line) in lines.iter().enumerate() {
+        let Some((col, content)) = entry(line) else {
+            continue;
+        };
+
+        // the block ends at the first line that isn't nested under the parent
+        if parent_col.is_some_and(|p| col <= p) {
+            return None;
+        }
+
+        // only look at the block's own entries, not ones nested in them
+        if *entry_col.get_or_insert(col) != col {
+            continue;
+        }
+
+        let content = content.trim_matches(|c| c == '"' || c == '\'');
+        let is_key = content == key
+            || content
+                .strip_prefix(key)
+                .map(|r| r.trim_start_matches(['"', '\'']))
+                .is_some_and(|r| r.starts_with(':'));
+
+        if is_key {
+            return Some((idx, col));
+        }
+    }
+
+    None
+}
+
+/// Column and content of a line's key or sequence item, skipping indentation
+/// and `- ` markers. `None` for blank and comment lines.
+fn entry(line: &str) -> Option<(usize, &str)> {
+    let mut col = 0;
+    let mut rest = line;
+
+    loop {
+        let trimmed = rest.trim_start_matches(' ');
+        col += rest.len() - trimmed.len();
+        rest = trimmed;
+
+        match rest.strip_prefix('-') {
+            Some(r) if r.is_empty() || r.starts_with(' ') => {
+                col += 1;
+                rest = r;
+            }
+            _ => break,
+        }
+    }
+
+    if rest.is_empty() || rest.starts_with('#') {
+        return None;
+    }
+
+    Some((col, rest))
+}
+
+/// How to show a file's path in diagnostics.
+fn display_file(path: &str) -> String {
+    if path.is_empty() {
+        "<input>".into()
+    } else {
+        path.into()
+    }
+}
@@ -0,0 +1,382 @@
+//! Resolution of `include:`d OpenCAN `.yml` files into a single [`YDesc`].
+//!
+//! Included files are read, have their own includes resolved, are namespaced
+//! if asked to, and are then merged together with the including file, so that
+//! building a network only ever sees one description. Includes of other
+//! formats (`.dbc`, ...) are imported while building the network, with their
+//! paths made absolute so they still resolve from the including file.
+
+use std::{
+    collections::{HashMap, HashSet},
+    path::{Path, PathBuf},
+};
+
+use anyhow::{anyhow, Context, Result};
+use opencan_core::CANNaming;
+
+use crate::{
+    error::{ComposeError, Diagnostic, Origin},
+    ymlfmt::*,
+};
+
+/// Whether an include is another OpenCAN `.yml` file, rather than a file to
+/// import.
+fn is_yaml(include: &YInclude) -> bool {
+    let path = include.path();
+    path.ends_with(".yml") || path.ends_with(".yaml")
+}
+
+/// Directory that includes in the file at `lookup_path` are relative to.
+pub(crate) fn include_dir(lookup_path: &str) -> &Path {
+    Path::new(lookup_path).parent().unwrap_or(Path::new(""))
+}
+
+impl YDesc {
+    /// Merge in the `.yml` files this description includes, and theirs in
+    /// turn.
+    pub fn resolve_includes(&mut self) -> Result<()> {
+        // the top-level file may not exist on disk, e.g. with `compose_str()`
+        let mut stack: Vec<PathBuf> = std::fs::canonicalize(&self.lookup_path)
+            .into_iter()
+            .collect();
+
+        // an invalid naming is reported when building the network
+        let naming = self.naming_or(&CANNaming::default()).unwrap_or_default();
+
+        self.merge_includes(None, &naming, &mut stack, &mut HashSet::new())
+    }
+
+    /// Resolve includes, given the namespace everything in this file will end
+    /// up in, the naming its messages are named with, the stack of files
+    /// currently being included (for cycle detection), and every (file,
+    /// namespace) merged so far.
+    fn merge_includes(
+        &mut self,
+        scope: Option<&str>,
+        naming: &CANNaming,
+        stack: &mut Vec<PathBuf>,
+        seen: &mut HashSet<(PathBuf, Option<String>)>,
+    ) -> Result<()> {
+        if !self.include.iter().any(is_yaml) {
+            return Ok(());
+        }
+
+        let dir = include_dir(&self.lookup_path).to_owned();
+        let mut merged = YDesc::default();
+
+        for include in std::mem::take(&mut self.include) {
+            if !is_yaml(&include) {
+                let path = std::fs::canonicalize(dir.join(include.path()))
+                    .context(format!("Failed to import `{}`", include.path()))?;
+                merged
+                    .include
+                    .push(include.with_path(path.display().to_string()));
+                continue;
+            }
+
+            let path = std::fs::canonicalize(dir.join(include.path()))
+                .context(format!("Failed to include `{}`", include.path()))?;
+
+            if stack.contains(&path) {
+                let chain: Vec<_> = stack[stack.iter().position(|p| p == &path).unwrap()..]
+                    .iter()
+                    .chain([&path])
+                    .map(|p| format!("`{}`", p.display()))
+                    .collect();
+
+                return Err(anyhow!("Include cycle: {}", chain.join(" -> ")));
+            }
+
+            let namespace = include.namespace();
+            let inner_scope = match (scope, namespace) {
+                (Some(outer), Some(ns)) => Some(format!("{outer}_{ns}")),
+                (outer, ns) => outer.or(ns).map(str::to_owned),
+            };
+
+            if !seen.insert((path.clone(), inner_scope.clone())) {
+                continue;
+            }
+
+            let failed = || format!("Failed to include `{}`", path.display());
+
+            let input = std::fs::read_to_string(&path).with_context(failed)?;
+            let lookup_path = path.display().to_string();
+
+            let mut desc: YDesc = serde_yaml::from_str(&input)
+                .map_err(|e| ComposeError::from(Diagnostic::from_yaml(&e, &lookup_path, &input)))
+                .with_context(failed)?;
+
+            desc.record_origins(&lookup_path);
+            desc.lookup_path = lookup_path;
+
+            let inner_naming = desc.naming_or(naming).with_context(failed)?;
+
+            stack.push(path.clone());
+            desc.merge_includes(inner_scope.as_deref(), &inner_naming, stack, seen)
+                .with_context(failed)?;
+            stack.pop();
+
+            if let Some(ns) = namespace {
+                let naming = desc.naming_or(naming).with_context(failed)?;
+                desc.apply_namespace(ns, &naming);
+            }
+
+            merged.merge(desc).with_context(failed)?;
+        }
+
+        // the including file's own definitions come after everything included
+        let lookup_path = std::mem::take(&mut self.lookup_path);
+        let source = std::mem::take(&mut self.source);
+        merged.merge(std::mem::take(self))?;
+        merged.lookup_path = lookup_path;
+        merged.source = source;
+
+        *self = merged;
+        Ok(())
+    }
+
+    /// The naming given here, or `inherited` if there isn't one.
+    fn naming_or(&self, inherited: &CANNaming) -> Result<CANNaming> {
+        match &self.naming {
+            Some(naming) => Ok(naming.to_naming()?),
+            None => Ok(inherited.clone()),
+        }
+    }
+
+    /// Note that the nodes, templates and value tables defined here come from
+    /// the file at `path`.
+    fn record_origins(&mut self, path: &str) {
+        let names = [
+            (
+                "nodes",
+                self.nodes.iter().map(|n| unmap(n).0).collect::<Vec<_>>(),
+            ),
+            (
+                "message_templates",
+                self.message_templates.iter().map(|t| unmap(t).0).collect(),
+            ),
+            (
+                "value_tables",
+                self.value_tables.iter().map(|t| unmap(t).0).collect(),
+            ),
+        ];
+
+        for (section, names) in names {
+            for name in names {
+                let origin = Origin {
+                    path: path.into(),
+                    name: name.clone(),
+                };
+                self.origins.insert((section.into(), name.clone()), origin);
+            }
+        }
+    }
+
+    /// Prefix the names of nodes, templates and value tables defined here with
+    /// `<namespace>_`, along with references to them. References to messages
+    /// are renamed after their node, according to `naming`.
+    fn apply_namespace(&mut self, namespace: &str, naming: &CANNaming) {
+        let prefixed = |name: &str| format!("{namespace}_{name}");
+
+        // everything defined here, including through includes, has an origin
+        self.origins = std::mem::take(&mut self.origins)
+            .into_iter()
+            .map(|((section, name), origin)| ((section, prefixed(&name)), origin))
+            .collect();
+
+        let nodes: HashSet<String> = self.nodes.iter().map(|n| unmap(n).0.clone()).collect();
+        let templates: HashSet<String> = self
+            .message_templates
+            .iter()
+            .map(|t| unmap(t).0.clone())
+            .collect();
+        let tables: HashSet<String> = self
+            .value_tables
+            .iter()
+            .map(|t| unmap(t).0.clone())
+            .collect();
+
+        let rename_signals = |signals: &mut Vec<HashMap<String, YSignal>>| {
+            for sdesc in signals.iter_mut().flat_map(|s| s.values_mut()) {
+                if let Some(table) = &mut sdesc.value_table {
+                    if tables.contains(table) {
+                        *table = prefixed(table);
+                    }
+                }
+            }
+        };
+
+        for tables in &mut self.value_tables {
+            *tables = tables.drain().map(|(n, t)| (prefixed(&n), t)).collect();
+        }
+
+        for tmap in &mut self.message_templates {
+            *tmap = tmap.drain().map(|(n, t)| (prefixed(&n), t)).collect();
+
+            for tdesc in tmap.values_mut() {
+                if let Some(extends) = &mut tdesc.extends {
+                    if templates.contains(extends) {
+                        *extends = prefixed(extends);
+                    }
+                }
+                rename_signals(&mut tdesc.signals);
+            }
+        }
+
+        for nmap in &mut self.nodes {
+            *nmap = nmap.drain().map(|(n, node)| (prefixed(&n), node)).collect();
+
+            for ndesc in nmap.values_mut() {
+                for mdesc in ndesc.messages.iter_mut().flat_map(|m| m.values_mut()) {
+                    if let Some(template) = &mut mdesc.from_template {
+                        if templates.contains(template) {
+                            *template = prefixed(template);
+                        }
+                    }
+                    if let Some(signals) = &mut mdesc.signals {
+                        rename_signals(signals);
+                    }
+                }
+
+                if let RxListOrDirective::List(rx) = &mut ndesc.rx {
+                    rename_rx(rx, &nodes, &templates, naming, &prefixed);
+                }
+            }
+        }
+    }
+
+    /// Add the definitions of `other` after this description's own.
+    ///
+    /// Nodes of the same name are combined. Templates and value tables of the
+    /// same name must be identical, and are kept once. Network attributes from
+    /// `other` replace those of the same name here, but bitrates and namings
+    /// must agree.
+    fn merge(&mut self, other: YDesc) -> Result<()> {
+        for nmap in other.nodes {
+            let (name, ndesc) = nmap.into_iter().next().expect("node maps have one entry");
+
+            match self.nodes.iter_mut().find_map(|n| n.get_mut(&name)) {
+                Some(existing) => existing.merge(ndesc),
+                None => self.nodes.push(HashMap::from([(name, ndesc)])),
+            }
+        }
+
+        merge_definitions(
+            &mut self.message_templates,
+            other.message_templates,
+            "template",
+        )?;
+        merge_definitions(&mut self.value_tables, other.value_tables, "value table")?;
+
+        match (self.bitrate, other.bitrate) {
+            (Some(a), Some(b)) if a != b => {
+                return Err(anyhow!("Conflicting bitrates {a} and {b}"));
+            }
+            (None, b) => self.bitrate = b,
+            _ => (),
+        }
+
+        if let (Some(a), Some(b)) = (&self.naming, &other.naming) {
+            let (a, b) = (a.to_naming()?, b.to_naming()?);
+            if a != b {
+                return Err(anyhow!(
+                    "Conflicting naming prefixes `{}` and `{}`",
+                    a.format(),
+                    b.format()
+                ));
+            }
+        }
+        if self.naming.is_none() {
+            self.naming = other.naming;
+        }
+
+        self.attributes.extend(other.attributes);
+        self.include.extend(other.include);
+
+        // where something is defined in several files, point at the first
+        for (key, origin) in other.origins {
+            self.origins.entry(key).or_insert(origin);
+        }
+
+        Ok(())
+    }
+}
+
+/// Rename references to nodes and templates in an rx list, for
+/// [`YDesc::apply_namespace()`].
+fn rename_rx(
+    rx: &mut [YRxEntry],
+    nodes: &HashSet<String>,
+    templates: &HashSet<String>,
+    naming: &CANNaming,
+    prefixed: &impl Fn(&str) -> String,
+) {
+    for entry in rx {
+        match entry {
+            // messages are named after their node, so they move with it;
+            // where node names overlap, the longest prefix is the sender
+            YRxEntry::Name(msg) => {
+                let renamed = nodes
+                    .iter()
+                    .filter_map(|n| {
+                        let prefix = naming.prefix(n);
+                        let name = msg.strip_prefix(&prefix)?;
+                        Some((prefix.len(), n, name))
+                    })
+                    .max()
+                    .map(|(_, n, name)| naming.name(&prefixed(n), name));
+
+                if let Some(renamed) = renamed {
+                    *msg = renamed;
+                }
+            }
+            YRxEntry::Select(YRxSelect::From(node)) if nodes.contains(node) => {
+                *node = prefixed(node);
+            }
+            YRxEntry::Select(YRxSelect::Template(template)) if templates.contains(template) => {
+                *template = prefixed(template);
+            }
+            YRxEntry::Select(YRxSelect::Exclude(excluded)) => {
+                rename_rx(excluded, nodes, templates, naming, prefixed);
+            }
+            YRxEntry::Select(_) => (),
+        }
+    }
+}
+
+impl YNode {
+    /// Add the messages, rx and attributes of `other` to this node.
+    fn merge(&mut self, other: YNode) {
+        if other.description.is_some() {
+            self.description = other.description;
+        }
+        self.messages.extend(other.messages);
+        self.attributes.extend(other.attributes);
+
+        match (&mut self.rx, other.rx) {
+            (RxListOrDirective::List(rx), RxListOrDirective::List(other)) => rx.extend(other),
+            (rx, other @ RxListOrDirective::Directive(_)) => *rx = other,
+            (RxListOrDirective::Directive(_), RxListOrDirective::List(_)) => (),
+        }
+    }
+}
+
+/// Add named definitions from `other` to `defs`, skipping identical
+/// redefinitions.
+fn merge_definitions<T: PartialEq>(
+    defs: &mut Vec<HashMap<String, T>>,
+    other: Vec<HashMap<String, T>>,
+    kind: &str,
+) -> Result<()> {
+    for map in other {
+        let (name, def) = unmap(&map);
+
+        match defs.iter().find_map(|d| d.get(name)) {
+            Some(existing) if existing == def => (),
+            Some(_) => return Err(anyhow!("Conflicting definitions of {kind} `{name}`")),
+            None => defs.push(map),
+        }
+    }
+
+    Ok(())
+}
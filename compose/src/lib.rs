@@ -2,13 +2,24 @@
 
 use std::fs;
 
-use anyhow::{Context, Result};
+use anyhow::{anyhow, Context, Result};
 use clap::Parser;
-use opencan_core::{translation::CantoolsTranslator, CANNetwork, TranslationFromOpencan};
+use opencan_core::{
+    translation::CantoolsTranslator, CANNetwork, CANSystem, Finding, Severity,
+    TranslationFromOpencan,
+};
 
 mod ymlfmt;
 use ymlfmt::*;
 
+mod error;
+use error::Source;
+pub use error::{ComposeError, Diagnostic, Location};
+
+mod include;
+
+mod schema;
+pub use schema::schema;
 mod translation;
 
 #[derive(Parser)]
@@ -24,16 +35,51 @@ pub struct Args {
     /// Dump composed network as Python to stdout
     #[clap(long, action)]
     pub dump_python: bool,
+
+    /// Fail on validation warnings as well as errors
+    #[clap(long, action)]
+    pub deny_warnings: bool,
+
+    /// Bus to compose, for definitions files describing several buses
+    #[clap(long)]
+    pub bus: Option<String>,
 }
 
 /// Compose YAML definitions into a `CANNetwork` given opencan_compose::Args.
+///
+/// Definitions describing several buses are composed as a whole, and then
+/// the bus given by `--bus` is picked out.
 pub fn compose(args: Args) -> Result<CANNetwork> {
     let input = fs::read_to_string(&args.in_file).context("Failed to read input file")?;
 
-    let net = compose_str(&input, &args.in_file).context(format!(
-        "Failed to ingest specifications file {}",
-        args.in_file
-    ))?;
+    let net = if describes_system(&input) {
+        let Some(bus) = &args.bus else {
+            return Err(anyhow!(
+                "{} describes several buses; choose one with --bus",
+                args.in_file
+            ));
+        };
+
+        let system = compose_system_str(&input, &args.in_file).context(format!(
+            "Failed to ingest specifications file {}",
+            args.in_file
+        ))?;
+
+        // other buses may have problems that don't matter to this one
+        validate(&system.validate_bus(bus), args.deny_warnings)?;
+
+        system
+            .into_bus(bus)
+            .context(format!("Bus `{bus}` does not exist in {}", args.in_file))?
+    } else {
+        let net = compose_str(&input, &args.in_file).context(format!(
+            "Failed to ingest specifications file {}",
+            args.in_file
+        ))?;
+
+        validate(&net.validate(), args.deny_warnings)?;
+        net
+    };
 
     if args.dump_json {
         println!("{}", serde_json::to_string_pretty(&net).unwrap());
@@ -46,26 +92,101 @@ pub fn compose(args: Args) -> Result<CANNetwork> {
     Ok(net)
 }
 
+/// Compose YAML definitions describing several buses into a `CANSystem`
+/// given opencan_compose::Args. `--bus` is ignored.
+pub fn compose_system(args: Args) -> Result<CANSystem> {
+    let input = fs::read_to_string(&args.in_file).context("Failed to read input file")?;
+
+    let system = compose_system_str(&input, &args.in_file).context(format!(
+        "Failed to ingest specifications file {}",
+        args.in_file
+    ))?;
+
+    validate(&system.validate(), args.deny_warnings)?;
+
+    if args.dump_json {
+        println!("{}", serde_json::to_string_pretty(&system).unwrap());
+    }
+
+    if args.dump_python {
+        for (name, net) in system.iter_buses() {
+            println!("# Bus `{name}`");
+            println!("{}", CantoolsTranslator::translate(net));
+        }
+    }
+
+    Ok(system)
+}
+
+/// Whether a definitions file describes several buses (with `buses:`) rather
+/// than a single network.
+pub fn describes_system(input: &str) -> bool {
+    serde_yaml::from_str::<serde_yaml::Value>(input).is_ok_and(|v| v.get("buses").is_some())
+}
+
+/// Print validation findings to stderr, failing if there are any errors (or
+/// any warnings, with `deny_warnings`).
+fn validate(findings: &[Finding], deny_warnings: bool) -> Result<()> {
+    for finding in findings {
+        eprintln!("{}: {finding}", finding.severity());
+    }
+
+    let fatal = findings
+        .iter()
+        .filter(|f| deny_warnings || f.severity() == Severity::Error)
+        .count();
+
+    if fatal > 0 {
+        return Err(anyhow!(
+            "Network failed validation with {fatal} finding(s)."
+        ));
+    }
+
+    Ok(())
+}
+
 /// Compose YAML definitions from a `&str` directly.
+///
+/// `path` is where `input` was read from, for resolving includes and
+/// locating errors; it may be empty. Errors in the definitions are returned
+/// as a [`ComposeError`].
 pub fn compose_str(input: &str, path: &str) -> Result<CANNetwork> {
-    let mut de: YDesc =
-        serde_yaml::from_str(input).context("Failed to parse specifications.".to_string())?;
+    let mut de: YDesc = serde_yaml::from_str(input)
+        .map_err(|e| ComposeError::from(Diagnostic::from_yaml(&e, path, input)))?;
 
     de.lookup_path = path.into();
+    de.source = Source {
+        path: path.into(),
+        text: input.into(),
+        prefix: vec![],
+    };
 
-    // We manually print out the error causes so we can use our own formatting rather than anyhow's.
-    let net = match de.into_network() {
-        Err(e) => {
-            eprintln!("Failed to compose network.\n");
-            eprintln!("What happened:");
-            for (i, cause) in e.chain().enumerate() {
-                eprintln!("`{} {}", "-".repeat(i), cause);
-            }
-            std::process::exit(-1);
-        }
+    Ok(de.into_network()?)
+}
 
-        Ok(net) => net,
+/// Compose YAML definitions of several buses from a `&str` directly.
+///
+/// Like [`compose_str()`], errors in the definitions are returned as a
+/// [`ComposeError`].
+pub fn compose_system_str(input: &str, path: &str) -> Result<CANSystem> {
+    let mut de: YSystem = serde_yaml::from_str(input)
+        .map_err(|e| ComposeError::from(Diagnostic::from_yaml(&e, path, input)))?;
+
+    de.source = Source {
+        path: path.into(),
+        text: input.into(),
+        prefix: vec![],
     };
 
-    Ok(net)
+    for bus in &mut de.buses {
+        for (name, bdesc) in bus.iter_mut() {
+            bdesc.lookup_path = path.into();
+            bdesc.source = Source {
+                prefix: vec!["buses".into(), name.clone()],
+                ..de.source.clone()
+            };
+        }
+    }
+
+    Ok(de.into_system()?)
 }
@@ -0,0 +1,195 @@
+//! JSON Schema for the YAML definitions format, generated from the
+//! `opencan_compose` format types ([`YDesc`], [`YSystem`], ...).
+
+use schemars::{
+    gen::SchemaGenerator,
+    schema::{ArrayValidation, InstanceType, ObjectValidation, RootSchema, Schema, SchemaObject},
+    JsonSchema,
+};
+
+use crate::ymlfmt::*;
+
+/// A definitions file, describing either a single network or several buses.
+#[allow(dead_code)]
+#[derive(JsonSchema)]
+#[schemars(untagged)]
+enum YFile {
+    Network(Box<YDesc>),
+    System(YSystem),
+}
+
+/// Generate the JSON Schema for `.yml` definitions files.
+///
+/// Editors can use it to complete and check definitions, e.g. with the YAML
+/// language server by starting a file with:
+///
+/// ```yaml
+/// # yaml-language-server: $schema=path/to/opencan.schema.json
+/// ```
+pub fn schema() -> RootSchema {
+    let mut schema = schemars::schema_for!(YFile);
+    schema.schema.metadata().title = Some("OpenCAN definitions".into());
+    schema
+}
+
+/// Schema for a map with a single `name: T` entry, as read by [`unmap()`].
+pub(crate) fn single_key_map<T: JsonSchema>(gen: &mut SchemaGenerator) -> Schema {
+    SchemaObject {
+        instance_type: Some(InstanceType::Object.into()),
+        object: Some(Box::new(ObjectValidation {
+            min_properties: Some(1),
+            max_properties: Some(1),
+            additional_properties: Some(Box::new(gen.subschema_for::<T>())),
+            ..Default::default()
+        })),
+        ..Default::default()
+    }
+    .into()
+}
+
+/// Schema for a list of [`single_key_map()`]s, like:
+///
+/// ```yaml
+/// signals:
+/// - voltage:
+///     width: 12
+/// - current:
+///     width: 12
+/// ```
+pub(crate) fn single_key_maps<T: JsonSchema>(gen: &mut SchemaGenerator) -> Schema {
+    SchemaObject {
+        instance_type: Some(InstanceType::Array.into()),
+        array: Some(Box::new(ArrayValidation {
+            items: Some(single_key_map::<T>(gen).into()),
+            ..Default::default()
+        })),
+        ..Default::default()
+    }
+    .into()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use jsonschema::JSONSchema;
+
+    use super::*;
+
+    /// Where the published schema lives, relative to this crate.
+    const SCHEMA_PATH: &str = "opencan.schema.json";
+
+    /// Errors validating the YAML `input` against the schema.
+    fn validate(input: &str) -> Vec<String> {
+        let schema = serde_json::to_value(schema()).unwrap();
+        let schema = JSONSchema::compile(&schema).unwrap();
+        let value: serde_json::Value = serde_yaml::from_str(input).unwrap();
+
+        // bound so the errors (which borrow `schema`) are dropped first
+        let errors = match schema.validate(&value) {
+            Ok(()) => vec![],
+            Err(errors) => errors
+                .map(|e| format!("{e} at {}", e.instance_path))
+                .collect(),
+        };
+        errors
+    }
+
+    #[test]
+    fn schema_published() {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join(SCHEMA_PATH);
+        let published = std::fs::read_to_string(&path).unwrap_or_default();
+        let generated = serde_json::to_string_pretty(&schema()).unwrap() + "\n";
+
+        assert!(
+            published == generated,
+            "{} is out of date; regenerate it with `opencan schema > compose/{SCHEMA_PATH}`",
+            path.display()
+        );
+    }
+
+    /// The schema and the format types agree on which gadgets are valid (some
+    /// are in an older format).
+    #[test]
+    fn schema_matches_gadgets() {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("gadgets");
+
+        for entry in std::fs::read_dir(dir).unwrap() {
+            let path = entry.unwrap().path();
+            let input = std::fs::read_to_string(&path).unwrap();
+
+            let parses = serde_yaml::from_str::<YDesc>(&input).is_ok()
+                || serde_yaml::from_str::<YSystem>(&input).is_ok();
+            let errors = validate(&input);
+
+            assert_eq!(parses, errors.is_empty(), "{}: {errors:#?}", path.display());
+        }
+    }
+
+    #[test]
+    fn schema_rejects() {
+        // a typo'd field
+        assert!(
+            !validate("nodes:\n- A:\n    messages:\n    - M: { id: 1, cycletim: 10 }\n").is_empty()
+        );
+
+        // two signals in one list entry
+        let input = "
+nodes:
+- A:
+    messages:
+    - M:
+        id: 1
+        signals:
+        - a: { width: 1 }
+          b: { width: 1 }
+";
+        assert!(!validate(input).is_empty());
+
+        // rx directive other than "*"
+        assert!(!validate("nodes:\n- A:\n    rx: all\n").is_empty());
+
+        // naming that's neither a separator nor a prefix
+        assert!(!validate("naming: { suffix: _ }\n").is_empty());
+    }
+
+    #[test]
+    fn schema_accepts() {
+        let input = r#"
+include:
+- common.yml
+- path: battery.yml
+  namespace: FRONT
+value_tables:
+- OnOff: [OFF, ON, { UNKNOWN: 3 }]
+naming: { separator: __ }
+nodes:
+- A:
+    rx: "*"
+    messages:
+    - M:
+        id: 0x10
+        signals:
+        - state: { value_table: OnOff, byte_order: big_endian }
+        - level: { width: 8, value_type: integer }
+- B:
+    rx:
+    - A_M
+    - A_*
+    - from: A
+    - exclude: [{ regex: ^A_D }]
+"#;
+        assert_eq!(validate(input), Vec::<String>::new());
+
+        let system = "
+buses:
+- main:
+    nodes:
+    - GW: {}
+gateways:
+- GW:
+  - { message: GW_Status, from: main, to: other }
+";
+        assert_eq!(validate(system), Vec::<String>::new());
+    }
+}
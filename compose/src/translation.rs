@@ -2,113 +2,389 @@
 //! [`opencan_core`] types.
 //!
 //! We build signals/messages/nodes and ultimately hand back a [`CANNetwork`].
-//! Errors originating inside `opencan_core` are bubbled up.
+//! Errors originating inside `opencan_core` are bubbled up, and collected into
+//! a [`ComposeError`] located at the definition they came from.
 
-use std::{collections::HashMap, path::Path};
+use std::collections::{HashMap, HashSet};
 
 use anyhow::{anyhow, Context, Result};
-use opencan_core::{translation::DbcImporter, *};
+use opencan_core::{
+    translation::{ArxmlImporter, DbcImporter, ImportMode, KcdImporter},
+    *,
+};
+use regex::Regex;
+
+use crate::{error::*, include::include_dir, ymlfmt::*};
+
+impl YSystem {
+    /// Make a `CANSystem` from a `YSystem` (top-level yml description of
+    /// several buses).
+    pub fn into_system(mut self) -> Result<CANSystem, ComposeError> {
+        let mut system = CANSystem::new();
+        let mut errors = Vec::new();
+
+        for bmap in &mut self.buses {
+            for bdesc in bmap.values_mut() {
+                if let Err(e) = bdesc.resolve_includes() {
+                    errors.extend(bdesc.include_error(e).diagnostics);
+                }
+            }
+        }
+        check(&mut errors)?;
 
-use crate::ymlfmt::*;
+        for bmap in &self.buses {
+            let (name, bdesc) = unmap(bmap);
+
+            let added = match bdesc.build_network() {
+                Ok(net) => system.insert_bus(name, net),
+                Err(e) => {
+                    errors.extend(e.diagnostics);
+                    continue;
+                }
+            };
+
+            if let Err(e) = added {
+                errors.push(self.diagnostic(&["buses", name], &e.into()));
+            }
+        }
+        check(&mut errors)?;
+
+        for gmap in &self.gateways {
+            let (gateway, routes) = unmap(gmap);
+
+            for route in routes {
+                let routed = route.can_id().map_err(anyhow::Error::from).and_then(|id| {
+                    system
+                        .insert_route(gateway, &route.message, &route.from, &route.to, id)
+                        .context(format!(
+                            "Could not route `{}` from bus `{}` to bus `{}` through `{gateway}`",
+                            route.message, route.from, route.to
+                        ))
+                });
+
+                if let Err(e) = routed {
+                    errors.push(self.diagnostic(&["gateways", gateway], &e));
+                }
+            }
+        }
+        check(&mut errors)?;
+
+        // rx lists can name routed messages, so they're filled in last
+        for bmap in &self.buses {
+            let (name, bdesc) = unmap(bmap);
+
+            if let Err(e) = bdesc.add_rx(system.bus_by_name_mut(name).unwrap()) {
+                errors.extend(e.diagnostics);
+            }
+        }
+        check(&mut errors)?;
+
+        Ok(system)
+    }
+
+    /// Make a `Diagnostic` for an error in the definition reached by `keys`.
+    fn diagnostic(&self, keys: &[&str], e: &anyhow::Error) -> Diagnostic {
+        Diagnostic::new(e, self.source.locate(keys))
+    }
+}
+
+impl YRoute {
+    /// Make a `CANId` from the `id` and `extended` fields, if an ID is given.
+    fn can_id(&self) -> Result<Option<CANId>, CANConstructionError> {
+        let Some(id) = self.id else {
+            return Ok(None);
+        };
+
+        match self.extended {
+            Some(true) => CANId::extended(id),
+            Some(false) => CANId::standard(id),
+            None => CANId::infer(id),
+        }
+        .map(Some)
+    }
+}
 
 impl YDesc {
     /// Make a `CANNetwork` from a `YDesc` (top-level yml description).
-    pub fn into_network(self) -> Result<CANNetwork> {
+    pub fn into_network(mut self) -> Result<CANNetwork, ComposeError> {
+        if let Err(e) = self.resolve_includes() {
+            return Err(self.include_error(e));
+        }
+
+        let mut net = self.build_network()?;
+        self.add_rx(&mut net)?;
+
+        Ok(net)
+    }
+
+    /// Make a `CANNetwork` with everything but each node's rx messages, which
+    /// may name messages routed in from other buses later.
+    ///
+    /// Each kind of definition is added in turn, stopping after the first
+    /// with errors, so that e.g. a broken template doesn't also fail every
+    /// message using it.
+    fn build_network(&self) -> Result<CANNetwork, ComposeError> {
         let mut net = CANNetwork::new();
+        let mut errors = Vec::new();
 
         // Includes
-        self.process_includes(&mut net)?;
+        self.process_includes(&mut net)
+            .map_err(|e| self.diagnostic(&["include"], &e))?;
+
+        // Naming, which takes precedence over any from the includes
+        if let Some(naming) = &self.naming {
+            let naming = naming
+                .to_naming()
+                .context("Invalid naming for network")
+                .map_err(|e| self.diagnostic(&["naming"], &e))?;
+            net.set_naming(naming);
+        }
+        let naming = net.naming().clone();
 
         // Bitrate
         if let Some(b) = self.bitrate {
             net.set_bitrate(b);
         }
 
+        // Network attributes
+        for (name, value) in &self.attributes {
+            net.set_attribute(name, value.to_attribute_value());
+        }
+
+        // Add all the value tables to the network
+        for tmap in &self.value_tables {
+            let (name, values) = unmap(tmap);
+
+            let added = Self::to_value_table(name, values)
+                .context(format!("Could not build value table `{name}`"))
+                .and_then(|table| {
+                    net.insert_value_table(table)
+                        .context(format!("Could not add value table `{name}` to network"))
+                });
+
+            if let Err(e) = added {
+                errors.push(self.diagnostic(&["value_tables", name], &e));
+            }
+        }
+        check(&mut errors)?;
+
         // Add all the templates to the network
         for tmap in &self.message_templates {
             let (name, tdesc) = unmap(tmap);
 
-            let template = tdesc
-                .to_template_message(name)
-                .context(format!("Could not build template `{name}`"))?;
+            let added = tdesc
+                .to_template_message(&net, name)
+                .context(format!("Could not build template `{name}`"))
+                .and_then(|template| {
+                    net.insert_template_message(template)
+                        .context(format!("Could not add template `{name}` to network"))
+                });
 
-            net.insert_template_message(template)
-                .context(format!("Could not add template `{name}` to network"))?;
+            if let Err(e) = added {
+                errors.push(self.diagnostic(&["message_templates", name], &e));
+            }
         }
+        check(&mut errors)?;
 
         // unmap all nodes into tuples
         let nodes: &Vec<_> = &self.nodes.iter().map(unmap).collect();
 
         // Add all the nodes to the network
-        for (name, _) in nodes {
+        for (name, ndesc) in nodes {
             // todo: for now, allow specifying nodes multiple times.
             // if we don't have this, you can't yet have nodes defined partially in a DBC
             // and partially in the yml.
-            match net.add_node(name) {
+            let added = match net.add_node(name) {
                 Ok(_) | Err(CANConstructionError::NodeAlreadyExists(_)) => Ok(()),
-                e => e
-            }?;
+                e => e,
+            }
+            .and_then(|_| net.set_node_attributes(name, to_attributes(&ndesc.attributes)))
+            .and_then(|_| match &ndesc.description {
+                Some(description) => net.set_node_description(name, Some(description.clone())),
+                None => Ok(()),
+            })
+            .context(format!("Could not build node `{name}`"));
+
+            if let Err(e) = added {
+                errors.push(self.diagnostic(&["nodes", name], &e));
+            }
         }
+        check(&mut errors)?;
 
         // Add all the messages in each node to the network
         for (name, ndesc) in nodes {
-            Self::add_node_msgs(&mut net, name, ndesc)
-                .context(format!("Could not build node `{name}`"))?;
+            for m in &ndesc.messages {
+                let (msg_name, mdesc) = unmap(m);
+
+                let full_name = naming.name(name, msg_name);
+                let added = mdesc
+                    .to_message(&net, &full_name, name)
+                    .and_then(|msg| Ok(net.insert_msg(msg)?))
+                    .context(format!("Could not build message `{full_name}`"));
+
+                if let Err(e) = added {
+                    errors.push(self.diagnostic(&["nodes", name, "messages", msg_name], &e));
+                }
+            }
         }
+        check(&mut errors)?;
 
-        // Fill in rx for each node
-        for (name, ndesc) in nodes {
-            // the rx field is either a directive like `rx: "*"` or a list of messages
-            match &ndesc.rx {
-                RxListOrDirective::List(list) => {
-                    for rx in list {
-                        net.set_message_rx_by_node(rx, name)
-                            .context(format!("Could not add rx message `{rx}` to node `{name}`"))?;
+        Ok(net)
+    }
+
+    /// Fill in rx for each node.
+    fn add_rx(&self, net: &mut CANNetwork) -> Result<(), ComposeError> {
+        let mut errors = Vec::new();
+
+        for (name, ndesc) in self.nodes.iter().map(unmap) {
+            // the rx field is either a directive like `rx: "*"` or a list of entries
+            let list = match &ndesc.rx {
+                RxListOrDirective::List(list) => list,
+                RxListOrDirective::Directive(RxDirective::Everything) => {
+                    let messages: Vec<String> = net
+                        .iter_messages()
+                        .filter(|m| m.tx_node() != Some(name))
+                        .map(|m| m.name.clone())
+                        .collect();
+
+                    for msg in &messages {
+                        net.set_message_rx_by_node(msg, name)
+                            .expect("message and node were just found in the network");
                     }
+                    continue;
                 }
-                RxListOrDirective::Directive(d) => {
-                    match d {
-                        RxDirective::Everything => {
-                            // collect all the message names in the network
-                            let messages: Vec<String> =
-                                net.iter_messages().map(|m| m.name.clone()).collect();
-
-                            // add each message to the node
-                            for msg in &messages {
-                                net.set_message_rx_by_node(msg, name)?;
-                            }
+            };
+
+            let mut selected = Vec::new();
+            let mut excluded = Vec::new();
+
+            for entry in list {
+                let (entries, into) = match entry {
+                    YRxEntry::Select(YRxSelect::Exclude(entries)) => (&entries[..], &mut excluded),
+                    entry => (std::slice::from_ref(entry), &mut selected),
+                };
+
+                for entry in entries {
+                    match entry.select(net, name) {
+                        Ok(messages) => into.extend(messages),
+                        Err(e) => {
+                            let e = e
+                                .context(format!("Could not select rx messages for node `{name}`"));
+                            let written = entry.written();
+                            errors.push(self.diagnostic(&["nodes", name, "rx", &written], &e));
                         }
                     }
                 }
             }
+
+            let excluded: HashSet<String> = excluded.into_iter().collect();
+            for msg in selected.iter().filter(|m| !excluded.contains(*m)) {
+                net.set_message_rx_by_node(msg, name)
+                    .expect("selected messages and node are in the network");
+            }
         }
 
-        Ok(net)
+        check(&mut errors)
     }
 
-    /// Add contents of a `YNode` to a given network (doesn't add node itself)
-    fn add_node_msgs(net: &mut CANNetwork, node_name: &str, ndesc: &YNode) -> Result<()> {
-        let msgs = ndesc.to_messages(net, node_name)?;
+    /// Make a `Diagnostic` for an error in the definition reached by `keys`,
+    /// e.g. `["nodes", "BMS", "messages", "Status"]`.
+    ///
+    /// Errors in a signal are located at the signal. Definitions merged in
+    /// from includes are located in the file they came from.
+    fn diagnostic(&self, keys: &[&str], e: &anyhow::Error) -> Diagnostic {
+        let mut keys = keys.to_vec();
+        if let Some(sig) = e.downcast_ref::<SignalContext>() {
+            keys.extend([sig.section, sig.name.as_str()]);
+        }
+
+        let origin = match keys[..] {
+            [section, name, ..] => self.origins.get(&(section.into(), name.into())),
+            _ => None,
+        };
+
+        let location = match origin {
+            Some(origin) => {
+                keys[1] = &origin.name;
+
+                std::fs::read_to_string(&origin.path).ok().and_then(|text| {
+                    Source {
+                        path: origin.path.clone(),
+                        text,
+                        prefix: vec![],
+                    }
+                    .locate(&keys)
+                })
+            }
+            None => self.source.locate(&keys),
+        };
+
+        Diagnostic::new(e, location)
+    }
 
-        for msg in msgs {
-            net.insert_msg(msg)?;
+    /// Make a `ComposeError` from a failure to resolve includes, which may
+    /// already be one (e.g. for a syntax error in an included file).
+    fn include_error(&self, e: anyhow::Error) -> ComposeError {
+        match e.downcast::<ComposeError>() {
+            Ok(e) => e,
+            Err(e) => self.diagnostic(&["include"], &e).into(),
         }
+    }
 
-        Ok(())
+    /// Make a `CANValueTable` from its list of values.
+    fn to_value_table(name: &str, values: &[YEnumeratedValue]) -> Result<CANValueTable> {
+        let mut table = CANValueTable::new(name)?;
+
+        for v in values {
+            match v {
+                YEnumeratedValue::Auto(s) => table.add_value_inferred(s)?,
+                YEnumeratedValue::Exact(map) => {
+                    let (name, &val) = unmap(map);
+                    table.add_value(name, val)?
+                }
+            };
+        }
+
+        Ok(table)
     }
 
     fn process_includes(&self, net: &mut CANNetwork) -> Result<()> {
         for include in &self.include {
-            if include.ends_with(".dbc") {
-                let path = Path::new(&self.lookup_path)
-                    .parent()
-                    .expect("path to .yml should have a parent")
-                    .join(include);
-                let dbc = std::fs::read_to_string(&path)
-                    .context(format!("Failed to import \'{}\'", &path.display()))?;
-                DbcImporter::import_network(dbc, net);
+            let mode = if include.lenient() {
+                ImportMode::Lenient
+            } else {
+                ImportMode::Strict
+            };
+
+            let include = include.path();
+            let path = include_dir(&self.lookup_path).join(include);
+            let failed = || format!("Failed to import \'{}\'", &path.display());
+            let read = || std::fs::read_to_string(&path).with_context(failed);
+
+            let warnings: Vec<String> = if include.ends_with(".dbc") {
+                DbcImporter::import_network(read()?, net, mode)
+                    .with_context(failed)?
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect()
+            } else if include.ends_with(".arxml") {
+                ArxmlImporter::import_network(read()?, net, mode)
+                    .with_context(failed)?
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect()
+            } else if include.ends_with(".kcd") {
+                KcdImporter::import_network(read()?, net, mode)
+                    .with_context(failed)?
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect()
             } else {
                 return Err(anyhow!("Unsupported file type for import \'{}\'", include));
+            };
+
+            for warning in warnings {
+                eprintln!("warning: {}: {warning}", path.display());
             }
         }
 
@@ -116,34 +392,132 @@ impl YDesc {
     }
 }
 
-impl YNode {
-    /// Make a `Vec<CANMessage>` from a `YNode`.
-    fn to_messages(&self, net: &CANNetwork, name: &str) -> Result<Vec<CANMessage>> {
-        let mut msgs = Vec::new();
+impl YRxEntry {
+    /// Names of the messages in `net` this entry selects for `node` to receive.
+    fn select(&self, net: &CANNetwork, node: &str) -> Result<Vec<String>> {
+        // a message named outright is taken as it is, even if `node` sends it
+        if let Self::Name(name) = self {
+            if !is_glob(name) {
+                if net.message_by_name(name).is_none() {
+                    return Err(CANConstructionError::MessageDoesNotExist(name.clone()).into());
+                }
+                return Ok(vec![name.clone()]);
+            }
+        }
 
-        for m in &self.messages {
-            let (msg_name, mdesc) = unmap(m);
+        let select = |pred: &dyn Fn(&CANMessage) -> bool| -> Vec<String> {
+            net.iter_messages()
+                .filter(|m| m.tx_node() != Some(node) && pred(m))
+                .map(|m| m.name.clone())
+                .collect()
+        };
 
-            let appended_name = format!("{name}_{msg_name}");
-            let msg = mdesc.to_message(net, &appended_name, name)?;
+        let (selected, what) = match self {
+            Self::Name(glob) => {
+                let re = glob_regex(glob);
+                (select(&|m| re.is_match(&m.name)), format!("`{glob}`"))
+            }
+            Self::Select(YRxSelect::From(tx)) => {
+                if net.node_by_name(tx).is_none() {
+                    return Err(CANConstructionError::NodeDoesNotExist(tx.clone()).into());
+                }
+                (
+                    select(&|m| m.tx_node() == Some(tx)),
+                    format!("`from: {tx}`"),
+                )
+            }
+            Self::Select(YRxSelect::Template(template)) => {
+                if net.template_message_by_name(template).is_none() {
+                    return Err(anyhow!("No template named `{template}` in network."));
+                }
+                (
+                    select(
+                        &|m| matches!(m.kind(), CANMessageKind::FromTemplate(t) if t == template),
+                    ),
+                    format!("`template: {template}`"),
+                )
+            }
+            Self::Select(YRxSelect::Regex(regex)) => {
+                let re = Regex::new(regex).context(format!("Invalid regex `{regex}`"))?;
+                (
+                    select(&|m| re.is_match(&m.name)),
+                    format!("`regex: {regex}`"),
+                )
+            }
+            Self::Select(YRxSelect::Exclude(_)) => {
+                return Err(anyhow!(
+                    "`exclude:` can only be used at the top of an rx list."
+                ));
+            }
+        };
 
-            msgs.push(msg);
+        if selected.is_empty() {
+            return Err(anyhow!(
+                "{what} selects no messages that `{node}` doesn't transmit itself."
+            ));
         }
 
-        Ok(msgs)
+        Ok(selected)
+    }
+
+    /// How this entry is written in an rx list, to find it for diagnostics.
+    fn written(&self) -> String {
+        match self {
+            Self::Name(name) => name.clone(),
+            Self::Select(YRxSelect::From(tx)) => format!("from: {tx}"),
+            Self::Select(YRxSelect::Template(template)) => format!("template: {template}"),
+            Self::Select(YRxSelect::Regex(regex)) => format!("regex: {regex}"),
+            Self::Select(YRxSelect::Exclude(_)) => "exclude".into(),
+        }
     }
 }
 
+/// Whether an rx entry is a glob rather than a message name.
+fn is_glob(name: &str) -> bool {
+    name.contains(['*', '?'])
+}
+
+/// Make a `Regex` matching whole names against a glob.
+fn glob_regex(glob: &str) -> Regex {
+    let pattern = regex::escape(glob).replace(r"\*", ".*").replace(r"\?", ".");
+    Regex::new(&format!("^{pattern}$")).expect("escaped globs are valid regexes")
+}
+
 impl YMessageTemplate {
     /// Make a template `CANMessage` from a `YMessageTemplate`.
-    fn to_template_message(&self, name: &str) -> Result<CANMessage> {
-        let mut msg = CANMessage::template().name(name);
+    fn to_template_message(&self, net: &CANNetwork, name: &str) -> Result<CANMessage> {
+        let mut msg = match &self.extends {
+            Some(parent_name) => {
+                let parent = net
+                    .template_message_by_name(parent_name)
+                    .context(format!("No template named `{parent_name}` in network."))?;
+
+                // attributes are merged, with this template's taking precedence
+                let mut attributes = parent.attributes.clone();
+                attributes.extend(to_attributes(&self.attributes));
+
+                parent.extend_template(name)?.attributes(attributes)
+            }
+            None => CANMessage::template()
+                .name(name)
+                .attributes(to_attributes(&self.attributes)),
+        };
 
         // cycletime
-        msg = msg.cycletime(self.cycletime);
+        if self.cycletime.is_some() {
+            msg = msg.cycletime(self.cycletime);
+        }
+
+        // frame format, needed before adding signals
+        if self.fd {
+            msg = msg.fd(true);
+        }
+        if self.brs {
+            msg = msg.brs(true);
+        }
 
         // Add signals
-        msg = YMessage::add_signals_to_message_builder(msg, &self.signals, "")?;
+        msg = YMessage::add_signals_to_message_builder(net, msg, &self.signals, "")?;
 
         let msg = msg.build()?;
         Ok(msg)
@@ -153,55 +527,128 @@ impl YMessageTemplate {
 impl YMessage {
     /// Make a `CANMessage` from a `YMessage`.
     fn to_message(&self, net: &CANNetwork, msg_name: &str, node_name: &str) -> Result<CANMessage> {
-        if let Some(template_name) = &self.from_template {
-            // Make sure there is no signals field
-            if self.signals.is_some() {
-                return Err(anyhow!("Message {msg_name} inherits signals from template `{template_name}` and cannot specify a `signals:` field."));
-            }
+        let id = self
+            .can_id()
+            .context(format!("Invalid ID for message `{msg_name}`"))?;
 
+        if let Some(template_name) = &self.from_template {
             // Find template
             let template = net
                 .template_message_by_name(template_name)
                 .context(format!("No template named `{template_name}` in network."))?;
 
             // Instantiate template
-            let signal_prefix = format!("{node_name}_");
-            let msg = template.template_instance(
+            let signal_prefix = net.naming().prefix(node_name);
+            let mut msg = template.template_instance(
                 msg_name,
-                self.id,
+                id,
                 &signal_prefix,
                 self.cycletime,
                 Some(node_name),
             )?;
 
+            if self.description.is_some() {
+                msg.description = self.description.clone();
+            }
+
+            // Instances can be promoted to FD, but not demoted from it
+            msg.fd |= self.fd;
+            msg.brs |= self.brs;
+
+            msg.attributes.extend(to_attributes(&self.attributes));
+
+            // Extra signals go after the template's
+            if let Some(signals) = &self.signals {
+                let mut extra = Vec::new();
+                for s in signals {
+                    let (sig_name, sdesc) = unmap(s);
+
+                    let sig = sdesc
+                        .to_signal(net, &format!("{signal_prefix}{sig_name}"), &signal_prefix)
+                        .context(SignalContext {
+                            section: "signals",
+                            name: sig_name.clone(),
+                            what: "Could not create signal",
+                        })?;
+                    extra.push((sdesc.start_bit, sig));
+                }
+
+                msg = msg.with_instance_signals(extra).context(format!(
+                    "Could not add signals to template instance `{msg_name}`"
+                ))?;
+            }
+
+            // Per-instance changes to template signals
+            for o in &self.overrides {
+                let (sig_name, odesc) = unmap(o);
+
+                msg = msg
+                    .override_template_signal(
+                        &format!("{signal_prefix}{sig_name}"),
+                        CANSignalOverride {
+                            description: odesc.description.clone(),
+                            scale: odesc.scale,
+                            offset: odesc.offset,
+                        },
+                    )
+                    .context(SignalContext {
+                        section: "overrides",
+                        name: sig_name.clone(),
+                        what: "Could not override signal",
+                    })?;
+            }
+
             return Ok(msg);
         }
 
+        if !self.overrides.is_empty() {
+            return Err(anyhow!("Message `{msg_name}` is not from a template and cannot specify an `overrides:` field."));
+        }
+
         // If we don't have a signals field, make a raw message
         let Some(signals) = &self.signals else {
-            return Ok(CANMessage::new_raw(msg_name, self.id, self.cycletime, Some(node_name)));
+            let mut msg = CANMessage::new_raw(msg_name, id, self.cycletime, Some(node_name));
+            msg.description = self.description.clone();
+            msg.fd = self.fd;
+            msg.brs = self.brs;
+            msg.attributes = to_attributes(&self.attributes);
+
+            return Ok(msg);
         };
 
         // First, make a CANMessageBuilder.
         let mut can_msg = CANMessageBuilder::default()
             .name(msg_name)
-            .id(self.id)
+            .description(self.description.clone())
+            .id(id)
             .cycletime(self.cycletime)
+            .fd(self.fd)
+            .brs(self.brs)
+            .attributes(to_attributes(&self.attributes))
             .tx_node(node_name);
 
         // Add signals
-        can_msg = Self::add_signals_to_message_builder(can_msg, signals, &format!("{node_name}_"))
+        let signal_prefix = net.naming().prefix(node_name);
+        can_msg = Self::add_signals_to_message_builder(net, can_msg, signals, &signal_prefix)
             .context(format!(
                 "Could not populate signals for message `{msg_name}`"
             ))?;
 
         // Build message and return
-        can_msg
-            .build()
-            .context(format!("Could not build message `{msg_name}`"))
+        Ok(can_msg.build()?)
+    }
+
+    /// Make a `CANId` from the `id` and `extended` fields.
+    fn can_id(&self) -> Result<CANId, CANConstructionError> {
+        match self.extended {
+            Some(true) => CANId::extended(self.id),
+            Some(false) => CANId::standard(self.id),
+            None => CANId::infer(self.id),
+        }
     }
 
     fn add_signals_to_message_builder(
+        net: &CANNetwork,
         mut message: CANMessageBuilder,
         signals: &Vec<HashMap<String, YSignal>>,
         signal_prefix: &str,
@@ -214,14 +661,22 @@ impl YMessage {
             let full_sig_name = format!("{signal_prefix}{sig_name}");
 
             let sig = sdesc
-                .to_signal(&full_sig_name)
-                .context(format!("Could not create signal `{sig_name}`."))?;
+                .to_signal(net, &full_sig_name, signal_prefix)
+                .context(SignalContext {
+                    section: "signals",
+                    name: sig_name.clone(),
+                    what: "Could not create signal",
+                })?;
 
             message = match start_bit {
                 Some(bit) => message.add_signal_fixed(bit, sig),
                 None => message.add_signal(sig),
             }
-            .context(format!("Could not add signal `{sig_name}`."))?;
+            .context(SignalContext {
+                section: "signals",
+                name: sig_name.clone(),
+                what: "Could not add signal",
+            })?;
         }
 
         Ok(message)
@@ -230,14 +685,36 @@ impl YMessage {
 
 impl YSignal {
     /// Turn a `YSignal` into a `CANSignal`.
-    fn to_signal(&self, sig_name: &str) -> Result<CANSignal> {
+    ///
+    /// `signal_prefix` is applied to the multiplexor name, if any, to match
+    /// the prefix of signals in the message. Value tables are looked up in
+    /// `net`.
+    fn to_signal(
+        &self,
+        net: &CANNetwork,
+        sig_name: &str,
+        signal_prefix: &str,
+    ) -> Result<CANSignal> {
         // First, make a CANSignalBuilder.
         let mut new_sig = CANSignal::builder()
             .name(sig_name)
             .description(self.description.clone())
             .twos_complement(self.twos_complement)
+            .byte_order(self.byte_order)
+            .value_type(self.value_type)
             .scale(self.scale)
-            .offset(self.offset);
+            .offset(self.offset)
+            .unit(self.unit.clone())
+            .min(self.min)
+            .max(self.max)
+            .initial_value(self.initial_value)
+            .sna_value(self.sna_value)
+            .error_value(self.error_value)
+            .mux(self.mux.as_ref().map(|m| CANSignalMux {
+                multiplexor: format!("{signal_prefix}{}", m.signal),
+                values: m.values.clone(),
+            }))
+            .attributes(to_attributes(&self.attributes));
 
         // Translate each enumerated value
         for h in &self.enumerated_values {
@@ -250,10 +727,23 @@ impl YSignal {
             };
         }
 
+        // Or take them from a value table
+        if let Some(table_name) = &self.value_table {
+            if !self.enumerated_values.is_empty() {
+                return Err(anyhow!("Signal `{sig_name}` uses value table `{table_name}` and cannot specify `enumerated_values:`."));
+            }
+
+            let table = net
+                .value_table_by_name(table_name)
+                .context(format!("No value table named `{table_name}` in network."))?;
+
+            new_sig = new_sig.use_value_table(table)?;
+        }
+
         // Either specify the width or infer it
-        new_sig = match self.width {
-            Some(w) => new_sig.width(w),
-            None => new_sig.infer_width_strict()?,
+        new_sig = match (self.width, self.value_type.float_width()) {
+            (Some(w), _) | (None, Some(w)) => new_sig.width(w),
+            (None, None) => new_sig.infer_width_strict()?,
         };
 
         // Build and return
@@ -262,3 +752,42 @@ impl YSignal {
             .context(format!("Could not build signal `{sig_name}`"))
     }
 }
+
+impl YNaming {
+    pub(crate) fn to_naming(&self) -> Result<CANNaming, CANConstructionError> {
+        match self {
+            Self::None => Ok(CANNaming::unprefixed()),
+            Self::Separator(separator) => CANNaming::with_separator(separator),
+            Self::Prefix(prefix) => CANNaming::new(prefix),
+        }
+    }
+}
+
+impl YAttributeValue {
+    fn to_attribute_value(&self) -> AttributeValue {
+        match self {
+            Self::Int(v) => AttributeValue::Int(*v),
+            Self::Float(v) => AttributeValue::Float(*v),
+            Self::String(v) => AttributeValue::String(v.clone()),
+            Self::Enum { variant } => AttributeValue::Enum(variant.clone()),
+        }
+    }
+}
+
+/// Turn a map of `YAttributeValue`s into `Attributes`.
+fn to_attributes(map: &HashMap<String, YAttributeValue>) -> Attributes {
+    map.iter()
+        .map(|(name, value)| (name.clone(), value.to_attribute_value()))
+        .collect()
+}
+
+/// Fail with the diagnostics collected so far, if there are any.
+fn check(errors: &mut Vec<Diagnostic>) -> Result<(), ComposeError> {
+    if errors.is_empty() {
+        return Ok(());
+    }
+
+    Err(ComposeError {
+        diagnostics: std::mem::take(errors),
+    })
+}
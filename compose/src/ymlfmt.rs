@@ -2,13 +2,22 @@
 
 use std::collections::HashMap;
 
+use opencan_core::{ByteOrder, SignalValueType};
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
+use crate::{
+    error::{Origin, Source},
+    schema::{single_key_map, single_key_maps},
+};
+
 /// Small helper for turning single-length maps into a tuple.
 ///
 /// Serde deserializes:
+/// ```yaml
 /// - signalName:
 ///     (parameter)
+/// ```
 ///
 /// As a `map<String, YSignal>` with length 1. We then typically have a vector
 /// of these, because it's both a sequence element and we still want to have
@@ -19,11 +28,11 @@ pub fn unmap<T>(map: &HashMap<String, T>) -> (&String, &T) {
     map.iter().next().unwrap()
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, JsonSchema, PartialEq)]
 #[serde(untagged)]
 pub enum YEnumeratedValue {
     Auto(String),
-    Exact(HashMap<String, u64>),
+    Exact(#[schemars(schema_with = "single_key_map::<u64>")] HashMap<String, u64>),
 }
 
 impl std::fmt::Debug for YEnumeratedValue {
@@ -35,7 +44,28 @@ impl std::fmt::Debug for YEnumeratedValue {
     }
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+/// Value of a user-defined attribute:
+///
+/// ```yaml
+/// attributes:
+///   Priority: 3
+///   Gain: 0.5
+///   Supplier: ACME
+///   Domain: { enum: Body }
+/// ```
+#[derive(Serialize, Deserialize, JsonSchema, Debug, PartialEq)]
+#[serde(untagged)]
+pub enum YAttributeValue {
+    Int(i64),
+    Float(f64),
+    String(String),
+    Enum {
+        #[serde(rename = "enum")]
+        variant: String,
+    },
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, PartialEq)]
 #[serde(deny_unknown_fields)]
 pub struct YSignal {
     pub width: Option<u32>,
@@ -47,78 +77,432 @@ pub struct YSignal {
     #[serde(default)]
     pub twos_complement: bool,
 
+    #[serde(default)]
+    pub byte_order: ByteOrder,
+
+    /// `integer` (default), `float` or `double`. Float signals are 32 bits
+    /// wide and double signals are 64 bits wide unless `width` says otherwise.
+    #[serde(default)]
+    pub value_type: SignalValueType,
+
     pub scale: Option<f64>,
     pub offset: Option<f64>,
 
+    /// Physical unit, e.g. `V` or `degC`.
     pub unit: Option<String>,
 
+    /// Smallest expected physical value.
+    pub min: Option<f64>,
+
+    /// Largest expected physical value.
+    pub max: Option<f64>,
+
+    /// Raw value until the signal is first received, or if the populate
+    /// function doesn't set it when transmitting.
+    pub initial_value: Option<u64>,
+
+    /// Raw value meaning "signal not available".
+    pub sna_value: Option<u64>,
+
+    /// Raw value meaning the transmitter has an error for this signal.
+    pub error_value: Option<u64>,
+
     #[serde(default)]
     pub enumerated_values: Vec<YEnumeratedValue>,
+
+    /// Take enumerated values from a network value table instead of
+    /// `enumerated_values`.
+    pub value_table: Option<String>,
+
+    /// Only send this signal for some values of a multiplexor signal.
+    pub mux: Option<YSignalMux>,
+
+    #[serde(default)]
+    pub attributes: HashMap<String, YAttributeValue>,
+}
+
+/// Multiplexing for a signal:
+///
+/// ```yaml
+/// - voltage:
+///     width: 16
+///     mux: { signal: mode, values: [0, 2] }
+/// ```
+#[derive(Serialize, Deserialize, JsonSchema, Debug, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct YSignalMux {
+    /// Name of the multiplexor signal in the same message. Must come before
+    /// the multiplexed signal.
+    pub signal: String,
+
+    /// Multiplexor values for which the signal is present.
+    pub values: Vec<u64>,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, JsonSchema, Debug, PartialEq)]
 pub struct YMessageTemplate {
+    /// Start from the signals, cycle time, frame format and attributes of
+    /// another template, which must be listed before this one. `signals` are
+    /// added after the other template's.
+    pub extends: Option<String>,
+
     pub cycletime: Option<u32>,
+
+    /// Send instances of this template as CAN FD frames.
+    #[serde(default)]
+    pub fd: bool,
+
+    /// Use bit rate switching. Requires `fd`.
+    #[serde(default)]
+    pub brs: bool,
+
+    /// Attributes of every instance of this template.
+    #[serde(default)]
+    pub attributes: HashMap<String, YAttributeValue>,
+
+    #[schemars(schema_with = "single_key_maps::<YSignal>")]
     pub signals: Vec<HashMap<String, YSignal>>,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, JsonSchema, Debug)]
 #[serde(deny_unknown_fields)]
 pub struct YMessage {
     pub id: u32,
 
+    pub description: Option<String>,
+
+    /// Whether `id` is a 29-bit extended ID. Inferred from `id` if not given.
+    pub extended: Option<bool>,
+
     pub from_template: Option<String>,
 
     pub cycletime: Option<u32>,
 
+    /// Send this message as a CAN FD frame. Messages from a CAN FD template
+    /// are always CAN FD frames.
+    #[serde(default)]
+    pub fd: bool,
+
+    /// Use bit rate switching. Requires `fd`.
+    #[serde(default)]
+    pub brs: bool,
+
+    /// Attributes of this message. For template instances, these are added
+    /// to the template's attributes, replacing any with the same name.
+    #[serde(default)]
+    pub attributes: HashMap<String, YAttributeValue>,
+
+    /// Signals of this message. For template instances, these are added
+    /// after the template's signals.
+    #[serde(default)]
+    #[schemars(schema_with = "single_key_maps::<YSignal>")]
     pub signals: Option<Vec<HashMap<String, YSignal>>>,
+
+    /// Changes to the template's signals for this instance only.
+    #[serde(default)]
+    #[schemars(schema_with = "single_key_maps::<YSignalOverride>")]
+    pub overrides: Vec<HashMap<String, YSignalOverride>>,
+}
+
+/// Per-instance changes to a template signal:
+///
+/// ```yaml
+/// overrides:
+/// - voltage: { scale: 0.02, description: Rail voltage }
+/// ```
+///
+/// A scale or offset can only be given to a signal that already has one in
+/// the template, so every instance keeps the template's C types.
+#[derive(Serialize, Deserialize, JsonSchema, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct YSignalOverride {
+    pub description: Option<String>,
+    pub scale: Option<f64>,
+    pub offset: Option<f64>,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, JsonSchema, Debug)]
 pub enum RxDirective {
-    /// Recieve all messages in the network
+    /// Recieve all messages in the network, other than the node's own
     ///
     ///   rx: "*"
     #[serde(rename = "*")]
     Everything,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, JsonSchema, Debug)]
 #[serde(untagged)]
 pub enum RxListOrDirective {
-    List(#[serde(default)] Vec<String>),
+    List(#[serde(default)] Vec<YRxEntry>),
     Directive(RxDirective),
 }
 
+/// An entry in a node's `rx` list:
+///
+/// ```yaml
+/// rx:
+/// - BRAKE_Status          # a message, by name
+/// - ENC_*                 # every message matching a glob (`*` and `?`)
+/// - from: STEER           # every message STEER transmits
+/// - template: NodeStatus  # every instance of a template
+/// - regex: ^PDM_.*Data$   # every message matching a regex
+/// - exclude:              # none of these, even if selected above
+///   - ENC_Debug
+///   - from: LOGGER
+/// ```
+///
+/// Everything but a plain message name only selects messages the node
+/// doesn't transmit itself, and must select at least one message.
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone)]
+#[serde(untagged)]
+pub enum YRxEntry {
+    Name(String),
+    Select(YRxSelect),
+}
+
+/// Selections of rx messages other than by name. See [`YRxEntry`].
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone)]
+#[serde(rename_all = "snake_case")]
+pub enum YRxSelect {
+    /// Every message transmitted by a node.
+    From(String),
+
+    /// Every instance of a template.
+    Template(String),
+
+    /// Every message whose name matches a regular expression.
+    Regex(String),
+
+    /// Messages not to receive, even if selected by other entries.
+    Exclude(Vec<YRxEntry>),
+}
+
 impl Default for RxListOrDirective {
     fn default() -> Self {
         Self::List(Vec::new())
     }
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, JsonSchema, Debug)]
 pub struct YNode {
+    pub description: Option<String>,
+
     #[serde(default)]
+    #[schemars(schema_with = "single_key_maps::<YMessage>")]
     pub messages: Vec<HashMap<String, YMessage>>,
 
     #[serde(default)]
     pub rx: RxListOrDirective,
+
+    #[serde(default)]
+    pub attributes: HashMap<String, YAttributeValue>,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Default)]
 #[serde(deny_unknown_fields)]
 pub struct YDesc {
+    #[serde(default)]
+    #[schemars(schema_with = "single_key_maps::<YNode>")]
     pub nodes: Vec<HashMap<String, YNode>>,
 
     #[serde(default)]
+    #[schemars(schema_with = "single_key_maps::<YMessageTemplate>")]
     pub message_templates: Vec<HashMap<String, YMessageTemplate>>,
 
+    /// Enumerated values shared between signals:
+    ///
+    /// ```yaml
+    /// value_tables:
+    /// - OnOff:
+    ///   - OFF
+    ///   - ON
+    /// ```
+    #[serde(default)]
+    #[schemars(schema_with = "single_key_maps::<Vec<YEnumeratedValue>>")]
+    pub value_tables: Vec<HashMap<String, Vec<YEnumeratedValue>>>,
+
     #[serde(default)]
     pub bitrate: Option<u32>,
 
+    /// How the names of each node's messages and signals are made. See
+    /// [`YNaming`].
+    #[serde(default, with = "serde_yaml::with::singleton_map")]
+    #[schemars(with = "Option<YNaming>")]
+    pub naming: Option<YNaming>,
+
+    /// Attributes of the network itself.
+    #[serde(default)]
+    pub attributes: HashMap<String, YAttributeValue>,
+
+    /// Other files whose definitions are added to this one's. See
+    /// [`YInclude`].
     #[serde(default)]
-    pub include: Vec<String>,
+    pub include: Vec<YInclude>,
 
     #[serde(default)]
+    #[schemars(skip)]
     pub lookup_path: String,
+
+    /// Where this description was read from, for locating errors.
+    #[serde(skip)]
+    pub source: Source,
+
+    /// Files that nodes, templates and value tables merged in from includes
+    /// were defined in, by section (e.g. `nodes`) and name.
+    #[serde(skip)]
+    pub origins: HashMap<(String, String), Origin>,
+}
+
+/// How the names of a node's messages and signals are made from the names
+/// they're given under the node. By default they're prefixed with the node's
+/// name and an underscore, so `Status` under node `BMS` is `BMS_Status`:
+///
+/// ```yaml
+/// naming: none                     # Status
+/// naming: { separator: "__" }      # BMS__Status
+/// naming: { prefix: "ECU_{node}_" } # ECU_BMS_Status
+/// ```
+///
+/// This applies to messages, their signals and template instances alike. A
+/// naming imported from an included `.dbc` is used unless one is given here.
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum YNaming {
+    /// Use names as they are given.
+    None,
+
+    /// Prefix names with the node's name and this separator.
+    Separator(String),
+
+    /// Prefix names with this format, where `{node}` stands for the node's
+    /// name.
+    Prefix(String),
+}
+
+/// A file to include, by path relative to the including file:
+///
+/// ```yaml
+/// include:
+/// - common/templates.yml
+/// - path: battery.yml
+///   namespace: FRONT
+/// - legacy.dbc
+/// ```
+///
+/// OpenCAN `.yml` files are merged in before the including file's own
+/// definitions:
+///     - Nodes defined in several files are combined.
+///     - Templates and value tables defined in several files must be
+///       identical, and are kept once.
+///     - A file included more than once is only merged once.
+///
+/// With a `namespace`, the nodes, templates and value tables the file defines
+/// (including through its own includes) are renamed to `<namespace>_<name>`,
+/// along with references to them in that file.
+///
+/// `.dbc`, `.kcd` and `.arxml` files are imported as they are. With
+/// `lenient: true`, the parts of such a file that can't be imported are
+/// skipped with a warning, rather than failing the import.
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone)]
+#[serde(untagged)]
+pub enum YInclude {
+    Path(String),
+    Namespaced {
+        path: String,
+        namespace: Option<String>,
+        #[serde(default)]
+        lenient: bool,
+    },
+}
+
+impl YInclude {
+    pub fn path(&self) -> &str {
+        match self {
+            Self::Path(path) | Self::Namespaced { path, .. } => path,
+        }
+    }
+
+    pub fn namespace(&self) -> Option<&str> {
+        match self {
+            Self::Path(_) => None,
+            Self::Namespaced { namespace, .. } => namespace.as_deref(),
+        }
+    }
+
+    pub fn lenient(&self) -> bool {
+        match self {
+            Self::Path(_) => false,
+            Self::Namespaced { lenient, .. } => *lenient,
+        }
+    }
+
+    /// The same include, of the file at `path` instead.
+    pub fn with_path(&self, path: String) -> Self {
+        match self {
+            Self::Path(_) => Self::Path(path),
+            Self::Namespaced {
+                namespace, lenient, ..
+            } => Self::Namespaced {
+                path,
+                namespace: namespace.clone(),
+                lenient: *lenient,
+            },
+        }
+    }
+}
+
+/// Top-level description of several buses and the gateways between them:
+///
+/// ```yaml
+/// buses:
+/// - chassis:
+///     bitrate: 500000
+///     nodes: ...
+/// - dbw:
+///     nodes: ...
+///
+/// gateways:
+/// - GW:
+///   - message: ABS_Speed
+///     from: chassis
+///     to: dbw
+///     id: 0x210
+/// ```
+///
+/// Each bus is described like a single-bus file. A node attached to several
+/// buses, like a gateway, is listed in each of them.
+#[derive(Serialize, Deserialize, JsonSchema, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct YSystem {
+    #[schemars(schema_with = "single_key_maps::<YDesc>")]
+    pub buses: Vec<HashMap<String, YDesc>>,
+
+    /// Routes through each gateway node.
+    #[serde(default)]
+    #[schemars(schema_with = "single_key_maps::<Vec<YRoute>>")]
+    pub gateways: Vec<HashMap<String, Vec<YRoute>>>,
+
+    /// Where this description was read from, for locating errors.
+    #[serde(skip)]
+    pub source: Source,
+}
+
+/// A message a gateway receives on one bus and transmits on another.
+#[derive(Serialize, Deserialize, JsonSchema, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct YRoute {
+    /// Full name of the message, as in `rx` lists.
+    pub message: String,
+
+    /// Bus the message is received from.
+    pub from: String,
+
+    /// Bus the message is transmitted onto.
+    pub to: String,
+
+    /// ID on the `to` bus. Keeps the message's ID if not given.
+    pub id: Option<u32>,
+
+    /// Whether `id` is a 29-bit extended ID. Inferred from `id` if not given.
+    pub extended: Option<bool>,
 }
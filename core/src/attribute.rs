@@ -0,0 +1,42 @@
+use std::collections::BTreeMap;
+use std::fmt::Display;
+
+use serde::{Deserialize, Serialize};
+
+/// Value of a user-defined attribute, like DBC `BA_` values.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum AttributeValue {
+    String(String),
+    Int(i64),
+    Float(f64),
+
+    /// One of a fixed set of names. The set is whatever the network uses
+    /// for this attribute, e.g. when it is exported as a DBC `ENUM`.
+    Enum(String),
+}
+
+impl AttributeValue {
+    /// Name of this value's type, as used in error messages.
+    pub const fn type_name(&self) -> &'static str {
+        match self {
+            Self::String(_) => "string",
+            Self::Int(_) => "int",
+            Self::Float(_) => "float",
+            Self::Enum(_) => "enum",
+        }
+    }
+}
+
+impl Display for AttributeValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::String(s) | Self::Enum(s) => write!(f, "{s}"),
+            Self::Int(i) => write!(f, "{i}"),
+            Self::Float(v) => write!(f, "{v}"),
+        }
+    }
+}
+
+/// User-defined attributes of a network, node, message or signal, by name.
+pub type Attributes = BTreeMap<String, AttributeValue>;
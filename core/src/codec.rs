@@ -0,0 +1,564 @@
+use std::collections::HashMap;
+use std::fmt::Display;
+
+use thiserror::Error;
+
+use crate::*;
+
+/// Raw value of a signal, as carried in a frame.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RawValue {
+    /// Value of a signal that is not twos-complement.
+    Unsigned(u64),
+
+    /// Sign-extended value of a twos-complement signal.
+    Signed(i64),
+
+    /// Value of a float or double signal.
+    Float(f64),
+}
+
+impl RawValue {
+    /// This raw value as a float, for scaling.
+    pub fn as_f64(&self) -> f64 {
+        match *self {
+            Self::Unsigned(v) => v as f64,
+            Self::Signed(v) => v as f64,
+            Self::Float(v) => v,
+        }
+    }
+
+    fn as_i128(&self) -> i128 {
+        match *self {
+            Self::Unsigned(v) => v.into(),
+            Self::Signed(v) => v.into(),
+            Self::Float(v) => v as i128,
+        }
+    }
+}
+
+impl Display for RawValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Unsigned(v) => write!(f, "{v}"),
+            Self::Signed(v) => write!(f, "{v}"),
+            Self::Float(v) => write!(f, "{v}"),
+        }
+    }
+}
+
+/// A signal decoded from a frame by [`CANMessage::decode()`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct DecodedSignal {
+    /// Name of the signal.
+    pub name: String,
+
+    /// Raw value of the signal.
+    pub raw: RawValue,
+
+    /// Physical value of the signal, after scale and offset.
+    pub physical: f64,
+
+    /// Name of the enumerated value matching the raw value, if any.
+    pub enumerated: Option<String>,
+
+    /// Whether the physical value is within the signal's min and max.
+    pub in_range: bool,
+}
+
+/// Value of a signal to be encoded by [`CANMessage::encode()`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum SignalValue {
+    /// Raw value, put in the frame as-is. Float and double signals take any
+    /// raw value as a float.
+    Raw(RawValue),
+
+    /// Physical value, converted to raw with the signal's scale and offset
+    /// and rounded to the nearest raw value.
+    Physical(f64),
+
+    /// Name of one of the signal's enumerated values.
+    Enumerated(String),
+}
+
+impl From<f64> for SignalValue {
+    fn from(v: f64) -> Self {
+        Self::Physical(v)
+    }
+}
+
+impl From<&str> for SignalValue {
+    fn from(v: &str) -> Self {
+        Self::Enumerated(v.into())
+    }
+}
+
+/// Errors from [`CANMessage::decode()`] and [`CANMessage::encode()`].
+#[derive(Debug, Clone, PartialEq, Error)]
+pub enum CodecError {
+    #[error("Frame for message `{message}` is {actual} bytes long, expected at least {expected}")]
+    FrameTooShort {
+        message: String,
+        expected: u32,
+        actual: usize,
+    },
+
+    #[error("Message `{message}` has no signal `{signal}`")]
+    UnknownSignal { message: String, signal: String },
+
+    #[error("No value given for signal `{signal}` of message `{message}`")]
+    MissingSignal { message: String, signal: String },
+
+    #[error(
+        "Value given for signal `{signal}` of message `{message}`, \
+         but its multiplexor selects another group"
+    )]
+    InactiveSignal { message: String, signal: String },
+
+    #[error("Signal `{signal}` has no enumerated value named `{name}`")]
+    UnknownEnumeratedValue { signal: String, name: String },
+
+    #[error("Physical value {value} of signal `{signal}` is outside its range {min} to {max}")]
+    PhysicalOutOfRange {
+        signal: String,
+        value: f64,
+        min: f64,
+        max: f64,
+    },
+
+    #[error("Raw value {value} does not fit in the {width} bits of signal `{signal}`")]
+    RawOutOfRange {
+        signal: String,
+        value: RawValue,
+        width: u32,
+    },
+}
+
+/// Smallest and largest raw values of a signal.
+fn raw_limits(sig: &CANSignal) -> (i128, i128) {
+    let width = sig.width.min(64);
+
+    if sig.twos_complement {
+        (-(1 << (width - 1)), (1 << (width - 1)) - 1)
+    } else {
+        (0, (1 << width) - 1)
+    }
+}
+
+/// IEEE-754 bits of a raw value for a float or double signal.
+fn float_bits(sig: &CANSignal, raw: f64) -> u64 {
+    if sig.value_type == SignalValueType::Float {
+        (raw as f32).to_bits().into()
+    } else {
+        raw.to_bits()
+    }
+}
+
+/// Whether the signal is present in a frame with these multiplexor values.
+fn is_active(sig: &CANSignal, raw_values: &HashMap<&str, u64>) -> bool {
+    sig.mux.as_ref().is_none_or(|mux| {
+        raw_values
+            .get(mux.multiplexor.as_str())
+            .is_some_and(|v| mux.values.contains(v))
+    })
+}
+
+impl CANMessage {
+    /// Decode the signals present in a frame of this message.
+    ///
+    /// Signals are returned in message order. Multiplexed signals are only
+    /// returned when their multiplexor selects them. Physical values outside
+    /// the signal's min and max are returned, but flagged with
+    /// [`in_range`](DecodedSignal::in_range).
+    pub fn decode(&self, data: &[u8]) -> Result<Vec<DecodedSignal>, CodecError> {
+        if data.len() < self.length as usize {
+            return Err(CodecError::FrameTooShort {
+                message: self.name.clone(),
+                expected: self.length,
+                actual: data.len(),
+            });
+        }
+
+        let mut raw_values: HashMap<&str, u64> = HashMap::new();
+        let mut decoded = Vec::new();
+
+        for sigbit in &self.signals {
+            let sig = &sigbit.sig;
+
+            if !is_active(sig, &raw_values) {
+                continue;
+            }
+
+            // gather the signal's bits, least significant first
+            let mut bits: u64 = 0;
+            for (i, pos) in sigbit.bit_positions().enumerate() {
+                if data[pos as usize / 8] >> (pos % 8) & 1 == 1 {
+                    bits |= 1 << i;
+                }
+            }
+            raw_values.insert(&sig.name, bits);
+
+            let raw = if sig.value_type == SignalValueType::Float {
+                RawValue::Float(f32::from_bits(bits as u32).into())
+            } else if sig.value_type == SignalValueType::Double {
+                RawValue::Float(f64::from_bits(bits))
+            } else if sig.twos_complement {
+                // sign extend
+                let shift = u64::BITS - sig.width;
+                RawValue::Signed(((bits << shift) as i64) >> shift)
+            } else {
+                RawValue::Unsigned(bits)
+            };
+
+            let physical = sig.raw_to_physical(raw.as_f64());
+
+            decoded.push(DecodedSignal {
+                name: sig.name.clone(),
+                raw,
+                physical,
+                enumerated: sig.enumerated_values.get_by_right(&bits).cloned(),
+                in_range: !(sig.min.is_some_and(|min| physical < min)
+                    || sig.max.is_some_and(|max| physical > max)),
+            });
+        }
+
+        Ok(decoded)
+    }
+
+    /// Encode a frame of this message from signal values.
+    ///
+    /// Every signal present in the frame needs a value, and multiplexed
+    /// signals that their multiplexor doesn't select must not be given one.
+    /// Physical values are checked against the signal's min and max, and
+    /// all values must fit in the signal's width.
+    pub fn encode(&self, values: &HashMap<String, SignalValue>) -> Result<Vec<u8>, CodecError> {
+        if let Some(unknown) = values.keys().find(|name| self.get_sig(name).is_none()) {
+            return Err(CodecError::UnknownSignal {
+                message: self.name.clone(),
+                signal: unknown.clone(),
+            });
+        }
+
+        let mut data = vec![0u8; self.length as usize];
+        let mut raw_values: HashMap<&str, u64> = HashMap::new();
+
+        for sigbit in &self.signals {
+            let sig = &sigbit.sig;
+            let value = values.get(&sig.name);
+
+            if !is_active(sig, &raw_values) {
+                if value.is_some() {
+                    return Err(CodecError::InactiveSignal {
+                        message: self.name.clone(),
+                        signal: sig.name.clone(),
+                    });
+                }
+                continue;
+            }
+
+            let Some(value) = value else {
+                return Err(CodecError::MissingSignal {
+                    message: self.name.clone(),
+                    signal: sig.name.clone(),
+                });
+            };
+
+            let bits = Self::signal_bits(sig, value)?;
+            raw_values.insert(&sig.name, bits);
+
+            for (i, pos) in sigbit.bit_positions().enumerate() {
+                if bits >> i & 1 == 1 {
+                    data[pos as usize / 8] |= 1 << (pos % 8);
+                }
+            }
+        }
+
+        Ok(data)
+    }
+
+    /// Bits of a signal's raw value, checked against the signal's range.
+    fn signal_bits(sig: &CANSignal, value: &SignalValue) -> Result<u64, CodecError> {
+        let (lo, hi) = raw_limits(sig);
+        let mask = u64::MAX >> (u64::BITS - sig.width.min(64));
+
+        let raw = match value {
+            SignalValue::Raw(raw) => *raw,
+
+            SignalValue::Enumerated(name) => {
+                let Some(&v) = sig.enumerated_values.get_by_left(name) else {
+                    return Err(CodecError::UnknownEnumeratedValue {
+                        signal: sig.name.clone(),
+                        name: name.clone(),
+                    });
+                };
+
+                // enumerated values are bit patterns, even for signed signals
+                if v & !mask != 0 {
+                    return Err(CodecError::RawOutOfRange {
+                        signal: sig.name.clone(),
+                        value: RawValue::Unsigned(v),
+                        width: sig.width,
+                    });
+                }
+
+                return Ok(v);
+            }
+
+            SignalValue::Physical(p) => {
+                let (min, max) = sig.physical_range();
+                let min = sig.min.unwrap_or(min);
+                let max = sig.max.unwrap_or(max);
+
+                let raw = (p - sig.offset.unwrap_or(0.0)) / sig.scale.unwrap_or(1.0);
+
+                if sig.value_type != SignalValueType::Integer {
+                    if !(min..=max).contains(p) {
+                        return Err(CodecError::PhysicalOutOfRange {
+                            signal: sig.name.clone(),
+                            value: *p,
+                            min,
+                            max,
+                        });
+                    }
+
+                    return Ok(float_bits(sig, raw));
+                }
+
+                let raw = raw.round();
+
+                // the physical range of the width can be slightly off the
+                // raw limits after scaling, so check both
+                if !(min..=max).contains(p) || raw < lo as f64 || raw > hi as f64 {
+                    return Err(CodecError::PhysicalOutOfRange {
+                        signal: sig.name.clone(),
+                        value: *p,
+                        min,
+                        max,
+                    });
+                }
+
+                if sig.twos_complement {
+                    RawValue::Signed(raw as i64)
+                } else {
+                    RawValue::Unsigned(raw as u64)
+                }
+            }
+        };
+
+        if sig.value_type != SignalValueType::Integer {
+            return Ok(float_bits(sig, raw.as_f64()));
+        }
+
+        let v = raw.as_i128();
+        if v < lo || v > hi {
+            return Err(CodecError::RawOutOfRange {
+                signal: sig.name.clone(),
+                value: raw,
+                width: sig.width,
+            });
+        }
+
+        Ok(v as u64 & mask)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::*;
+
+    fn msg() -> CANMessage {
+        CANMessage::builder()
+            .name("Status")
+            .id(0x10)
+            .add_signals([
+                new_sig()
+                    .name("mode")
+                    .width(2)
+                    .add_enumerated_value("IDLE", 0)
+                    .unwrap()
+                    .add_enumerated_value("RUN", 1)
+                    .unwrap()
+                    .build()
+                    .unwrap(),
+                new_sig()
+                    .name("temp")
+                    .width(8)
+                    .twos_complement(true)
+                    .scale(Some(0.5))
+                    .offset(Some(20.))
+                    .max(Some(60.))
+                    .build()
+                    .unwrap(),
+                new_sig()
+                    .name("speed")
+                    .width(12)
+                    .byte_order(ByteOrder::BigEndian)
+                    .mux(Some(CANSignalMux {
+                        multiplexor: "mode".into(),
+                        values: vec![1],
+                    }))
+                    .build()
+                    .unwrap(),
+            ])
+            .unwrap()
+            .build()
+            .unwrap()
+    }
+
+    fn values<const N: usize>(v: [(&str, SignalValue); N]) -> HashMap<String, SignalValue> {
+        v.into_iter().map(|(n, v)| (n.into(), v)).collect()
+    }
+
+    #[test]
+    fn round_trip() {
+        let msg = msg();
+
+        let data = msg
+            .encode(&values([
+                ("mode", "RUN".into()),
+                ("temp", (-10.).into()),
+                ("speed", SignalValue::Raw(RawValue::Unsigned(0xABC))),
+            ]))
+            .unwrap();
+
+        let decoded = msg.decode(&data).unwrap();
+        assert_eq!(decoded.len(), 3);
+        assert_eq!(decoded[0].enumerated.as_deref(), Some("RUN"));
+        assert_eq!(decoded[1].raw, RawValue::Signed(-60));
+        assert_eq!(decoded[1].physical, -10.);
+        assert!(decoded[1].in_range);
+        assert_eq!(decoded[2].raw, RawValue::Unsigned(0xABC));
+
+        // inactive signals are not decoded
+        let decoded = msg.decode(&[0, 0, 0, 0]).unwrap();
+        assert_eq!(decoded.len(), 2);
+        assert_eq!(decoded[1].physical, 20.);
+
+        // out of range values are still decoded
+        let decoded = msg.decode(&[0xFC, 0x01, 0, 0]).unwrap();
+        assert_eq!(decoded[1].raw, RawValue::Signed(127));
+        assert!(!decoded[1].in_range);
+
+        assert!(matches!(
+            msg.decode(&[0]),
+            Err(CodecError::FrameTooShort { expected: 4, .. })
+        ));
+    }
+
+    #[test]
+    fn big_endian() {
+        let msg = CANMessage::builder()
+            .name("Big")
+            .id(0x10)
+            .add_signals([
+                new_sig()
+                    .name("a")
+                    .width(12)
+                    .byte_order(ByteOrder::BigEndian)
+                    .build()
+                    .unwrap(),
+                new_sig()
+                    .name("b")
+                    .width(12)
+                    .byte_order(ByteOrder::BigEndian)
+                    .twos_complement(true)
+                    .build()
+                    .unwrap(),
+                new_sig().name("c").width(4).build().unwrap(),
+            ])
+            .unwrap()
+            .build()
+            .unwrap();
+        let data = [0x12, 0x3F, 0xFE, 0x05];
+
+        let decoded = msg.decode(&data).unwrap();
+        assert_eq!(decoded[0].raw, RawValue::Unsigned(0x123));
+        assert_eq!(decoded[1].raw, RawValue::Signed(-2));
+        assert_eq!(decoded[2].raw, RawValue::Unsigned(0x5));
+
+        let values = decoded
+            .into_iter()
+            .map(|d| (d.name, SignalValue::Raw(d.raw)))
+            .collect();
+        assert_eq!(msg.encode(&values).unwrap(), data);
+    }
+
+    #[test]
+    fn float_round_trip() {
+        let msg = CANMessage::builder()
+            .name("Imu")
+            .id(0x20)
+            .fd(true)
+            .add_signals([
+                new_sig()
+                    .name("accel")
+                    .width(32)
+                    .value_type(SignalValueType::Float)
+                    .build()
+                    .unwrap(),
+                new_sig()
+                    .name("lat")
+                    .width(64)
+                    .value_type(SignalValueType::Double)
+                    .scale(Some(2.))
+                    .build()
+                    .unwrap(),
+            ])
+            .unwrap()
+            .build()
+            .unwrap();
+
+        let data = msg
+            .encode(&values([("accel", (-9.81).into()), ("lat", 47.25.into())]))
+            .unwrap();
+        assert_eq!(data[..4], (-9.81f32).to_le_bytes());
+        assert_eq!(data[4..12], 23.625f64.to_le_bytes());
+
+        let decoded = msg.decode(&data).unwrap();
+        assert_eq!(decoded[0].raw, RawValue::Float((-9.81f32).into()));
+        assert_eq!(decoded[1].raw, RawValue::Float(23.625));
+        assert_eq!(decoded[1].physical, 47.25);
+    }
+
+    #[test]
+    fn encode_errors() {
+        let msg = msg();
+
+        assert!(matches!(
+            msg.encode(&values([("mode", "IDLE".into())])),
+            Err(CodecError::MissingSignal { signal, .. }) if signal == "temp"
+        ));
+        assert!(matches!(
+            msg.encode(&values([
+                ("mode", "IDLE".into()),
+                ("temp", 0.0.into()),
+                ("speed", 0.0.into())
+            ])),
+            Err(CodecError::InactiveSignal { signal, .. }) if signal == "speed"
+        ));
+        assert!(matches!(
+            msg.encode(&values([("mode", "STOP".into()), ("temp", 0.0.into())])),
+            Err(CodecError::UnknownEnumeratedValue { .. })
+        ));
+        assert!(matches!(
+            msg.encode(&values([("mode", "IDLE".into()), ("temp", 61.0.into())])),
+            Err(CodecError::PhysicalOutOfRange { max, .. }) if max == 60.
+        ));
+        assert!(matches!(
+            msg.encode(&values([("mode", "IDLE".into()), ("temp", (-45.).into())])),
+            Err(CodecError::PhysicalOutOfRange { .. })
+        ));
+        assert!(matches!(
+            msg.encode(&values([
+                ("mode", SignalValue::Raw(RawValue::Unsigned(4))),
+                ("temp", 0.0.into())
+            ])),
+            Err(CodecError::RawOutOfRange { width: 2, .. })
+        ));
+        assert!(matches!(
+            msg.encode(&values([("nope", 0.0.into())])),
+            Err(CodecError::UnknownSignal { .. })
+        ));
+    }
+}
@@ -0,0 +1,759 @@
+use std::fmt::Display;
+
+use serde::Serialize;
+
+use crate::*;
+
+/// Whether a [`Change`] keeps nodes built against the old network working.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Compatibility {
+    /// Frames on the bus are unaffected, or old nodes can safely ignore the change.
+    Compatible,
+
+    /// Nodes built against the old network will misinterpret or miss frames.
+    Breaking,
+}
+
+impl Display for Compatibility {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Compatibility::Compatible => write!(f, "compatible"),
+            Compatibility::Breaking => write!(f, "breaking"),
+        }
+    }
+}
+
+/// A difference between two networks, found by [`CANNetwork::diff()`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Change {
+    /// Whether this change breaks the wire format.
+    pub compatibility: Compatibility,
+
+    /// What changed.
+    #[serde(flatten)]
+    pub kind: ChangeKind,
+}
+
+/// What changed between two networks. Items are matched up by name.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "change", rename_all = "snake_case")]
+pub enum ChangeKind {
+    NodeAdded {
+        node: String,
+    },
+    NodeRemoved {
+        node: String,
+    },
+    MessageAdded {
+        message: String,
+    },
+    MessageRemoved {
+        message: String,
+    },
+    MessageChanged {
+        message: String,
+        field: String,
+        old: String,
+        new: String,
+    },
+    /// A message ID now belongs to a different message.
+    IdReused {
+        id: CANId,
+        old_message: String,
+        new_message: String,
+    },
+    RxAdded {
+        node: String,
+        message: String,
+    },
+    RxRemoved {
+        node: String,
+        message: String,
+    },
+    SignalAdded {
+        message: String,
+        signal: String,
+    },
+    SignalRemoved {
+        message: String,
+        signal: String,
+    },
+    SignalChanged {
+        message: String,
+        signal: String,
+        field: String,
+        old: String,
+        new: String,
+    },
+    EnumeratedValueAdded {
+        message: String,
+        signal: String,
+        value: u64,
+        name: String,
+    },
+    EnumeratedValueRemoved {
+        message: String,
+        signal: String,
+        value: u64,
+        name: String,
+    },
+    EnumeratedValueRenamed {
+        message: String,
+        signal: String,
+        value: u64,
+        old: String,
+        new: String,
+    },
+}
+
+impl Display for Change {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        use ChangeKind::*;
+
+        write!(f, "[{}] ", self.compatibility)?;
+
+        match &self.kind {
+            NodeAdded { node } => write!(f, "Node `{node}` added"),
+            NodeRemoved { node } => write!(f, "Node `{node}` removed"),
+            MessageAdded { message } => write!(f, "Message `{message}` added"),
+            MessageRemoved { message } => write!(f, "Message `{message}` removed"),
+            MessageChanged {
+                message,
+                field,
+                old,
+                new,
+            } => write!(f, "Message `{message}`: {field} changed from {old} to {new}"),
+            IdReused {
+                id,
+                old_message,
+                new_message,
+            } => write!(
+                f,
+                "Message ID {id} moved from `{old_message}` to `{new_message}`"
+            ),
+            RxAdded { node, message } => {
+                write!(f, "Node `{node}` now receives message `{message}`")
+            }
+            RxRemoved { node, message } => {
+                write!(f, "Node `{node}` no longer receives message `{message}`")
+            }
+            SignalAdded { message, signal } => {
+                write!(f, "Signal `{signal}` added to message `{message}`")
+            }
+            SignalRemoved { message, signal } => {
+                write!(f, "Signal `{signal}` removed from message `{message}`")
+            }
+            SignalChanged {
+                message,
+                signal,
+                field,
+                old,
+                new,
+            } => write!(
+                f,
+                "Signal `{signal}` in message `{message}`: {field} changed from {old} to {new}"
+            ),
+            EnumeratedValueAdded {
+                message,
+                signal,
+                value,
+                name,
+            } => write!(
+                f,
+                "Enumerated value `{name}` ({value}) added to signal `{signal}` in message `{message}`"
+            ),
+            EnumeratedValueRemoved {
+                message,
+                signal,
+                value,
+                name,
+            } => write!(
+                f,
+                "Enumerated value `{name}` ({value}) removed from signal `{signal}` in message `{message}`"
+            ),
+            EnumeratedValueRenamed {
+                message,
+                signal,
+                value,
+                old,
+                new,
+            } => write!(
+                f,
+                "Enumerated value {value} of signal `{signal}` in message `{message}` renamed from `{old}` to `{new}`"
+            ),
+        }
+    }
+}
+
+/// Formatting of field values in [`ChangeKind::MessageChanged`] and
+/// [`ChangeKind::SignalChanged`].
+trait FieldValue: PartialEq {
+    fn show(&self) -> String;
+}
+
+macro_rules! display_field_value {
+    ($($t:ty),*) => {
+        $(impl FieldValue for $t {
+            fn show(&self) -> String {
+                self.to_string()
+            }
+        })*
+    };
+}
+
+display_field_value!(u32, u64, bool, f64, str, String, CANId);
+
+impl<T: FieldValue + ?Sized> FieldValue for &T {
+    fn show(&self) -> String {
+        (*self).show()
+    }
+}
+
+impl<T: FieldValue> FieldValue for Option<T> {
+    fn show(&self) -> String {
+        self.as_ref().map_or_else(|| "none".into(), |v| v.show())
+    }
+}
+
+impl FieldValue for ByteOrder {
+    fn show(&self) -> String {
+        match self {
+            ByteOrder::LittleEndian => "little endian".into(),
+            ByteOrder::BigEndian => "big endian".into(),
+        }
+    }
+}
+
+impl FieldValue for SignalValueType {
+    fn show(&self) -> String {
+        match self {
+            SignalValueType::Integer => "integer".into(),
+            SignalValueType::Float => "float".into(),
+            SignalValueType::Double => "double".into(),
+        }
+    }
+}
+
+impl FieldValue for CANSignalMux {
+    fn show(&self) -> String {
+        format!("`{}` in {:?}", self.multiplexor, self.values)
+    }
+}
+
+/// Collects changes while diffing.
+struct Differ {
+    changes: Vec<Change>,
+}
+
+impl Differ {
+    fn push(&mut self, compatibility: Compatibility, kind: ChangeKind) {
+        self.changes.push(Change {
+            compatibility,
+            kind,
+        });
+    }
+
+    /// Record a change to a message field if the values differ.
+    fn message_field<T: FieldValue>(
+        &mut self,
+        compatibility: Compatibility,
+        message: &str,
+        field: &str,
+        old: T,
+        new: T,
+    ) {
+        if old != new {
+            self.push(
+                compatibility,
+                ChangeKind::MessageChanged {
+                    message: message.into(),
+                    field: field.into(),
+                    old: old.show(),
+                    new: new.show(),
+                },
+            );
+        }
+    }
+
+    /// Record a change to a signal field if the values differ.
+    fn signal_field<T: FieldValue>(
+        &mut self,
+        compatibility: Compatibility,
+        (message, signal): (&str, &str),
+        field: &str,
+        old: T,
+        new: T,
+    ) {
+        if old != new {
+            self.push(
+                compatibility,
+                ChangeKind::SignalChanged {
+                    message: message.into(),
+                    signal: signal.into(),
+                    field: field.into(),
+                    old: old.show(),
+                    new: new.show(),
+                },
+            );
+        }
+    }
+
+    fn nodes(&mut self, old: &CANNetwork, new: &CANNetwork) {
+        for node in old.iter_nodes() {
+            if new.node_by_name(&node.name).is_none() {
+                self.push(
+                    Compatibility::Compatible,
+                    ChangeKind::NodeRemoved {
+                        node: node.name.clone(),
+                    },
+                );
+            }
+        }
+
+        for node in new.iter_nodes() {
+            if old.node_by_name(&node.name).is_none() {
+                self.push(
+                    Compatibility::Compatible,
+                    ChangeKind::NodeAdded {
+                        node: node.name.clone(),
+                    },
+                );
+            }
+        }
+    }
+
+    fn messages(&mut self, old: &CANNetwork, new: &CANNetwork) {
+        use Compatibility::*;
+
+        for msg in old.iter_messages() {
+            let Some(new_msg) = new.message_by_name(&msg.name) else {
+                self.push(
+                    Breaking,
+                    ChangeKind::MessageRemoved {
+                        message: msg.name.clone(),
+                    },
+                );
+                continue;
+            };
+
+            let name = &msg.name;
+            self.message_field(Breaking, name, "id", msg.id, new_msg.id);
+            self.message_field(Breaking, name, "length", msg.length, new_msg.length);
+            self.message_field(Breaking, name, "fd", msg.fd, new_msg.fd);
+            self.message_field(Breaking, name, "brs", msg.brs, new_msg.brs);
+            self.message_field(
+                Compatible,
+                name,
+                "cycletime",
+                msg.cycletime,
+                new_msg.cycletime,
+            );
+            self.message_field(
+                Compatible,
+                name,
+                "tx_node",
+                msg.tx_node(),
+                new_msg.tx_node(),
+            );
+
+            self.signals(msg, new_msg);
+        }
+
+        for msg in new.iter_messages() {
+            if old.message_by_name(&msg.name).is_none() {
+                self.push(
+                    Compatible,
+                    ChangeKind::MessageAdded {
+                        message: msg.name.clone(),
+                    },
+                );
+            }
+
+            if let Some(old_msg) = old.message_by_id(&msg.id) {
+                if old_msg.name != msg.name {
+                    self.push(
+                        Breaking,
+                        ChangeKind::IdReused {
+                            id: msg.id,
+                            old_message: old_msg.name.clone(),
+                            new_message: msg.name.clone(),
+                        },
+                    );
+                }
+            }
+        }
+    }
+
+    fn signals(&mut self, old: &CANMessage, new: &CANMessage) {
+        use Compatibility::*;
+
+        let message = old.name.as_str();
+
+        for sigbit in &old.signals {
+            let sig = &sigbit.sig;
+            let Some(new_sigbit) = new.get_sig(&sig.name) else {
+                self.push(
+                    Breaking,
+                    ChangeKind::SignalRemoved {
+                        message: message.into(),
+                        signal: sig.name.clone(),
+                    },
+                );
+                continue;
+            };
+            let new_sig = &new_sigbit.sig;
+
+            let at = (message, sig.name.as_str());
+            self.signal_field(
+                Breaking,
+                at,
+                "start bit",
+                sigbit.start(),
+                new_sigbit.start(),
+            );
+            self.signal_field(Breaking, at, "width", sig.width, new_sig.width);
+            self.signal_field(
+                Breaking,
+                at,
+                "byte order",
+                sig.byte_order,
+                new_sig.byte_order,
+            );
+            self.signal_field(
+                Breaking,
+                at,
+                "twos_complement",
+                sig.twos_complement,
+                new_sig.twos_complement,
+            );
+            self.signal_field(
+                Breaking,
+                at,
+                "value type",
+                sig.value_type,
+                new_sig.value_type,
+            );
+            self.signal_field(Breaking, at, "scale", sig.scale, new_sig.scale);
+            self.signal_field(Breaking, at, "offset", sig.offset, new_sig.offset);
+            self.signal_field(Breaking, at, "mux", &sig.mux, &new_sig.mux);
+            self.signal_field(Compatible, at, "unit", &sig.unit, &new_sig.unit);
+            self.signal_field(Compatible, at, "min", sig.min, new_sig.min);
+            self.signal_field(Compatible, at, "max", sig.max, new_sig.max);
+            self.signal_field(
+                Compatible,
+                at,
+                "description",
+                &sig.description,
+                &new_sig.description,
+            );
+            self.signal_field(
+                Compatible,
+                at,
+                "initial value",
+                sig.initial_value,
+                new_sig.initial_value,
+            );
+            self.signal_field(
+                Compatible,
+                at,
+                "SNA value",
+                sig.sna_value,
+                new_sig.sna_value,
+            );
+            self.signal_field(
+                Compatible,
+                at,
+                "error value",
+                sig.error_value,
+                new_sig.error_value,
+            );
+            self.signal_field(
+                Compatible,
+                at,
+                "value table",
+                &sig.value_table,
+                &new_sig.value_table,
+            );
+
+            self.enumerated_values(message, sig, new_sig);
+        }
+
+        for sigbit in &new.signals {
+            if old.get_sig(&sigbit.sig.name).is_none() {
+                self.push(
+                    Compatible,
+                    ChangeKind::SignalAdded {
+                        message: message.into(),
+                        signal: sigbit.sig.name.clone(),
+                    },
+                );
+            }
+        }
+    }
+
+    /// Enumerated values are matched up by value, since that's what is on the wire.
+    fn enumerated_values(&mut self, message: &str, old: &CANSignal, new: &CANSignal) {
+        let mut values: Vec<u64> = old
+            .enumerated_values
+            .right_values()
+            .chain(new.enumerated_values.right_values())
+            .copied()
+            .collect();
+        values.sort();
+        values.dedup();
+
+        for value in values {
+            let old_name = old.enumerated_values.get_by_right(&value);
+            let new_name = new.enumerated_values.get_by_right(&value);
+
+            let (compatibility, kind) = match (old_name, new_name) {
+                (Some(name), None) => (
+                    Compatibility::Breaking,
+                    ChangeKind::EnumeratedValueRemoved {
+                        message: message.into(),
+                        signal: old.name.clone(),
+                        value,
+                        name: name.clone(),
+                    },
+                ),
+                (None, Some(name)) => (
+                    Compatibility::Compatible,
+                    ChangeKind::EnumeratedValueAdded {
+                        message: message.into(),
+                        signal: old.name.clone(),
+                        value,
+                        name: name.clone(),
+                    },
+                ),
+                (Some(o), Some(n)) if o != n => (
+                    Compatibility::Compatible,
+                    ChangeKind::EnumeratedValueRenamed {
+                        message: message.into(),
+                        signal: old.name.clone(),
+                        value,
+                        old: o.clone(),
+                        new: n.clone(),
+                    },
+                ),
+                _ => continue,
+            };
+
+            self.push(compatibility, kind);
+        }
+    }
+
+    fn rx(&mut self, old: &CANNetwork, new: &CANNetwork) {
+        for node in new.iter_nodes() {
+            let Some(old_node) = old.node_by_name(&node.name) else {
+                continue;
+            };
+
+            let mut removed: Vec<_> = old_node
+                .rx_messages
+                .keys()
+                .filter(|m| !node.rx_messages.contains_key(*m))
+                .collect();
+            removed.sort();
+
+            let mut added: Vec<_> = node
+                .rx_messages
+                .keys()
+                .filter(|m| !old_node.rx_messages.contains_key(*m))
+                .collect();
+            added.sort();
+
+            for message in removed {
+                self.push(
+                    Compatibility::Compatible,
+                    ChangeKind::RxRemoved {
+                        node: node.name.clone(),
+                        message: message.clone(),
+                    },
+                );
+            }
+
+            for message in added {
+                self.push(
+                    Compatibility::Compatible,
+                    ChangeKind::RxAdded {
+                        node: node.name.clone(),
+                        message: message.clone(),
+                    },
+                );
+            }
+        }
+    }
+}
+
+impl CANNetwork {
+    /// Compare this network against a newer version of it.
+    ///
+    /// Nodes, messages and signals are matched up by name, so a rename shows
+    /// up as a removal and an addition.
+    pub fn diff(&self, new: &CANNetwork) -> Vec<Change> {
+        let mut differ = Differ {
+            changes: Vec::new(),
+        };
+
+        differ.nodes(self, new);
+        differ.messages(self, new);
+        differ.rx(self, new);
+
+        differ.changes
+    }
+}
+
+impl CANMessage {
+    /// Wire-breaking differences between this message and another copy of it,
+    /// e.g. on another bus. IDs and transmitters are not compared.
+    pub(crate) fn layout_changes(&self, other: &CANMessage) -> Vec<Change> {
+        let mut differ = Differ {
+            changes: Vec::new(),
+        };
+
+        let name = &self.name;
+        differ.message_field(
+            Compatibility::Breaking,
+            name,
+            "length",
+            self.length,
+            other.length,
+        );
+        differ.message_field(Compatibility::Breaking, name, "fd", self.fd, other.fd);
+        differ.message_field(Compatibility::Breaking, name, "brs", self.brs, other.brs);
+        differ.signals(self, other);
+
+        differ
+            .changes
+            .into_iter()
+            .filter(|c| c.compatibility == Compatibility::Breaking)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::*;
+
+    fn net(sigs: Vec<(u32, CANSignal)>, id: u32, rx: bool) -> CANNetwork {
+        let mut net = CANNetwork::new();
+        net.add_node("A").unwrap();
+        net.add_node("B").unwrap();
+
+        let msg = CANMessage::builder()
+            .name("Status")
+            .id(id)
+            .tx_node("A")
+            .add_signals_fixed(sigs)
+            .unwrap()
+            .build()
+            .unwrap();
+        net.insert_msg(msg).unwrap();
+
+        if rx {
+            net.set_message_rx_by_node("Status", "B").unwrap();
+        }
+
+        net
+    }
+
+    fn mode(values: &[(&str, u64)]) -> CANSignal {
+        let mut sig = new_sig().name("mode").width(2);
+        for (name, value) in values {
+            sig = sig.add_enumerated_value(name, *value).unwrap();
+        }
+        sig.build().unwrap()
+    }
+
+    #[test]
+    fn diff_identical() {
+        let a = net(vec![(0, basic_sig("x"))], 0x10, true);
+        assert!(a.diff(&a).is_empty());
+    }
+
+    #[test]
+    fn diff_classifies() {
+        let old = net(
+            vec![
+                (0, mode(&[("OFF", 0), ("ON", 1)])),
+                (2, basic_sig("x")),
+                (3, basic_sig("gone")),
+            ],
+            0x10,
+            true,
+        );
+        let new = net(
+            vec![
+                (0, mode(&[("OFF", 0), ("ENABLED", 1), ("FAULT", 2)])),
+                (
+                    4,
+                    new_sig()
+                        .name("x")
+                        .width(1)
+                        .unit(Some("V".into()))
+                        .build()
+                        .unwrap(),
+                ),
+                (5, basic_sig("extra")),
+            ],
+            0x10,
+            false,
+        );
+
+        let changes: Vec<_> = old
+            .diff(&new)
+            .into_iter()
+            .map(|c| (c.compatibility, c.to_string()))
+            .collect();
+
+        use Compatibility::*;
+        assert_eq!(
+            changes,
+            [
+                (Compatible, "[compatible] Enumerated value 1 of signal `mode` in message `Status` renamed from `ON` to `ENABLED`".into()),
+                (Compatible, "[compatible] Enumerated value `FAULT` (2) added to signal `mode` in message `Status`".into()),
+                (Breaking, "[breaking] Signal `x` in message `Status`: start bit changed from 2 to 4".into()),
+                (Compatible, "[compatible] Signal `x` in message `Status`: unit changed from none to V".into()),
+                (Breaking, "[breaking] Signal `gone` removed from message `Status`".into()),
+                (Compatible, "[compatible] Signal `extra` added to message `Status`".into()),
+                (Compatible, "[compatible] Node `B` no longer receives message `Status`".into()),
+            ]
+        );
+    }
+
+    #[test]
+    fn diff_id_reuse() {
+        let old = net(vec![(0, basic_sig("x"))], 0x10, true);
+
+        let mut new = net(vec![(0, basic_sig("x"))], 0x11, true);
+        let other = CANMessage::builder()
+            .name("Other")
+            .id(0x10)
+            .build()
+            .unwrap();
+        new.insert_msg(other).unwrap();
+
+        let kinds: Vec<_> = old.diff(&new).into_iter().map(|c| c.kind).collect();
+        assert_eq!(
+            kinds,
+            [
+                ChangeKind::MessageChanged {
+                    message: "Status".into(),
+                    field: "id".into(),
+                    old: "0x10".into(),
+                    new: "0x11".into()
+                },
+                ChangeKind::MessageAdded {
+                    message: "Other".into()
+                },
+                ChangeKind::IdReused {
+                    id: CANId::Standard(0x10),
+                    old_message: "Status".into(),
+                    new_message: "Other".into()
+                },
+            ]
+        );
+    }
+}
@@ -3,6 +3,8 @@ use std::fmt::Display;
 use derive_builder::UninitializedFieldError;
 use thiserror::Error;
 
+use crate::CANId;
+
 fn maybe_space_name<T: Display>(opt: &Option<T>) -> String {
     match opt {
         Some(s) => format!(" `{s}`"),
@@ -26,17 +28,62 @@ pub enum CANConstructionError {
     #[error("Refusing to infer width when width already specified of signal{}", maybe_space_name(.0))]
     SignalWidthAlreadySpecified(Option<String>),
 
+    #[error("Signal `{0}` has minimum {1}, which is greater than its maximum {2}.")]
+    SignalMinAboveMax(String, f64, f64),
+
+    #[error(
+        "Signal `{0}` has physical limit {1}, but its width, scale and offset can only \
+            represent {2} to {3}."
+    )]
+    SignalRangeNotRepresentable(String, f64, f64, f64),
+
+    #[error("Signal `{0}` is {1} bits wide, but integer signals can be at most 64 bits wide.")]
+    SignalTooWide(String, u32),
+
+    #[error("Signal `{0}` is {1} bits wide, but its floating point value type needs {2} bits.")]
+    FloatSignalWidth(String, u32, u32),
+
+    #[error("Floating point signal `{0}` cannot also be twos-complement.")]
+    FloatSignalTwosComplement(String),
+
+    #[error("Signal `{0}` has {1} {2}, which does not fit in {3} bits.")]
+    SignalRawValueOutOfRange(String, &'static str, u64, u32),
+
+    #[error("Floating point signal `{0}` cannot have {1}.")]
+    FloatSignalRawValue(String, &'static str),
+
     #[error("Enumerated value name `{0}` already exists for signal (previous value = {1});")]
     EnumeratedValueNameAlreadyExists(String, u64),
 
     #[error("Enumerated value `{1}` already named as `{0}`; values can only be named once")]
     EnumeratedValueValueAlreadyNamed(String, u64),
 
+    #[error("Value table with name `{0}` already exists in network.")]
+    ValueTableNameAlreadyExists(String),
+
+    #[error("Value table name `{0}` includes invalid character `{1}`.")]
+    ValueTableNameInvalidChar(String, char),
+
+    #[error("Value table name is empty.")]
+    ValueTableNameEmpty,
+
+    #[error("Value table `{0}` does not exist.")]
+    ValueTableDoesNotExist(String),
+
+    #[error("Signal `{0}` uses value table `{1}` but has different enumerated values.")]
+    SignalValueTableMismatch(String, String),
+
     #[error("Message with name `{0}` already exists in network.")]
     MessageNameAlreadyExists(String),
 
-    #[error("Message with id 0x{0:x} already exists in network.")]
-    MessageIdAlreadyExists(u32),
+    #[error("Message with id {0} already exists in network.")]
+    MessageIdAlreadyExists(CANId),
+
+    #[error("Message ID 0x{0:x} does not fit in a {1}-bit identifier.")]
+    MessageIdOutOfRange(u32, u32),
+
+    #[error("Message `{0}` uses bit rate switching but is not a CAN FD message.")]
+    MessageBrsWithoutFd(String),
 
     #[error("Message name `{0}` includes invalid character `{1}`.")]
     MessageNameInvalidChar(String, char),
@@ -44,21 +91,75 @@ pub enum CANConstructionError {
     #[error("Message name is empty.")]
     MessageNameEmpty,
 
+    #[error("Naming prefix `{0}` includes invalid character `{1}`.")]
+    NamingPrefixInvalidChar(String, char),
+
     #[error("Message `{0}` does not exist.")]
     MessageDoesNotExist(String),
 
     #[error("Message `{0}` is not a template")]
     MessageIsNotATemplate(String),
 
+    #[error("Message `{0}` is not a template instance")]
+    MessageIsNotATemplateInstance(String),
+
+    #[error(
+        "Signal `{0}` in template instance `{1}` can only override its scale or offset if \
+            its template gives it one."
+    )]
+    TemplateSignalOverride(String, String),
+
+    #[error("Message `{0}` claims {1} template signals, but is not a template instance with that many signals.")]
+    TemplateSignalsMismatch(String, usize),
+
     #[error("Template message name `{0}` already exists in network.")]
     TemplateMessageNameAlreadyExists(String),
 
+    #[error("Template message `{0}` does not exist.")]
+    TemplateMessageDoesNotExist(String),
+
+    #[error("Template message `{0}` is still used by message `{1}`.")]
+    TemplateMessageInUse(String, String),
+
+    #[error("Signal `{0}` does not exist in message `{1}`.")]
+    SignalDoesNotExist(String, String),
+
+    #[error("Template message `{1}` is stored under the name `{0}`.")]
+    TemplateMessageKeyMismatch(String, String),
+
+    #[error("Value table `{1}` is stored under the name `{0}`.")]
+    ValueTableKeyMismatch(String, String),
+
+    #[error("Message `{0}` is a raw message but has signals.")]
+    RawMessageHasSignals(String),
+
+    #[error("Message `{0}` has length {1}, but its signals need a length of {2}.")]
+    MessageLengthMismatch(String, u32, u32),
+
+    #[error("Node `{0}` and message `{1}` disagree on whether `{0}` transmits `{1}`.")]
+    NodeTxMessageMismatch(String, String),
+
+    #[error("Network format version {0} is not supported (expected {1}).")]
+    UnsupportedFormatVersion(u32, u32),
+
     #[error("Node with name `{0}` already exists in network.")]
     NodeAlreadyExists(String),
 
     #[error("Node with name `{0}` does not exist in network.")]
     NodeDoesNotExist(String),
 
+    #[error("Node `{0}` is not attached to bus `{1}`.")]
+    NodeNotOnBus(String, String),
+
+    #[error("Bus with name `{0}` already exists in system.")]
+    BusAlreadyExists(String),
+
+    #[error("Bus `{0}` does not exist.")]
+    BusDoesNotExist(String),
+
+    #[error("Message `{0}` cannot be routed from bus `{1}` onto the same bus.")]
+    RouteWithinBus(String, String),
+
     #[error(
         "Signal `{0}` has start bit {1}, which precedes previous signal `{2}`'s start bit of \
             {3}. Signals must be added to message in order."
@@ -71,6 +172,17 @@ pub enum CANConstructionError {
     #[error("Signal `{0}` does not fit in message and would end at bit {1}; max is {2}")]
     SignalWillNotFitInMessage(String, u32, u32),
 
+    #[error(
+        "Signal `{0}` is multiplexed by `{1}`, which is not an earlier signal in this message."
+    )]
+    MultiplexorDoesNotExist(String, String),
+
+    #[error("Signal `{0}` is multiplexed by `{1}` but has no multiplexor values.")]
+    MultiplexorValuesEmpty(String, String),
+
+    #[error("Signal `{0}` is present when multiplexor `{1}` is {2}, which does not fit in `{1}`.")]
+    MultiplexorValueOutOfRange(String, String, u64),
+
     #[error("Missing required field `{0}`")]
     UninitializedFieldError(String),
 }
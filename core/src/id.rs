@@ -0,0 +1,138 @@
+use std::fmt::Display;
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::*;
+
+/// A CAN message identifier.
+///
+/// Standard and extended identifiers with the same raw value are distinct
+/// identifiers, and both can be present on the same bus.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+pub enum CANId {
+    /// 11-bit standard identifier.
+    Standard(u32),
+
+    /// 29-bit extended identifier.
+    Extended(u32),
+}
+
+impl CANId {
+    /// Highest valid standard (11-bit) identifier.
+    pub const MAX_STANDARD: u32 = 0x7FF;
+
+    /// Highest valid extended (29-bit) identifier.
+    pub const MAX_EXTENDED: u32 = 0x1FFF_FFFF;
+
+    /// Make a standard identifier, checking that it fits in 11 bits.
+    pub fn standard(id: u32) -> Result<Self, CANConstructionError> {
+        let id = Self::Standard(id);
+        id.check()?;
+        Ok(id)
+    }
+
+    /// Make an extended identifier, checking that it fits in 29 bits.
+    pub fn extended(id: u32) -> Result<Self, CANConstructionError> {
+        let id = Self::Extended(id);
+        id.check()?;
+        Ok(id)
+    }
+
+    /// Make an identifier, choosing a standard identifier if `id` fits in
+    /// 11 bits and an extended identifier otherwise.
+    pub fn infer(id: u32) -> Result<Self, CANConstructionError> {
+        let id = Self::from(id);
+        id.check()?;
+        Ok(id)
+    }
+
+    /// Raw value of this identifier.
+    pub const fn raw(&self) -> u32 {
+        match self {
+            Self::Standard(id) | Self::Extended(id) => *id,
+        }
+    }
+
+    /// Whether this is an extended (29-bit) identifier.
+    pub const fn is_extended(&self) -> bool {
+        matches!(self, Self::Extended(_))
+    }
+
+    /// Width of this identifier in bits.
+    pub const fn bits(&self) -> u32 {
+        match self {
+            Self::Standard(_) => 11,
+            Self::Extended(_) => 29,
+        }
+    }
+
+    /// Check that the raw value fits in this kind of identifier.
+    pub fn check(&self) -> Result<(), CANConstructionError> {
+        let max = match self {
+            Self::Standard(_) => Self::MAX_STANDARD,
+            Self::Extended(_) => Self::MAX_EXTENDED,
+        };
+
+        if self.raw() > max {
+            return Err(CANConstructionError::MessageIdOutOfRange(
+                self.raw(),
+                self.bits(),
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+/// Unchecked conversion that picks standard or extended by value.
+/// See [`CANId::infer()`] for a checked version.
+impl From<u32> for CANId {
+    fn from(id: u32) -> Self {
+        if id <= Self::MAX_STANDARD {
+            Self::Standard(id)
+        } else {
+            Self::Extended(id)
+        }
+    }
+}
+
+impl Display for CANId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Standard(id) => write!(f, "0x{id:x}"),
+            Self::Extended(id) => write!(f, "0x{id:x} (extended)"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn id_ranges() {
+        assert!(CANId::standard(0x7FF).is_ok());
+        assert!(matches!(
+            CANId::standard(0x800),
+            Err(CANConstructionError::MessageIdOutOfRange(0x800, 11))
+        ));
+
+        assert!(CANId::extended(0x10).is_ok());
+        assert!(CANId::extended(0x1FFF_FFFF).is_ok());
+        assert!(matches!(
+            CANId::extended(0x2000_0000),
+            Err(CANConstructionError::MessageIdOutOfRange(0x2000_0000, 29))
+        ));
+    }
+
+    #[test]
+    fn id_inference() {
+        assert_eq!(CANId::infer(0x7FF).unwrap(), CANId::Standard(0x7FF));
+        assert_eq!(CANId::infer(0x800).unwrap(), CANId::Extended(0x800));
+        assert!(CANId::infer(0x2000_0000).is_err());
+
+        // same raw value, different identifiers
+        assert_ne!(CANId::Standard(0x10), CANId::Extended(0x10));
+    }
+}
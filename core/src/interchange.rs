@@ -12,6 +12,10 @@ pub fn sorted_map<S: Serializer, K: Serialize + Ord, V: Serialize>(
     BTreeMap::from_iter(items).serialize(serializer)
 }
 
+pub fn format_version<S: Serializer>(_: &(), serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_u32(crate::CANNetwork::FORMAT_VERSION)
+}
+
 pub fn sorted_bimap_by_second<
     S: Serializer,
     A: Hash + Eq + Serialize + Ord,
@@ -8,14 +8,38 @@ pub use signal::*;
 mod message;
 pub use message::*;
 
+mod id;
+pub use id::*;
+
 mod node;
 
 mod network;
 pub use network::*;
 
+mod naming;
+pub use naming::*;
+
+mod system;
+pub use system::*;
+
 mod error;
 pub use error::*;
 
+mod validate;
+pub use validate::*;
+
+mod diff;
+pub use diff::*;
+
+mod codec;
+pub use codec::*;
+
+mod attribute;
+pub use attribute::*;
+
+mod value_table;
+pub use value_table::*;
+
 pub mod translation;
 pub use translation::TranslationFromOpencan;
 pub use translation::TranslationToOpencan;
@@ -4,10 +4,25 @@ use std::ops::Index;
 use derive_builder::Builder;
 use serde::{Deserialize, Serialize};
 
+use crate::attribute::*;
 use crate::error::*;
+use crate::id::*;
 use crate::signal::*;
 
-const MAX_MESSAGE_BIT: u32 = 63;
+/// Last usable bit in a classic CAN frame (8 bytes).
+const MAX_CLASSIC_MESSAGE_BIT: u32 = 63;
+
+/// Last usable bit in a CAN FD frame (64 bytes).
+const MAX_FD_MESSAGE_BIT: u32 = 511;
+
+/// Payload lengths that a CAN FD DLC can express, in bytes.
+const FD_PAYLOAD_LENGTHS: [u32; 16] = [0, 1, 2, 3, 4, 5, 6, 7, 8, 12, 16, 20, 24, 32, 48, 64];
+
+/// Round a payload length in bytes up to the next length a CAN FD DLC can
+/// express. Returns `None` for lengths over 64 bytes.
+pub fn fd_payload_length(len: u32) -> Option<u32> {
+    FD_PAYLOAD_LENGTHS.into_iter().find(|&l| l >= len)
+}
 
 /// [`CANSignal`] with its position (start bit) in its message.
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -18,13 +33,37 @@ pub struct CANSignalWithPosition {
 
 impl CANSignalWithPosition {
     /// Start bit of signal.
+    ///
+    /// For little-endian signals this is the least significant bit; for
+    /// big-endian signals this is the most significant bit.
     pub const fn start(&self) -> u32 {
         self.bit
     }
 
     /// Last bit of signal.
+    ///
+    /// For little-endian signals this is the most significant bit; for
+    /// big-endian signals this is the least significant bit.
     pub const fn end(&self) -> u32 {
-        self.start() + self.sig.width - 1
+        match self.sig.byte_order {
+            ByteOrder::LittleEndian => self.start() + self.sig.width - 1,
+            ByteOrder::BigEndian => {
+                sawtooth_from_linear(sawtooth_to_linear(self.start()) + self.sig.width - 1)
+            }
+        }
+    }
+
+    /// Positions of each of this signal's bits within the message, starting
+    /// from the signal's least significant bit.
+    ///
+    /// Positions count up from bit 0 of byte 0, so bit 3 of byte 1 is position 11.
+    pub fn bit_positions(&self) -> impl Iterator<Item = u32> + '_ {
+        (0..self.sig.width).map(move |i| match self.sig.byte_order {
+            ByteOrder::LittleEndian => self.start() + i,
+            ByteOrder::BigEndian => {
+                sawtooth_from_linear(sawtooth_to_linear(self.start()) + (self.sig.width - 1 - i))
+            }
+        })
     }
 
     fn name_clone(&self) -> String {
@@ -32,13 +71,61 @@ impl CANSignalWithPosition {
     }
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+/// Convert a sawtooth bit number (bit 7 of byte 0 is 7, bit 0 of byte 1 is 8)
+/// into a linear big-endian bit index (bit 7 of byte 0 is 0, bit 0 of byte 0 is 7).
+const fn sawtooth_to_linear(bit: u32) -> u32 {
+    (bit / 8) * 8 + (7 - bit % 8)
+}
+
+/// Inverse of [`sawtooth_to_linear()`].
+const fn sawtooth_from_linear(linear: u32) -> u32 {
+    (linear / 8) * 8 + (7 - linear % 8)
+}
+
+/// Muxes that must all be satisfied for `sig` to be present, starting with
+/// its own and continuing outwards through any nested multiplexors.
+fn mux_chain<'a>(
+    signals: &'a [CANSignalWithPosition],
+    sig_map: &HashMap<String, usize>,
+    sig: &'a CANSignal,
+) -> Vec<&'a CANSignalMux> {
+    let mut chain = Vec::new();
+    let mut cur = sig;
+
+    while let Some(mux) = &cur.mux {
+        chain.push(mux);
+
+        let Some(&idx) = sig_map.get(&mux.multiplexor) else {
+            break;
+        };
+        cur = &signals[idx].sig;
+    }
+
+    chain
+}
+
+/// Whether signals with these mux chains can never be present in the same frame.
+fn mux_chains_exclusive(a: &[&CANSignalMux], b: &[&CANSignalMux]) -> bool {
+    a.iter().any(|ma| b.iter().any(|mb| ma.excludes(mb)))
+}
+
+/// Per-instance changes to a template signal, for
+/// [`CANMessage::override_template_signal()`]. `None` keeps the template's value.
+#[derive(Debug, Clone, Default)]
+pub struct CANSignalOverride {
+    pub description: Option<String>,
+    pub scale: Option<f64>,
+    pub offset: Option<f64>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
 pub enum CANMessageKind {
     /// A raw message.
     /// Raw messages do not have a fixed length and cannot contain signals.
     Raw,
 
     /// A normal message (does not originate from a template).
+    #[default]
     Independent,
 
     /// A template message.
@@ -48,33 +135,43 @@ pub enum CANMessageKind {
     FromTemplate(String),
 }
 
-impl Default for CANMessageKind {
-    fn default() -> Self {
-        Self::Independent
-    }
-}
-
 /// A validated description of a CAN message.
 #[derive(Debug, Serialize, Deserialize, Clone, Builder)]
+#[serde(try_from = "CANMessageDef")]
 #[builder(build_fn(name = "__build", error = "CANConstructionError", private))]
-#[builder(pattern = "owned")]
+#[builder(pattern = "owned", derive(Clone))]
 pub struct CANMessage {
     /// Message kind.
     #[builder(setter(custom), default)]
-    kind: CANMessageKind,
+    pub(crate) kind: CANMessageKind,
 
     /// Message name.
     #[builder(setter(into))]
     pub name: String,
 
+    /// Description of this message.
+    #[builder(setter(into), default)]
+    #[serde(default)]
+    pub description: Option<String>,
+
     /// Message ID.
-    // todo - extended/non-extended as enum
-    pub id: u32,
+    #[builder(setter(into))]
+    pub id: CANId,
 
     /// Message cycle time in milliseconds.
     #[builder(default)]
     pub cycletime: Option<u32>,
 
+    /// Message is sent as a CAN FD frame.
+    #[builder(default)]
+    #[serde(default)]
+    pub fd: bool,
+
+    /// CAN FD frame uses bit rate switching. Only valid for FD messages.
+    #[builder(default)]
+    #[serde(default)]
+    pub brs: bool,
+
     /// Message length in bytes.
     #[builder(setter(custom), field(type = "u32"))]
     pub length: u32,
@@ -83,52 +180,215 @@ pub struct CANMessage {
     #[builder(setter(into, strip_option), default)]
     pub tx_node: Option<String>,
 
-    /// Signals with positions in this message ordered by start bit.
+    /// Signals with positions in this message, in the order they were added.
+    /// Little-endian signals in the same multiplexed group are ordered by start bit.
     #[builder(setter(custom), field(type = "Vec<CANSignalWithPosition>"))]
     pub signals: Vec<CANSignalWithPosition>,
 
+    /// User-defined attributes of this message.
+    #[builder(default)]
+    #[serde(default)]
+    pub attributes: Attributes,
+
+    /// Number of signals a template instance takes from its template, before
+    /// any signals of its own.
+    #[builder(setter(custom), default)]
+    #[serde(default)]
+    template_signals: usize,
+
+    /// Prefix a template instance gave to its template's signal names.
+    #[builder(setter(custom), default)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    instance_prefix: Option<String>,
+
     #[builder(setter(custom), field(type = "HashMap<String, usize>"))]
     #[serde(skip)]
     sig_map: HashMap<String, usize>,
 }
 
+/// Serialized form of a [`CANMessage`], before the signal map is rebuilt and
+/// the message is validated.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct CANMessageDef {
+    kind: CANMessageKind,
+    name: String,
+    #[serde(default)]
+    description: Option<String>,
+    id: CANId,
+    cycletime: Option<u32>,
+    #[serde(default)]
+    fd: bool,
+    #[serde(default)]
+    brs: bool,
+    length: u32,
+    tx_node: Option<String>,
+    signals: Vec<CANSignalWithPosition>,
+    #[serde(default)]
+    attributes: Attributes,
+    #[serde(default)]
+    template_signals: Option<usize>,
+    #[serde(default)]
+    instance_prefix: Option<String>,
+}
+
+impl TryFrom<CANMessageDef> for CANMessage {
+    type Error = CANConstructionError;
+
+    /// Rebuild a message from its serialized form by adding each signal
+    /// again, which re-runs all of the builder's checks.
+    fn try_from(def: CANMessageDef) -> Result<Self, Self::Error> {
+        let msg = if let CANMessageKind::Raw = def.kind {
+            if !def.signals.is_empty() {
+                return Err(CANConstructionError::RawMessageHasSignals(def.name));
+            }
+
+            let mut msg =
+                CANMessage::new_raw(&def.name, def.id, def.cycletime, def.tx_node.as_deref());
+            msg.description = def.description;
+            msg.fd = def.fd;
+            msg.brs = def.brs;
+            msg.attributes = def.attributes;
+
+            CANMessageBuilder::check_name_validity(&msg.name)?;
+            msg.id.check()?;
+            msg.check_frame_format()?;
+
+            msg
+        } else {
+            // instances from before these were recorded only had their
+            // template's signals, prefixed with their node's name
+            let template_signals = match (&def.kind, def.template_signals) {
+                (CANMessageKind::FromTemplate(_), None) => def.signals.len(),
+                (_, count) => count.unwrap_or_default(),
+            };
+            let instance_prefix = match (&def.kind, def.instance_prefix, &def.tx_node) {
+                (CANMessageKind::FromTemplate(_), None, Some(tx_node)) => {
+                    Some(format!("{tx_node}_"))
+                }
+                (_, prefix, _) => prefix,
+            };
+
+            let shell = CANMessage {
+                kind: def.kind,
+                name: def.name,
+                description: def.description,
+                id: def.id,
+                cycletime: def.cycletime,
+                fd: def.fd,
+                brs: def.brs,
+                length: 0,
+                tx_node: def.tx_node,
+                signals: Vec::new(),
+                attributes: def.attributes,
+                template_signals,
+                instance_prefix,
+                sig_map: HashMap::new(),
+            };
+
+            if shell.template_signals > def.signals.len()
+                || (shell.template_signals > 0
+                    && !matches!(shell.kind, CANMessageKind::FromTemplate(_)))
+            {
+                return Err(CANConstructionError::TemplateSignalsMismatch(
+                    shell.name,
+                    shell.template_signals,
+                ));
+            }
+
+            shell.with_signals(def.signals)?
+        };
+
+        if msg.length != def.length {
+            return Err(CANConstructionError::MessageLengthMismatch(
+                msg.name, def.length, msg.length,
+            ));
+        }
+
+        Ok(msg)
+    }
+}
+
 impl CANMessageBuilder {
     /// Make a [`CANMessage`] from this builder.
     ///
     /// Notes:
     ///     - Message names must be at least one character long and must contain
     ///       only ASCII letters, numbers, and underscores.
-    // todo: check message ID validity and choose extended or non-extended
+    ///     - Message IDs must fit in their kind of identifier (11 or 29 bits).
+    ///     - FD message lengths are rounded up to the next valid CAN FD length.
+    ///     - Classic messages must fit in 8 bytes, and only FD messages may
+    ///       use bit rate switching.
     pub fn build(mut self) -> Result<CANMessage, CANConstructionError> {
         // set message length in bytes
-        self.length = self.signals.last().map_or(0, |s| {
-            let bits = s.end() + 1;
-
-            (bits / 8) + ((bits % 8) != 0) as u32 // ceiling integer divide
-        });
+        self.length = self
+            .signals
+            .iter()
+            .flat_map(|s| s.bit_positions())
+            .max()
+            .map_or(0, |last_bit| (last_bit / 8) + 1);
+
+        if self.fd == Some(true) {
+            // signals are limited to MAX_FD_MESSAGE_BIT, so this always fits
+            self.length = fd_payload_length(self.length).unwrap_or(self.length);
+        }
 
         let msg = self.__build()?;
 
         Self::check_name_validity(&msg.name)?;
+        msg.id.check()?;
+        msg.check_frame_format()?;
 
         Ok(msg)
     }
 
     /// Add a single signal with the next available start bit.
     /// See [`add_signal_fixed()`][CANMessageBuilder::add_signal_fixed()] for more details.
+    ///
+    /// Little-endian signals are placed after the highest bit in use.
+    /// Big-endian signals are placed after the last bit in use in sawtooth order,
+    /// so consecutive big-endian signals are laid out back-to-back.
+    ///
+    /// Bits used only by other multiplexed groups are ignored, so each group
+    /// is laid out from the end of the signals it is present with.
     pub fn add_signal(self, sig: CANSignal) -> Result<Self, CANConstructionError> {
-        let bit = self.signals.last().map_or(0, |s| s.end() + 1);
+        let chain = mux_chain(&self.signals, &self.sig_map, &sig);
+        let used_bits = self
+            .signals
+            .iter()
+            .filter(|s| {
+                !mux_chains_exclusive(&chain, &mux_chain(&self.signals, &self.sig_map, &s.sig))
+            })
+            .flat_map(|s| s.bit_positions());
+
+        let bit = match sig.byte_order {
+            ByteOrder::LittleEndian => used_bits.max().map_or(0, |b| b + 1),
+            ByteOrder::BigEndian => {
+                sawtooth_from_linear(used_bits.map(sawtooth_to_linear).max().map_or(0, |b| b + 1))
+            }
+        };
 
         self.add_signal_fixed(bit, sig)
     }
 
     /// Add a single signal with start bit specified.
     ///
+    /// Big-endian signals use DBC-style sawtooth bit numbering, where the
+    /// start bit is the most significant bit of the signal.
+    ///
+    /// Multiplexed signals must be added after their multiplexor.
+    ///
     /// Checks:
     ///  - signal name does not repeat ([`SignalNameAlreadyExists`][CANConstructionError::SignalNameAlreadyExists])
-    ///  - signals are specified in order([`MessageSignalOutOfOrder`][CANConstructionError::MessageSignalsOutOfOrder])
-    ///  - signals in message do not overlap ([`SignalsOverlap`][CANConstructionError::SignalsOverlap])
+    ///  - multiplexor exists and can hold the mux values ([`MultiplexorDoesNotExist`][CANConstructionError::MultiplexorDoesNotExist],
+    ///    [`MultiplexorValueOutOfRange`][CANConstructionError::MultiplexorValueOutOfRange])
+    ///  - little-endian signals in the same multiplexed group are specified in order
+    ///    ([`MessageSignalOutOfOrder`][CANConstructionError::MessageSignalsOutOfOrder])
+    ///  - signals that can be present together do not overlap ([`SignalsOverlap`][CANConstructionError::SignalsOverlap])
     ///  - signal does not extend past end of message ([`SignalWillNotFitInMessage`][CANConstructionError::SignalWillNotFitInMessage])
+    ///
+    /// Classic messages end at bit 63 and FD messages end at bit 511, so set
+    /// [`fd()`][Self::fd()] before adding signals past the first 8 bytes.
     pub fn add_signal_fixed(
         mut self,
         bit: u32,
@@ -139,39 +399,92 @@ impl CANMessageBuilder {
             return Err(CANConstructionError::SignalNameAlreadyExists(sig.name));
         }
 
-        if let Some(last) = self.signals.last() {
-            // Check that this signal comes after the last signal.
-            if bit <= (last.start()) {
-                return Err(CANConstructionError::MessageSignalsOutOfOrder(
+        // Check that the multiplexor exists and can hold each value
+        if let Some(mux) = &sig.mux {
+            let Some(&idx) = self.sig_map.get(&mux.multiplexor) else {
+                return Err(CANConstructionError::MultiplexorDoesNotExist(
                     sig.name,
-                    bit,
-                    last.name_clone(),
-                    last.start(),
+                    mux.multiplexor.clone(),
+                ));
+            };
+
+            if mux.values.is_empty() {
+                return Err(CANConstructionError::MultiplexorValuesEmpty(
+                    sig.name,
+                    mux.multiplexor.clone(),
                 ));
             }
-            // Check signal ranges don't overlap
-            // The signals are stored sorted by start bit in self.signals, so we
-            // only need to ensure the last signal's end bit is no later than this
-            // signal's start bit
-            if bit <= (last.end()) {
-                return Err(CANConstructionError::SignalsOverlap(
-                    last.name_clone(),
+
+            let width = self.signals[idx].sig.width;
+            if let Some(&v) = mux
+                .values
+                .iter()
+                .find(|&&v| width < u64::BITS && v >> width != 0)
+            {
+                return Err(CANConstructionError::MultiplexorValueOutOfRange(
                     sig.name,
-                    bit,
+                    mux.multiplexor.clone(),
+                    v,
                 ));
             }
         }
 
+        // Check that this signal comes after the last little-endian signal in
+        // the same multiplexed group.
+        // Big-endian signals don't have a meaningful ordering by start bit.
+        if sig.byte_order == ByteOrder::LittleEndian {
+            if let Some(last) = self
+                .signals
+                .iter()
+                .rev()
+                .find(|s| s.sig.byte_order == ByteOrder::LittleEndian && s.sig.mux == sig.mux)
+            {
+                if bit <= (last.start()) {
+                    return Err(CANConstructionError::MessageSignalsOutOfOrder(
+                        sig.name,
+                        bit,
+                        last.name_clone(),
+                        last.start(),
+                    ));
+                }
+            }
+        }
+
         // Check signal end position is not past the end of the message
         let new = CANSignalWithPosition { bit, sig };
-        if new.end() > MAX_MESSAGE_BIT {
+        let max_bit = CANMessage::max_bit(self.fd.unwrap_or_default());
+        if new.end() > max_bit {
             return Err(CANConstructionError::SignalWillNotFitInMessage(
                 new.name_clone(),
                 new.end(),
-                MAX_MESSAGE_BIT,
+                max_bit,
             ));
         }
 
+        // Check signal ranges don't overlap.
+        // Signals of mixed byte order can interleave, so check every bit
+        // against every existing signal that can be present in the same frame.
+        let chain = mux_chain(&self.signals, &self.sig_map, &new.sig);
+        for existing in &self.signals {
+            if mux_chains_exclusive(
+                &chain,
+                &mux_chain(&self.signals, &self.sig_map, &existing.sig),
+            ) {
+                continue;
+            }
+
+            if let Some(overlap) = new
+                .bit_positions()
+                .find(|b| existing.bit_positions().any(|e| e == *b))
+            {
+                return Err(CANConstructionError::SignalsOverlap(
+                    existing.name_clone(),
+                    new.name_clone(),
+                    overlap,
+                ));
+            }
+        }
+
         self.sig_map.insert(new.name_clone(), self.signals.len());
         self.signals.push(new);
 
@@ -208,7 +521,7 @@ impl CANMessageBuilder {
 
     /// Check validity of message name - it should not be empty and should
     /// contain a limited set of characters - `[a-zA-Z0-9_]`.
-    fn check_name_validity(name: &str) -> Result<(), CANConstructionError> {
+    pub(crate) fn check_name_validity(name: &str) -> Result<(), CANConstructionError> {
         if name.is_empty() {
             return Err(CANConstructionError::MessageNameEmpty);
         }
@@ -250,18 +563,24 @@ impl CANMessage {
     /// Create a new raw message.
     pub fn new_raw(
         name: &str,
-        id: u32,
+        id: CANId,
         cycletime: Option<u32>,
         tx_node: Option<&str>,
     ) -> CANMessage {
         CANMessage {
             kind: CANMessageKind::Raw,
             name: name.into(),
+            description: None,
             id,
             cycletime,
+            fd: false,
+            brs: false,
             length: 0, // todo: maybe make it an Option?
             tx_node: tx_node.map(|t| t.into()),
             signals: Default::default(),
+            attributes: Default::default(),
+            template_signals: 0,
+            instance_prefix: None,
             sig_map: Default::default(),
         }
     }
@@ -270,22 +589,141 @@ impl CANMessage {
         &self.kind
     }
 
+    /// Last usable bit in a classic or FD message.
+    const fn max_bit(fd: bool) -> u32 {
+        if fd {
+            MAX_FD_MESSAGE_BIT
+        } else {
+            MAX_CLASSIC_MESSAGE_BIT
+        }
+    }
+
+    /// Largest payload in bytes this message's frame format can carry.
+    pub const fn max_length(&self) -> u32 {
+        Self::max_bit(self.fd) / 8 + 1
+    }
+
+    /// Check that the message length suits its frame format, and that
+    /// bit rate switching is only used with FD frames.
+    pub(crate) fn check_frame_format(&self) -> Result<(), CANConstructionError> {
+        if self.brs && !self.fd {
+            return Err(CANConstructionError::MessageBrsWithoutFd(self.name.clone()));
+        }
+
+        let max_bit = Self::max_bit(self.fd);
+        for sigbit in &self.signals {
+            if sigbit.end() > max_bit {
+                return Err(CANConstructionError::SignalWillNotFitInMessage(
+                    sigbit.name_clone(),
+                    sigbit.end(),
+                    max_bit,
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Make a copy of this message with different signals, laid out and
+    /// checked by a [builder](CANMessageBuilder) as if they were added anew.
+    fn with_signals(
+        &self,
+        signals: Vec<CANSignalWithPosition>,
+    ) -> Result<CANMessage, CANConstructionError> {
+        let mut builder = self.empty_builder();
+
+        for sigbit in signals {
+            sigbit.sig.check()?;
+            builder = builder.add_signal_fixed(sigbit.bit, sigbit.sig)?;
+        }
+
+        builder.build()
+    }
+
+    /// A [builder](CANMessageBuilder) with everything about this message
+    /// except its signals.
+    fn empty_builder(&self) -> CANMessageBuilder {
+        CANMessageBuilder {
+            kind: Some(self.kind.clone()),
+            tx_node: Some(self.tx_node.clone()),
+            template_signals: Some(self.template_signals),
+            instance_prefix: Some(self.instance_prefix.clone()),
+            ..Default::default()
+        }
+        .name(self.name.clone())
+        .description(self.description.clone())
+        .id(self.id)
+        .cycletime(self.cycletime)
+        .fd(self.fd)
+        .brs(self.brs)
+        .attributes(self.attributes.clone())
+    }
+
+    /// Make a copy of this message with one signal edited. Renaming the
+    /// signal also updates signals it multiplexes.
+    pub(crate) fn with_edited_signal(
+        &self,
+        name: &str,
+        edit: impl FnOnce(&mut CANSignal),
+    ) -> Result<CANMessage, CANConstructionError> {
+        let Some(&idx) = self.sig_map.get(name) else {
+            return Err(CANConstructionError::SignalDoesNotExist(
+                name.into(),
+                self.name.clone(),
+            ));
+        };
+
+        let mut signals = self.signals.clone();
+        edit(&mut signals[idx].sig);
+
+        let new_name = signals[idx].sig.name.clone();
+        if new_name != name {
+            for mux in signals.iter_mut().filter_map(|s| s.sig.mux.as_mut()) {
+                if mux.multiplexor == name {
+                    mux.multiplexor = new_name.clone();
+                }
+            }
+        }
+
+        self.with_signals(signals)
+    }
+
     /// Get a [signal](CANSignalWithPosition) from this message by name.
     pub fn get_sig(&self, name: &str) -> Option<&CANSignalWithPosition> {
         let &idx = self.sig_map.get(name)?;
         Some(&self.signals[idx])
     }
 
+    /// Whether any signals in this message are multiplexed.
+    pub fn is_multiplexed(&self) -> bool {
+        self.signals.iter().any(|s| s.sig.mux.is_some())
+    }
+
+    /// Whether the named signal is a multiplexor for other signals in this message.
+    pub fn is_multiplexor(&self, name: &str) -> bool {
+        self.signals
+            .iter()
+            .any(|s| s.sig.mux.as_ref().is_some_and(|m| m.multiplexor == name))
+    }
+
+    /// Muxes that must all be satisfied for a signal in this message to be
+    /// present, starting with the signal's own mux and continuing outwards
+    /// through nested multiplexors. Empty for signals that are always present.
+    pub fn mux_chain<'a>(&'a self, sig: &'a CANSignal) -> Vec<&'a CANSignalMux> {
+        mux_chain(&self.signals, &self.sig_map, sig)
+    }
+
     /// Get transmitting node.
     pub fn tx_node(&self) -> Option<&str> {
         self.tx_node.as_deref()
     }
 
-    /// Create a new CAN message from this template.
+    /// Create a new CAN message from this template, putting `signal_prefix`
+    /// in front of the names of the template's signals.
     pub fn template_instance(
         &self,
         name: &str,
-        id: u32,
+        id: CANId,
         signal_prefix: &str,
         cycletime: Option<u32>,
         tx_node: Option<&str>,
@@ -296,6 +734,8 @@ impl CANMessage {
             ));
         }
 
+        id.check()?;
+
         let mut new = self.clone();
 
         // Change the kind
@@ -316,14 +756,151 @@ impl CANMessage {
 
             sigbit.sig.name = name.clone();
             sig_map.insert(name, i);
+
+            if let Some(mux) = &mut sigbit.sig.mux {
+                mux.multiplexor = format!("{}{}", signal_prefix, mux.multiplexor);
+            }
         }
 
         // replace sig_map
         new.sig_map = sig_map;
+        new.template_signals = new.signals.len();
+        new.instance_prefix = Some(signal_prefix.into());
 
         // done; return new message
         Ok(new)
     }
+
+    /// Copy of this message as forwarded onto another bus by `gateway`, with
+    /// ID `id`. Template instances become independent messages, since their
+    /// template need not exist on the other bus.
+    pub(crate) fn routed(&self, gateway: &str, id: CANId) -> CANMessage {
+        let mut new = self.clone();
+
+        if matches!(new.kind, CANMessageKind::FromTemplate(_)) {
+            new.kind = CANMessageKind::Independent;
+            new.template_signals = 0;
+            new.instance_prefix = None;
+        }
+
+        new.id = id;
+        new.tx_node = Some(gateway.into());
+
+        new
+    }
+
+    /// Get a [builder](CANMessageBuilder) for a template message that starts
+    /// with this template's signals, frame format, cycle time and attributes.
+    /// Further signals are added after this template's.
+    pub fn extend_template(&self, name: &str) -> Result<CANMessageBuilder, CANConstructionError> {
+        if !matches!(self.kind, CANMessageKind::Template) {
+            return Err(CANConstructionError::MessageIsNotATemplate(
+                self.name.clone(),
+            ));
+        }
+
+        let mut builder = self.empty_builder().name(name);
+        for sigbit in &self.signals {
+            builder = builder.add_signal_fixed(sigbit.bit, sigbit.sig.clone())?;
+        }
+
+        Ok(builder)
+    }
+
+    /// Prefix a template instance gave to the names of its template's
+    /// signals, usually from the network's [naming](crate::CANNaming).
+    /// `None` for other kinds of message.
+    pub fn instance_prefix(&self) -> Option<&str> {
+        self.instance_prefix.as_deref()
+    }
+
+    /// Signals that a template instance adds after its template's signals.
+    /// Empty for other kinds of message.
+    pub fn instance_signals(&self) -> &[CANSignalWithPosition] {
+        &self.signals[self.template_signal_count()..]
+    }
+
+    /// Number of signals at the start of `signals` that a template instance
+    /// took from its template. All of them for other kinds of message.
+    fn template_signal_count(&self) -> usize {
+        if matches!(self.kind, CANMessageKind::FromTemplate(_)) {
+            // `signals` may since have been shortened
+            self.template_signals.min(self.signals.len())
+        } else {
+            self.signals.len()
+        }
+    }
+
+    /// Add signals to a template instance, after its template's signals.
+    /// Signals without a start bit are placed after the signals before them.
+    pub fn with_instance_signals(
+        &self,
+        signals: impl IntoIterator<Item = (Option<u32>, CANSignal)>,
+    ) -> Result<CANMessage, CANConstructionError> {
+        if !matches!(self.kind, CANMessageKind::FromTemplate(_)) {
+            return Err(CANConstructionError::MessageIsNotATemplateInstance(
+                self.name.clone(),
+            ));
+        }
+
+        let mut builder = self.empty_builder();
+        for sigbit in &self.signals {
+            builder = builder.add_signal_fixed(sigbit.bit, sigbit.sig.clone())?;
+        }
+
+        for (bit, sig) in signals {
+            builder = match bit {
+                Some(bit) => builder.add_signal_fixed(bit, sig)?,
+                None => builder.add_signal(sig)?,
+            };
+        }
+
+        builder.build()
+    }
+
+    /// Change a template signal in a template instance.
+    ///
+    /// A signal can only be given a scale or offset if its template gives it
+    /// one, so that instances keep the decoded types of their template.
+    pub fn override_template_signal(
+        &self,
+        name: &str,
+        over: CANSignalOverride,
+    ) -> Result<CANMessage, CANConstructionError> {
+        if !matches!(self.kind, CANMessageKind::FromTemplate(_)) {
+            return Err(CANConstructionError::MessageIsNotATemplateInstance(
+                self.name.clone(),
+            ));
+        }
+
+        let template_signals = &self.signals[..self.template_signal_count()];
+        let Some(sigbit) = template_signals.iter().find(|s| s.sig.name == name) else {
+            return Err(CANConstructionError::SignalDoesNotExist(
+                name.into(),
+                self.name.clone(),
+            ));
+        };
+
+        let scaled = sigbit.sig.scale.is_some() || sigbit.sig.offset.is_some();
+        if !scaled && (over.scale.is_some() || over.offset.is_some()) {
+            return Err(CANConstructionError::TemplateSignalOverride(
+                name.into(),
+                self.name.clone(),
+            ));
+        }
+
+        self.with_edited_signal(name, |s| {
+            if let Some(description) = over.description {
+                s.description = Some(description);
+            }
+            if let Some(scale) = over.scale {
+                s.scale = Some(scale);
+            }
+            if let Some(offset) = over.offset {
+                s.offset = Some(offset);
+            }
+        })
+    }
 }
 
 // Easy indexing of msg["signal"]. Panics if signal absent.
@@ -368,19 +945,19 @@ mod tests {
     fn basic_sig_lookup() {
         // empty
         let msg = basic_msg([]);
-        assert!(matches!(msg.get_sig("sigA"), None));
+        assert!(msg.get_sig("sigA").is_none());
 
         // one signal
         let msg = basic_msg([basic_sig("sigA")]);
         assert!(matches!(msg["sigA"].name.as_str(), "sigA"));
-        assert!(matches!(msg.get_sig("siga"), None));
+        assert!(msg.get_sig("siga").is_none());
 
         // three signals
         let msg = basic_msg([basic_sig("sigA"), basic_sig("sigB"), basic_sig("sigC")]);
         assert!(matches!(msg["sigA"].name.as_str(), "sigA"));
         assert!(matches!(msg["sigB"].name.as_str(), "sigB"));
         assert!(matches!(msg["sigC"].name.as_str(), "sigC"));
-        assert!(matches!(msg.get_sig("sigD"), None));
+        assert!(msg.get_sig("sigD").is_none());
     }
 
     #[test]
@@ -401,7 +978,7 @@ mod tests {
         // Valid names
         let valid_names = ["test", "0", "_test_", "_", "___", "THING1_THING2"];
         for name in valid_names {
-            assert!(matches!(try_msg(name), Ok(_)));
+            assert!(try_msg(name).is_ok());
         }
 
         // Empty name
@@ -442,4 +1019,384 @@ mod tests {
             Err(CANConstructionError::SignalNameAlreadyExists(..))
         ));
     }
+
+    fn be_sig(name: &str, width: u32) -> CANSignal {
+        new_sig()
+            .name(name)
+            .width(width)
+            .byte_order(ByteOrder::BigEndian)
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn big_endian_sawtooth_layout() {
+        let msg = new_msg()
+            .name("TestMessage")
+            .id(0x10)
+            .add_signal_fixed(7, be_sig("sigA", 12))
+            .unwrap()
+            .build()
+            .unwrap();
+
+        let sig = msg.get_sig("sigA").unwrap();
+        assert_eq!(sig.start(), 7);
+        assert_eq!(sig.end(), 12);
+        assert_eq!(
+            sig.bit_positions().collect::<Vec<_>>(),
+            [12, 13, 14, 15, 0, 1, 2, 3, 4, 5, 6, 7]
+        );
+        assert_eq!(msg.length, 2);
+    }
+
+    #[test]
+    fn big_endian_auto_layout() {
+        let msg = new_msg()
+            .name("TestMessage")
+            .id(0x10)
+            .add_signals([be_sig("sigA", 12), be_sig("sigB", 12)])
+            .unwrap()
+            .build()
+            .unwrap();
+
+        assert_eq!(msg.get_sig("sigA").unwrap().start(), 7);
+        assert_eq!(msg.get_sig("sigB").unwrap().start(), 11);
+        assert_eq!(msg.get_sig("sigB").unwrap().end(), 16);
+        assert_eq!(msg.length, 3);
+    }
+
+    #[test]
+    fn mixed_byte_order_overlap() {
+        assert!(matches!(
+            new_msg()
+                .name("TestMessage")
+                .id(0x10)
+                .add_signal_fixed(0, new_sig().name("sigA").width(8).build().unwrap())
+                .unwrap()
+                .add_signal_fixed(7, be_sig("sigB", 4)),
+            Err(CANConstructionError::SignalsOverlap(a, b, 4)) if a == "sigA" && b == "sigB"
+        ));
+
+        // little-endian signal after big-endian signal in the same byte
+        assert!(new_msg()
+            .name("TestMessage")
+            .id(0x10)
+            .add_signal_fixed(7, be_sig("sigA", 4))
+            .unwrap()
+            .add_signal_fixed(0, new_sig().name("sigB").width(4).build().unwrap())
+            .is_ok());
+    }
+
+    #[test]
+    fn big_endian_signal_fits() {
+        let try_msg = |bit, width| {
+            new_msg()
+                .name("TestMessage")
+                .id(0x10)
+                .add_signal_fixed(bit, be_sig("s", width))
+        };
+
+        assert!(try_msg(63, 2).is_ok());
+        assert!(matches!(
+            try_msg(56, 2),
+            Err(CANConstructionError::SignalWillNotFitInMessage(..))
+        ));
+    }
+
+    #[test]
+    fn fd_length_rounding() {
+        // message with a byte-wide signal ending at bit `end`
+        let fd_msg = |end: u32| {
+            new_msg()
+                .name("TestMessage")
+                .id(0x10)
+                .fd(true)
+                .add_signal_fixed(end - 8, new_sig().name("s").width(8).build().unwrap())
+                .unwrap()
+                .build()
+                .unwrap()
+        };
+
+        assert_eq!(fd_msg(8).length, 1);
+        assert_eq!(fd_msg(64).length, 8);
+        assert_eq!(fd_msg(72).length, 12);
+        assert_eq!(fd_msg(200).length, 32);
+        assert_eq!(fd_msg(400).length, 64);
+
+        assert_eq!(fd_payload_length(49), Some(64));
+        assert_eq!(fd_payload_length(65), None);
+    }
+
+    #[test]
+    fn fd_signal_fits() {
+        let try_msg = |fd, bit| {
+            new_msg()
+                .name("TestMessage")
+                .id(0x10)
+                .fd(fd)
+                .add_signal_fixed(bit, basic_sig("s"))
+        };
+
+        assert!(matches!(
+            try_msg(false, 64),
+            Err(CANConstructionError::SignalWillNotFitInMessage(_, _, 63))
+        ));
+        assert!(try_msg(true, 64).is_ok());
+        assert!(try_msg(true, 511).is_ok());
+        assert!(matches!(
+            try_msg(true, 512),
+            Err(CANConstructionError::SignalWillNotFitInMessage(_, _, 511))
+        ));
+
+        // dropping FD after laying out signals
+        assert!(matches!(
+            try_msg(true, 64).unwrap().fd(false).build(),
+            Err(CANConstructionError::SignalWillNotFitInMessage(..))
+        ));
+    }
+
+    #[test]
+    fn brs_requires_fd() {
+        let try_msg = |fd| {
+            new_msg()
+                .name("TestMessage")
+                .id(0x10)
+                .fd(fd)
+                .brs(true)
+                .build()
+        };
+
+        assert!(matches!(
+            try_msg(false),
+            Err(CANConstructionError::MessageBrsWithoutFd(..))
+        ));
+        assert!(try_msg(true).is_ok());
+    }
+
+    fn mux_sig(name: &str, width: u32, multiplexor: &str, values: &[u64]) -> CANSignal {
+        new_sig()
+            .name(name)
+            .width(width)
+            .mux(Some(CANSignalMux {
+                multiplexor: multiplexor.into(),
+                values: values.into(),
+            }))
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn mux_groups_share_bits() {
+        let msg = new_msg()
+            .name("TestMessage")
+            .id(0x10)
+            .add_signal(new_sig().name("mode").width(2).build().unwrap())
+            .unwrap()
+            .add_signal(mux_sig("voltage", 16, "mode", &[0]))
+            .unwrap()
+            .add_signal(mux_sig("current", 8, "mode", &[1, 2]))
+            .unwrap()
+            .add_signal(mux_sig("temp", 8, "mode", &[1]))
+            .unwrap()
+            .add_signal(basic_sig("always"))
+            .unwrap()
+            .build()
+            .unwrap();
+
+        // groups are laid out from the end of the multiplexor
+        assert_eq!(msg.get_sig("voltage").unwrap().start(), 2);
+        assert_eq!(msg.get_sig("current").unwrap().start(), 2);
+        // `temp` is present with `current`, so goes after it
+        assert_eq!(msg.get_sig("temp").unwrap().start(), 10);
+        // unmuxed signals go after everything
+        assert_eq!(msg.get_sig("always").unwrap().start(), 18);
+
+        assert!(msg.is_multiplexed());
+        assert!(msg.is_multiplexor("mode"));
+        assert!(!msg.is_multiplexor("voltage"));
+    }
+
+    #[test]
+    fn mux_overlap() {
+        let base = || {
+            new_msg()
+                .name("TestMessage")
+                .id(0x10)
+                .add_signal_fixed(0, new_sig().name("mode").width(2).build().unwrap())
+                .unwrap()
+                .add_signal_fixed(8, mux_sig("a", 8, "mode", &[0, 1]))
+                .unwrap()
+        };
+
+        // disjoint values may overlap
+        assert!(base()
+            .add_signal_fixed(8, mux_sig("b", 8, "mode", &[2]))
+            .is_ok());
+
+        // shared values may not
+        assert!(matches!(
+            base().add_signal_fixed(12, mux_sig("b", 8, "mode", &[1, 2])),
+            Err(CANConstructionError::SignalsOverlap(..))
+        ));
+
+        // nor may unmuxed signals
+        assert!(matches!(
+            base().add_signal_fixed(12, basic_sig("b")),
+            Err(CANConstructionError::SignalsOverlap(..))
+        ));
+    }
+
+    #[test]
+    fn nested_mux() {
+        let msg = new_msg()
+            .name("TestMessage")
+            .id(0x10)
+            .add_signal(new_sig().name("mode").width(2).build().unwrap())
+            .unwrap()
+            .add_signal(mux_sig("submode", 2, "mode", &[1]))
+            .unwrap()
+            .add_signal(mux_sig("a", 8, "submode", &[0]))
+            .unwrap()
+            .add_signal(mux_sig("b", 8, "submode", &[1]))
+            .unwrap()
+            .add_signal(mux_sig("c", 8, "mode", &[0]))
+            .unwrap()
+            .build()
+            .unwrap();
+
+        // `c` can't be present with `a`, which requires mode = 1
+        assert_eq!(msg.get_sig("a").unwrap().start(), 4);
+        assert_eq!(msg.get_sig("b").unwrap().start(), 4);
+        assert_eq!(msg.get_sig("c").unwrap().start(), 2);
+
+        let chain = msg.mux_chain(&msg["a"]);
+        assert_eq!(chain.len(), 2);
+        assert_eq!(chain[0].multiplexor, "submode");
+        assert_eq!(chain[1].multiplexor, "mode");
+    }
+
+    #[test]
+    fn mux_checks() {
+        let try_msg = |sig| {
+            new_msg()
+                .name("TestMessage")
+                .id(0x10)
+                .add_signal(new_sig().name("mode").width(2).build().unwrap())
+                .unwrap()
+                .add_signal(sig)
+        };
+
+        assert!(matches!(
+            try_msg(mux_sig("a", 8, "nonexistent", &[0])),
+            Err(CANConstructionError::MultiplexorDoesNotExist(..))
+        ));
+        assert!(matches!(
+            try_msg(mux_sig("a", 8, "mode", &[])),
+            Err(CANConstructionError::MultiplexorValuesEmpty(..))
+        ));
+        assert!(matches!(
+            try_msg(mux_sig("a", 8, "mode", &[4])),
+            Err(CANConstructionError::MultiplexorValueOutOfRange(_, _, 4))
+        ));
+        assert!(try_msg(mux_sig("a", 8, "mode", &[3])).is_ok());
+    }
+
+    #[test]
+    fn template_extension_and_instances() {
+        let base = CANMessage::template()
+            .name("Base")
+            .add_signal(basic_sig("alive"))
+            .unwrap()
+            .add_signal(
+                new_sig()
+                    .name("temp")
+                    .width(8)
+                    .scale(Some(0.5))
+                    .build()
+                    .unwrap(),
+            )
+            .unwrap()
+            .build()
+            .unwrap();
+
+        // extensions start with the parent's signals
+        let status = base
+            .extend_template("Status")
+            .unwrap()
+            .add_signal(basic_sig("fault"))
+            .unwrap()
+            .build()
+            .unwrap();
+        assert!(matches!(status.kind(), CANMessageKind::Template));
+        assert_eq!(status.get_sig("fault").unwrap().start(), 9);
+        assert_eq!(status.length, 2);
+
+        let instance = status
+            .template_instance("A_Status", CANId::Standard(0x10), "A_", None, Some("A"))
+            .unwrap();
+        assert!(instance.instance_signals().is_empty());
+
+        // extra signals go after the template's
+        let instance = instance
+            .with_instance_signals([(None, basic_sig("A_extra"))])
+            .unwrap();
+        assert_eq!(instance.instance_signals().len(), 1);
+        assert_eq!(instance.get_sig("A_extra").unwrap().start(), 10);
+
+        // signals taken off the end never shift the template's signals
+        // into the instance's
+        let mut truncated = instance.clone();
+        for len in (0..instance.signals.len()).rev() {
+            truncated.signals.truncate(len);
+            assert!(truncated.instance_signals().is_empty());
+        }
+        let mut extended = instance.clone();
+        extended.signals.truncate(3);
+        extended.signals.push(instance.signals[3].clone());
+        assert_eq!(extended.instance_signals().len(), 1);
+        assert_eq!(extended.instance_signals()[0].sig.name, "A_extra");
+        assert!(matches!(instance.kind(), CANMessageKind::FromTemplate(t) if t == "Status"));
+
+        // overrides keep the template's decoded types
+        let over = |scale| CANSignalOverride {
+            description: Some("Overridden".into()),
+            scale,
+            offset: None,
+        };
+        let instance = instance
+            .override_template_signal("A_temp", over(Some(0.25)))
+            .unwrap();
+        assert_eq!(instance["A_temp"].scale, Some(0.25));
+        assert_eq!(
+            instance["A_temp"].description.as_deref(),
+            Some("Overridden")
+        );
+        assert_eq!(instance.instance_signals().len(), 1);
+
+        assert!(instance
+            .override_template_signal("A_alive", over(None))
+            .is_ok());
+        assert!(matches!(
+            instance.override_template_signal("A_alive", over(Some(2.))),
+            Err(CANConstructionError::TemplateSignalOverride(..))
+        ));
+        assert!(matches!(
+            instance.override_template_signal("A_extra", over(None)),
+            Err(CANConstructionError::SignalDoesNotExist(..))
+        ));
+        assert!(matches!(
+            truncated.override_template_signal("A_alive", over(None)),
+            Err(CANConstructionError::SignalDoesNotExist(..))
+        ));
+
+        // only for templates and instances respectively
+        assert!(matches!(
+            instance.extend_template("X"),
+            Err(CANConstructionError::MessageIsNotATemplate(_))
+        ));
+        assert!(matches!(
+            base.with_instance_signals([]),
+            Err(CANConstructionError::MessageIsNotATemplateInstance(_))
+        ));
+    }
 }
@@ -0,0 +1,131 @@
+use serde::{Deserialize, Serialize};
+
+use crate::error::*;
+
+/// How the names of a node's messages and signals are made from the names
+/// they're given in definitions.
+///
+/// Names are prefixed according to a format in which `{node}` stands for the
+/// name of the node, e.g. `{node}_` (the default) turns message `Status` of
+/// node `BMS` into `BMS_Status`. The format can also be empty, to use names
+/// as they are.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct CANNaming {
+    prefix: String,
+}
+
+impl Default for CANNaming {
+    fn default() -> Self {
+        Self {
+            prefix: format!("{}_", Self::NODE),
+        }
+    }
+}
+
+impl TryFrom<String> for CANNaming {
+    type Error = CANConstructionError;
+
+    fn try_from(prefix: String) -> Result<Self, Self::Error> {
+        Self::new(&prefix)
+    }
+}
+
+impl From<CANNaming> for String {
+    fn from(naming: CANNaming) -> Self {
+        naming.prefix
+    }
+}
+
+impl CANNaming {
+    /// Placeholder for the node name in a prefix format.
+    pub const NODE: &'static str = "{node}";
+
+    /// Prefix names using the format `prefix`.
+    ///
+    /// Apart from `{node}` placeholders, the format may contain only ASCII
+    /// letters, numbers, and underscores, like message names.
+    pub fn new(prefix: &str) -> Result<Self, CANConstructionError> {
+        if let Some(c) = prefix
+            .replace(Self::NODE, "")
+            .chars()
+            .find(|c| !c.is_ascii_alphanumeric() && c != &'_')
+        {
+            return Err(CANConstructionError::NamingPrefixInvalidChar(
+                prefix.into(),
+                c,
+            ));
+        }
+
+        Ok(Self {
+            prefix: prefix.into(),
+        })
+    }
+
+    /// Use names as they are given.
+    pub fn unprefixed() -> Self {
+        Self {
+            prefix: String::new(),
+        }
+    }
+
+    /// Prefix names with the node name followed by `separator`.
+    pub fn with_separator(separator: &str) -> Result<Self, CANConstructionError> {
+        Self::new(&format!("{}{separator}", Self::NODE))
+    }
+
+    /// The prefix format, e.g. `{node}_`.
+    pub fn format(&self) -> &str {
+        &self.prefix
+    }
+
+    /// The prefix for names of `node`'s messages and signals.
+    pub fn prefix(&self, node: &str) -> String {
+        self.prefix.replace(Self::NODE, node)
+    }
+
+    /// The full name of `node`'s message or signal `name`.
+    pub fn name(&self, node: &str, name: &str) -> String {
+        self.prefix(node) + name
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_naming() {
+        assert_eq!(CANNaming::default().name("BMS", "Status"), "BMS_Status");
+        assert_eq!(CANNaming::unprefixed().name("BMS", "Status"), "Status");
+
+        let sep = CANNaming::with_separator("__").unwrap();
+        assert_eq!(sep.format(), "{node}__");
+        assert_eq!(sep.name("BMS", "Status"), "BMS__Status");
+
+        let custom = CANNaming::new("ECU_{node}_").unwrap();
+        assert_eq!(custom.prefix("BMS"), "ECU_BMS_");
+
+        for prefix in ["{node}.", "{nod}_", "{node}-{node}", " "] {
+            assert!(matches!(
+                CANNaming::new(prefix),
+                Err(CANConstructionError::NamingPrefixInvalidChar(..))
+            ));
+        }
+
+        assert!(matches!(
+            CANNaming::with_separator("::"),
+            Err(CANConstructionError::NamingPrefixInvalidChar(..))
+        ));
+    }
+
+    #[test]
+    fn test_naming_serde() {
+        let naming = CANNaming::with_separator("__").unwrap();
+        let json = serde_json::to_string(&naming).unwrap();
+        assert_eq!(json, r#""{node}__""#);
+        assert_eq!(serde_json::from_str::<CANNaming>(&json).unwrap(), naming);
+
+        assert!(serde_json::from_str::<CANNaming>(r#""{node}-""#).is_err());
+    }
+}
@@ -2,15 +2,28 @@ use std::collections::HashMap;
 
 use serde::{Deserialize, Serialize};
 
+use crate::attribute::*;
 use crate::error::*;
+use crate::id::*;
 use crate::message::*;
+use crate::naming::*;
 use crate::node::*;
+use crate::signal::*;
+use crate::value_table::*;
 
 #[derive(Debug, Serialize, Deserialize)]
+#[serde(try_from = "CANNetworkDef")]
 pub struct CANNetwork {
+    /// Serialized format version; always [`CANNetwork::FORMAT_VERSION`].
+    #[serde(serialize_with = "crate::format_version")]
+    format_version: (),
+
     /// Bitrate for this network
     bitrate: Option<u32>,
 
+    /// How names of messages and signals are made from their node's name.
+    naming: CANNaming,
+
     /// Owning Vec of all CANNode in this network.
     nodes: Vec<CANNode>,
 
@@ -21,19 +34,134 @@ pub struct CANNetwork {
     #[serde(serialize_with = "crate::sorted_map")]
     template_messages: HashMap<String, CANMessage>,
 
+    /// Map of all value tables in this network.
+    #[serde(serialize_with = "crate::sorted_map")]
+    value_tables: HashMap<String, CANValueTable>,
+
+    /// User-defined attributes of this network.
+    attributes: Attributes,
+
     /// index into .messages
     #[serde(skip)]
     messages_by_name: HashMap<String, usize>,
 
     /// index into .messages
     #[serde(skip)]
-    messages_by_id: HashMap<u32, usize>,
+    messages_by_id: HashMap<CANId, usize>,
 
     #[serde(skip)]
     /// index into .nodes
     nodes_by_name: HashMap<String, usize>,
 }
 
+/// Serialized form of a [`CANNetwork`], before indices are rebuilt and the
+/// network is validated.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct CANNetworkDef {
+    format_version: u32,
+    bitrate: Option<u32>,
+    #[serde(default)]
+    naming: CANNaming,
+    nodes: Vec<CANNode>,
+    messages: Vec<CANMessage>,
+    template_messages: HashMap<String, CANMessage>,
+    #[serde(default)]
+    value_tables: HashMap<String, CANValueTable>,
+    #[serde(default)]
+    attributes: Attributes,
+}
+
+impl TryFrom<CANNetworkDef> for CANNetwork {
+    type Error = CANConstructionError;
+
+    /// Rebuild a network from its serialized form, running the same checks
+    /// as building it up by hand.
+    fn try_from(def: CANNetworkDef) -> Result<Self, Self::Error> {
+        if def.format_version != Self::FORMAT_VERSION {
+            return Err(CANConstructionError::UnsupportedFormatVersion(
+                def.format_version,
+                Self::FORMAT_VERSION,
+            ));
+        }
+
+        let mut net = Self::new();
+        net.bitrate = def.bitrate;
+        net.naming = def.naming;
+        net.attributes = def.attributes;
+
+        let mut tables: Vec<_> = def.value_tables.into_iter().collect();
+        tables.sort_by(|a, b| a.0.cmp(&b.0));
+
+        for (name, table) in tables {
+            if name != table.name {
+                return Err(CANConstructionError::ValueTableKeyMismatch(
+                    name, table.name,
+                ));
+            }
+
+            net.insert_value_table(table)?;
+        }
+
+        for node in &def.nodes {
+            net.add_node(&node.name)?;
+            net.set_node_attributes(&node.name, node.attributes.clone())?;
+            net.set_node_description(&node.name, node.description.clone())?;
+        }
+
+        for msg in def.messages {
+            net.insert_msg(msg)?;
+        }
+
+        for node in &def.nodes {
+            // tx lists are derived from each message's tx_node, so they
+            // must agree with what was just rebuilt
+            let rebuilt = &net.nodes[net.nodes_by_name[&node.name]].tx_messages;
+            let mut tx: Vec<_> = node
+                .tx_messages
+                .keys()
+                .filter(|m| !rebuilt.contains_key(*m))
+                .chain(
+                    rebuilt
+                        .keys()
+                        .filter(|m| !node.tx_messages.contains_key(*m)),
+                )
+                .collect();
+            tx.sort();
+
+            if let Some(msg) = tx.first() {
+                return Err(CANConstructionError::NodeTxMessageMismatch(
+                    node.name.clone(),
+                    (*msg).clone(),
+                ));
+            }
+
+            let mut rx: Vec<_> = node.rx_messages.keys().collect();
+            rx.sort();
+
+            for msg in rx {
+                net.set_message_rx_by_node(msg, &node.name)?;
+            }
+        }
+
+        let mut templates: Vec<_> = def.template_messages.into_iter().collect();
+        templates.sort_by(|a, b| a.0.cmp(&b.0));
+
+        for (name, template) in templates {
+            if name != template.name {
+                return Err(CANConstructionError::TemplateMessageKeyMismatch(
+                    name,
+                    template.name,
+                ));
+            }
+
+            net.insert_template_message(template)?;
+        }
+
+        Ok(net)
+    }
+}
+
 impl Default for CANNetwork {
     fn default() -> Self {
         Self::new()
@@ -41,14 +169,23 @@ impl Default for CANNetwork {
 }
 
 impl CANNetwork {
+    /// Version of the serialized network format. Serialized networks with
+    /// any other version are rejected on load.
+    pub const FORMAT_VERSION: u32 = 1;
+
     /// Create a new (empty) network.
     pub fn new() -> Self {
         Self {
+            format_version: (),
             bitrate: None,
+            naming: CANNaming::default(),
             nodes: Vec::new(),
             messages: Vec::new(),
 
             template_messages: HashMap::new(),
+            value_tables: HashMap::new(),
+
+            attributes: Attributes::new(),
 
             messages_by_name: HashMap::new(),
             messages_by_id: HashMap::new(),
@@ -64,7 +201,7 @@ impl CANNetwork {
     }
 
     /// Get message in this network by ID.
-    pub fn message_by_id(&self, id: &u32) -> Option<&CANMessage> {
+    pub fn message_by_id(&self, id: &CANId) -> Option<&CANMessage> {
         let &idx = self.messages_by_id.get(id)?;
         Some(&self.messages[idx])
     }
@@ -77,12 +214,94 @@ impl CANNetwork {
         self.bitrate
     }
 
+    /// How names of messages and signals in this network are made from
+    /// their node's name. Only used when building up the network; names
+    /// already in it are left alone when this changes.
+    pub fn naming(&self) -> &CANNaming {
+        &self.naming
+    }
+
+    pub fn set_naming(&mut self, naming: CANNaming) {
+        self.naming = naming;
+    }
+
+    /// User-defined attributes of this network.
+    pub fn attributes(&self) -> &Attributes {
+        &self.attributes
+    }
+
+    /// Set a user-defined attribute of this network, replacing any previous value.
+    pub fn set_attribute(&mut self, name: &str, value: AttributeValue) {
+        self.attributes.insert(name.into(), value);
+    }
+
+    /// Set a user-defined attribute of a node, replacing any previous value.
+    pub fn set_node_attribute(
+        &mut self,
+        node: &str,
+        name: &str,
+        value: AttributeValue,
+    ) -> Result<(), CANConstructionError> {
+        self.set_node_attributes(node, Attributes::from([(name.into(), value)]))
+    }
+
+    /// Set several user-defined attributes of a node at once.
+    pub fn set_node_attributes(
+        &mut self,
+        node: &str,
+        attributes: Attributes,
+    ) -> Result<(), CANConstructionError> {
+        let Some(&node_idx) = self.nodes_by_name.get(node) else {
+            return Err(CANConstructionError::NodeDoesNotExist(node.into()));
+        };
+
+        self.nodes[node_idx].attributes.extend(attributes);
+
+        Ok(())
+    }
+
+    /// Set the description of a node.
+    pub fn set_node_description(
+        &mut self,
+        node: &str,
+        description: Option<String>,
+    ) -> Result<(), CANConstructionError> {
+        let Some(&node_idx) = self.nodes_by_name.get(node) else {
+            return Err(CANConstructionError::NodeDoesNotExist(node.into()));
+        };
+
+        self.nodes[node_idx].description = description;
+
+        Ok(())
+    }
+
+    /// Set a user-defined attribute of a message, replacing any previous value.
+    pub fn set_message_attribute(
+        &mut self,
+        msg: &str,
+        name: &str,
+        value: AttributeValue,
+    ) -> Result<(), CANConstructionError> {
+        let Some(&msg_idx) = self.messages_by_name.get(msg) else {
+            return Err(CANConstructionError::MessageDoesNotExist(msg.into()));
+        };
+
+        self.messages[msg_idx].attributes.insert(name.into(), value);
+
+        Ok(())
+    }
+
     /// Insert a message into the network.
     ///
     /// Notes:
     ///     - Checks for message ID and name uniqueness.
+    ///     - Checks that the message ID is in range.
+    ///     - Checks that the message fits its frame format (classic or FD).
     ///     - If a node is specified, it must already exist in the network.
     pub fn insert_msg(&mut self, msg: CANMessage) -> Result<(), CANConstructionError> {
+        msg.id.check()?;
+        msg.check_frame_format()?;
+
         if self.messages_by_name.contains_key(&msg.name) {
             return Err(CANConstructionError::MessageNameAlreadyExists(msg.name));
         }
@@ -91,6 +310,8 @@ impl CANNetwork {
             return Err(CANConstructionError::MessageIdAlreadyExists(msg.id));
         }
 
+        self.check_value_tables(&msg)?;
+
         let msg_idx = self.messages.len();
 
         if let Some(node) = &msg.tx_node {
@@ -138,12 +359,12 @@ impl CANNetwork {
     }
 
     /// Iterate over messages in this network.
-    pub fn iter_messages(&self) -> std::slice::Iter<CANMessage> {
+    pub fn iter_messages(&self) -> std::slice::Iter<'_, CANMessage> {
         self.messages.iter()
     }
 
     /// Iterate over nodes in this network.
-    pub fn iter_nodes(&self) -> std::slice::Iter<CANNode> {
+    pub fn iter_nodes(&self) -> std::slice::Iter<'_, CANNode> {
         self.nodes.iter()
     }
 
@@ -198,6 +419,8 @@ impl CANNetwork {
     ) -> Result<(), CANConstructionError> {
         let name = template.name.clone();
 
+        self.check_value_tables(&template)?;
+
         if self
             .template_messages
             .insert(name.clone(), template)
@@ -212,11 +435,692 @@ impl CANNetwork {
     pub fn template_message_by_name(&self, name: &str) -> Option<&CANMessage> {
         self.template_messages.get(name)
     }
+
+    /// Iterate over template messages in this network, in no particular order.
+    pub fn iter_template_messages(&self) -> impl Iterator<Item = &CANMessage> {
+        self.template_messages.values()
+    }
+
+    /// Add a value table to the network.
+    pub fn insert_value_table(&mut self, table: CANValueTable) -> Result<(), CANConstructionError> {
+        CANValueTable::check_name_validity(&table.name)?;
+
+        if self.value_tables.contains_key(&table.name) {
+            return Err(CANConstructionError::ValueTableNameAlreadyExists(
+                table.name,
+            ));
+        }
+
+        self.value_tables.insert(table.name.clone(), table);
+
+        Ok(())
+    }
+
+    /// Remove a value table, whether or not any signals still use it.
+    pub(crate) fn remove_value_table(&mut self, name: &str) -> Option<CANValueTable> {
+        self.value_tables.remove(name)
+    }
+
+    pub fn value_table_by_name(&self, name: &str) -> Option<&CANValueTable> {
+        self.value_tables.get(name)
+    }
+
+    /// Iterate over value tables in this network, in no particular order.
+    pub fn iter_value_tables(&self) -> impl Iterator<Item = &CANValueTable> {
+        self.value_tables.values()
+    }
+
+    /// Check that signals using value tables refer to tables in this network
+    /// and have the same values.
+    fn check_value_tables(&self, msg: &CANMessage) -> Result<(), CANConstructionError> {
+        for sigbit in &msg.signals {
+            let Some(name) = &sigbit.sig.value_table else {
+                continue;
+            };
+
+            let table = self
+                .value_tables
+                .get(name)
+                .ok_or_else(|| CANConstructionError::ValueTableDoesNotExist(name.clone()))?;
+
+            if table.values != sigbit.sig.enumerated_values {
+                return Err(CANConstructionError::SignalValueTableMismatch(
+                    sigbit.sig.name.clone(),
+                    name.clone(),
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Remove a message from the network, along with any rx entries for it.
+    pub fn remove_msg(&mut self, name: &str) -> Result<CANMessage, CANConstructionError> {
+        let Some(&msg_idx) = self.messages_by_name.get(name) else {
+            return Err(CANConstructionError::MessageDoesNotExist(name.into()));
+        };
+
+        for node in &mut self.nodes {
+            node.rx_messages.remove(name);
+        }
+
+        let msg = self.messages.remove(msg_idx);
+        self.reindex();
+
+        Ok(msg)
+    }
+
+    /// Rename a message, keeping its rx entries.
+    ///
+    /// Notes:
+    ///     - The new name must be valid and unique, as for [`insert_msg()`][Self::insert_msg].
+    pub fn rename_msg(&mut self, name: &str, new_name: &str) -> Result<(), CANConstructionError> {
+        let Some(&msg_idx) = self.messages_by_name.get(name) else {
+            return Err(CANConstructionError::MessageDoesNotExist(name.into()));
+        };
+
+        CANMessageBuilder::check_name_validity(new_name)?;
+
+        if self.messages_by_name.contains_key(new_name) {
+            return Err(CANConstructionError::MessageNameAlreadyExists(
+                new_name.into(),
+            ));
+        }
+
+        for node in &mut self.nodes {
+            if node.rx_messages.remove(name).is_some() {
+                node.rx_messages.insert(new_name.into(), msg_idx);
+            }
+        }
+
+        self.messages[msg_idx].name = new_name.into();
+        self.reindex();
+
+        Ok(())
+    }
+
+    /// Set or clear the transmitting node of a message.
+    pub fn set_message_tx_node(
+        &mut self,
+        msg: &str,
+        node: Option<&str>,
+    ) -> Result<(), CANConstructionError> {
+        let Some(&msg_idx) = self.messages_by_name.get(msg) else {
+            return Err(CANConstructionError::MessageDoesNotExist(msg.into()));
+        };
+
+        if let Some(node) = node {
+            if !self.nodes_by_name.contains_key(node) {
+                return Err(CANConstructionError::NodeDoesNotExist(node.into()));
+            }
+        }
+
+        self.messages[msg_idx].tx_node = node.map(Into::into);
+        self.reindex();
+
+        Ok(())
+    }
+
+    /// Edit a signal of a message in place.
+    ///
+    /// The edited message is laid out and checked again as if it were built
+    /// and inserted from scratch, value tables included; if that fails, the
+    /// message is left unchanged. Renaming a multiplexor also updates the
+    /// signals it multiplexes.
+    pub fn edit_signal(
+        &mut self,
+        msg: &str,
+        signal: &str,
+        edit: impl FnOnce(&mut CANSignal),
+    ) -> Result<(), CANConstructionError> {
+        let Some(&msg_idx) = self.messages_by_name.get(msg) else {
+            return Err(CANConstructionError::MessageDoesNotExist(msg.into()));
+        };
+
+        let edited = self.messages[msg_idx].with_edited_signal(signal, edit)?;
+        self.check_value_tables(&edited)?;
+        self.messages[msg_idx] = edited;
+
+        Ok(())
+    }
+
+    /// Remove a node from the network. Messages it transmitted are kept, but
+    /// no longer have a transmitting node.
+    pub fn remove_node(&mut self, name: &str) -> Result<(), CANConstructionError> {
+        let Some(&node_idx) = self.nodes_by_name.get(name) else {
+            return Err(CANConstructionError::NodeDoesNotExist(name.into()));
+        };
+
+        for msg in &mut self.messages {
+            if msg.tx_node() == Some(name) {
+                msg.tx_node = None;
+            }
+        }
+
+        self.nodes.remove(node_idx);
+        self.reindex();
+
+        Ok(())
+    }
+
+    /// Rename a node, keeping the messages it transmits and receives.
+    pub fn rename_node(&mut self, name: &str, new_name: &str) -> Result<(), CANConstructionError> {
+        let Some(&node_idx) = self.nodes_by_name.get(name) else {
+            return Err(CANConstructionError::NodeDoesNotExist(name.into()));
+        };
+
+        if self.nodes_by_name.contains_key(new_name) {
+            return Err(CANConstructionError::NodeAlreadyExists(new_name.into()));
+        }
+
+        for msg in &mut self.messages {
+            if msg.tx_node() == Some(name) {
+                msg.tx_node = Some(new_name.into());
+            }
+        }
+
+        self.nodes[node_idx].name = new_name.into();
+        self.reindex();
+
+        Ok(())
+    }
+
+    /// Stop a node from receiving a message.
+    ///
+    /// Removing an rx entry that doesn't exist is not an error.
+    pub fn remove_message_rx_by_node(
+        &mut self,
+        msg: &str,
+        node: &str,
+    ) -> Result<(), CANConstructionError> {
+        if !self.messages_by_name.contains_key(msg) {
+            return Err(CANConstructionError::MessageDoesNotExist(msg.into()));
+        }
+
+        let Some(&node_idx) = self.nodes_by_name.get(node) else {
+            return Err(CANConstructionError::NodeDoesNotExist(node.into()));
+        };
+
+        self.nodes[node_idx].rx_messages.remove(msg);
+
+        Ok(())
+    }
+
+    /// Stop all nodes from receiving a message.
+    pub fn clear_message_rx(&mut self, msg: &str) -> Result<(), CANConstructionError> {
+        if !self.messages_by_name.contains_key(msg) {
+            return Err(CANConstructionError::MessageDoesNotExist(msg.into()));
+        }
+
+        for node in &mut self.nodes {
+            node.rx_messages.remove(msg);
+        }
+
+        Ok(())
+    }
+
+    /// Remove a template message. Fails if any message was made from it.
+    pub fn remove_template_message(
+        &mut self,
+        name: &str,
+    ) -> Result<CANMessage, CANConstructionError> {
+        if !self.template_messages.contains_key(name) {
+            return Err(CANConstructionError::TemplateMessageDoesNotExist(
+                name.into(),
+            ));
+        }
+
+        if let Some(instance) = self
+            .messages
+            .iter()
+            .find(|m| matches!(m.kind(), CANMessageKind::FromTemplate(t) if t == name))
+        {
+            return Err(CANConstructionError::TemplateMessageInUse(
+                name.into(),
+                instance.name.clone(),
+            ));
+        }
+
+        Ok(self.template_messages.remove(name).unwrap())
+    }
+
+    /// Rename a template message, updating the messages made from it.
+    pub fn rename_template_message(
+        &mut self,
+        name: &str,
+        new_name: &str,
+    ) -> Result<(), CANConstructionError> {
+        if !self.template_messages.contains_key(name) {
+            return Err(CANConstructionError::TemplateMessageDoesNotExist(
+                name.into(),
+            ));
+        }
+
+        CANMessageBuilder::check_name_validity(new_name)?;
+
+        if self.template_messages.contains_key(new_name) {
+            return Err(CANConstructionError::TemplateMessageNameAlreadyExists(
+                new_name.into(),
+            ));
+        }
+
+        let mut template = self.template_messages.remove(name).unwrap();
+        template.name = new_name.into();
+        self.template_messages.insert(new_name.into(), template);
+
+        for msg in &mut self.messages {
+            if matches!(&msg.kind, CANMessageKind::FromTemplate(t) if t == name) {
+                msg.kind = CANMessageKind::FromTemplate(new_name.into());
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Rebuild every index from `messages`, `nodes`, each message's tx node,
+    /// and the message names in each node's rx map. Rx entries for messages
+    /// that no longer exist are dropped.
+    fn reindex(&mut self) {
+        self.messages_by_name = self
+            .messages
+            .iter()
+            .enumerate()
+            .map(|(i, m)| (m.name.clone(), i))
+            .collect();
+
+        self.messages_by_id = self
+            .messages
+            .iter()
+            .enumerate()
+            .map(|(i, m)| (m.id, i))
+            .collect();
+
+        self.nodes_by_name = self
+            .nodes
+            .iter()
+            .enumerate()
+            .map(|(i, n)| (n.name.clone(), i))
+            .collect();
+
+        for node in &mut self.nodes {
+            node.tx_messages = self
+                .messages
+                .iter()
+                .enumerate()
+                .filter(|(_, m)| m.tx_node() == Some(node.name.as_str()))
+                .map(|(i, m)| (m.name.clone(), i))
+                .collect();
+
+            node.rx_messages = node
+                .rx_messages
+                .keys()
+                .filter_map(|m| Some((m.clone(), *self.messages_by_name.get(m)?)))
+                .collect();
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::{CANConstructionError, CANNetwork};
+    use crate::tests::*;
+    use crate::{
+        AttributeValue, CANConstructionError, CANId, CANMessage, CANMessageKind, CANNaming,
+        CANNetwork, CANSignal, CANValueTable,
+    };
+
+    fn json_net() -> CANNetwork {
+        let mut net = CANNetwork::new();
+        net.set_bitrate(500_000);
+        net.set_naming(CANNaming::with_separator("__").unwrap());
+        net.add_node("A").unwrap();
+        net.add_node("B").unwrap();
+
+        let template = CANMessage::template()
+            .name("Heartbeat")
+            .add_signal(basic_sig("alive"))
+            .unwrap()
+            .build()
+            .unwrap();
+        let instance = template
+            .template_instance(
+                "A_Heartbeat",
+                CANId::Standard(0x20),
+                "A_",
+                Some(100),
+                Some("A"),
+            )
+            .unwrap()
+            .with_instance_signals([(None, basic_sig("A_uptime"))])
+            .unwrap();
+        net.insert_template_message(template).unwrap();
+        net.insert_msg(instance).unwrap();
+
+        let msg = CANMessage::builder()
+            .name("Data")
+            .id(CANId::Extended(0x1000))
+            .tx_node("B")
+            .add_signals([
+                basic_sig("x"),
+                new_sig().name("y").width(7).build().unwrap(),
+            ])
+            .unwrap()
+            .build()
+            .unwrap();
+        net.insert_msg(msg).unwrap();
+
+        net.insert_msg(CANMessage::new_raw(
+            "Raw",
+            CANId::Standard(0x30),
+            None,
+            Some("A"),
+        ))
+        .unwrap();
+
+        net.set_message_rx_by_node("Data", "A").unwrap();
+        net.set_message_rx_by_node("A_Heartbeat", "B").unwrap();
+
+        net.set_attribute("Bus", AttributeValue::String("Body".into()));
+        net.set_node_attribute("A", "Address", AttributeValue::Int(4))
+            .unwrap();
+
+        net
+    }
+
+    fn load(json: &str) -> Result<CANNetwork, String> {
+        serde_json::from_str(json).map_err(|e| e.to_string())
+    }
+
+    #[test]
+    fn json_round_trip() {
+        let net = json_net();
+        let json = serde_json::to_string(&net).unwrap();
+        let loaded = load(&json).unwrap();
+
+        assert_eq!(loaded.bitrate(), Some(500_000));
+        assert_eq!(loaded.naming().format(), "{node}__");
+        assert_eq!(
+            loaded.message_by_id(&CANId::Extended(0x1000)).unwrap().name,
+            "Data"
+        );
+        assert_eq!(loaded.message_by_name("Data").unwrap()["y"].width, 7);
+        assert_eq!(
+            loaded.message_by_name("A_Heartbeat").unwrap().cycletime,
+            Some(100)
+        );
+        assert_eq!(
+            loaded
+                .message_by_name("A_Heartbeat")
+                .unwrap()
+                .instance_signals()
+                .len(),
+            1
+        );
+        assert_eq!(
+            loaded
+                .message_by_name("A_Heartbeat")
+                .unwrap()
+                .instance_prefix(),
+            Some("A_")
+        );
+        assert!(loaded.template_message_by_name("Heartbeat").is_some());
+        assert_eq!(loaded.attributes(), json_net().attributes());
+        assert_eq!(
+            loaded.node_by_name("A").unwrap().attributes["Address"],
+            AttributeValue::Int(4)
+        );
+
+        let rx: Vec<_> = loaded
+            .rx_messages_by_node("B")
+            .unwrap()
+            .into_iter()
+            .map(|m| &m.name)
+            .collect();
+        assert_eq!(rx, ["A_Heartbeat"]);
+
+        let mut tx: Vec<_> = loaded
+            .tx_messages_by_node("A")
+            .unwrap()
+            .into_iter()
+            .map(|m| &m.name)
+            .collect();
+        tx.sort();
+        assert_eq!(tx, ["A_Heartbeat", "Raw"]);
+
+        // serializing again gives the same output
+        assert_eq!(serde_json::to_string(&loaded).unwrap(), json);
+    }
+
+    #[test]
+    fn json_rejects_inconsistent() {
+        let json = serde_json::to_value(json_net()).unwrap();
+
+        let mut bad = json.clone();
+        bad["format_version"] = (CANNetwork::FORMAT_VERSION + 1).into();
+        assert!(load(&bad.to_string())
+            .unwrap_err()
+            .contains("format version"));
+
+        // missing index entry for a transmitted message
+        let mut bad = json.clone();
+        bad["nodes"][1]["tx_messages"] = serde_json::json!({});
+        assert!(load(&bad.to_string()).unwrap_err().contains("disagree"));
+
+        // rx of a message that doesn't exist
+        let mut bad = json.clone();
+        bad["nodes"][0]["rx_messages"]["Nope"] = 0.into();
+        assert!(load(&bad.to_string())
+            .unwrap_err()
+            .contains("`Nope` does not exist"));
+
+        // overlapping signals
+        let mut bad = json.clone();
+        bad["messages"][1]["signals"][0]["sig"]["width"] = 2.into();
+        assert!(load(&bad.to_string()).unwrap_err().contains("overlap"));
+
+        // more template signals than signals
+        let mut bad = json.clone();
+        bad["messages"][0]["template_signals"] = 3.into();
+        assert!(load(&bad.to_string())
+            .unwrap_err()
+            .contains("3 template signals"));
+
+        // wrong length
+        let mut bad = json.clone();
+        bad["messages"][1]["length"] = 8.into();
+        assert!(load(&bad.to_string()).unwrap_err().contains("length 8"));
+
+        // naming prefix that can't make valid names
+        let mut bad = json;
+        bad["naming"] = "{node}.".into();
+        assert!(load(&bad.to_string())
+            .unwrap_err()
+            .contains("invalid character"));
+    }
+
+    /// Networks saved before naming was configurable load with the default
+    /// naming, which their template instances were made with.
+    #[test]
+    fn json_without_naming() {
+        let mut json = serde_json::to_value(json_net()).unwrap();
+        json.as_object_mut().unwrap().remove("naming");
+        json["messages"][0]
+            .as_object_mut()
+            .unwrap()
+            .remove("instance_prefix");
+
+        let loaded = load(&json.to_string()).unwrap();
+        assert_eq!(loaded.naming(), &CANNaming::default());
+        assert_eq!(
+            loaded
+                .message_by_name("A_Heartbeat")
+                .unwrap()
+                .instance_prefix(),
+            Some("A_")
+        );
+    }
+
+    fn names<'a>(msgs: impl IntoIterator<Item = &'a CANMessage>) -> Vec<&'a str> {
+        let mut names: Vec<_> = msgs.into_iter().map(|m| m.name.as_str()).collect();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn remove_and_rename_messages() {
+        let mut net = json_net();
+
+        let removed = net.remove_msg("A_Heartbeat").unwrap();
+        assert_eq!(removed.name, "A_Heartbeat");
+        assert!(net.message_by_name("A_Heartbeat").is_none());
+        assert!(net.message_by_id(&CANId::Standard(0x20)).is_none());
+        assert!(net.rx_messages_by_node("B").unwrap().is_empty());
+        assert_eq!(names(net.tx_messages_by_node("A").unwrap()), ["Raw"]);
+
+        // indices after the removed message still line up
+        assert_eq!(
+            net.message_by_id(&CANId::Extended(0x1000)).unwrap().name,
+            "Data"
+        );
+        assert_eq!(names(net.rx_messages_by_node("A").unwrap()), ["Data"]);
+
+        net.rename_msg("Data", "Payload").unwrap();
+        assert!(net.message_by_name("Data").is_none());
+        assert_eq!(
+            net.message_by_id(&CANId::Extended(0x1000)).unwrap().name,
+            "Payload"
+        );
+        assert_eq!(names(net.rx_messages_by_node("A").unwrap()), ["Payload"]);
+        assert_eq!(names(net.tx_messages_by_node("B").unwrap()), ["Payload"]);
+
+        assert!(matches!(
+            net.rename_msg("Payload", "Raw"),
+            Err(CANConstructionError::MessageNameAlreadyExists(_))
+        ));
+        assert!(matches!(
+            net.remove_msg("Data"),
+            Err(CANConstructionError::MessageDoesNotExist(_))
+        ));
+
+        // the network still round-trips through its own consistency checks
+        let json = serde_json::to_string(&net).unwrap();
+        load(&json).unwrap();
+    }
+
+    #[test]
+    fn remove_and_rename_nodes() {
+        let mut net = json_net();
+
+        net.rename_node("A", "C").unwrap();
+        assert!(net.node_by_name("A").is_none());
+        assert_eq!(net.message_by_name("Raw").unwrap().tx_node(), Some("C"));
+        assert_eq!(
+            names(net.tx_messages_by_node("C").unwrap()),
+            ["A_Heartbeat", "Raw"]
+        );
+        assert_eq!(names(net.rx_messages_by_node("C").unwrap()), ["Data"]);
+
+        net.remove_node("C").unwrap();
+        assert!(net.node_by_name("C").is_none());
+        assert_eq!(net.message_by_name("Raw").unwrap().tx_node(), None);
+        assert_eq!(
+            names(net.rx_messages_by_node("B").unwrap()),
+            ["A_Heartbeat"]
+        );
+        assert_eq!(names(net.tx_messages_by_node("B").unwrap()), ["Data"]);
+
+        assert!(matches!(
+            net.rename_node("B", "B"),
+            Err(CANConstructionError::NodeAlreadyExists(_))
+        ));
+
+        let json = serde_json::to_string(&net).unwrap();
+        load(&json).unwrap();
+    }
+
+    #[test]
+    fn reassign_tx_and_clear_rx() {
+        let mut net = json_net();
+
+        net.set_message_tx_node("Data", Some("A")).unwrap();
+        assert_eq!(
+            names(net.tx_messages_by_node("A").unwrap()),
+            ["A_Heartbeat", "Data", "Raw"]
+        );
+        assert!(net.tx_messages_by_node("B").unwrap().is_empty());
+
+        net.set_message_tx_node("Raw", None).unwrap();
+        assert_eq!(
+            names(net.tx_messages_by_node("A").unwrap()),
+            ["A_Heartbeat", "Data"]
+        );
+
+        assert!(matches!(
+            net.set_message_tx_node("Raw", Some("Z")),
+            Err(CANConstructionError::NodeDoesNotExist(_))
+        ));
+
+        net.remove_message_rx_by_node("Data", "A").unwrap();
+        assert!(net.rx_messages_by_node("A").unwrap().is_empty());
+
+        net.set_message_rx_by_node("Raw", "A").unwrap();
+        net.set_message_rx_by_node("Raw", "B").unwrap();
+        net.clear_message_rx("Raw").unwrap();
+        assert!(net.rx_messages_by_node("A").unwrap().is_empty());
+        assert_eq!(
+            names(net.rx_messages_by_node("B").unwrap()),
+            ["A_Heartbeat"]
+        );
+    }
+
+    #[test]
+    fn remove_and_rename_templates() {
+        let mut net = json_net();
+
+        assert!(matches!(
+            net.remove_template_message("Heartbeat"),
+            Err(CANConstructionError::TemplateMessageInUse(t, m)) if t == "Heartbeat" && m == "A_Heartbeat"
+        ));
+
+        net.rename_template_message("Heartbeat", "Alive").unwrap();
+        assert!(net.template_message_by_name("Heartbeat").is_none());
+        assert_eq!(net.template_message_by_name("Alive").unwrap().name, "Alive");
+        assert!(matches!(
+            net.message_by_name("A_Heartbeat").unwrap().kind(),
+            CANMessageKind::FromTemplate(t) if t == "Alive"
+        ));
+
+        net.remove_msg("A_Heartbeat").unwrap();
+        assert_eq!(net.remove_template_message("Alive").unwrap().name, "Alive");
+        assert!(matches!(
+            net.remove_template_message("Alive"),
+            Err(CANConstructionError::TemplateMessageDoesNotExist(_))
+        ));
+    }
+
+    #[test]
+    fn edit_signals() {
+        let mut net = json_net();
+
+        net.edit_signal("Data", "y", |s| {
+            s.name = "z".into();
+            s.width = 9;
+        })
+        .unwrap();
+        let msg = net.message_by_name("Data").unwrap();
+        assert_eq!(msg["z"].width, 9);
+        assert_eq!(msg.length, 2);
+
+        // growing `x` into `z` fails and leaves the message untouched
+        assert!(net.edit_signal("Data", "x", |s| s.width = 2).is_err());
+        let msg = net.message_by_name("Data").unwrap();
+        assert_eq!(msg["x"].width, 1);
+        assert_eq!(msg["z"].width, 9);
+
+        assert!(matches!(
+            net.edit_signal("Data", "nope", |_| ()),
+            Err(CANConstructionError::SignalDoesNotExist(..))
+        ));
+    }
 
     #[test]
     fn node_name_unique() {
@@ -228,4 +1132,103 @@ mod tests {
             net.add_node("TEST"),
             Err(CANConstructionError::NodeAlreadyExists(t)) if t == "TEST"));
     }
+
+    #[test]
+    fn value_tables() {
+        let mut net = CANNetwork::new();
+
+        let mut table = CANValueTable::new("OnOff").unwrap();
+        table.add_value("OFF", 0).unwrap();
+        table.add_value_inferred("ON").unwrap();
+        assert_eq!(table.values.get_by_left("ON"), Some(&1));
+
+        // names are used in generated C code
+        assert!(matches!(
+            CANValueTable::new("On-Off"),
+            Err(CANConstructionError::ValueTableNameInvalidChar(_, '-'))
+        ));
+        assert!(matches!(
+            CANValueTable::new(""),
+            Err(CANConstructionError::ValueTableNameEmpty)
+        ));
+
+        let mut renamed = table.clone();
+        renamed.name = "On Off".into();
+        assert!(matches!(
+            net.insert_value_table(renamed),
+            Err(CANConstructionError::ValueTableNameInvalidChar(_, ' '))
+        ));
+
+        let msg = |sig: CANSignal| {
+            CANMessage::builder()
+                .name("Status")
+                .id(0x10)
+                .add_signal(sig)
+                .unwrap()
+                .build()
+                .unwrap()
+        };
+        let sig = new_sig()
+            .name("power")
+            .use_value_table(&table)
+            .unwrap()
+            .infer_width()
+            .unwrap()
+            .build()
+            .unwrap();
+        assert_eq!(sig.width, 1);
+
+        // the table has to exist first
+        assert!(matches!(
+            net.insert_msg(msg(sig.clone())),
+            Err(CANConstructionError::ValueTableDoesNotExist(t)) if t == "OnOff"
+        ));
+
+        net.insert_value_table(table.clone()).unwrap();
+        assert!(matches!(
+            net.insert_value_table(table),
+            Err(CANConstructionError::ValueTableNameAlreadyExists(_))
+        ));
+
+        // and agree with the signal's values
+        let mut changed = sig.clone();
+        changed.enumerated_values.insert("STANDBY".into(), 2);
+        assert!(matches!(
+            net.insert_msg(msg(changed)),
+            Err(CANConstructionError::SignalValueTableMismatch(..))
+        ));
+
+        net.insert_msg(msg(sig)).unwrap();
+
+        // edits are held to the same checks
+        assert!(matches!(
+            net.edit_signal("Status", "power", |s| s.value_table = Some("Nope".into())),
+            Err(CANConstructionError::ValueTableDoesNotExist(t)) if t == "Nope"
+        ));
+        assert!(matches!(
+            net.edit_signal("Status", "power", |s| {
+                s.enumerated_values.remove_by_left("ON");
+            }),
+            Err(CANConstructionError::SignalValueTableMismatch(..))
+        ));
+        assert_eq!(
+            net.message_by_name("Status").unwrap()["power"]
+                .enumerated_values
+                .len(),
+            2
+        );
+
+        let json = serde_json::to_string(&net).unwrap();
+        let loaded = load(&json).unwrap();
+        assert_eq!(
+            loaded.value_table_by_name("OnOff"),
+            net.value_table_by_name("OnOff")
+        );
+        assert_eq!(
+            loaded.message_by_name("Status").unwrap()["power"]
+                .value_table
+                .as_deref(),
+            Some("OnOff")
+        );
+    }
 }
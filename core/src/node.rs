@@ -2,12 +2,18 @@ use std::collections::HashMap;
 
 use serde::{Deserialize, Serialize};
 
+use crate::Attributes;
+
 /// A validated description of a CAN node.
 #[derive(Debug, Serialize, Deserialize)]
 pub struct CANNode {
     /// Name of this node.
     pub name: String,
 
+    /// Description of this node.
+    #[serde(default)]
+    pub description: Option<String>,
+
     /// index into parent CANNetwork messages vec
     #[serde(serialize_with = "crate::sorted_map")]
     pub(crate) tx_messages: HashMap<String, usize>,
@@ -15,6 +21,10 @@ pub struct CANNode {
     /// index into parent CANNetwork messages vec
     #[serde(serialize_with = "crate::sorted_map")]
     pub(crate) rx_messages: HashMap<String, usize>,
+
+    /// User-defined attributes of this node.
+    #[serde(default)]
+    pub attributes: Attributes,
 }
 
 impl CANNode {
@@ -22,8 +32,10 @@ impl CANNode {
     pub fn new(name: String) -> Self {
         Self {
             name,
+            description: None,
             tx_messages: HashMap::new(),
             rx_messages: HashMap::new(),
+            attributes: Attributes::new(),
         }
     }
 
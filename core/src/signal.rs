@@ -2,11 +2,73 @@ use derive_builder::Builder;
 use serde::{Deserialize, Serialize};
 
 use crate::error::*;
+use crate::{Attributes, CANValueTable};
+
+/// Byte order (endianness) of a signal within its message.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum ByteOrder {
+    /// Intel byte order. The start bit is the least significant bit.
+    #[default]
+    LittleEndian,
+
+    /// Motorola byte order. The start bit is the most significant bit, and
+    /// bits are numbered in the DBC "sawtooth" fashion.
+    BigEndian,
+}
+
+/// How a signal's raw bits are interpreted, like DBC `SIG_VALTYPE_`.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum SignalValueType {
+    /// Unsigned or twos-complement integer, depending on `twos_complement`.
+    #[default]
+    Integer,
+
+    /// IEEE-754 single-precision float. The signal must be 32 bits wide.
+    Float,
+
+    /// IEEE-754 double-precision float. The signal must be 64 bits wide.
+    Double,
+}
+
+impl SignalValueType {
+    /// Width in bits required by this value type, if it is a float type.
+    pub const fn float_width(&self) -> Option<u32> {
+        match self {
+            Self::Integer => None,
+            Self::Float => Some(32),
+            Self::Double => Some(64),
+        }
+    }
+}
+
+/// Multiplexing of a signal: the signal is only present in its message when
+/// the multiplexor signal holds one of the given values.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct CANSignalMux {
+    /// Name of the multiplexor signal in the same message.
+    pub multiplexor: String,
+
+    /// Multiplexor values for which this signal is present.
+    pub values: Vec<u64>,
+}
+
+impl CANSignalMux {
+    /// Whether two signals with these muxes can never be present in the
+    /// same frame, i.e. they use the same multiplexor with disjoint values.
+    pub fn excludes(&self, other: &CANSignalMux) -> bool {
+        self.multiplexor == other.multiplexor
+            && !self.values.iter().any(|v| other.values.contains(v))
+    }
+}
 
 /// A validated description of a CAN signal.
 #[derive(Debug, Serialize, Deserialize, Clone, Builder)]
 #[builder(build_fn(name = "__build", error = "CANConstructionError", private))]
-#[builder(pattern = "owned")]
+#[builder(pattern = "owned", derive(Clone))]
 pub struct CANSignal {
     /// Name of this signal.
     #[builder(setter(into))]
@@ -23,6 +85,22 @@ pub struct CANSignal {
     #[builder(default)]
     pub twos_complement: bool,
 
+    /// Byte order of this signal.
+    #[builder(default)]
+    #[serde(default)]
+    pub byte_order: ByteOrder,
+
+    /// Whether the signal is an integer or an IEEE-754 float.
+    #[builder(default)]
+    #[serde(default)]
+    pub value_type: SignalValueType,
+
+    /// Multiplexing of this signal, if it is only present for some values
+    /// of a multiplexor signal.
+    #[builder(default)]
+    #[serde(default)]
+    pub mux: Option<CANSignalMux>,
+
     /// Numerical offset for this signal.
     #[builder(default)]
     pub offset: Option<f64>,
@@ -31,11 +109,52 @@ pub struct CANSignal {
     #[builder(default)]
     pub scale: Option<f64>,
 
+    /// Physical unit of this signal, e.g. `V` or `degC`.
+    #[builder(default)]
+    #[serde(default)]
+    pub unit: Option<String>,
+
+    /// Smallest expected physical value of this signal.
+    #[builder(default)]
+    #[serde(default)]
+    pub min: Option<f64>,
+
+    /// Largest expected physical value of this signal.
+    #[builder(default)]
+    #[serde(default)]
+    pub max: Option<f64>,
+
+    /// Raw value of this signal until it is first received or populated,
+    /// like DBC `GenSigStartValue`.
+    #[builder(default)]
+    #[serde(default)]
+    pub initial_value: Option<u64>,
+
+    /// Raw value the transmitter sends when this signal is not available (SNA).
+    #[builder(default)]
+    #[serde(default)]
+    pub sna_value: Option<u64>,
+
+    /// Raw value the transmitter sends to report an error for this signal.
+    #[builder(default)]
+    #[serde(default)]
+    pub error_value: Option<u64>,
+
     /// Bijective (bidirectional) map of enumerated values for this signal.
     #[builder(setter(custom), field(type = "bimap::BiMap<String, u64>"))]
     #[serde(serialize_with = "crate::sorted_bimap_by_second")]
     pub enumerated_values: bimap::BiMap<String, u64>,
 
+    /// Network value table this signal's enumerated values come from, if any.
+    #[builder(default)]
+    #[serde(default)]
+    pub value_table: Option<String>,
+
+    /// User-defined attributes of this signal.
+    #[builder(default)]
+    #[serde(default)]
+    pub attributes: Attributes,
+
     // annoying hack
     #[serde(skip)]
     #[builder(setter(custom), field(type = "Option<u64>"))]
@@ -44,13 +163,15 @@ pub struct CANSignal {
 
 impl CANSignalBuilder {
     /// Make a [`CANSignal`] from this builder.
+    ///
+    /// Checks that the width is nonzero and that any physical min/max can
+    /// be represented with the signal's width, scale and offset.
     pub fn build(self) -> Result<CANSignal, CANConstructionError> {
         let s = self.__build()?;
-        if s.width == 0 {
-            return Err(CANConstructionError::SignalHasZeroWidth(s.name));
-        }
+        s.check()?;
 
-        // check that the highest enumerated value can fit within the width of the signal
+        // enumerated values that don't fit in the width are reported by
+        // CANNetwork::validate()
 
         Ok(s)
     }
@@ -128,6 +249,19 @@ impl CANSignalBuilder {
         Ok(self)
     }
 
+    /// Use the values of a network value table as this signal's enumerated
+    /// values.
+    pub fn use_value_table(mut self, table: &CANValueTable) -> Result<Self, CANConstructionError> {
+        let mut values: Vec<_> = table.values.iter().collect();
+        values.sort_by_key(|v| v.1);
+
+        for (name, &val) in values {
+            self = self.add_enumerated_value(name, val)?;
+        }
+
+        Ok(self.value_table(Some(table.name.clone())))
+    }
+
     fn min_width_for_enumerated_values(&self) -> u32 {
         self._highest_enumerated_value.map_or(0, |v| {
             // this is ilog2() - .ilog2() stable in 1.67
@@ -140,6 +274,150 @@ impl CANSignal {
     pub fn builder() -> CANSignalBuilder {
         CANSignalBuilder::default()
     }
+
+    /// Convert a raw value of this signal to its physical value.
+    pub fn raw_to_physical(&self, raw: f64) -> f64 {
+        raw * self.scale.unwrap_or(1.0) + self.offset.unwrap_or(0.0)
+    }
+
+    /// Smallest and largest physical values that this signal's width,
+    /// scale and offset can represent.
+    pub fn physical_range(&self) -> (f64, f64) {
+        let width = self.width.min(64) as i32;
+
+        let (raw_min, raw_max) = if self.value_type == SignalValueType::Float {
+            (f32::MIN.into(), f32::MAX.into())
+        } else if self.value_type == SignalValueType::Double {
+            (f64::MIN, f64::MAX)
+        } else if self.twos_complement {
+            (-(2f64.powi(width - 1)), 2f64.powi(width - 1) - 1.0)
+        } else {
+            (0.0, 2f64.powi(width) - 1.0)
+        };
+
+        let a = self.raw_to_physical(raw_min);
+        let b = self.raw_to_physical(raw_max);
+
+        (a.min(b), a.max(b))
+    }
+
+    /// Check the signal's width, value type and physical min/max.
+    pub(crate) fn check(&self) -> Result<(), CANConstructionError> {
+        if self.width == 0 {
+            return Err(CANConstructionError::SignalHasZeroWidth(self.name.clone()));
+        }
+
+        if let Some(needed) = self.value_type.float_width() {
+            if self.width != needed {
+                return Err(CANConstructionError::FloatSignalWidth(
+                    self.name.clone(),
+                    self.width,
+                    needed,
+                ));
+            }
+
+            if self.twos_complement {
+                return Err(CANConstructionError::FloatSignalTwosComplement(
+                    self.name.clone(),
+                ));
+            }
+        } else if self.width > u64::BITS {
+            return Err(CANConstructionError::SignalTooWide(
+                self.name.clone(),
+                self.width,
+            ));
+        }
+
+        self.check_raw_values()?;
+
+        if let (Some(min), Some(max)) = (self.min, self.max) {
+            if min > max {
+                return Err(CANConstructionError::SignalMinAboveMax(
+                    self.name.clone(),
+                    min,
+                    max,
+                ));
+            }
+        }
+
+        // allow for rounding in the scale, e.g. 255 * 0.1 != 25.5
+        let (lo, hi) = self.physical_range();
+        let slack = (hi - lo).abs() * 1e-9;
+
+        for limit in [self.min, self.max].into_iter().flatten() {
+            if limit < lo - slack || limit > hi + slack {
+                return Err(CANConstructionError::SignalRangeNotRepresentable(
+                    self.name.clone(),
+                    limit,
+                    lo,
+                    hi,
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Check that the initial, SNA and error raw values fit in the signal.
+    ///
+    /// Float signals can't have SNA or error values, since a raw bit pattern
+    /// can't be compared reliably once it's a float (e.g. NaN), and their
+    /// initial value must be a finite number.
+    fn check_raw_values(&self) -> Result<(), CANConstructionError> {
+        let values = [
+            ("initial value", self.initial_value),
+            ("SNA value", self.sna_value),
+            ("error value", self.error_value),
+        ];
+
+        for (what, value) in values {
+            let Some(value) = value else {
+                continue;
+            };
+
+            if self.width < u64::BITS && value >> self.width != 0 {
+                return Err(CANConstructionError::SignalRawValueOutOfRange(
+                    self.name.clone(),
+                    what,
+                    value,
+                    self.width,
+                ));
+            }
+        }
+
+        let finite_initial = match self.value_type {
+            SignalValueType::Integer => return Ok(()),
+            SignalValueType::Float => self
+                .initial_value
+                .is_none_or(|v| f32::from_bits(v as u32).is_finite()),
+            SignalValueType::Double => self
+                .initial_value
+                .is_none_or(|v| f64::from_bits(v).is_finite()),
+        };
+
+        if !finite_initial {
+            return Err(CANConstructionError::FloatSignalRawValue(
+                self.name.clone(),
+                "a non-finite initial value",
+            ));
+        }
+
+        if self.sna_value.is_some() {
+            return Err(CANConstructionError::FloatSignalRawValue(
+                self.name.clone(),
+                "an SNA value",
+            ));
+        }
+
+        if self.error_value.is_some() {
+            return Err(CANConstructionError::FloatSignalRawValue(
+                self.name.clone(),
+                "an error value",
+            ));
+        }
+
+        Ok(())
+    }
 }
 
 #[cfg(test)]
@@ -168,6 +446,19 @@ pub(crate) mod tests {
         assert!(matches!(try_sig(1), Ok(..)));
     }
 
+    #[test]
+    fn signal_width_too_wide() {
+        let try_sig = |width| -> Result<_, CANConstructionError> {
+            new_sig().name("testSignal").width(width).build()
+        };
+
+        assert!(matches!(try_sig(64), Ok(..)));
+        assert!(matches!(
+            try_sig(65),
+            Err(CANConstructionError::SignalTooWide(_, 65))
+        ));
+    }
+
     #[test]
     fn signal_width_nonexistent() {
         assert!(matches!(
@@ -200,4 +491,114 @@ pub(crate) mod tests {
             Err(CANConstructionError::SignalWidthAlreadySpecified(..))
         ));
     }
+
+    #[test]
+    fn signal_float_width() {
+        let sig = |ty, width| {
+            new_sig()
+                .name("testSignal")
+                .width(width)
+                .value_type(ty)
+                .build()
+        };
+
+        assert!(sig(SignalValueType::Float, 32).is_ok());
+        assert!(sig(SignalValueType::Double, 64).is_ok());
+        assert!(matches!(
+            sig(SignalValueType::Float, 64),
+            Err(CANConstructionError::FloatSignalWidth(_, 64, 32))
+        ));
+        assert!(matches!(
+            new_sig()
+                .name("testSignal")
+                .width(32)
+                .value_type(SignalValueType::Float)
+                .twos_complement(true)
+                .build(),
+            Err(CANConstructionError::FloatSignalTwosComplement(..))
+        ));
+    }
+
+    #[test]
+    fn signal_physical_range() {
+        let sig = |min, max| {
+            new_sig()
+                .name("testSignal")
+                .width(8)
+                .twos_complement(true)
+                .scale(Some(0.5))
+                .offset(Some(10.))
+                .min(min)
+                .max(max)
+                .build()
+        };
+
+        assert_eq!(sig(None, None).unwrap().physical_range(), (-54., 73.5));
+        assert!(sig(Some(-54.), Some(73.5)).is_ok());
+        assert!(matches!(
+            sig(Some(-60.), None),
+            Err(CANConstructionError::SignalRangeNotRepresentable(_, v, ..)) if v == -60.
+        ));
+        assert!(matches!(
+            sig(Some(5.), Some(0.)),
+            Err(CANConstructionError::SignalMinAboveMax(..))
+        ));
+
+        // scale rounding doesn't reject the top of the range
+        let sig = new_sig()
+            .name("testSignal")
+            .width(8)
+            .scale(Some(0.1))
+            .max(Some(25.5))
+            .unit(Some("V".into()))
+            .build()
+            .unwrap();
+        assert_eq!(sig.unit.as_deref(), Some("V"));
+    }
+
+    #[test]
+    fn signal_raw_values() {
+        let base_sig = || new_sig().name("testSignal").width(4);
+
+        assert!(base_sig()
+            .initial_value(Some(3))
+            .sna_value(Some(15))
+            .error_value(Some(14))
+            .build()
+            .is_ok());
+        assert!(matches!(
+            base_sig().sna_value(Some(16)).build(),
+            Err(CANConstructionError::SignalRawValueOutOfRange(
+                _,
+                "SNA value",
+                16,
+                4
+            ))
+        ));
+
+        let float_sig = || {
+            new_sig()
+                .name("testSignal")
+                .width(32)
+                .value_type(SignalValueType::Float)
+        };
+
+        assert!(float_sig()
+            .initial_value(Some(1.5f32.to_bits().into()))
+            .build()
+            .is_ok());
+        assert!(matches!(
+            float_sig()
+                .initial_value(Some(f32::NAN.to_bits().into()))
+                .build(),
+            Err(CANConstructionError::FloatSignalRawValue(..))
+        ));
+        assert!(matches!(
+            float_sig().error_value(Some(0)).build(),
+            Err(CANConstructionError::FloatSignalRawValue(
+                _,
+                "an error value"
+            ))
+        ));
+    }
 }
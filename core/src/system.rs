@@ -0,0 +1,441 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::*;
+use crate::id::*;
+use crate::network::*;
+
+/// A message forwarded from one bus onto another by a gateway node.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CANRoute {
+    /// Node that receives the message on `from` and transmits it on `to`.
+    pub gateway: String,
+
+    /// Name of the message, which is the same on both buses.
+    pub message: String,
+
+    /// Bus the message is received from.
+    pub from: String,
+
+    /// Bus the message is transmitted onto.
+    pub to: String,
+
+    /// ID of the message on `to`, which may differ from its ID on `from`.
+    pub id: CANId,
+}
+
+/// Several named CAN buses, and the gateway routes between them.
+///
+/// Each bus is a complete [`CANNetwork`]. Nodes attached to more than one bus
+/// appear by the same name in each of them. Routed messages are copied onto
+/// the destination bus, transmitted there by the gateway, so each bus can be
+/// used on its own, e.g. for codegen.
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(try_from = "CANSystemDef")]
+pub struct CANSystem {
+    /// Buses in this system, by name.
+    buses: BTreeMap<String, CANNetwork>,
+
+    /// Routes between buses, in the order they were added.
+    routes: Vec<CANRoute>,
+}
+
+/// Serialized form of a [`CANSystem`], checked when converted.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct CANSystemDef {
+    buses: BTreeMap<String, CANNetwork>,
+    routes: Vec<CANRoute>,
+}
+
+impl TryFrom<CANSystemDef> for CANSystem {
+    type Error = CANConstructionError;
+
+    /// Rebuild a system from its serialized form, checking its routes the
+    /// same way as adding them by hand. The routed copies must already be on
+    /// their destination buses.
+    fn try_from(def: CANSystemDef) -> Result<Self, Self::Error> {
+        let mut system = Self {
+            buses: def.buses,
+            routes: Vec::new(),
+        };
+
+        for route in &def.routes {
+            system.check_route(&route.gateway, &route.message, &route.from, &route.to)?;
+
+            if system.buses[&route.to]
+                .message_by_name(&route.message)
+                .is_none()
+            {
+                return Err(CANConstructionError::MessageDoesNotExist(
+                    route.message.clone(),
+                ));
+            }
+        }
+
+        system.routes = def.routes;
+        Ok(system)
+    }
+}
+
+impl CANSystem {
+    /// Create a new (empty) system.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a bus to the system.
+    pub fn insert_bus(&mut self, name: &str, net: CANNetwork) -> Result<(), CANConstructionError> {
+        if self.buses.contains_key(name) {
+            return Err(CANConstructionError::BusAlreadyExists(name.into()));
+        }
+
+        self.buses.insert(name.into(), net);
+        Ok(())
+    }
+
+    /// Get a bus in this system by name.
+    pub fn bus_by_name(&self, name: &str) -> Option<&CANNetwork> {
+        self.buses.get(name)
+    }
+
+    /// Get a bus in this system by name, to change it.
+    ///
+    /// Changes to routed messages aren't carried over to other buses; use
+    /// [`CANSystem::validate()`] to check that routes still line up.
+    pub fn bus_by_name_mut(&mut self, name: &str) -> Option<&mut CANNetwork> {
+        self.buses.get_mut(name)
+    }
+
+    /// Take a single bus out of this system.
+    pub fn into_bus(mut self, name: &str) -> Option<CANNetwork> {
+        self.buses.remove(name)
+    }
+
+    /// Iterate over buses in this system, in name order.
+    pub fn iter_buses(&self) -> impl Iterator<Item = (&str, &CANNetwork)> {
+        self.buses.iter().map(|(name, net)| (name.as_str(), net))
+    }
+
+    /// Names of the buses a node is attached to, in name order.
+    pub fn buses_by_node(&self, node: &str) -> Vec<&str> {
+        self.iter_buses()
+            .filter(|(_, net)| net.node_by_name(node).is_some())
+            .map(|(name, _)| name)
+            .collect()
+    }
+
+    /// Iterate over routes in this system.
+    pub fn iter_routes(&self) -> std::slice::Iter<'_, CANRoute> {
+        self.routes.iter()
+    }
+
+    /// Route a message from bus `from` onto bus `to` through `gateway`.
+    ///
+    /// The message keeps its ID unless `id` remaps it. The gateway is made to
+    /// receive the message on `from` (unless it transmits it there), and a copy
+    /// transmitted by the gateway is added to `to`, along with any value
+    /// tables it uses that `to` doesn't have yet.
+    ///
+    /// Notes:
+    ///     - The gateway must be attached to both buses.
+    ///     - The copy must fit into `to` like any other message, so its name and
+    ///       ID must be free there. If it doesn't, `to` is left unchanged.
+    pub fn insert_route(
+        &mut self,
+        gateway: &str,
+        message: &str,
+        from: &str,
+        to: &str,
+        id: Option<CANId>,
+    ) -> Result<(), CANConstructionError> {
+        self.check_route(gateway, message, from, to)?;
+
+        let source = &self.buses[from];
+        let msg = source.message_by_name(message).unwrap();
+
+        let id = id.unwrap_or(msg.id);
+        let routed = msg.routed(gateway, id);
+        let receive = msg.tx_node() != Some(gateway);
+
+        let tables: Vec<_> = routed
+            .signals
+            .iter()
+            .filter_map(|s| s.sig.value_table.as_deref())
+            .filter_map(|t| source.value_table_by_name(t))
+            .cloned()
+            .collect();
+
+        // leave `to` as it was if the copy doesn't fit
+        let dest = self.buses.get_mut(to).unwrap();
+        let mut added = Vec::new();
+        let inserted = tables
+            .into_iter()
+            .try_for_each(|table| {
+                if dest.value_table_by_name(&table.name).is_none() {
+                    added.push(table.name.clone());
+                    dest.insert_value_table(table)?;
+                }
+                Ok(())
+            })
+            .and_then(|()| dest.insert_msg(routed));
+
+        if let Err(e) = inserted {
+            for table in added {
+                dest.remove_value_table(&table);
+            }
+            return Err(e);
+        }
+
+        if receive {
+            self.buses
+                .get_mut(from)
+                .unwrap()
+                .set_message_rx_by_node(message, gateway)
+                .expect("routed message and gateway were checked above");
+        }
+
+        self.routes.push(CANRoute {
+            gateway: gateway.into(),
+            message: message.into(),
+            from: from.into(),
+            to: to.into(),
+            id,
+        });
+
+        Ok(())
+    }
+
+    /// Check that `message` can be routed from bus `from` onto bus `to`
+    /// through `gateway`: the buses are different, exist and both have the
+    /// gateway, and the message is on `from`.
+    fn check_route(
+        &self,
+        gateway: &str,
+        message: &str,
+        from: &str,
+        to: &str,
+    ) -> Result<(), CANConstructionError> {
+        if from == to {
+            return Err(CANConstructionError::RouteWithinBus(
+                message.into(),
+                from.into(),
+            ));
+        }
+
+        for bus in [from, to] {
+            let net = self
+                .buses
+                .get(bus)
+                .ok_or_else(|| CANConstructionError::BusDoesNotExist(bus.into()))?;
+
+            if net.node_by_name(gateway).is_none() {
+                return Err(CANConstructionError::NodeNotOnBus(
+                    gateway.into(),
+                    bus.into(),
+                ));
+            }
+        }
+
+        if self.buses[from].message_by_name(message).is_none() {
+            return Err(CANConstructionError::MessageDoesNotExist(message.into()));
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::*;
+    use crate::*;
+
+    fn bus(nodes: &[&str]) -> CANNetwork {
+        let mut net = CANNetwork::new();
+        for node in nodes {
+            net.add_node(node).unwrap();
+        }
+        net
+    }
+
+    fn system() -> CANSystem {
+        let mut chassis = bus(&["ABS", "GW"]);
+        let mut on_off = CANValueTable::new("OnOff").unwrap();
+        on_off.add_value_inferred("OFF").unwrap();
+        on_off.add_value_inferred("ON").unwrap();
+        let active = new_sig()
+            .name("ABS_active")
+            .use_value_table(&on_off)
+            .unwrap()
+            .infer_width()
+            .unwrap()
+            .build()
+            .unwrap();
+        chassis.insert_value_table(on_off).unwrap();
+
+        let speed = CANMessage::builder()
+            .name("ABS_Speed")
+            .id(0x100)
+            .tx_node("ABS")
+            .add_signals([basic_sig("ABS_speed"), active])
+            .unwrap()
+            .build()
+            .unwrap();
+        chassis.insert_msg(speed).unwrap();
+
+        let mut dbw = bus(&["DBW", "GW"]);
+        let cmd = CANMessage::builder()
+            .name("DBW_Command")
+            .id(0x120)
+            .tx_node("DBW")
+            .build()
+            .unwrap();
+        dbw.insert_msg(cmd).unwrap();
+
+        let mut system = CANSystem::new();
+        system.insert_bus("chassis", chassis).unwrap();
+        system.insert_bus("dbw", dbw).unwrap();
+        system
+    }
+
+    #[test]
+    fn routes() {
+        let mut system = system();
+        assert_eq!(system.buses_by_node("GW"), ["chassis", "dbw"]);
+        assert_eq!(system.buses_by_node("ABS"), ["chassis"]);
+
+        // 0x120 is taken on the destination bus
+        assert!(matches!(
+            system.insert_route("GW", "ABS_Speed", "chassis", "dbw", Some(0x120.into())),
+            Err(CANConstructionError::MessageIdAlreadyExists(..))
+        ));
+        // without leaving the message's value table behind
+        assert!(system
+            .bus_by_name("dbw")
+            .unwrap()
+            .value_table_by_name("OnOff")
+            .is_none());
+        assert!(matches!(
+            system.insert_route("ABS", "ABS_Speed", "chassis", "dbw", Some(0x200.into())),
+            Err(CANConstructionError::NodeNotOnBus(n, b)) if n == "ABS" && b == "dbw"
+        ));
+        assert!(matches!(
+            system.insert_route("GW", "ABS_Speed", "chassis", "chassis", None),
+            Err(CANConstructionError::RouteWithinBus(..))
+        ));
+
+        system
+            .insert_route("GW", "ABS_Speed", "chassis", "dbw", Some(0x200.into()))
+            .unwrap();
+        system
+            .insert_route("GW", "DBW_Command", "dbw", "chassis", None)
+            .unwrap();
+
+        let chassis = system.bus_by_name("chassis").unwrap();
+        let dbw = system.bus_by_name("dbw").unwrap();
+
+        let routed = dbw.message_by_name("ABS_Speed").unwrap();
+        assert_eq!(routed.id, CANId::from(0x200));
+        assert!(dbw.value_table_by_name("OnOff").is_some());
+        assert_eq!(routed.tx_node(), Some("GW"));
+        assert!(routed.get_sig("ABS_speed").is_some());
+        assert!(chassis
+            .node_by_name("GW")
+            .unwrap()
+            .rx_messages
+            .contains_key("ABS_Speed"));
+
+        let routed = chassis.message_by_name("DBW_Command").unwrap();
+        assert_eq!(routed.id, CANId::from(0x120));
+
+        // routing a message back where it came from clashes with the original
+        assert!(matches!(
+            system.insert_route("GW", "ABS_Speed", "dbw", "chassis", None),
+            Err(CANConstructionError::MessageNameAlreadyExists(..))
+        ));
+
+        assert_eq!(system.iter_routes().count(), 2);
+        assert!(system.validate().iter().all(|f| !matches!(
+            f,
+            Finding::RoutedMessageMissing { .. }
+                | Finding::RoutedMessageChanged { .. }
+                | Finding::GatewayNotRouting { .. }
+        )));
+
+        // changing the copy on one bus breaks the route
+        let dbw = system.bus_by_name_mut("dbw").unwrap();
+        let sig = basic_sig("ABS_speed2");
+        dbw.edit_signal("ABS_Speed", "ABS_speed", |s| *s = sig)
+            .unwrap();
+
+        assert!(system.validate().contains(&Finding::RoutedMessageChanged {
+            message: "ABS_Speed".into(),
+            from: "chassis".into(),
+            to: "dbw".into(),
+            change: Change {
+                compatibility: Compatibility::Breaking,
+                kind: ChangeKind::SignalRemoved {
+                    message: "ABS_Speed".into(),
+                    signal: "ABS_speed".into(),
+                },
+            },
+        }));
+    }
+
+    #[test]
+    fn json_round_trip() {
+        let mut system = system();
+        system
+            .insert_route("GW", "ABS_Speed", "chassis", "dbw", Some(0x200.into()))
+            .unwrap();
+
+        let json = serde_json::to_string(&system).unwrap();
+        let back: CANSystem = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(
+            back.iter_routes().collect::<Vec<_>>(),
+            system.iter_routes().collect::<Vec<_>>()
+        );
+        assert!(back
+            .bus_by_name("dbw")
+            .unwrap()
+            .message_by_name("ABS_Speed")
+            .is_some());
+    }
+
+    #[test]
+    fn json_checks_routes() {
+        let mut system = system();
+        system
+            .insert_route("GW", "ABS_Speed", "chassis", "dbw", Some(0x200.into()))
+            .unwrap();
+        let json = serde_json::to_value(&system).unwrap();
+
+        let with_route = |key: &str, value: &str| {
+            let mut json = json.clone();
+            json["routes"][0][key] = value.into();
+            serde_json::from_value::<CANSystem>(json)
+        };
+
+        for (key, value, error) in [
+            ("from", "body", "Bus `body` does not exist."),
+            ("to", "chassis", "onto the same bus"),
+            ("gateway", "ABS", "Node `ABS` is not attached to bus `dbw`."),
+            ("message", "ABS_Status", "`ABS_Status` does not exist"),
+        ] {
+            let err = with_route(key, value).unwrap_err().to_string();
+            assert!(err.contains(error), "{err}");
+        }
+
+        // the routed copy must be on the destination bus
+        system
+            .bus_by_name_mut("dbw")
+            .unwrap()
+            .remove_msg("ABS_Speed")
+            .unwrap();
+        let json = serde_json::to_string(&system).unwrap();
+        assert!(serde_json::from_str::<CANSystem>(&json).is_err());
+    }
+}
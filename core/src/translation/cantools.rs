@@ -26,6 +26,13 @@ fn bool_to_py(b: bool) -> &'static str {
     }
 }
 
+fn byte_order_to_py(b: ByteOrder) -> &'static str {
+    match b {
+        ByteOrder::LittleEndian => "little_endian",
+        ByteOrder::BigEndian => "big_endian",
+    }
+}
+
 impl<'n> TranslationFromOpencan for CantoolsTranslator<'n> {
     fn translate(net: &CANNetwork) -> String {
         CantoolsTranslator { net }.dump_network()
@@ -81,6 +88,8 @@ impl<'n> CantoolsTranslator<'n> {
             cantools.database.can.Message(
                 name = {:?},
                 frame_id = {:#x},
+                is_extended_frame = {},
+                is_fd = {},
                 length = {},
                 senders = ['{}'],
                 cycle_time = {},
@@ -90,7 +99,9 @@ impl<'n> CantoolsTranslator<'n> {
             )
             ",
             msg.name,
-            msg.id,
+            msg.id.raw(),
+            bool_to_py(msg.id.is_extended()),
+            bool_to_py(msg.fd),
             msg.length,
             option_to_py(&msg.tx_node),
             option_to_py(&msg.cycletime),
@@ -129,7 +140,15 @@ impl<'n> CantoolsTranslator<'n> {
                 comment = {:?},
                 scale = {},
                 offset = {},
+                minimum = {},
+                maximum = {},
+                unit = {},
                 is_signed = {},
+                is_float = {},
+                byte_order = {:?},
+                is_multiplexer = {},
+                multiplexer_ids = {},
+                multiplexer_signal = {},
                 choices = {{
             {}
                 }},
@@ -142,7 +161,15 @@ impl<'n> CantoolsTranslator<'n> {
             option_to_py(&s.sig.description),
             s.sig.scale.unwrap_or(1.0),
             s.sig.offset.unwrap_or(0.0),
+            option_to_py(&s.sig.min),
+            option_to_py(&s.sig.max),
+            option_to_py(&s.sig.unit.as_ref().map(|u| format!("{u:?}"))),
             bool_to_py(s.sig.twos_complement),
+            bool_to_py(s.sig.value_type != SignalValueType::Integer),
+            byte_order_to_py(s.sig.byte_order),
+            bool_to_py(msg.is_multiplexor(&s.sig.name)),
+            option_to_py(&s.sig.mux.as_ref().map(|m| format!("{:?}", m.values))),
+            option_to_py(&s.sig.mux.as_ref().map(|m| format!("{:?}", m.multiplexor))),
             indent(&Self::signal_py_choices(&s.sig), &" ".repeat(8))
         )
     }
@@ -0,0 +1,1113 @@
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+
+use roxmltree::{Document, Node};
+use thiserror::Error;
+
+use super::{c_enumerated_values, parse_int, ImportMode};
+use crate::{
+    ByteOrder, CANConstructionError, CANId, CANMessage, CANNetwork, CANSignal, CANSignalBuilder,
+    SignalValueType, TranslationToOpencan,
+};
+
+/// Compu-method categories we can translate to scale, offset and
+/// enumerated values.
+const SUPPORTED_COMPU_CATEGORIES: [&str; 5] = [
+    "IDENTICAL",
+    "LINEAR",
+    "SCALE_LINEAR",
+    "TEXTTABLE",
+    "SCALE_LINEAR_AND_TEXTTABLE",
+];
+
+/// Error importing an ARXML file, naming the element at fault by its path,
+/// e.g. `/Vehicle/Frames/BMS_Status`.
+#[derive(Debug, Error)]
+pub enum ArxmlImportError {
+    #[error("Failed to parse ARXML file: {0}")]
+    Parse(String),
+
+    #[error("`{element}` has no `{child}`.")]
+    MissingElement {
+        element: String,
+        child: &'static str,
+    },
+
+    #[error("Reference `{reference}` in `{element}` does not point to a `{expected}`.")]
+    BrokenReference {
+        element: String,
+        reference: String,
+        expected: &'static str,
+    },
+
+    #[error("`{element}` has {what} `{value}`, which is not valid.")]
+    InvalidValue {
+        element: String,
+        what: &'static str,
+        value: String,
+    },
+
+    #[error("`{element}` uses {what}, which is not supported.")]
+    Unsupported { element: String, what: String },
+
+    #[error("Could not import node `{node}`: {cause}")]
+    Node {
+        node: String,
+        cause: CANConstructionError,
+    },
+
+    #[error("Could not import message `{message}`: {cause}")]
+    Message {
+        message: String,
+        cause: CANConstructionError,
+    },
+
+    #[error("Could not import signal `{signal}` in message `{message}`: {cause}")]
+    Signal {
+        message: String,
+        signal: String,
+        cause: CANConstructionError,
+    },
+
+    #[error("Could not add receiver `{node}` of message `{message}`: {cause}")]
+    Receiver {
+        message: String,
+        node: String,
+        cause: CANConstructionError,
+    },
+}
+
+/// Importer for the classic CAN subset of AUTOSAR System Template (`.arxml`)
+/// files.
+///
+/// Imports:
+///     - ECU instances as nodes.
+///     - CAN frame triggerings as messages, with the frame's name, the
+///       triggering's identifier, and the ECUs on its frame ports as the
+///       transmitter and receivers.
+///     - I-signals mapped into the frame's I-signal I-PDUs as signals, with
+///       scale, offset, unit and enumerated values from their compu-methods.
+///     - Cyclic timings of I-PDUs as message cycle times.
+///
+/// Big-endian (`MOST-SIGNIFICANT-BYTE-FIRST`) start positions are taken as
+/// the most significant bit, like DBC start bits.
+pub struct ArxmlImporter<'a, 'input> {
+    doc: &'a Document<'input>,
+    mode: ImportMode,
+
+    /// Identifiable elements by their absolute path, for resolving references.
+    elements: HashMap<String, Node<'a, 'input>>,
+
+    /// Problems skipped over in lenient mode.
+    warnings: RefCell<Vec<ArxmlImportError>>,
+}
+
+impl TranslationToOpencan for ArxmlImporter<'_, '_> {
+    type Error = ArxmlImportError;
+
+    fn import_network(
+        input: String,
+        net: &mut CANNetwork,
+        mode: ImportMode,
+    ) -> Result<Vec<ArxmlImportError>, ArxmlImportError> {
+        let doc = Document::parse(&input).map_err(|e| ArxmlImportError::Parse(e.to_string()))?;
+
+        let import = ArxmlImporter::new(&doc, mode);
+        import.import(net)?;
+
+        Ok(import.warnings.into_inner())
+    }
+}
+
+impl<'a, 'input> ArxmlImporter<'a, 'input> {
+    fn new(doc: &'a Document<'input>, mode: ImportMode) -> Self {
+        let elements = doc
+            .descendants()
+            .filter(|n| short_name(*n).is_some())
+            .map(|n| (element_path(n), n))
+            .collect();
+
+        Self {
+            doc,
+            mode,
+            elements,
+            warnings: RefCell::new(Vec::new()),
+        }
+    }
+
+    /// Deal with a part of the file that can't be imported: return it as an
+    /// error in strict mode, or keep it as a warning in lenient mode.
+    fn skip(&self, err: ArxmlImportError) -> Result<(), ArxmlImportError> {
+        match self.mode {
+            ImportMode::Strict => Err(err),
+            ImportMode::Lenient => {
+                self.warnings.borrow_mut().push(err);
+                Ok(())
+            }
+        }
+    }
+
+    /// All elements in the file with the given tag.
+    fn elements_by_tag(&self, tag: &'static str) -> impl Iterator<Item = Node<'a, 'input>> {
+        self.doc
+            .descendants()
+            .filter(move |n| n.tag_name().name() == tag)
+    }
+
+    fn import(&self, net: &mut CANNetwork) -> Result<(), ArxmlImportError> {
+        // Add all the ECUs to the network as nodes
+        for ecu in self.elements_by_tag("ECU-INSTANCE") {
+            let node = match self.required_text(ecu, "SHORT-NAME") {
+                Ok(node) => node,
+                Err(e) => {
+                    self.skip(e)?;
+                    continue;
+                }
+            };
+
+            if let Err(cause) = net.add_node(node) {
+                self.skip(ArxmlImportError::Node {
+                    node: node.into(),
+                    cause,
+                })?;
+            }
+        }
+
+        // Bitrate of the (first) CAN cluster
+        if let Some((cluster, baudrate)) = self
+            .elements_by_tag("CAN-CLUSTER")
+            .find_map(|c| Some((c, descendant_text(c, "BAUDRATE")?)))
+        {
+            match parse_int(baudrate).and_then(|b| u32::try_from(b).ok()) {
+                Some(b) => net.set_bitrate(b),
+                None => self.skip(ArxmlImportError::InvalidValue {
+                    element: element_path(cluster),
+                    what: "baudrate",
+                    value: baudrate.into(),
+                })?,
+            }
+        }
+
+        // Add a message to the network for each frame triggering
+        let mut receivers = Vec::new();
+        for triggering in self.elements_by_tag("CAN-FRAME-TRIGGERING") {
+            let res = self.translate_frame(triggering).and_then(|(msg, rx)| {
+                let name = msg.name.clone();
+                net.insert_msg(msg)
+                    .map_err(|cause| ArxmlImportError::Message {
+                        message: name.clone(),
+                        cause,
+                    })
+                    .map(|()| receivers.push((name, rx)))
+            });
+
+            if let Err(e) = res {
+                self.skip(e)?;
+            }
+        }
+
+        // Build RX mapping
+        for (message, nodes) in receivers {
+            for node in nodes {
+                if let Err(cause) = net.set_message_rx_by_node(&message, &node) {
+                    self.skip(ArxmlImportError::Receiver {
+                        message: message.clone(),
+                        node,
+                        cause,
+                    })?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Translate a frame triggering into a message and the names of the
+    /// nodes that receive it.
+    fn translate_frame(
+        &self,
+        triggering: Node<'a, 'input>,
+    ) -> Result<(CANMessage, Vec<String>), ArxmlImportError> {
+        let frame = self.resolve(triggering, "FRAME-REF", "CAN-FRAME")?;
+        let name = short_name(frame).unwrap();
+
+        // ID
+        let identifier = self.required_text(triggering, "IDENTIFIER")?;
+        let id = parse_int(identifier)
+            .and_then(|i| u32::try_from(i).ok())
+            .ok_or_else(|| ArxmlImportError::InvalidValue {
+                element: element_path(triggering),
+                what: "identifier",
+                value: identifier.into(),
+            })?;
+
+        let id = match child_text(triggering, "CAN-ADDRESSING-MODE") {
+            Some("EXTENDED") => CANId::Extended(id),
+            _ => CANId::Standard(id),
+        };
+
+        let mut msg = CANMessage::builder().name(name).id(id);
+
+        // transmitter and receivers, from the ECUs owning the frame ports
+        let mut rx = Vec::new();
+        let mut seen = HashSet::new();
+        for port_ref in descendants_by_tag(triggering, "FRAME-PORT-REF") {
+            let port = self.resolve_ref(port_ref, "FRAME-PORT")?;
+            let Some(ecu) = port
+                .ancestors()
+                .find(|n| n.tag_name().name() == "ECU-INSTANCE")
+                .and_then(short_name)
+            else {
+                continue;
+            };
+
+            match child_text(port, "COMMUNICATION-DIRECTION") {
+                Some("OUT") => msg = msg.tx_node(ecu),
+                Some("IN") if seen.insert(ecu) => rx.push(ecu.to_owned()),
+                _ => (),
+            }
+        }
+
+        // signals, from each PDU in the frame
+        let mut signals = Vec::new();
+        let mut cycletime = None;
+        for mapping in descendants_by_tag(frame, "PDU-TO-FRAME-MAPPING") {
+            let pdu = self.resolve_ref(
+                child(mapping, "PDU-REF").ok_or_else(|| ArxmlImportError::MissingElement {
+                    element: element_path(mapping),
+                    child: "PDU-REF",
+                })?,
+                "I-SIGNAL-I-PDU",
+            )?;
+
+            let offset = match child_text(mapping, "START-POSITION") {
+                Some(pos) => self.position(mapping, pos)?,
+                None => 0,
+            };
+
+            for sig_mapping in descendants_by_tag(pdu, "I-SIGNAL-TO-I-PDU-MAPPING") {
+                // signal groups are mapped alongside their signals
+                if child(sig_mapping, "I-SIGNAL-REF").is_none() {
+                    continue;
+                }
+
+                match self.translate_signal(name, sig_mapping, offset) {
+                    Ok(s) => signals.push(s),
+                    Err(e) => self.skip(e)?,
+                }
+            }
+
+            if cycletime.is_none() {
+                match self.pdu_cycletime(pdu) {
+                    Ok(c) => cycletime = c,
+                    Err(e) => self.skip(e)?,
+                }
+            }
+        }
+
+        signals.sort_by_key(|s| s.0);
+        for (bit, sig) in signals {
+            let signal = sig.name.clone();
+
+            match msg.clone().add_signal_fixed(bit, sig) {
+                Ok(m) => msg = m,
+                Err(cause) => self.skip(ArxmlImportError::Signal {
+                    message: name.into(),
+                    signal,
+                    cause,
+                })?,
+            }
+        }
+
+        let msg = msg
+            .cycletime(cycletime)
+            .build()
+            .map_err(|cause| ArxmlImportError::Message {
+                message: name.into(),
+                cause,
+            })?;
+
+        Ok((msg, rx))
+    }
+
+    /// Get the cycle time of an I-PDU in milliseconds, if it's sent cyclically.
+    fn pdu_cycletime(&self, pdu: Node<'a, 'input>) -> Result<Option<u32>, ArxmlImportError> {
+        // AUTOSAR 4.1+ calls the period TIME-PERIOD, 4.0 calls it REPETITION-PERIOD
+        let Some(period) = descendants_by_tag(pdu, "CYCLIC-TIMING")
+            .flat_map(|t| t.descendants())
+            .find(|n| matches!(n.tag_name().name(), "TIME-PERIOD" | "REPETITION-PERIOD"))
+            .and_then(|p| descendant_text(p, "VALUE"))
+        else {
+            return Ok(None);
+        };
+
+        // periods are in seconds
+        let ms =
+            period.parse::<f64>().ok().map(|s| s * 1000.0).filter(|ms| {
+                (ms.round() - ms).abs() < 1e-6 && (0.0..=u32::MAX as f64).contains(ms)
+            });
+
+        match ms {
+            Some(ms) if ms.round() == 0.0 => Ok(None),
+            Some(ms) => Ok(Some(ms.round() as u32)),
+            None => Err(ArxmlImportError::InvalidValue {
+                element: element_path(pdu),
+                what: "cycle time",
+                value: period.into(),
+            }),
+        }
+    }
+
+    /// Translate an I-signal mapped into a PDU, returning the signal and its
+    /// start bit in the frame.
+    fn translate_signal(
+        &self,
+        message: &str,
+        mapping: Node<'a, 'input>,
+        offset: u32,
+    ) -> Result<(u32, CANSignal), ArxmlImportError> {
+        let isig = self.resolve(mapping, "I-SIGNAL-REF", "I-SIGNAL")?;
+        let name = short_name(isig).unwrap();
+        let system_signal = self.resolve_optional(isig, "SYSTEM-SIGNAL-REF", "SYSTEM-SIGNAL")?;
+
+        let length = self.required_text(isig, "LENGTH")?;
+        let width = self.position(isig, length)?;
+
+        let start = self.required_text(mapping, "START-POSITION")?;
+        let start = self.position(mapping, start)? + offset;
+
+        let mut sig = CANSignal::builder().name(name).width(width);
+
+        // endianness
+        sig = sig.byte_order(match child_text(mapping, "PACKING-BYTE-ORDER") {
+            Some("MOST-SIGNIFICANT-BYTE-FIRST") => ByteOrder::BigEndian,
+            _ => ByteOrder::LittleEndian,
+        });
+
+        // signedness and float value types, from the base type
+        let encoding = self
+            .resolve_optional(isig, "BASE-TYPE-REF", "SW-BASE-TYPE")?
+            .and_then(|t| child_text(t, "BASE-TYPE-ENCODING"));
+
+        let value_type = match (encoding, width) {
+            (Some("IEEE754"), 32) => SignalValueType::Float,
+            (Some("IEEE754"), 64) => SignalValueType::Double,
+            _ => SignalValueType::Integer,
+        };
+        sig = sig
+            .value_type(value_type)
+            .twos_complement(encoding == Some("2C"));
+
+        // description
+        let description = system_signal
+            .and_then(|s| child(s, "DESC"))
+            .or_else(|| child(isig, "DESC"))
+            .and_then(|d| descendant_text(d, "L-2"));
+        sig = sig.description(description.map(Into::into));
+
+        // scale, offset, unit and enumerated values, preferring the I-signal's
+        // compu-method to the system signal's
+        let compu = match self.resolve_optional(isig, "COMPU-METHOD-REF", "COMPU-METHOD")? {
+            Some(c) => Some(c),
+            None => match system_signal {
+                Some(s) => self.resolve_optional(s, "COMPU-METHOD-REF", "COMPU-METHOD")?,
+                None => None,
+            },
+        };
+
+        if let Some(compu) = compu {
+            sig = self.apply_compu_method(message, name, sig, compu)?;
+        }
+
+        // initial value
+        if let Some(init) = child(isig, "INIT-VALUE").and_then(|v| descendant_text(v, "VALUE")) {
+            let raw = match value_type {
+                SignalValueType::Float => init.parse::<f32>().ok().map(|v| v.to_bits().into()),
+                SignalValueType::Double => init.parse::<f64>().ok().map(f64::to_bits),
+                SignalValueType::Integer => parse_int(init).and_then(|v| match u64::try_from(v) {
+                    Ok(v) => Some(v),
+                    // keep only the signal's bits of a negative value
+                    Err(_) if encoding == Some("2C") && v >= i64::MIN.into() => {
+                        Some((v as u64) & (u64::MAX >> (u64::BITS - width.clamp(1, 64))))
+                    }
+                    Err(_) => None,
+                }),
+            };
+
+            match raw {
+                Some(raw) => sig = sig.initial_value(Some(raw)),
+                None => self.skip(ArxmlImportError::InvalidValue {
+                    element: element_path(isig),
+                    what: "initial value",
+                    value: init.into(),
+                })?,
+            }
+        }
+
+        let sig = sig.build().map_err(|cause| ArxmlImportError::Signal {
+            message: message.into(),
+            signal: name.into(),
+            cause,
+        })?;
+
+        Ok((start, sig))
+    }
+
+    /// Apply a compu-method's linear scale (as scale and offset), its text
+    /// table (as enumerated values) and its unit to a signal.
+    fn apply_compu_method(
+        &self,
+        message: &str,
+        signal: &str,
+        mut sig: CANSignalBuilder,
+        compu: Node<'a, 'input>,
+    ) -> Result<CANSignalBuilder, ArxmlImportError> {
+        let category = child_text(compu, "CATEGORY").unwrap_or("IDENTICAL");
+        if !SUPPORTED_COMPU_CATEGORIES.contains(&category) {
+            self.skip(ArxmlImportError::Unsupported {
+                element: element_path(compu),
+                what: format!("compu-method category `{category}`"),
+            })?;
+            return Ok(sig);
+        }
+
+        let scales: Vec<_> = child(compu, "COMPU-INTERNAL-TO-PHYS")
+            .map(|c| descendants_by_tag(c, "COMPU-SCALE").collect())
+            .unwrap_or_default();
+
+        // linear scale
+        let mut linear = scales
+            .iter()
+            .filter_map(|s| child(*s, "COMPU-RATIONAL-COEFFS"));
+
+        if let Some(coeffs) = linear.next() {
+            if linear.next().is_some() {
+                self.skip(ArxmlImportError::Unsupported {
+                    element: element_path(compu),
+                    what: "more than one linear scale".into(),
+                })?;
+            }
+
+            let coeff = |tag: &'static str| -> Vec<f64> {
+                child(coeffs, tag)
+                    .map(|c| {
+                        children_by_tag(c, "V")
+                            .filter_map(|v| v.text()?.trim().parse().ok())
+                            .collect()
+                    })
+                    .unwrap_or_default()
+            };
+
+            let numerator = coeff("COMPU-NUMERATOR");
+            let denominator = coeff("COMPU-DENOMINATOR").first().copied().unwrap_or(1.0);
+
+            let (Some(&offset), Some(&factor)) = (numerator.first(), numerator.get(1)) else {
+                return Err(ArxmlImportError::MissingElement {
+                    element: element_path(compu),
+                    child: "COMPU-NUMERATOR",
+                });
+            };
+
+            if denominator == 0.0 {
+                return Err(ArxmlImportError::InvalidValue {
+                    element: element_path(compu),
+                    what: "denominator",
+                    value: denominator.to_string(),
+                });
+            }
+
+            if factor != denominator {
+                sig = sig.scale(Some(factor / denominator));
+            }
+
+            if offset != 0.0 {
+                sig = sig.offset(Some(offset / denominator));
+            }
+        }
+
+        // text table
+        let mut values = Vec::new();
+        for scale in &scales {
+            let Some(vt) = child(*scale, "COMPU-CONST").and_then(|c| child_text(c, "VT")) else {
+                continue;
+            };
+
+            let lower = child_text(*scale, "LOWER-LIMIT").and_then(parse_int);
+            let upper = child_text(*scale, "UPPER-LIMIT").and_then(parse_int);
+
+            match (lower, upper) {
+                (Some(l), Some(u)) if l == u && l >= 0 => values.push((vt.to_owned(), l as u64)),
+                _ => self.skip(ArxmlImportError::InvalidValue {
+                    element: element_path(compu),
+                    what: "text table entry",
+                    value: vt.into(),
+                })?,
+            }
+        }
+
+        for (name, value) in c_enumerated_values(values) {
+            sig = match sig.clone().add_enumerated_value(&name, value) {
+                Ok(s) => s,
+                Err(cause) => {
+                    self.skip(ArxmlImportError::Signal {
+                        message: message.into(),
+                        signal: signal.into(),
+                        cause,
+                    })?;
+                    sig
+                }
+            };
+        }
+
+        // unit
+        if let Some(unit) = self.resolve_optional(compu, "UNIT-REF", "UNIT")? {
+            let unit = child_text(unit, "DISPLAY-NAME").or_else(|| short_name(unit));
+            sig = sig.unit(unit.map(Into::into));
+        }
+
+        Ok(sig)
+    }
+
+    /// Parse a bit position or length.
+    fn position(&self, element: Node, text: &str) -> Result<u32, ArxmlImportError> {
+        parse_int(text)
+            .and_then(|p| u32::try_from(p).ok())
+            .ok_or_else(|| ArxmlImportError::InvalidValue {
+                element: element_path(element),
+                what: "bit position or length",
+                value: text.into(),
+            })
+    }
+
+    /// Get the text of a child element that must be there.
+    fn required_text(
+        &self,
+        element: Node<'a, 'input>,
+        tag: &'static str,
+    ) -> Result<&'a str, ArxmlImportError> {
+        child_text(element, tag).ok_or_else(|| ArxmlImportError::MissingElement {
+            element: element_path(element),
+            child: tag,
+        })
+    }
+
+    /// Follow the first reference with tag `ref_tag` in `element` (at any
+    /// depth), which should point to an element with tag `expected`.
+    fn resolve(
+        &self,
+        element: Node,
+        ref_tag: &'static str,
+        expected: &'static str,
+    ) -> Result<Node<'a, 'input>, ArxmlImportError> {
+        let reference = descendants_by_tag(element, ref_tag).next().ok_or_else(|| {
+            ArxmlImportError::MissingElement {
+                element: element_path(element),
+                child: ref_tag,
+            }
+        })?;
+
+        self.resolve_ref(reference, expected)
+    }
+
+    /// Like [`resolve()`](Self::resolve), but for references that may be
+    /// left out. Broken references are skipped over in lenient mode.
+    fn resolve_optional(
+        &self,
+        element: Node,
+        ref_tag: &'static str,
+        expected: &'static str,
+    ) -> Result<Option<Node<'a, 'input>>, ArxmlImportError> {
+        let Some(reference) = descendants_by_tag(element, ref_tag).next() else {
+            return Ok(None);
+        };
+
+        match self.resolve_ref(reference, expected) {
+            Ok(n) => Ok(Some(n)),
+            Err(e) => self.skip(e).map(|()| None),
+        }
+    }
+
+    /// Follow a reference element, which should point to an element with
+    /// tag `expected`.
+    fn resolve_ref(
+        &self,
+        reference: Node,
+        expected: &'static str,
+    ) -> Result<Node<'a, 'input>, ArxmlImportError> {
+        let path = reference.text().unwrap_or_default().trim();
+
+        match self.elements.get(path) {
+            Some(n) if n.tag_name().name() == expected => Ok(*n),
+            Some(n) => Err(ArxmlImportError::Unsupported {
+                element: path.into(),
+                what: format!(
+                    "a `{}` where a `{expected}` is expected",
+                    n.tag_name().name()
+                ),
+            }),
+            None => Err(ArxmlImportError::BrokenReference {
+                element: reference
+                    .ancestors()
+                    .find(|n| short_name(*n).is_some())
+                    .map(element_path)
+                    .unwrap_or_default(),
+                reference: path.into(),
+                expected,
+            }),
+        }
+    }
+}
+
+/// Get the first direct child of an element with the given tag.
+fn child<'a, 'input>(element: Node<'a, 'input>, tag: &str) -> Option<Node<'a, 'input>> {
+    element.children().find(|n| n.tag_name().name() == tag)
+}
+
+/// Get the direct children of an element with the given tag.
+fn children_by_tag<'a, 'input>(
+    element: Node<'a, 'input>,
+    tag: &'static str,
+) -> impl Iterator<Item = Node<'a, 'input>> {
+    element
+        .children()
+        .filter(move |n| n.tag_name().name() == tag)
+}
+
+/// Get all elements with the given tag below an element.
+fn descendants_by_tag<'a, 'input>(
+    element: Node<'a, 'input>,
+    tag: &'static str,
+) -> impl Iterator<Item = Node<'a, 'input>> {
+    element
+        .descendants()
+        .filter(move |n| n.tag_name().name() == tag)
+}
+
+/// Get the trimmed text of the first direct child with the given tag.
+fn child_text<'a>(element: Node<'a, '_>, tag: &str) -> Option<&'a str> {
+    child(element, tag)?.text().map(str::trim)
+}
+
+/// Get the trimmed text of the first element with the given tag below an
+/// element.
+fn descendant_text<'a>(element: Node<'a, '_>, tag: &'static str) -> Option<&'a str> {
+    descendants_by_tag(element, tag)
+        .next()?
+        .text()
+        .map(str::trim)
+}
+
+/// Get the `SHORT-NAME` of an element, if it's identifiable.
+fn short_name<'a>(element: Node<'a, '_>) -> Option<&'a str> {
+    child_text(element, "SHORT-NAME")
+}
+
+/// Get the absolute path of an element, made of its own and its identifiable
+/// ancestors' short names, e.g. `/Vehicle/Frames/BMS_Status`.
+fn element_path(element: Node) -> String {
+    let mut names: Vec<_> = element.ancestors().filter_map(short_name).collect();
+    names.reverse();
+
+    names.iter().map(|n| format!("/{n}")).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ARXML: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<AUTOSAR xmlns="http://autosar.org/schema/r4.0">
+  <AR-PACKAGES>
+    <AR-PACKAGE>
+      <SHORT-NAME>Vehicle</SHORT-NAME>
+      <ELEMENTS>
+        <ECU-INSTANCE>
+          <SHORT-NAME>BMS</SHORT-NAME>
+          <CONNECTORS>
+            <CAN-COMMUNICATION-CONNECTOR>
+              <SHORT-NAME>Conn</SHORT-NAME>
+              <ECU-COMM-PORT-INSTANCES>
+                <FRAME-PORT>
+                  <SHORT-NAME>Status_Out</SHORT-NAME>
+                  <COMMUNICATION-DIRECTION>OUT</COMMUNICATION-DIRECTION>
+                </FRAME-PORT>
+                <FRAME-PORT>
+                  <SHORT-NAME>Command_In</SHORT-NAME>
+                  <COMMUNICATION-DIRECTION>IN</COMMUNICATION-DIRECTION>
+                </FRAME-PORT>
+              </ECU-COMM-PORT-INSTANCES>
+            </CAN-COMMUNICATION-CONNECTOR>
+          </CONNECTORS>
+        </ECU-INSTANCE>
+        <ECU-INSTANCE>
+          <SHORT-NAME>VCU</SHORT-NAME>
+          <CONNECTORS>
+            <CAN-COMMUNICATION-CONNECTOR>
+              <SHORT-NAME>Conn</SHORT-NAME>
+              <ECU-COMM-PORT-INSTANCES>
+                <FRAME-PORT>
+                  <SHORT-NAME>Status_In</SHORT-NAME>
+                  <COMMUNICATION-DIRECTION>IN</COMMUNICATION-DIRECTION>
+                </FRAME-PORT>
+                <FRAME-PORT>
+                  <SHORT-NAME>Command_Out</SHORT-NAME>
+                  <COMMUNICATION-DIRECTION>OUT</COMMUNICATION-DIRECTION>
+                </FRAME-PORT>
+              </ECU-COMM-PORT-INSTANCES>
+            </CAN-COMMUNICATION-CONNECTOR>
+          </CONNECTORS>
+        </ECU-INSTANCE>
+
+        <CAN-CLUSTER>
+          <SHORT-NAME>Powertrain</SHORT-NAME>
+          <CAN-CLUSTER-VARIANTS>
+            <CAN-CLUSTER-CONDITIONAL>
+              <BAUDRATE>500000</BAUDRATE>
+              <PHYSICAL-CHANNELS>
+                <CAN-PHYSICAL-CHANNEL>
+                  <SHORT-NAME>Channel</SHORT-NAME>
+                  <FRAME-TRIGGERINGS>
+                    <CAN-FRAME-TRIGGERING>
+                      <SHORT-NAME>Status_Triggering</SHORT-NAME>
+                      <FRAME-PORT-REFS>
+                        <FRAME-PORT-REF DEST="FRAME-PORT">/Vehicle/BMS/Conn/Status_Out</FRAME-PORT-REF>
+                        <FRAME-PORT-REF DEST="FRAME-PORT">/Vehicle/VCU/Conn/Status_In</FRAME-PORT-REF>
+                      </FRAME-PORT-REFS>
+                      <FRAME-REF DEST="CAN-FRAME">/Vehicle/Frames/BMS_Status</FRAME-REF>
+                      <CAN-ADDRESSING-MODE>STANDARD</CAN-ADDRESSING-MODE>
+                      <IDENTIFIER>0x120</IDENTIFIER>
+                    </CAN-FRAME-TRIGGERING>
+                    <CAN-FRAME-TRIGGERING>
+                      <SHORT-NAME>Command_Triggering</SHORT-NAME>
+                      <FRAME-PORT-REFS>
+                        <FRAME-PORT-REF DEST="FRAME-PORT">/Vehicle/VCU/Conn/Command_Out</FRAME-PORT-REF>
+                        <FRAME-PORT-REF DEST="FRAME-PORT">/Vehicle/BMS/Conn/Command_In</FRAME-PORT-REF>
+                      </FRAME-PORT-REFS>
+                      <FRAME-REF DEST="CAN-FRAME">/Vehicle/Frames/VCU_Command</FRAME-REF>
+                      <CAN-ADDRESSING-MODE>EXTENDED</CAN-ADDRESSING-MODE>
+                      <IDENTIFIER>416</IDENTIFIER>
+                    </CAN-FRAME-TRIGGERING>
+                  </FRAME-TRIGGERINGS>
+                </CAN-PHYSICAL-CHANNEL>
+              </PHYSICAL-CHANNELS>
+            </CAN-CLUSTER-CONDITIONAL>
+          </CAN-CLUSTER-VARIANTS>
+        </CAN-CLUSTER>
+      </ELEMENTS>
+
+      <AR-PACKAGES>
+        <AR-PACKAGE>
+          <SHORT-NAME>Frames</SHORT-NAME>
+          <ELEMENTS>
+            <CAN-FRAME>
+              <SHORT-NAME>BMS_Status</SHORT-NAME>
+              <FRAME-LENGTH>3</FRAME-LENGTH>
+              <PDU-TO-FRAME-MAPPINGS>
+                <PDU-TO-FRAME-MAPPING>
+                  <SHORT-NAME>Status_Mapping</SHORT-NAME>
+                  <PACKING-BYTE-ORDER>MOST-SIGNIFICANT-BYTE-LAST</PACKING-BYTE-ORDER>
+                  <PDU-REF DEST="I-SIGNAL-I-PDU">/Vehicle/Pdus/Status_Pdu</PDU-REF>
+                  <START-POSITION>0</START-POSITION>
+                </PDU-TO-FRAME-MAPPING>
+              </PDU-TO-FRAME-MAPPINGS>
+            </CAN-FRAME>
+            <CAN-FRAME>
+              <SHORT-NAME>VCU_Command</SHORT-NAME>
+              <FRAME-LENGTH>2</FRAME-LENGTH>
+              <PDU-TO-FRAME-MAPPINGS>
+                <PDU-TO-FRAME-MAPPING>
+                  <SHORT-NAME>Command_Mapping</SHORT-NAME>
+                  <PDU-REF DEST="I-SIGNAL-I-PDU">/Vehicle/Pdus/Command_Pdu</PDU-REF>
+                  <START-POSITION>8</START-POSITION>
+                </PDU-TO-FRAME-MAPPING>
+              </PDU-TO-FRAME-MAPPINGS>
+            </CAN-FRAME>
+          </ELEMENTS>
+        </AR-PACKAGE>
+
+        <AR-PACKAGE>
+          <SHORT-NAME>Pdus</SHORT-NAME>
+          <ELEMENTS>
+            <I-SIGNAL-I-PDU>
+              <SHORT-NAME>Status_Pdu</SHORT-NAME>
+              <LENGTH>3</LENGTH>
+              <I-PDU-TIMING-SPECIFICATIONS>
+                <I-PDU-TIMING>
+                  <TRANSMISSION-MODE-DECLARATION>
+                    <TRANSMISSION-MODE-TRUE-TIMING>
+                      <CYCLIC-TIMING>
+                        <TIME-PERIOD>
+                          <VALUE>0.1</VALUE>
+                        </TIME-PERIOD>
+                      </CYCLIC-TIMING>
+                    </TRANSMISSION-MODE-TRUE-TIMING>
+                  </TRANSMISSION-MODE-DECLARATION>
+                </I-PDU-TIMING>
+              </I-PDU-TIMING-SPECIFICATIONS>
+              <I-SIGNAL-TO-PDU-MAPPINGS>
+                <I-SIGNAL-TO-I-PDU-MAPPING>
+                  <SHORT-NAME>voltage_Mapping</SHORT-NAME>
+                  <I-SIGNAL-REF DEST="I-SIGNAL">/Vehicle/Signals/BMS_voltage</I-SIGNAL-REF>
+                  <PACKING-BYTE-ORDER>MOST-SIGNIFICANT-BYTE-LAST</PACKING-BYTE-ORDER>
+                  <START-POSITION>0</START-POSITION>
+                </I-SIGNAL-TO-I-PDU-MAPPING>
+                <I-SIGNAL-TO-I-PDU-MAPPING>
+                  <SHORT-NAME>state_Mapping</SHORT-NAME>
+                  <I-SIGNAL-REF DEST="I-SIGNAL">/Vehicle/Signals/BMS_state</I-SIGNAL-REF>
+                  <PACKING-BYTE-ORDER>MOST-SIGNIFICANT-BYTE-LAST</PACKING-BYTE-ORDER>
+                  <START-POSITION>16</START-POSITION>
+                </I-SIGNAL-TO-I-PDU-MAPPING>
+              </I-SIGNAL-TO-PDU-MAPPINGS>
+            </I-SIGNAL-I-PDU>
+            <I-SIGNAL-I-PDU>
+              <SHORT-NAME>Command_Pdu</SHORT-NAME>
+              <LENGTH>1</LENGTH>
+              <I-SIGNAL-TO-PDU-MAPPINGS>
+                <I-SIGNAL-TO-I-PDU-MAPPING>
+                  <SHORT-NAME>torque_Mapping</SHORT-NAME>
+                  <I-SIGNAL-REF DEST="I-SIGNAL">/Vehicle/Signals/VCU_torque</I-SIGNAL-REF>
+                  <PACKING-BYTE-ORDER>MOST-SIGNIFICANT-BYTE-FIRST</PACKING-BYTE-ORDER>
+                  <START-POSITION>7</START-POSITION>
+                </I-SIGNAL-TO-I-PDU-MAPPING>
+              </I-SIGNAL-TO-PDU-MAPPINGS>
+            </I-SIGNAL-I-PDU>
+          </ELEMENTS>
+        </AR-PACKAGE>
+
+        <AR-PACKAGE>
+          <SHORT-NAME>Signals</SHORT-NAME>
+          <ELEMENTS>
+            <I-SIGNAL>
+              <SHORT-NAME>BMS_voltage</SHORT-NAME>
+              <INIT-VALUE>
+                <NUMERICAL-VALUE-SPECIFICATION>
+                  <VALUE>1200</VALUE>
+                </NUMERICAL-VALUE-SPECIFICATION>
+              </INIT-VALUE>
+              <LENGTH>16</LENGTH>
+              <SYSTEM-SIGNAL-REF DEST="SYSTEM-SIGNAL">/Vehicle/Signals/voltage</SYSTEM-SIGNAL-REF>
+            </I-SIGNAL>
+            <SYSTEM-SIGNAL>
+              <SHORT-NAME>voltage</SHORT-NAME>
+              <DESC>
+                <L-2 L="EN">Pack voltage</L-2>
+              </DESC>
+              <PHYSICAL-PROPS>
+                <SW-DATA-DEF-PROPS-VARIANTS>
+                  <SW-DATA-DEF-PROPS-CONDITIONAL>
+                    <COMPU-METHOD-REF DEST="COMPU-METHOD">/Vehicle/Compu/Voltage</COMPU-METHOD-REF>
+                  </SW-DATA-DEF-PROPS-CONDITIONAL>
+                </SW-DATA-DEF-PROPS-VARIANTS>
+              </PHYSICAL-PROPS>
+            </SYSTEM-SIGNAL>
+            <I-SIGNAL>
+              <SHORT-NAME>BMS_state</SHORT-NAME>
+              <LENGTH>2</LENGTH>
+              <NETWORK-REPRESENTATION-PROPS>
+                <SW-DATA-DEF-PROPS-VARIANTS>
+                  <SW-DATA-DEF-PROPS-CONDITIONAL>
+                    <COMPU-METHOD-REF DEST="COMPU-METHOD">/Vehicle/Compu/State</COMPU-METHOD-REF>
+                  </SW-DATA-DEF-PROPS-CONDITIONAL>
+                </SW-DATA-DEF-PROPS-VARIANTS>
+              </NETWORK-REPRESENTATION-PROPS>
+            </I-SIGNAL>
+            <I-SIGNAL>
+              <SHORT-NAME>VCU_torque</SHORT-NAME>
+              <INIT-VALUE>
+                <NUMERICAL-VALUE-SPECIFICATION>
+                  <VALUE>-1</VALUE>
+                </NUMERICAL-VALUE-SPECIFICATION>
+              </INIT-VALUE>
+              <LENGTH>8</LENGTH>
+              <NETWORK-REPRESENTATION-PROPS>
+                <SW-DATA-DEF-PROPS-VARIANTS>
+                  <SW-DATA-DEF-PROPS-CONDITIONAL>
+                    <BASE-TYPE-REF DEST="SW-BASE-TYPE">/Vehicle/Types/sint8</BASE-TYPE-REF>
+                  </SW-DATA-DEF-PROPS-CONDITIONAL>
+                </SW-DATA-DEF-PROPS-VARIANTS>
+              </NETWORK-REPRESENTATION-PROPS>
+            </I-SIGNAL>
+          </ELEMENTS>
+        </AR-PACKAGE>
+
+        <AR-PACKAGE>
+          <SHORT-NAME>Compu</SHORT-NAME>
+          <ELEMENTS>
+            <COMPU-METHOD>
+              <SHORT-NAME>Voltage</SHORT-NAME>
+              <CATEGORY>LINEAR</CATEGORY>
+              <UNIT-REF DEST="UNIT">/Vehicle/Units/Volt</UNIT-REF>
+              <COMPU-INTERNAL-TO-PHYS>
+                <COMPU-SCALES>
+                  <COMPU-SCALE>
+                    <COMPU-RATIONAL-COEFFS>
+                      <COMPU-NUMERATOR>
+                        <V>-100</V>
+                        <V>1</V>
+                      </COMPU-NUMERATOR>
+                      <COMPU-DENOMINATOR>
+                        <V>10</V>
+                      </COMPU-DENOMINATOR>
+                    </COMPU-RATIONAL-COEFFS>
+                  </COMPU-SCALE>
+                </COMPU-SCALES>
+              </COMPU-INTERNAL-TO-PHYS>
+            </COMPU-METHOD>
+            <COMPU-METHOD>
+              <SHORT-NAME>State</SHORT-NAME>
+              <CATEGORY>TEXTTABLE</CATEGORY>
+              <COMPU-INTERNAL-TO-PHYS>
+                <COMPU-SCALES>
+                  <COMPU-SCALE>
+                    <LOWER-LIMIT>0</LOWER-LIMIT>
+                    <UPPER-LIMIT>0</UPPER-LIMIT>
+                    <COMPU-CONST><VT>Off</VT></COMPU-CONST>
+                  </COMPU-SCALE>
+                  <COMPU-SCALE>
+                    <LOWER-LIMIT>1</LOWER-LIMIT>
+                    <UPPER-LIMIT>1</UPPER-LIMIT>
+                    <COMPU-CONST><VT>Charging</VT></COMPU-CONST>
+                  </COMPU-SCALE>
+                  <COMPU-SCALE>
+                    <LOWER-LIMIT>3</LOWER-LIMIT>
+                    <UPPER-LIMIT>3</UPPER-LIMIT>
+                    <COMPU-CONST><VT>Not available</VT></COMPU-CONST>
+                  </COMPU-SCALE>
+                </COMPU-SCALES>
+              </COMPU-INTERNAL-TO-PHYS>
+            </COMPU-METHOD>
+          </ELEMENTS>
+        </AR-PACKAGE>
+
+        <AR-PACKAGE>
+          <SHORT-NAME>Units</SHORT-NAME>
+          <ELEMENTS>
+            <UNIT>
+              <SHORT-NAME>Volt</SHORT-NAME>
+              <DISPLAY-NAME>V</DISPLAY-NAME>
+            </UNIT>
+          </ELEMENTS>
+        </AR-PACKAGE>
+
+        <AR-PACKAGE>
+          <SHORT-NAME>Types</SHORT-NAME>
+          <ELEMENTS>
+            <SW-BASE-TYPE>
+              <SHORT-NAME>sint8</SHORT-NAME>
+              <BASE-TYPE-ENCODING>2C</BASE-TYPE-ENCODING>
+            </SW-BASE-TYPE>
+          </ELEMENTS>
+        </AR-PACKAGE>
+      </AR-PACKAGES>
+    </AR-PACKAGE>
+  </AR-PACKAGES>
+</AUTOSAR>
+"#;
+
+    #[test]
+    fn import_arxml() {
+        let mut net = CANNetwork::new();
+        let warnings =
+            ArxmlImporter::import_network(ARXML.into(), &mut net, ImportMode::Strict).unwrap();
+        assert!(warnings.is_empty());
+
+        assert!(net.node_by_name("BMS").is_some());
+        assert!(net.node_by_name("VCU").is_some());
+        assert_eq!(net.bitrate(), Some(500000));
+
+        let status = net.message_by_name("BMS_Status").unwrap();
+        assert_eq!(status.id, CANId::Standard(0x120));
+        assert_eq!(status.tx_node(), Some("BMS"));
+        assert_eq!(status.cycletime, Some(100));
+        assert_eq!(status.length, 3);
+
+        let voltage = &status["BMS_voltage"];
+        assert_eq!(voltage.width, 16);
+        assert_eq!(voltage.scale, Some(0.1));
+        assert_eq!(voltage.offset, Some(-10.0));
+        assert_eq!(voltage.unit.as_deref(), Some("V"));
+        assert_eq!(voltage.description.as_deref(), Some("Pack voltage"));
+        assert_eq!(voltage.initial_value, Some(1200));
+
+        let state = &status["BMS_state"];
+        assert_eq!(status.get_sig("BMS_state").unwrap().start(), 16);
+        assert_eq!(state.enumerated_values.get_by_left("CHARGING"), Some(&1));
+        assert_eq!(
+            state.enumerated_values.get_by_left("NOT_AVAILABLE"),
+            Some(&3)
+        );
+
+        // PDU starts at the second byte of the frame
+        let command = net.message_by_name("VCU_Command").unwrap();
+        assert_eq!(command.id, CANId::Extended(416));
+        assert_eq!(command.cycletime, None);
+        let torque = command.get_sig("VCU_torque").unwrap();
+        assert_eq!(torque.start(), 15);
+        assert_eq!(torque.sig.byte_order, ByteOrder::BigEndian);
+        assert!(torque.sig.twos_complement);
+        assert_eq!(torque.sig.initial_value, Some(0xFF));
+
+        let rx = net.rx_messages_by_node("VCU").unwrap();
+        assert_eq!(rx.len(), 1);
+        assert_eq!(rx[0].name, "BMS_Status");
+        assert_eq!(
+            net.rx_messages_by_node("BMS").unwrap()[0].name,
+            "VCU_Command"
+        );
+    }
+
+    #[test]
+    fn import_arxml_broken_reference() {
+        let arxml = ARXML.replace("/Vehicle/Compu/State<", "/Vehicle/Compu/Mode<");
+
+        let mut net = CANNetwork::new();
+        let err =
+            ArxmlImporter::import_network(arxml.clone(), &mut net, ImportMode::Strict).unwrap_err();
+        assert!(matches!(
+            err,
+            ArxmlImportError::BrokenReference { reference, .. } if reference == "/Vehicle/Compu/Mode"
+        ));
+
+        // the signal falls back to its raw value in lenient mode
+        let mut net = CANNetwork::new();
+        let warnings = ArxmlImporter::import_network(arxml, &mut net, ImportMode::Lenient).unwrap();
+        assert_eq!(warnings.len(), 1);
+        assert!(net.message_by_name("BMS_Status").unwrap()["BMS_state"]
+            .enumerated_values
+            .is_empty());
+
+        let mut net = CANNetwork::new();
+        assert!(matches!(
+            ArxmlImporter::import_network("<AUTOSAR>".into(), &mut net, ImportMode::Lenient),
+            Err(ArxmlImportError::Parse(..))
+        ));
+    }
+
+    #[test]
+    fn import_arxml_unnamed_ecu() {
+        let arxml = ARXML.replace("<SHORT-NAME>VCU</SHORT-NAME>", "");
+
+        let mut net = CANNetwork::new();
+        let err =
+            ArxmlImporter::import_network(arxml.clone(), &mut net, ImportMode::Strict).unwrap_err();
+        assert!(matches!(
+            err,
+            ArxmlImportError::MissingElement {
+                child: "SHORT-NAME",
+                ..
+            }
+        ));
+
+        let mut net = CANNetwork::new();
+        let warnings = ArxmlImporter::import_network(arxml, &mut net, ImportMode::Lenient).unwrap();
+        assert!(matches!(
+            warnings[0],
+            ArxmlImportError::MissingElement {
+                child: "SHORT-NAME",
+                ..
+            }
+        ));
+        assert!(net.node_by_name("BMS").is_some());
+        assert!(net.node_by_name("VCU").is_none());
+    }
+}
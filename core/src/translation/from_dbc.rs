@@ -1,128 +1,874 @@
-use std::collections::HashMap;
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
 
 use can_dbc::AttributeValuedForObjectType::MessageDefinitionAttributeValue;
-use can_dbc::ByteOrder;
+use thiserror::Error;
 
-use crate::{CANMessage, CANNetwork, CANSignal, TranslationToOpencan};
+use super::{c_enumerated_values, order_multiplexors_first, ImportMode};
+use crate::{
+    AttributeValue, ByteOrder, CANConstructionError, CANId, CANMessage, CANNaming, CANNetwork,
+    CANSignal, CANSignalMux, CANValueTable, SignalValueType, TranslationToOpencan,
+};
+
+/// DBC files mark extended message IDs by setting bit 31.
+const DBC_EXTENDED_ID_FLAG: u32 = 1 << 31;
+
+/// Placeholder node name for DBC messages and signals with no node.
+const DBC_NO_NODE: &str = "Vector__XXX";
+
+/// Message attribute giving the message cycle time in milliseconds.
+const DBC_CYCLE_TIME_ATTR: &str = "GenMsgCycleTime";
+
+/// Message attribute giving the frame format, e.g. `StandardCAN_FD`.
+const DBC_FRAME_FORMAT_ATTR: &str = "VFrameFormat";
+
+/// Message attribute marking CAN FD frames that use bit rate switching.
+const DBC_BRS_ATTR: &str = "CANFD_BRS";
+
+/// Signal attribute giving the signal's raw initial value.
+const DBC_START_VALUE_ATTR: &str = "GenSigStartValue";
+
+/// Network attribute giving the network's [naming](CANNaming) prefix
+/// format, exported by OpenCAN when it isn't the default.
+pub(crate) const DBC_NAMING_ATTR: &str = "OpenCANNaming";
+
+/// Attributes that are imported as network, message or signal properties
+/// rather than as user-defined attributes.
+pub(crate) const DBC_NATIVE_ATTRS: [&str; 5] = [
+    DBC_CYCLE_TIME_ATTR,
+    DBC_FRAME_FORMAT_ATTR,
+    DBC_BRS_ATTR,
+    DBC_START_VALUE_ATTR,
+    DBC_NAMING_ATTR,
+];
+
+/// Most multiplexor values an `SG_MUL_VAL_` entry may give one signal, since
+/// each value is stored separately.
+const DBC_MAX_MUX_VALUES: u64 = 4096;
+
+/// Most lines dropped from a DBC file in lenient mode, since the whole file
+/// is parsed again after each one.
+const DBC_MAX_SKIPPED_LINES: usize = 100;
+
+/// Type of a user-defined attribute, from its `BA_DEF_` definition.
+enum DbcAttributeType {
+    Int,
+    Float,
+    String,
+    Enum(Vec<String>),
+}
+
+/// Error importing a DBC file, naming the part of the file at fault.
+#[derive(Debug, Error)]
+pub enum DbcImportError {
+    #[error("Failed to parse DBC file at line {line}: `{text}`")]
+    Parse { line: usize, text: String },
+
+    #[error("Failed to parse DBC file, and could not find the line at fault")]
+    ParseUnlocated,
+
+    #[error("Could not import node `{node}`: {cause}")]
+    Node {
+        node: String,
+        cause: CANConstructionError,
+    },
+
+    #[error("Could not import message `{message}`: {cause}")]
+    Message {
+        message: String,
+        cause: CANConstructionError,
+    },
+
+    #[error("Could not import signal `{signal}` in message `{message}`: {cause}")]
+    Signal {
+        message: String,
+        signal: String,
+        cause: CANConstructionError,
+    },
+
+    #[error("Could not import value table `{table}`: {cause}")]
+    ValueTable {
+        table: String,
+        cause: CANConstructionError,
+    },
+
+    #[error(
+        "Value `{name}` of value table `{table}` has value {value}, which is not a \
+            non-negative integer."
+    )]
+    InvalidValueTableEntry {
+        table: String,
+        name: String,
+        value: f64,
+    },
+
+    #[error("Could not add receiver `{node}` of message `{message}`: {cause}")]
+    Receiver {
+        message: String,
+        node: String,
+        cause: CANConstructionError,
+    },
+
+    #[error("Signal `{signal}` in message `{message}` is multiplexed, but the message has no multiplexor.")]
+    MissingMultiplexor { message: String, signal: String },
+
+    #[error(
+        "Value description `{name}` of signal `{signal}` in message `{message}` has value \
+            {value}, which is not a non-negative integer."
+    )]
+    InvalidValueDescription {
+        message: String,
+        signal: String,
+        name: String,
+        value: f64,
+    },
+
+    #[error(
+        "Signal `{signal}` in message `{message}` is present for {count} multiplexor values; \
+            at most {max} are supported."
+    )]
+    TooManyMuxValues {
+        message: String,
+        signal: String,
+        count: u64,
+        max: u64,
+    },
+
+    #[error("Message `{message}` has cycle time `{value}`, which is not a whole number of milliseconds.")]
+    InvalidCycleTime { message: String, value: String },
+
+    #[error(
+        "Signal `{signal}` in message `{message}` has start value `{value}`, which is not a \
+            raw value of the signal."
+    )]
+    InvalidStartValue {
+        message: String,
+        signal: String,
+        value: String,
+    },
+
+    #[error("Attribute `{attribute}` has value `{value}`, which does not match its definition.")]
+    InvalidAttribute { attribute: String, value: String },
+}
 
 pub struct DbcImporter {
     dbc: can_dbc::DBC,
+    mode: ImportMode,
+
+    /// Problems skipped over in lenient mode.
+    warnings: RefCell<Vec<DbcImportError>>,
 }
 
 impl TranslationToOpencan for DbcImporter {
-    fn import_network(input: String, net: &mut CANNetwork) {
-        let import = Self {
-            dbc: can_dbc::DBC::try_from(input.as_str()).unwrap(),
-        };
+    type Error = DbcImportError;
 
-        // Add all the nodes to the network
-        for node in &import.dbc.nodes().iter().next().unwrap().0 {
-            net.add_node(node).unwrap();
+    fn import_network(
+        input: String,
+        net: &mut CANNetwork,
+        mode: ImportMode,
+    ) -> Result<Vec<DbcImportError>, DbcImportError> {
+        let import = Self::parse(&input, mode)?;
+        import.import(net)?;
+
+        Ok(import.warnings.into_inner())
+    }
+}
+
+impl DbcImporter {
+    /// Parse a DBC file.
+    ///
+    /// In lenient mode, lines the parser can't handle are dropped one at a
+    /// time until the rest of the file parses.
+    fn parse(input: &str, mode: ImportMode) -> Result<Self, DbcImportError> {
+        let mut text = input.to_owned();
+        // line number in `input` of each line still in `text`
+        let mut line_nums: Vec<usize> = (1..=input.lines().count()).collect();
+        let mut warnings = Vec::new();
+
+        loop {
+            // `rest` is the tail of `text` that didn't parse
+            let offset = match can_dbc::DBC::try_from(text.as_str()) {
+                Ok(dbc) => return Ok(Self::new(dbc, mode, warnings)),
+                Err(can_dbc::Error::Incomplete(dbc, rest)) if rest.trim().is_empty() => {
+                    return Ok(Self::new(dbc, mode, warnings))
+                }
+                Err(can_dbc::Error::Incomplete(_, rest)) => text.len() - rest.trim_start().len(),
+                Err(can_dbc::Error::Nom(nom::Err::Error(e) | nom::Err::Failure(e))) => {
+                    text.len() - e.input.trim_start().len()
+                }
+                Err(_) => return Err(DbcImportError::ParseUnlocated),
+            };
+
+            let idx = text[..offset].matches('\n').count();
+            let Some(&line) = line_nums.get(idx).filter(|_| offset < text.len()) else {
+                return Err(DbcImportError::ParseUnlocated);
+            };
+
+            let start = text[..offset].rfind('\n').map_or(0, |i| i + 1);
+            let end = text[offset..]
+                .find('\n')
+                .map_or(text.len(), |i| offset + i + 1);
+
+            let err = DbcImportError::Parse {
+                line,
+                text: text[start..end].trim().into(),
+            };
+
+            if mode == ImportMode::Strict || warnings.len() >= DBC_MAX_SKIPPED_LINES {
+                return Err(err);
+            }
+
+            warnings.push(err);
+            text.replace_range(start..end, "");
+            line_nums.remove(idx);
         }
+    }
 
-        // Build list of messages, excluding multiplexed ones
-        let messages: Vec<_> = import
-            .dbc
-            .messages()
-            .iter()
-            .filter(|m| {
-                let mux = import.dbc.message_multiplexor_switch(*m.message_id());
-                match mux {
-                    Ok(None) => true,
-                    Ok(_) | Err(_) => {
-                        eprintln!(
-                            "WARNING: ignoring multiplexed message `{}` during DBC import!",
-                            m.message_name()
-                        );
-                        false
+    fn new(dbc: can_dbc::DBC, mode: ImportMode, warnings: Vec<DbcImportError>) -> Self {
+        Self {
+            dbc,
+            mode,
+            warnings: RefCell::new(warnings),
+        }
+    }
+
+    /// Deal with a part of the file that can't be imported: return it as an
+    /// error in strict mode, or keep it as a warning in lenient mode.
+    fn skip(&self, err: DbcImportError) -> Result<(), DbcImportError> {
+        match self.mode {
+            ImportMode::Strict => Err(err),
+            ImportMode::Lenient => {
+                self.warnings.borrow_mut().push(err);
+                Ok(())
+            }
+        }
+    }
+
+    /// Keep a problem that's dropped in either mode as a warning.
+    fn warn(&self, err: DbcImportError) {
+        self.warnings.borrow_mut().push(err);
+    }
+
+    fn import(&self, net: &mut CANNetwork) -> Result<(), DbcImportError> {
+        self.import_naming(net)?;
+
+        // Add all the value tables to the network, so signals can use them
+        for dbc_table in self.dbc.value_tables() {
+            let name = dbc_table.value_table_name();
+
+            let values =
+                self.enumerated_values(dbc_table.value_descriptions(), |value_name, value| {
+                    DbcImportError::InvalidValueTableEntry {
+                        table: name.clone(),
+                        name: value_name.into(),
+                        value,
                     }
+                })?;
+
+            let res = CANValueTable::new(name).and_then(|mut table| {
+                values
+                    .into_iter()
+                    .try_for_each(|(value_name, value)| table.add_value(&value_name, value))
+                    .and_then(|()| net.insert_value_table(table))
+            });
+
+            if let Err(cause) = res {
+                self.skip(DbcImportError::ValueTable {
+                    table: name.clone(),
+                    cause,
+                })?;
+            }
+        }
+
+        // Add all the nodes to the network
+        for node in self.dbc.nodes().iter().flat_map(|n| &n.0) {
+            let res = net
+                .add_node(node)
+                .and_then(|()| net.set_node_description(node, self.node_comment(node)));
+
+            if let Err(cause) = res {
+                self.skip(DbcImportError::Node {
+                    node: node.clone(),
+                    cause,
+                })?;
+            }
+        }
+
+        // Add all the messages to the network
+        let mut skipped = HashSet::new();
+        for dbc_msg in self.dbc.messages() {
+            let name = dbc_msg.message_name();
+
+            let res = self.translate_message(net, dbc_msg).and_then(|msg| {
+                net.insert_msg(msg)
+                    .map_err(|cause| DbcImportError::Message {
+                        message: name.clone(),
+                        cause,
+                    })
+            });
+
+            if let Err(e) = res {
+                skipped.insert(name);
+                self.skip(e)?;
+            }
+        }
+
+        // Build RX mapping
+        for dbc_msg in self.dbc.messages() {
+            let name = dbc_msg.message_name();
+            if skipped.contains(name) {
+                continue;
+            }
+
+            let mut seen = HashSet::new();
+            for receiver in dbc_msg.signals().iter().flat_map(|s| s.receivers()) {
+                if receiver == DBC_NO_NODE || !seen.insert(receiver) {
+                    continue;
                 }
-            })
-            .collect();
 
-        // Add all the messages in each node to the network
-        for dbc_msg in &messages {
-            let message_id = *dbc_msg.message_id();
+                if let Err(cause) = net.set_message_rx_by_node(name, receiver) {
+                    self.skip(DbcImportError::Receiver {
+                        message: name.clone(),
+                        node: receiver.clone(),
+                        cause,
+                    })?;
+                }
+            }
+        }
 
-            let mut msg = CANMessage::builder()
-                .name(dbc_msg.message_name())
-                .id(message_id.0);
+        self.import_attributes(net)
+    }
 
-            // tx node
-            match dbc_msg.transmitter() {
-                can_dbc::Transmitter::NodeName(node) => msg = msg.tx_node(node),
-                can_dbc::Transmitter::VectorXXX => todo!("support for anonymous tx node"),
+    fn translate_message(
+        &self,
+        net: &CANNetwork,
+        dbc_msg: &can_dbc::Message,
+    ) -> Result<CANMessage, DbcImportError> {
+        let message_id = *dbc_msg.message_id();
+        let name = dbc_msg.message_name();
+
+        let id = if message_id.0 & DBC_EXTENDED_ID_FLAG != 0 {
+            CANId::Extended(message_id.0 & !DBC_EXTENDED_ID_FLAG)
+        } else {
+            CANId::Standard(message_id.0)
+        };
+
+        let mut msg = CANMessage::builder().name(name).id(id);
+
+        // tx node - Vector__XXX means there isn't one
+        if let can_dbc::Transmitter::NodeName(node) = dbc_msg.transmitter() {
+            msg = msg.tx_node(node);
+        }
+
+        // frame format, needed before adding signals
+        let fd = self
+            .message_enum_attribute(message_id, DBC_FRAME_FORMAT_ATTR)
+            .is_some_and(|f| f.ends_with("CAN_FD"));
+
+        // CANFD_BRS commonly defaults to 1, but only means anything for FD frames
+        let brs = fd
+            && self
+                .message_enum_attribute(message_id, DBC_BRS_ATTR)
+                .is_some_and(|b| b == "1");
+
+        msg = msg.fd(fd).brs(brs);
+
+        // description
+        if let Some(comment) = self.dbc.message_comment(message_id) {
+            msg = msg.description(Some(comment.to_owned()));
+        }
+
+        // signals
+        let mut opencan_signals = Vec::new();
+        for dbc_signal in dbc_msg.signals() {
+            match self.translate_signal(dbc_msg, dbc_signal) {
+                Ok(mut sig) => {
+                    sig.value_table = Self::matching_value_table(net, &sig);
+                    opencan_signals.push((dbc_signal.start_bit as u32, sig));
+                }
+                Err(e) => self.skip(e)?,
             }
+        }
+
+        opencan_signals.sort_by_key(|s| s.0);
+        for (bit, sig) in order_multiplexors_first(opencan_signals) {
+            let signal = sig.name.clone();
 
-            // signals
-            let mut opencan_signals: Vec<_> = dbc_msg
-                .signals()
+            match msg.clone().add_signal_fixed(bit, sig) {
+                Ok(m) => msg = m,
+                Err(cause) => self.skip(DbcImportError::Signal {
+                    message: name.clone(),
+                    signal,
+                    cause,
+                })?,
+            }
+        }
+
+        // cycletime
+        let cycletime = match self.message_cycletime(dbc_msg) {
+            Ok(c) => c,
+            Err(e) => {
+                self.skip(e)?;
+                None
+            }
+        };
+
+        msg.cycletime(cycletime)
+            .build()
+            .map_err(|cause| DbcImportError::Message {
+                message: name.clone(),
+                cause,
+            })
+    }
+
+    /// Get the cycle time of a message, if it has a nonzero one.
+    fn message_cycletime(&self, dbc_msg: &can_dbc::Message) -> Result<Option<u32>, DbcImportError> {
+        let Some(value) =
+            self.dbc
+                .attribute_values()
                 .iter()
-                .map(|dbc_signal| {
-                    (
-                        dbc_signal.start_bit as u32,
-                        import.translate_signal(dbc_msg, dbc_signal),
-                    )
+                .find_map(|a| match a.attribute_value() {
+                    MessageDefinitionAttributeValue(id, v)
+                        if id == dbc_msg.message_id()
+                            && a.attribute_name() == DBC_CYCLE_TIME_ATTR =>
+                    {
+                        v.as_ref()
+                    }
+                    _ => None,
                 })
-                .collect();
+        else {
+            return Ok(None);
+        };
 
-            opencan_signals.sort_by_key(|s| s.0);
-            msg = msg.add_signals_fixed(opencan_signals).unwrap();
+        // Some tools write the cycle time as a string, e.g. "100".
+        let (t, raw) = match value {
+            can_dbc::AttributeValue::AttributeValueU64(t) => {
+                (u32::try_from(*t).ok(), t.to_string())
+            }
+            can_dbc::AttributeValue::AttributeValueI64(t) => {
+                (u32::try_from(*t).ok(), t.to_string())
+            }
+            can_dbc::AttributeValue::AttributeValueF64(t) => (
+                (t.fract() == 0.0 && (0.0..=u32::MAX as f64).contains(t)).then_some(*t as u32),
+                t.to_string(),
+            ),
+            can_dbc::AttributeValue::AttributeValueCharString(t) => {
+                (t.trim().parse().ok(), t.clone())
+            }
+        };
 
-            // cycletime
-            let cycletime = import.dbc.attribute_values().iter().find_map(|a| {
-                if a.attribute_name() != "GenMsgCycleTime" {
-                    return None;
+        match t {
+            Some(0) => Ok(None),
+            Some(t) => Ok(Some(t)),
+            None => Err(DbcImportError::InvalidCycleTime {
+                message: dbc_msg.message_name().clone(),
+                value: raw,
+            }),
+        }
+    }
+
+    /// Get the value of an enum message attribute as the name of its variant,
+    /// falling back to the attribute's default value.
+    fn message_enum_attribute(&self, message_id: can_dbc::MessageId, attr: &str) -> Option<String> {
+        let value = self
+            .dbc
+            .attribute_values()
+            .iter()
+            .find_map(|a| match a.attribute_value() {
+                MessageDefinitionAttributeValue(id, v)
+                    if *id == message_id && a.attribute_name() == attr =>
+                {
+                    v.as_ref()
                 }
+                _ => None,
+            })
+            .or_else(|| {
+                self.dbc
+                    .attribute_defaults()
+                    .iter()
+                    .find(|d| d.attribute_name() == attr)
+                    .map(|d| d.attribute_value())
+            })?;
+
+        // Values are usually indices into the enum's variants, but some tools
+        // write the variant name instead.
+        let idx = match value {
+            can_dbc::AttributeValue::AttributeValueU64(i) => *i as usize,
+            can_dbc::AttributeValue::AttributeValueI64(i) => *i as usize,
+            can_dbc::AttributeValue::AttributeValueF64(i) => *i as usize,
+            can_dbc::AttributeValue::AttributeValueCharString(s) => return Some(s.clone()),
+        };
+
+        self.message_enum_variants(attr).into_iter().nth(idx)
+    }
 
-                if let MessageDefinitionAttributeValue(id, v) = a.attribute_value() {
-                    if *id != message_id {
-                        return None;
+    /// Get the variant names of an enum attribute.
+    fn message_enum_variants(&self, attr: &str) -> Vec<String> {
+        match self.attribute_type(attr) {
+            Some(DbcAttributeType::Enum(variants)) => variants,
+            _ => Vec::new(),
+        }
+    }
+
+    /// Get the definition of an attribute, along with the rest of its text
+    /// after the name, e.g. `ENUM "StandardCAN","ExtendedCAN"`.
+    fn attribute_definition(&self, attr: &str) -> Option<(&can_dbc::AttributeDefinition, &str)> {
+        // can_dbc doesn't parse attribute definitions, so we get the raw text,
+        // e.g. `"VFrameFormat" ENUM "StandardCAN","ExtendedCAN"`
+        self.dbc.attribute_definitions().iter().find_map(|d| {
+            let (can_dbc::AttributeDefinition::Message(def)
+            | can_dbc::AttributeDefinition::Node(def)
+            | can_dbc::AttributeDefinition::Signal(def)
+            | can_dbc::AttributeDefinition::Plain(def)) = d
+            else {
+                return None;
+            };
+
+            Some((d, def.trim().strip_prefix(&format!("\"{attr}\""))?.trim()))
+        })
+    }
+
+    /// Get the type of an attribute from its definition.
+    fn attribute_type(&self, attr: &str) -> Option<DbcAttributeType> {
+        let (_, def) = self.attribute_definition(attr)?;
+        let (ty, rest) = def.split_once(char::is_whitespace).unwrap_or((def, ""));
+
+        Some(match ty {
+            "INT" | "HEX" => DbcAttributeType::Int,
+            "FLOAT" => DbcAttributeType::Float,
+            "STRING" => DbcAttributeType::String,
+            "ENUM" => DbcAttributeType::Enum(
+                rest.split(',')
+                    .map(|v| v.trim().trim_matches('"').to_owned())
+                    .collect(),
+            ),
+            _ => return None,
+        })
+    }
+
+    /// Import user-defined attribute values, other than the ones we
+    /// translate to message properties.
+    fn import_attributes(&self, net: &mut CANNetwork) -> Result<(), DbcImportError> {
+        use can_dbc::AttributeValuedForObjectType::*;
+
+        // message names by DBC ID, for messages that were imported
+        let messages: HashMap<_, _> = self
+            .dbc
+            .messages()
+            .iter()
+            .filter(|m| net.message_by_name(m.message_name()).is_some())
+            .map(|m| (m.message_id().0, m.message_name().clone()))
+            .collect();
+
+        for attr in self.dbc.attribute_values() {
+            let name = attr.attribute_name();
+            if DBC_NATIVE_ATTRS.contains(&name.as_str()) {
+                continue;
+            }
+
+            let res = match attr.attribute_value() {
+                RawAttributeValue(v) => self.attribute_value(name, v).map(|v| {
+                    net.set_attribute(name, v);
+                }),
+                NetworkNodeAttributeValue(node, v) => {
+                    if net.node_by_name(node).is_none() {
+                        continue;
                     }
 
-                    let t = match v.as_ref().unwrap() {
-                        can_dbc::AttributeValue::AttributeValueU64(t) => *t as u32,
-                        can_dbc::AttributeValue::AttributeValueI64(t) => *t as u32,
-                        can_dbc::AttributeValue::AttributeValueF64(t) => *t as u32,
-                        can_dbc::AttributeValue::AttributeValueCharString(t) => {
-                            panic!("Didn't expect GenMsgCycleTime to be AttributeValueCharString ('{t}').")
-                        }
+                    self.attribute_value(name, v).and_then(|v| {
+                        net.set_node_attribute(node, name, v).map_err(|cause| {
+                            DbcImportError::Node {
+                                node: node.clone(),
+                                cause,
+                            }
+                        })
+                    })
+                }
+                MessageDefinitionAttributeValue(id, Some(v)) => {
+                    let Some(message) = messages.get(&id.0) else {
+                        continue;
                     };
 
-                    if t != 0 {
-                        return Some(t);
-                    }
+                    self.attribute_value(name, v).and_then(|v| {
+                        net.set_message_attribute(message, name, v)
+                            .map_err(|cause| DbcImportError::Message {
+                                message: message.clone(),
+                                cause,
+                            })
+                    })
                 }
+                SignalAttributeValue(id, signal, v) => {
+                    let Some(message) = messages.get(&id.0) else {
+                        continue;
+                    };
 
-                None
-            });
+                    // signals can be skipped in lenient mode
+                    if net
+                        .message_by_name(message)
+                        .unwrap()
+                        .get_sig(signal)
+                        .is_none()
+                    {
+                        continue;
+                    }
 
-            msg = msg.cycletime(cycletime);
+                    self.attribute_value(name, v).and_then(|v| {
+                        net.edit_signal(message, signal, |s| {
+                            s.attributes.insert(name.clone(), v);
+                        })
+                        .map_err(|cause| DbcImportError::Signal {
+                            message: message.clone(),
+                            signal: signal.clone(),
+                            cause,
+                        })
+                    })
+                }
+                _ => continue,
+            };
 
-            // insert message into network
-            net.insert_msg(msg.build().unwrap()).unwrap();
+            if let Err(e) = res {
+                self.skip(e)?;
+            }
         }
 
-        // Build RX mapping
-        for dbc_msg in &messages {
-            for signal in dbc_msg.signals() {
-                for reciever in signal.receivers() {
-                    if reciever != "Vector__XXX" {
-                        net.set_message_rx_by_node(dbc_msg.message_name(), reciever)
-                            .unwrap();
+        self.import_attribute_defaults(net, &messages)
+    }
+
+    /// Give the imported nodes, messages and signals without a value of a
+    /// user-defined attribute its `BA_DEF_DEF_` default, as DBC files mean.
+    fn import_attribute_defaults(
+        &self,
+        net: &mut CANNetwork,
+        messages: &HashMap<u32, String>,
+    ) -> Result<(), DbcImportError> {
+        use can_dbc::AttributeDefinition::*;
+
+        for default in self.dbc.attribute_defaults() {
+            let name = default.attribute_name();
+            if DBC_NATIVE_ATTRS.contains(&name.as_str()) {
+                continue;
+            }
+
+            let value = match self.attribute_value(name, default.attribute_value()) {
+                Ok(value) => value,
+                Err(e) => {
+                    self.skip(e)?;
+                    continue;
+                }
+            };
+
+            match self.attribute_definition(name).map(|d| d.0) {
+                Some(Plain(_)) if !net.attributes().contains_key(name) => {
+                    net.set_attribute(name, value);
+                }
+                Some(Node(_)) => {
+                    for node in self.dbc.nodes().iter().flat_map(|n| &n.0) {
+                        if net
+                            .node_by_name(node)
+                            .is_some_and(|n| !n.attributes.contains_key(name))
+                        {
+                            net.set_node_attribute(node, name, value.clone())
+                                .map_err(|cause| DbcImportError::Node {
+                                    node: node.clone(),
+                                    cause,
+                                })?;
+                        }
+                    }
+                }
+                Some(Message(_)) => {
+                    for message in messages.values() {
+                        if !net
+                            .message_by_name(message)
+                            .unwrap()
+                            .attributes
+                            .contains_key(name)
+                        {
+                            net.set_message_attribute(message, name, value.clone())
+                                .map_err(|cause| DbcImportError::Message {
+                                    message: message.clone(),
+                                    cause,
+                                })?;
+                        }
+                    }
+                }
+                Some(Signal(_)) => {
+                    for message in messages.values() {
+                        let signals: Vec<String> = net
+                            .message_by_name(message)
+                            .unwrap()
+                            .signals
+                            .iter()
+                            .filter(|s| !s.sig.attributes.contains_key(name))
+                            .map(|s| s.sig.name.clone())
+                            .collect();
+
+                        for signal in signals {
+                            net.edit_signal(message, &signal, |s| {
+                                s.attributes.insert(name.clone(), value.clone());
+                            })
+                            .map_err(|cause| {
+                                DbcImportError::Signal {
+                                    message: message.clone(),
+                                    signal: signal.clone(),
+                                    cause,
+                                }
+                            })?;
+                        }
                     }
                 }
+                _ => {}
             }
         }
+
+        Ok(())
+    }
+
+    /// Take the network's naming from the DBC, if it has one.
+    fn import_naming(&self, net: &mut CANNetwork) -> Result<(), DbcImportError> {
+        use can_dbc::{AttributeValue::AttributeValueCharString, AttributeValuedForObjectType::*};
+
+        for attr in self.dbc.attribute_values() {
+            let RawAttributeValue(AttributeValueCharString(format)) = attr.attribute_value() else {
+                continue;
+            };
+            if attr.attribute_name() != DBC_NAMING_ATTR {
+                continue;
+            }
+
+            match CANNaming::new(format) {
+                Ok(naming) => net.set_naming(naming),
+                Err(_) => self.skip(DbcImportError::InvalidAttribute {
+                    attribute: DBC_NAMING_ATTR.into(),
+                    value: format.clone(),
+                })?,
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Comment on a node (`CM_ BU_`), if there is one.
+    fn node_comment(&self, node: &str) -> Option<String> {
+        self.dbc.comments().iter().find_map(|c| match c {
+            can_dbc::Comment::Node { node_name, comment } if node_name == node => {
+                Some(comment.clone())
+            }
+            _ => None,
+        })
+    }
+
+    /// Convert an attribute value according to the attribute's definition.
+    /// Values of undefined attributes are kept as floats or strings.
+    fn attribute_value(
+        &self,
+        attr: &str,
+        value: &can_dbc::AttributeValue,
+    ) -> Result<AttributeValue, DbcImportError> {
+        use can_dbc::AttributeValue::*;
+
+        let number = match value {
+            AttributeValueU64(v) => Some(*v as f64),
+            AttributeValueI64(v) => Some(*v as f64),
+            AttributeValueF64(v) => Some(*v),
+            AttributeValueCharString(_) => None,
+        };
+
+        let converted = match (self.attribute_type(attr), number, value) {
+            (Some(DbcAttributeType::Int), Some(n), _) if n.fract() == 0.0 => {
+                Some(AttributeValue::Int(n as i64))
+            }
+            (Some(DbcAttributeType::Float) | None, Some(n), _) => Some(AttributeValue::Float(n)),
+            (Some(DbcAttributeType::String) | None, _, AttributeValueCharString(s)) => {
+                Some(AttributeValue::String(s.clone()))
+            }
+            // enum values are usually indices, but some tools write the name
+            (Some(DbcAttributeType::Enum(variants)), Some(n), _) => variants
+                .get(n as usize)
+                .filter(|_| n >= 0.0 && n.fract() == 0.0)
+                .map(|v| AttributeValue::Enum(v.clone())),
+            (Some(DbcAttributeType::Enum(variants)), _, AttributeValueCharString(s)) => variants
+                .contains(s)
+                .then(|| AttributeValue::Enum(s.clone())),
+            _ => None,
+        };
+
+        converted.ok_or_else(|| DbcImportError::InvalidAttribute {
+            attribute: attr.into(),
+            value: match value {
+                AttributeValueCharString(s) => s.clone(),
+                _ => number.unwrap().to_string(),
+            },
+        })
+    }
+
+    /// Work out which multiplexor values a signal is present for, if any.
+    ///
+    /// Extended multiplexing (`SG_MUL_VAL_`) gives the multiplexor and value
+    /// ranges explicitly; otherwise the signal is multiplexed by the message's
+    /// single `M` signal.
+    fn signal_mux(
+        &self,
+        dbc_msg: &can_dbc::Message,
+        dbc_signal: &can_dbc::Signal,
+    ) -> Result<Option<CANSignalMux>, DbcImportError> {
+        if let Some(ext) = self.dbc.extended_multiplex().iter().find(|e| {
+            e.message_id() == dbc_msg.message_id() && e.signal_name() == dbc_signal.name()
+        }) {
+            let count = ext
+                .mappings()
+                .iter()
+                .filter(|m| m.min_value() <= m.max_value())
+                .map(|m| (m.max_value() - m.min_value()).saturating_add(1))
+                .fold(0u64, u64::saturating_add);
+
+            // ranges come straight from the file, so don't expand huge ones
+            if count > DBC_MAX_MUX_VALUES {
+                return Err(DbcImportError::TooManyMuxValues {
+                    message: dbc_msg.message_name().clone(),
+                    signal: dbc_signal.name().clone(),
+                    count,
+                    max: DBC_MAX_MUX_VALUES,
+                });
+            }
+
+            return Ok(Some(CANSignalMux {
+                multiplexor: ext.multiplexor_signal_name().clone(),
+                values: ext
+                    .mappings()
+                    .iter()
+                    .flat_map(|m| *m.min_value()..=*m.max_value())
+                    .collect(),
+            }));
+        }
+
+        let value = match dbc_signal.multiplexer_indicator() {
+            can_dbc::MultiplexIndicator::MultiplexedSignal(v)
+            | can_dbc::MultiplexIndicator::MultiplexorAndMultiplexedSignal(v) => *v,
+            can_dbc::MultiplexIndicator::Multiplexor | can_dbc::MultiplexIndicator::Plain => {
+                return Ok(None)
+            }
+        };
+
+        let multiplexor = dbc_msg
+            .signals()
+            .iter()
+            .find(|s| *s.multiplexer_indicator() == can_dbc::MultiplexIndicator::Multiplexor)
+            .ok_or_else(|| DbcImportError::MissingMultiplexor {
+                message: dbc_msg.message_name().clone(),
+                signal: dbc_signal.name().clone(),
+            })?;
+
+        Ok(Some(CANSignalMux {
+            multiplexor: multiplexor.name().clone(),
+            values: vec![value],
+        }))
     }
-}
 
-impl DbcImporter {
     fn translate_signal(
         &self,
         dbc_msg: &can_dbc::Message,
         dbc_signal: &can_dbc::Signal,
-    ) -> CANSignal {
+    ) -> Result<CANSignal, DbcImportError> {
         let &message_id = dbc_msg.message_id();
         let signal_name = dbc_signal.name();
 
@@ -130,16 +876,32 @@ impl DbcImporter {
             .name(dbc_signal.name())
             .width(dbc_signal.signal_size as _);
 
+        // float value types, which are always marked as signed
+        let value_type = match self
+            .dbc
+            .extended_value_type_for_signal(message_id, signal_name)
+        {
+            Some(can_dbc::SignalExtendedValueType::IEEEfloat32Bit) => SignalValueType::Float,
+            Some(can_dbc::SignalExtendedValueType::IEEEdouble64bit) => SignalValueType::Double,
+            _ => SignalValueType::Integer,
+        };
+        sig = sig.value_type(value_type);
+
         // twos complement
-        if matches!(dbc_signal.value_type(), can_dbc::ValueType::Signed) {
+        if value_type == SignalValueType::Integer
+            && matches!(dbc_signal.value_type(), can_dbc::ValueType::Signed)
+        {
             sig = sig.twos_complement(true);
         }
 
+        // multiplexing
+        sig = sig.mux(self.signal_mux(dbc_msg, dbc_signal)?);
+
         // endianness
-        assert!(
-            matches!(dbc_signal.byte_order(), ByteOrder::LittleEndian),
-            "OpenCAN doesn't support big-endian signals yet."
-        );
+        sig = sig.byte_order(match dbc_signal.byte_order() {
+            can_dbc::ByteOrder::LittleEndian => ByteOrder::LittleEndian,
+            can_dbc::ByteOrder::BigEndian => ByteOrder::BigEndian,
+        });
 
         // scale
         if dbc_signal.factor != 1.0 {
@@ -151,6 +913,11 @@ impl DbcImporter {
             sig = sig.offset(Some(dbc_signal.offset));
         }
 
+        // unit
+        if !dbc_signal.unit().is_empty() {
+            sig = sig.unit(Some(dbc_signal.unit().clone()));
+        }
+
         // description
         if let Some(comment) = self.dbc.signal_comment(message_id, signal_name) {
             sig = sig.description(Some(comment.to_owned()));
@@ -161,57 +928,477 @@ impl DbcImporter {
             .dbc
             .value_descriptions_for_signal(message_id, signal_name)
         {
-            let mut enumerated_values: Vec<(String, u64)> = Vec::new();
-
-            for val_desc in d {
-                // unfortunately some people do insane things with their value descriptions.
-                // we are going to normalize these names and prevent collisions.
-                let name = val_desc.b();
-
-                // map naughty characters to _
-                let normalized_name: String = name
-                    .to_ascii_uppercase()
-                    .chars()
-                    .map(|c| match c {
-                        'A'..='Z' | '0'..='9' => c,
-                        _ => '_',
-                    })
-                    .collect();
-
-                // trim trailing/leading '_'
-                let normalized_name = normalized_name.trim_matches('_');
+            let enumerated_values =
+                self.enumerated_values(d, |name, value| DbcImportError::InvalidValueDescription {
+                    message: dbc_msg.message_name().clone(),
+                    signal: signal_name.clone(),
+                    name: name.into(),
+                    value,
+                })?;
 
-                // get the value
-                let value = val_desc.a();
-                if value.fract() != 0.0 {
-                    panic!("Expected integer value description!");
+            for (name, val) in enumerated_values {
+                match sig.clone().add_enumerated_value(&name, val) {
+                    Ok(s) => sig = s,
+                    Err(cause) => self.skip(self.signal_error(dbc_msg, dbc_signal, cause))?,
                 }
+            }
+        }
+
+        // initial value
+        match self.signal_start_value(dbc_msg, dbc_signal) {
+            Ok(v) => sig = sig.initial_value(v),
+            Err(e) => self.skip(e)?,
+        }
 
-                // push
-                enumerated_values.push((normalized_name.into(), *value as _));
+        // physical range - [0|0] means none is given. Ranges the signal
+        // can't represent are common in the wild, so only they are dropped,
+        // with a warning even in strict mode.
+        if dbc_signal.min != 0.0 || dbc_signal.max != 0.0 {
+            match sig
+                .clone()
+                .min(Some(dbc_signal.min))
+                .max(Some(dbc_signal.max))
+                .build()
+            {
+                Err(
+                    cause @ (CANConstructionError::SignalMinAboveMax(..)
+                    | CANConstructionError::SignalRangeNotRepresentable(..)),
+                ) => self.warn(self.signal_error(dbc_msg, dbc_signal, cause)),
+                res => return res.map_err(|cause| self.signal_error(dbc_msg, dbc_signal, cause)),
             }
+        }
+
+        sig.build()
+            .map_err(|cause| self.signal_error(dbc_msg, dbc_signal, cause))
+    }
 
-            // find duplicate names
-            let mut occurences: HashMap<String, u64> = HashMap::new();
-            for val in &enumerated_values {
-                *occurences.entry(val.0.clone()).or_insert(0) += 1;
+    /// Get the raw start value of a signal, if it has one. Negative start
+    /// values of signed signals are turned into their twos-complement bits.
+    fn signal_start_value(
+        &self,
+        dbc_msg: &can_dbc::Message,
+        dbc_signal: &can_dbc::Signal,
+    ) -> Result<Option<u64>, DbcImportError> {
+        use can_dbc::AttributeValue::*;
+
+        let Some(value) =
+            self.dbc
+                .attribute_values()
+                .iter()
+                .find_map(|a| match a.attribute_value() {
+                    can_dbc::AttributeValuedForObjectType::SignalAttributeValue(id, signal, v)
+                        if id == dbc_msg.message_id()
+                            && signal == dbc_signal.name()
+                            && a.attribute_name() == DBC_START_VALUE_ATTR =>
+                    {
+                        Some(v)
+                    }
+                    _ => None,
+                })
+        else {
+            return Ok(None);
+        };
+
+        let width = dbc_signal.signal_size as u32;
+        let signed = matches!(dbc_signal.value_type(), can_dbc::ValueType::Signed);
+
+        let int = match value {
+            AttributeValueU64(v) => Some(i128::from(*v)),
+            AttributeValueI64(v) => Some(i128::from(*v)),
+            AttributeValueF64(v) if v.fract() == 0.0 => Some(*v as i128),
+            _ => None,
+        };
+
+        let raw = int.and_then(|v| match u64::try_from(v) {
+            Ok(v) => Some(v),
+            // keep only the signal's bits of a negative value
+            Err(_) if signed && v >= i64::MIN.into() => {
+                Some((v as u64) & (u64::MAX >> (u64::BITS - width.clamp(1, 64))))
             }
+            Err(_) => None,
+        });
 
-            // actually add the enumerated values
-            for val in enumerated_values {
-                let name = if occurences[&val.0] > 1 {
-                    // make unique names if there was more than one occurrence
-                    format!("{}_{}", val.1, &val.0)
-                } else {
-                    val.0
-                };
+        raw.map(Some)
+            .ok_or_else(|| DbcImportError::InvalidStartValue {
+                message: dbc_msg.message_name().clone(),
+                signal: dbc_signal.name().clone(),
+                value: match value {
+                    AttributeValueU64(v) => v.to_string(),
+                    AttributeValueI64(v) => v.to_string(),
+                    AttributeValueF64(v) => v.to_string(),
+                    AttributeValueCharString(v) => v.clone(),
+                },
+            })
+    }
+
+    /// Turn DBC value descriptions into enumerated values with names that
+    /// are usable in C (see [`c_enumerated_values`]). Values that aren't
+    /// non-negative integers are reported with `invalid` and left out.
+    fn enumerated_values(
+        &self,
+        descriptions: &[can_dbc::ValDescription],
+        invalid: impl Fn(&str, f64) -> DbcImportError,
+    ) -> Result<Vec<(String, u64)>, DbcImportError> {
+        let mut enumerated_values: Vec<(String, u64)> = Vec::new();
 
-                let val = val.1;
+        for val_desc in descriptions {
+            let name = val_desc.b();
 
-                sig = sig.add_enumerated_value(&name, val).unwrap();
+            // get the value
+            let value = *val_desc.a();
+            if value.fract() != 0.0 || value < 0.0 {
+                self.skip(invalid(name, value))?;
+                continue;
             }
+
+            // push
+            enumerated_values.push((name.clone(), value as _));
+        }
+
+        Ok(c_enumerated_values(enumerated_values))
+    }
+
+    /// DBC files don't say which value table a signal uses, so pick the
+    /// first (by name) with exactly the signal's enumerated values.
+    fn matching_value_table(net: &CANNetwork, sig: &CANSignal) -> Option<String> {
+        if sig.enumerated_values.is_empty() {
+            return None;
         }
 
-        sig.build().unwrap()
+        net.iter_value_tables()
+            .filter(|t| t.values == sig.enumerated_values)
+            .map(|t| t.name.clone())
+            .min()
+    }
+
+    fn signal_error(
+        &self,
+        dbc_msg: &can_dbc::Message,
+        dbc_signal: &can_dbc::Signal,
+        cause: CANConstructionError,
+    ) -> DbcImportError {
+        DbcImportError::Signal {
+            message: dbc_msg.message_name().clone(),
+            signal: dbc_signal.name().clone(),
+            cause,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FD_DBC: &str = r#"VERSION ""
+
+NS_ :
+
+BS_:
+
+BU_: TX RX
+
+BO_ 16 Classic: 8 TX
+ SG_ sigA : 0|8@1+ (1,0) [0|0] "" RX
+
+BO_ 17 Fd: 12 TX
+ SG_ sigB : 64|16@1+ (1,0) [0|0] "" RX
+
+BA_DEF_ BO_ "VFrameFormat" ENUM "StandardCAN","ExtendedCAN","reserved","reserved","reserved","reserved","reserved","reserved","reserved","reserved","reserved","reserved","reserved","reserved","StandardCAN_FD","ExtendedCAN_FD";
+BA_DEF_ BO_ "CANFD_BRS" ENUM "0","1";
+BA_DEF_DEF_ "VFrameFormat" "StandardCAN";
+BA_DEF_DEF_ "CANFD_BRS" "1";
+BA_ "VFrameFormat" BO_ 17 14;
+"#;
+
+    #[test]
+    fn import_fd_frame_format() {
+        let mut net = CANNetwork::new();
+        DbcImporter::import_network(FD_DBC.into(), &mut net, ImportMode::Strict).unwrap();
+
+        let classic = net.message_by_name("Classic").unwrap();
+        assert!(!classic.fd);
+        assert!(!classic.brs);
+        assert_eq!(classic.length, 1);
+
+        let fd = net.message_by_name("Fd").unwrap();
+        assert!(fd.fd);
+        assert!(fd.brs);
+        assert_eq!(fd.length, 12);
+    }
+
+    const START_VALUE_DBC: &str = r#"VERSION ""
+
+NS_ :
+
+BS_:
+
+BU_: TX RX
+
+BO_ 16 Status: 2 TX
+ SG_ level : 0|8@1+ (1,0) [0|0] "" RX
+ SG_ trim : 8|4@1- (1,0) [0|0] "" RX
+
+BA_DEF_ SG_ "GenSigStartValue" INT -8 255;
+BA_DEF_DEF_ "GenSigStartValue" 0;
+BA_ "GenSigStartValue" SG_ 16 level 200;
+BA_ "GenSigStartValue" SG_ 16 trim -2;
+"#;
+
+    #[test]
+    fn import_start_values() {
+        let mut net = CANNetwork::new();
+        DbcImporter::import_network(START_VALUE_DBC.into(), &mut net, ImportMode::Strict).unwrap();
+
+        let msg = net.message_by_name("Status").unwrap();
+        assert_eq!(msg["level"].initial_value, Some(200));
+        assert_eq!(msg["trim"].initial_value, Some(0b1110));
+        assert!(msg["level"].attributes.is_empty());
+    }
+
+    const DEFAULTS_DBC: &str = r#"VERSION ""
+
+NS_ :
+
+BS_:
+
+BU_: TX RX
+
+BO_ 16 Status: 1 TX
+ SG_ level : 0|8@1+ (1,0) [0|0] "" RX
+
+BO_ 17 Command: 1 RX
+ SG_ mode : 0|8@1+ (1,0) [0|0] "" TX
+
+BA_DEF_ BO_ "Priority" INT 0 10;
+BA_DEF_ BU_ "Domain" ENUM "Body","Chassis";
+BA_DEF_ "BusType" STRING;
+BA_DEF_DEF_ "Priority" 5;
+BA_DEF_DEF_ "Domain" "Chassis";
+BA_DEF_DEF_ "BusType" "CAN";
+BA_ "Priority" BO_ 16 1;
+BA_ "Domain" BU_ TX 0;
+"#;
+
+    #[test]
+    fn import_attribute_defaults() {
+        let mut net = CANNetwork::new();
+        DbcImporter::import_network(DEFAULTS_DBC.into(), &mut net, ImportMode::Strict).unwrap();
+
+        let priority = |m| net.message_by_name(m).unwrap().attributes["Priority"].clone();
+        assert_eq!(priority("Status"), AttributeValue::Int(1));
+        assert_eq!(priority("Command"), AttributeValue::Int(5));
+
+        let domain = |n| net.node_by_name(n).unwrap().attributes["Domain"].clone();
+        assert_eq!(domain("TX"), AttributeValue::Enum("Body".into()));
+        assert_eq!(domain("RX"), AttributeValue::Enum("Chassis".into()));
+
+        assert_eq!(
+            net.attributes()["BusType"],
+            AttributeValue::String("CAN".into())
+        );
+        assert!(net.message_by_name("Status").unwrap()["level"]
+            .attributes
+            .is_empty());
+    }
+
+    const MUX_DBC: &str = r#"VERSION ""
+
+NS_ :
+
+BS_:
+
+BU_: TX RX
+
+BO_ 32 Simple: 3 TX
+ SG_ mode M : 0|2@1+ (1,0) [0|0] "" RX
+ SG_ voltage m0 : 8|16@1+ (1,0) [0|0] "" RX
+ SG_ current m1 : 8|12@1+ (1,0) [0|0] "" RX
+
+BO_ 33 Nested: 4 TX
+ SG_ sub m1M : 8|2@1+ (1,0) [0|0] "" RX
+ SG_ a m0 : 16|8@1+ (1,0) [0|0] "" RX
+ SG_ b m1 : 16|8@1+ (1,0) [0|0] "" RX
+ SG_ mode M : 24|2@1+ (1,0) [0|0] "" RX
+
+SG_MUL_VAL_ 33 sub mode 1-2;
+SG_MUL_VAL_ 33 a sub 0-0;
+SG_MUL_VAL_ 33 b sub 1-1, 3-3;
+"#;
+
+    #[test]
+    fn import_mux() {
+        let mut net = CANNetwork::new();
+        DbcImporter::import_network(MUX_DBC.into(), &mut net, ImportMode::Strict).unwrap();
+
+        let simple = net.message_by_name("Simple").unwrap();
+        assert!(simple.is_multiplexor("mode"));
+        assert_eq!(
+            simple["voltage"].mux,
+            Some(CANSignalMux {
+                multiplexor: "mode".into(),
+                values: vec![0]
+            })
+        );
+        assert_eq!(simple["current"].mux.as_ref().unwrap().values, [1]);
+
+        let nested = net.message_by_name("Nested").unwrap();
+        assert_eq!(nested.signals[0].sig.name, "mode");
+        assert_eq!(nested["sub"].mux.as_ref().unwrap().values, [1, 2]);
+        assert_eq!(nested["b"].mux.as_ref().unwrap().multiplexor, "sub");
+        assert_eq!(nested["b"].mux.as_ref().unwrap().values, [1, 3]);
+        assert_eq!(nested.mux_chain(&nested["a"]).len(), 2);
+    }
+
+    #[test]
+    fn import_mux_range_too_large() {
+        let dbc = MUX_DBC.replace("33 b sub 1-1, 3-3", "33 b sub 0-4294967295");
+
+        let mut net = CANNetwork::new();
+        let err = DbcImporter::import_network(dbc, &mut net, ImportMode::Strict).unwrap_err();
+
+        assert!(matches!(
+            err,
+            DbcImportError::TooManyMuxValues { signal, count: 4294967296, .. } if signal == "b"
+        ));
+    }
+
+    const MESSY_DBC: &str = r#"VERSION ""
+
+NS_ :
+
+BS_:
+
+BU_: TX RX
+
+BO_ 48 Anonymous: 2 Vector__XXX
+ SG_ state : 0|4@1+ (1,0) [0|0] "" RX
+ SG_ level : 4|4@1+ (1,0) [0|0] "" RX
+
+BO_ 49 Overlapping: 2 TX
+ SG_ x : 0|8@1+ (1,0) [0|300] "V" RX
+ SG_ y : 4|8@1+ (1,0) [0|0] "" RX
+
+BO_ 50 Unsent: 1 Nobody
+ SG_ z : 0|8@1+ (1,0) [0|0] "" RX
+
+BA_DEF_ BO_ "GenMsgCycleTime" STRING ;
+BA_ "GenMsgCycleTime" BO_ 48 "250";
+BA_ "GenMsgCycleTime" BO_ 49 "fast";
+VAL_ 48 state 0 "OFF" 1.5 "HALF" 2 "ON" ;
+"#;
+
+    #[test]
+    fn import_strict_names_fault() {
+        let mut net = CANNetwork::new();
+        let err = DbcImporter::import_network(MESSY_DBC.into(), &mut net, ImportMode::Strict)
+            .unwrap_err();
+
+        assert!(matches!(
+            err,
+            DbcImportError::InvalidValueDescription { message, signal, value, .. }
+                if message == "Anonymous" && signal == "state" && value == 1.5
+        ));
+    }
+
+    #[test]
+    fn import_lenient_warns() {
+        let mut net = CANNetwork::new();
+        let warnings =
+            DbcImporter::import_network(MESSY_DBC.into(), &mut net, ImportMode::Lenient).unwrap();
+
+        // anonymous transmitter and string cycle time
+        let anon = net.message_by_name("Anonymous").unwrap();
+        assert_eq!(anon.tx_node(), None);
+        assert_eq!(anon.cycletime, Some(250));
+        assert_eq!(anon["state"].enumerated_values.len(), 2);
+
+        // overlapping signal is dropped, rest of the message is kept
+        let overlapping = net.message_by_name("Overlapping").unwrap();
+        assert!(overlapping.get_sig("x").is_some());
+        assert!(overlapping.get_sig("y").is_none());
+        assert_eq!(overlapping["x"].unit.as_deref(), Some("V"));
+        assert_eq!(overlapping["x"].max, None);
+        assert_eq!(overlapping.cycletime, None);
+
+        // message from unknown node is dropped, along with its receivers
+        assert!(net.message_by_name("Unsent").is_none());
+        assert_eq!(net.rx_messages_by_node("RX").unwrap().len(), 2);
+
+        assert!(matches!(
+            &warnings[..],
+            [
+                DbcImportError::InvalidValueDescription { .. },
+                DbcImportError::Signal { cause: CANConstructionError::SignalRangeNotRepresentable(..), .. },
+                DbcImportError::Signal { message, signal, cause: CANConstructionError::SignalsOverlap(..) },
+                DbcImportError::InvalidCycleTime { value, .. },
+                DbcImportError::Message { cause: CANConstructionError::NodeDoesNotExist(..), .. },
+            ] if message == "Overlapping" && signal == "y" && value == "fast"
+        ));
+    }
+
+    #[test]
+    fn import_lenient_skips_bad_lines() {
+        let dbc = MUX_DBC
+            .replace(
+                " SG_ current m1",
+                " SG_ bogus ??? nonsense\n SG_ current m1",
+            )
+            .replace(" SG_ a m0", " SG_ junk\n SG_ a m0");
+
+        let mut net = CANNetwork::new();
+        let err =
+            DbcImporter::import_network(dbc.clone(), &mut net, ImportMode::Strict).unwrap_err();
+        assert!(matches!(
+            err,
+            DbcImportError::Parse { line: 12, ref text } if text == "SG_ bogus ??? nonsense"
+        ));
+
+        let mut net = CANNetwork::new();
+        let warnings = DbcImporter::import_network(dbc, &mut net, ImportMode::Lenient).unwrap();
+        assert!(matches!(
+            &warnings[..],
+            [
+                DbcImportError::Parse { line: 12, .. },
+                DbcImportError::Parse { line: 17, text },
+            ] if text == "SG_ junk"
+        ));
+        assert!(net
+            .message_by_name("Simple")
+            .unwrap()
+            .get_sig("current")
+            .is_some());
+        assert!(net.message_by_name("Nested").is_some());
+    }
+
+    #[test]
+    fn import_unrepresentable_range() {
+        let dbc = MUX_DBC.replace(
+            "mode M : 0|2@1+ (1,0) [0|0]",
+            "mode M : 0|2@1+ (1,0) [0|10]",
+        );
+
+        let mut net = CANNetwork::new();
+        let warnings = DbcImporter::import_network(dbc, &mut net, ImportMode::Strict).unwrap();
+        assert!(matches!(
+            &warnings[..],
+            [DbcImportError::Signal {
+                cause: CANConstructionError::SignalRangeNotRepresentable(..),
+                ..
+            }]
+        ));
+
+        let mode = &net.message_by_name("Simple").unwrap()["mode"];
+        assert_eq!((mode.min, mode.max), (None, None));
+    }
+
+    #[test]
+    fn import_unlocated_parse_error() {
+        // the parser gives up at the end of the file, past every line
+        let dbc = "VERSION \"\"\n\nBO_ 1 M: 1 TX\n";
+        for mode in [ImportMode::Strict, ImportMode::Lenient] {
+            let mut net = CANNetwork::new();
+            let err = DbcImporter::import_network(dbc.into(), &mut net, mode);
+            assert!(matches!(err, Err(DbcImportError::ParseUnlocated)));
+        }
     }
 }
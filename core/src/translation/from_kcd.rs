@@ -0,0 +1,594 @@
+use std::cell::RefCell;
+use std::collections::{BTreeSet, HashMap};
+
+use roxmltree::{Document, Node};
+use thiserror::Error;
+
+use super::{c_enumerated_values, order_multiplexors_first, parse_int, ImportMode};
+use crate::{
+    ByteOrder, CANConstructionError, CANId, CANMessage, CANNetwork, CANSignal, CANSignalBuilder,
+    CANSignalMux, SignalValueType, TranslationToOpencan,
+};
+
+/// Convert between KCD big-endian bit offsets, which count from the most
+/// significant bit of each byte, and DBC-style start bits. The conversion is
+/// its own inverse.
+pub(crate) const fn kcd_big_endian_offset(bit: u32) -> u32 {
+    8 * (bit / 8) + (7 - bit % 8)
+}
+
+/// Error importing a KCD file, naming the part of the file at fault.
+#[derive(Debug, Error)]
+pub enum KcdImportError {
+    #[error("Failed to parse KCD file: {0}")]
+    Parse(String),
+
+    #[error("{element} has no `{attribute}`.")]
+    MissingAttribute {
+        element: String,
+        attribute: &'static str,
+    },
+
+    #[error("{element} has {attribute} `{value}`, which is not valid.")]
+    InvalidValue {
+        element: String,
+        attribute: &'static str,
+        value: String,
+    },
+
+    #[error("{element} uses {what}, which is not supported.")]
+    Unsupported { element: String, what: &'static str },
+
+    #[error("Message `{message}` refers to node ID `{id}`, which does not exist.")]
+    UnknownNode { message: String, id: String },
+
+    #[error("Could not import node `{node}`: {cause}")]
+    Node {
+        node: String,
+        cause: CANConstructionError,
+    },
+
+    #[error("Could not import message `{message}`: {cause}")]
+    Message {
+        message: String,
+        cause: CANConstructionError,
+    },
+
+    #[error("Could not import signal `{signal}` in message `{message}`: {cause}")]
+    Signal {
+        message: String,
+        signal: String,
+        cause: CANConstructionError,
+    },
+
+    #[error("Could not add receiver `{node}` of message `{message}`: {cause}")]
+    Receiver {
+        message: String,
+        node: String,
+        cause: CANConstructionError,
+    },
+}
+
+/// Importer for KCD (Kayak) network definitions.
+///
+/// The messages of every bus in the file are imported into the one network,
+/// which takes the first bus's baudrate. Multiplex groups become signals
+/// multiplexed by the `Multiplex` signal, and label sets become enumerated
+/// values.
+pub struct KcdImporter<'a, 'input> {
+    doc: &'a Document<'input>,
+    mode: ImportMode,
+
+    /// Node names by KCD node ID.
+    nodes: HashMap<&'a str, &'a str>,
+
+    /// Problems skipped over in lenient mode.
+    warnings: RefCell<Vec<KcdImportError>>,
+}
+
+impl TranslationToOpencan for KcdImporter<'_, '_> {
+    type Error = KcdImportError;
+
+    fn import_network(
+        input: String,
+        net: &mut CANNetwork,
+        mode: ImportMode,
+    ) -> Result<Vec<KcdImportError>, KcdImportError> {
+        let doc = Document::parse(&input).map_err(|e| KcdImportError::Parse(e.to_string()))?;
+
+        let import = KcdImporter::new(&doc, mode);
+        import.import(net)?;
+
+        Ok(import.warnings.into_inner())
+    }
+}
+
+impl<'a, 'input> KcdImporter<'a, 'input> {
+    fn new(doc: &'a Document<'input>, mode: ImportMode) -> Self {
+        let nodes = children_by_tag(doc.root_element(), "Node")
+            .filter_map(|n| Some((n.attribute("id")?, n.attribute("name")?)))
+            .collect();
+
+        Self {
+            doc,
+            mode,
+            nodes,
+            warnings: RefCell::new(Vec::new()),
+        }
+    }
+
+    /// Deal with a part of the file that can't be imported: return it as an
+    /// error in strict mode, or keep it as a warning in lenient mode.
+    fn skip(&self, err: KcdImportError) -> Result<(), KcdImportError> {
+        match self.mode {
+            ImportMode::Strict => Err(err),
+            ImportMode::Lenient => {
+                self.warnings.borrow_mut().push(err);
+                Ok(())
+            }
+        }
+    }
+
+    fn import(&self, net: &mut CANNetwork) -> Result<(), KcdImportError> {
+        let root = self.doc.root_element();
+        if root.tag_name().name() != "NetworkDefinition" {
+            return Err(KcdImportError::Parse(format!(
+                "expected `NetworkDefinition`, found `{}`",
+                root.tag_name().name()
+            )));
+        }
+
+        // Add all the nodes to the network
+        for node in children_by_tag(root, "Node") {
+            let Some(name) = node.attribute("name") else {
+                self.skip(KcdImportError::MissingAttribute {
+                    element: "Node".into(),
+                    attribute: "name",
+                })?;
+                continue;
+            };
+
+            if let Err(cause) = net.add_node(name) {
+                self.skip(KcdImportError::Node {
+                    node: name.into(),
+                    cause,
+                })?;
+            }
+        }
+
+        // Bitrate of the first bus
+        if let Some(baudrate) = children_by_tag(root, "Bus").find_map(|b| b.attribute("baudrate")) {
+            match parse_int(baudrate).and_then(|b| u32::try_from(b).ok()) {
+                Some(b) => net.set_bitrate(b),
+                None => self.skip(KcdImportError::InvalidValue {
+                    element: "Bus".into(),
+                    attribute: "baudrate",
+                    value: baudrate.into(),
+                })?,
+            }
+        }
+
+        // Add all the messages to the network
+        let mut receivers = Vec::new();
+        for kcd_msg in children_by_tag(root, "Bus").flat_map(|b| children_by_tag(b, "Message")) {
+            let res = self.translate_message(kcd_msg).and_then(|(msg, rx)| {
+                let name = msg.name.clone();
+                net.insert_msg(msg)
+                    .map_err(|cause| KcdImportError::Message {
+                        message: name.clone(),
+                        cause,
+                    })
+                    .map(|()| receivers.push((name, rx)))
+            });
+
+            if let Err(e) = res {
+                self.skip(e)?;
+            }
+        }
+
+        // Build RX mapping
+        for (message, nodes) in receivers {
+            for node in nodes {
+                if let Err(cause) = net.set_message_rx_by_node(&message, node) {
+                    self.skip(KcdImportError::Receiver {
+                        message: message.clone(),
+                        node: node.into(),
+                        cause,
+                    })?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Translate a message, returning it with the names of the nodes that
+    /// consume any of its signals.
+    fn translate_message(
+        &self,
+        kcd_msg: Node<'a, 'input>,
+    ) -> Result<(CANMessage, BTreeSet<&'a str>), KcdImportError> {
+        let name = kcd_msg
+            .attribute("name")
+            .ok_or_else(|| KcdImportError::MissingAttribute {
+                element: "Message".into(),
+                attribute: "name",
+            })?;
+        let element = format!("Message `{name}`");
+
+        // ID
+        let raw_id = kcd_msg
+            .attribute("id")
+            .ok_or_else(|| KcdImportError::MissingAttribute {
+                element: element.clone(),
+                attribute: "id",
+            })?;
+        let id = parse_int(raw_id)
+            .and_then(|i| u32::try_from(i).ok())
+            .ok_or_else(|| KcdImportError::InvalidValue {
+                element: element.clone(),
+                attribute: "id",
+                value: raw_id.into(),
+            })?;
+
+        let id = match kcd_msg.attribute("format") {
+            Some("extended") => CANId::Extended(id),
+            _ => CANId::Standard(id),
+        };
+
+        let mut msg = CANMessage::builder().name(name).id(id);
+
+        // tx node
+        let producers: Vec<_> = children_by_tag(kcd_msg, "Producer")
+            .flat_map(|p| children_by_tag(p, "NodeRef"))
+            .collect();
+        if let Some(producer) = producers.first() {
+            msg = msg.tx_node(self.node_ref(name, *producer)?);
+        }
+
+        // cycletime
+        let cycletime = match kcd_msg.attribute("interval") {
+            None => None,
+            Some(i) => match parse_int(i).and_then(|i| u32::try_from(i).ok()) {
+                Some(0) => None,
+                Some(i) => Some(i),
+                None => {
+                    self.skip(KcdImportError::InvalidValue {
+                        element: element.clone(),
+                        attribute: "interval",
+                        value: i.into(),
+                    })?;
+                    None
+                }
+            },
+        };
+
+        // signals, including multiplexors and the signals in each mux group
+        let mut signals: Vec<(u32, CANSignal)> = Vec::new();
+        let mut consumers = BTreeSet::new();
+        let mut add = |signals: &mut Vec<(u32, CANSignal)>,
+                       kcd_sig: Node<'a, 'input>,
+                       mux: Option<CANSignalMux>|
+         -> Result<(), KcdImportError> {
+            for node_ref in
+                children_by_tag(kcd_sig, "Consumer").flat_map(|c| children_by_tag(c, "NodeRef"))
+            {
+                match self.node_ref(name, node_ref) {
+                    Ok(n) => {
+                        consumers.insert(n);
+                    }
+                    Err(e) => self.skip(e)?,
+                }
+            }
+
+            let (bit, sig) = match self.translate_signal(name, kcd_sig, mux) {
+                Ok(s) => s,
+                Err(e) => return self.skip(e),
+            };
+
+            // a signal in several mux groups is multiplexed by all their values
+            match signals.iter_mut().find(|s| s.1.name == sig.name) {
+                Some((b, existing)) if *b == bit => {
+                    if let (Some(m), Some(new)) = (&mut existing.mux, sig.mux) {
+                        m.values.extend(new.values);
+                    }
+                }
+                _ => signals.push((bit, sig)),
+            }
+
+            Ok(())
+        };
+
+        for child in kcd_msg.children().filter(Node::is_element) {
+            match child.tag_name().name() {
+                "Signal" => add(&mut signals, child, None)?,
+                "Multiplex" => {
+                    add(&mut signals, child, None)?;
+                    let multiplexor = child.attribute("name").unwrap_or_default();
+
+                    for group in children_by_tag(child, "MuxGroup") {
+                        let raw = group.attribute("count").unwrap_or_default();
+                        let Some(value) = parse_int(raw).and_then(|v| u64::try_from(v).ok()) else {
+                            self.skip(KcdImportError::InvalidValue {
+                                element: format!("MuxGroup of `{multiplexor}` in {element}"),
+                                attribute: "count",
+                                value: raw.into(),
+                            })?;
+                            continue;
+                        };
+
+                        for kcd_sig in children_by_tag(group, "Signal") {
+                            let mux = CANSignalMux {
+                                multiplexor: multiplexor.into(),
+                                values: vec![value],
+                            };
+                            add(&mut signals, kcd_sig, Some(mux))?;
+                        }
+                    }
+                }
+                _ => (),
+            }
+        }
+
+        signals.sort_by_key(|s| s.0);
+        for (bit, sig) in order_multiplexors_first(signals) {
+            let signal = sig.name.clone();
+
+            match msg.clone().add_signal_fixed(bit, sig) {
+                Ok(m) => msg = m,
+                Err(cause) => self.skip(KcdImportError::Signal {
+                    message: name.into(),
+                    signal,
+                    cause,
+                })?,
+            }
+        }
+
+        let msg = msg
+            .cycletime(cycletime)
+            .build()
+            .map_err(|cause| KcdImportError::Message {
+                message: name.into(),
+                cause,
+            })?;
+
+        Ok((msg, consumers))
+    }
+
+    /// Translate a `Signal` or `Multiplex` element, returning the signal and
+    /// its start bit.
+    fn translate_signal(
+        &self,
+        message: &str,
+        kcd_sig: Node<'a, 'input>,
+        mux: Option<CANSignalMux>,
+    ) -> Result<(u32, CANSignal), KcdImportError> {
+        let name = kcd_sig
+            .attribute("name")
+            .ok_or_else(|| KcdImportError::MissingAttribute {
+                element: format!("Signal in message `{message}`"),
+                attribute: "name",
+            })?;
+        let element = format!("Signal `{name}` in message `{message}`");
+
+        let number = |attribute: &'static str, default: u32| -> Result<u32, KcdImportError> {
+            let Some(raw) = kcd_sig.attribute(attribute) else {
+                return Ok(default);
+            };
+
+            parse_int(raw)
+                .and_then(|v| u32::try_from(v).ok())
+                .ok_or_else(|| KcdImportError::InvalidValue {
+                    element: element.clone(),
+                    attribute,
+                    value: raw.into(),
+                })
+        };
+
+        let offset = number("offset", 0)?;
+        let width = number("length", 1)?;
+
+        let mut sig = CANSignal::builder().name(name).width(width).mux(mux);
+
+        // endianness
+        let (byte_order, bit) = match kcd_sig.attribute("endianess") {
+            Some("big") => (ByteOrder::BigEndian, kcd_big_endian_offset(offset)),
+            _ => (ByteOrder::LittleEndian, offset),
+        };
+        sig = sig.byte_order(byte_order);
+
+        // description
+        if let Some(notes) = child(kcd_sig, "Notes").and_then(|n| n.text()) {
+            sig = sig.description(Some(notes.trim().into()));
+        }
+
+        // value type, scale, offset, unit and range
+        if let Some(value) = child(kcd_sig, "Value") {
+            sig = self.apply_value(&element, sig, value)?;
+        }
+
+        // enumerated values
+        if let Some(labels) = child(kcd_sig, "LabelSet") {
+            let mut values = Vec::new();
+
+            for label in labels.children().filter(Node::is_element) {
+                if label.tag_name().name() == "LabelGroup" {
+                    self.skip(KcdImportError::Unsupported {
+                        element: element.clone(),
+                        what: "label groups",
+                    })?;
+                    continue;
+                }
+
+                let raw = label.attribute("value").unwrap_or_default();
+                match parse_int(raw).and_then(|v| u64::try_from(v).ok()) {
+                    Some(v) => values.push((label.attribute("name").unwrap_or(raw).into(), v)),
+                    None => self.skip(KcdImportError::InvalidValue {
+                        element: element.clone(),
+                        attribute: "label value",
+                        value: raw.into(),
+                    })?,
+                }
+            }
+
+            for (value_name, value) in c_enumerated_values(values) {
+                match sig.clone().add_enumerated_value(&value_name, value) {
+                    Ok(s) => sig = s,
+                    Err(cause) => self.skip(KcdImportError::Signal {
+                        message: message.into(),
+                        signal: name.into(),
+                        cause,
+                    })?,
+                }
+            }
+        }
+
+        let sig = sig.build().map_err(|cause| KcdImportError::Signal {
+            message: message.into(),
+            signal: name.into(),
+            cause,
+        })?;
+
+        Ok((bit, sig))
+    }
+
+    /// Apply the attributes of a signal's `Value` element.
+    fn apply_value(
+        &self,
+        element: &str,
+        mut sig: CANSignalBuilder,
+        value: Node,
+    ) -> Result<CANSignalBuilder, KcdImportError> {
+        let float = |attribute: &'static str| -> Result<Option<f64>, KcdImportError> {
+            value
+                .attribute(attribute)
+                .map(|raw| {
+                    raw.trim()
+                        .parse()
+                        .map_err(|_| KcdImportError::InvalidValue {
+                            element: element.into(),
+                            attribute,
+                            value: raw.into(),
+                        })
+                })
+                .transpose()
+        };
+
+        match value.attribute("type") {
+            Some("signed") => sig = sig.twos_complement(true),
+            Some("single") => sig = sig.value_type(SignalValueType::Float),
+            Some("double") => sig = sig.value_type(SignalValueType::Double),
+            _ => (),
+        }
+
+        if let Some(slope) = float("slope")?.filter(|s| *s != 1.0) {
+            sig = sig.scale(Some(slope));
+        }
+
+        if let Some(intercept) = float("intercept")?.filter(|i| *i != 0.0) {
+            sig = sig.offset(Some(intercept));
+        }
+
+        if let Some(unit) = value.attribute("unit").filter(|u| !u.is_empty()) {
+            sig = sig.unit(Some(unit.into()));
+        }
+
+        Ok(sig.min(float("min")?).max(float("max")?))
+    }
+
+    /// Get the name of the node a `NodeRef` refers to.
+    fn node_ref(&self, message: &str, node_ref: Node) -> Result<&'a str, KcdImportError> {
+        let id = node_ref.attribute("id").unwrap_or_default();
+
+        self.nodes
+            .get(id)
+            .copied()
+            .ok_or_else(|| KcdImportError::UnknownNode {
+                message: message.into(),
+                id: id.into(),
+            })
+    }
+}
+
+/// Get the first direct child of an element with the given tag.
+fn child<'a, 'input>(element: Node<'a, 'input>, tag: &str) -> Option<Node<'a, 'input>> {
+    element.children().find(|n| n.tag_name().name() == tag)
+}
+
+/// Get the direct children of an element with the given tag.
+fn children_by_tag<'a, 'input>(
+    element: Node<'a, 'input>,
+    tag: &'static str,
+) -> impl Iterator<Item = Node<'a, 'input>> {
+    element
+        .children()
+        .filter(move |n| n.tag_name().name() == tag)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KCD: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<NetworkDefinition xmlns="http://kayak.2codeornot2code.org/1.0">
+  <Document name="test"/>
+  <Node id="10" name="ECU"/>
+  <Node id="20" name="Display"/>
+  <Bus name="Body" baudrate="125000">
+    <Message id="0x0A0" name="Lights" interval="50">
+      <Producer>
+        <NodeRef id="10"/>
+      </Producer>
+      <Signal name="beam" offset="0" length="2">
+        <Consumer>
+          <NodeRef id="20"/>
+        </Consumer>
+        <LabelSet>
+          <Label name="Off" value="0"/>
+          <Label name="Low beam" value="1"/>
+          <LabelGroup name="High" from="2" to="3"/>
+        </LabelSet>
+      </Signal>
+      <Signal name="brightness" offset="15" length="12" endianess="big">
+        <Value slope="0.1" unit="%"/>
+      </Signal>
+    </Message>
+  </Bus>
+</NetworkDefinition>
+"#;
+
+    #[test]
+    fn import_kcd() {
+        let mut net = CANNetwork::new();
+        let err =
+            KcdImporter::import_network(KCD.into(), &mut net, ImportMode::Strict).unwrap_err();
+        assert!(matches!(
+            err,
+            KcdImportError::Unsupported { what, .. } if what == "label groups"
+        ));
+
+        let mut net = CANNetwork::new();
+        let warnings =
+            KcdImporter::import_network(KCD.into(), &mut net, ImportMode::Lenient).unwrap();
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(net.bitrate(), Some(125000));
+
+        let msg = net.message_by_name("Lights").unwrap();
+        assert_eq!(msg.id, CANId::Standard(0xA0));
+        assert_eq!(msg.cycletime, Some(50));
+        assert_eq!(msg.tx_node(), Some("ECU"));
+
+        let beam = &msg["beam"];
+        assert_eq!(beam.enumerated_values.get_by_left("LOW_BEAM"), Some(&1));
+        assert_eq!(beam.enumerated_values.len(), 2);
+
+        // offset 15 counts from the most significant bit of byte 1
+        let brightness = msg.get_sig("brightness").unwrap();
+        assert_eq!(brightness.start(), 8);
+        assert_eq!(brightness.sig.scale, Some(0.1));
+        assert_eq!(brightness.sig.unit.as_deref(), Some("%"));
+
+        let rx = net.rx_messages_by_node("Display").unwrap();
+        assert_eq!(rx[0].name, "Lights");
+    }
+}
@@ -1,18 +1,152 @@
-use crate::CANNetwork;
+use std::collections::{HashMap, HashSet};
+
+use crate::{CANNetwork, CANSignal};
 
 pub mod cantools;
 pub use cantools::*;
 
+pub mod from_arxml;
+pub use from_arxml::*;
+
 pub mod from_dbc;
 pub use from_dbc::*;
 
+pub mod from_kcd;
+pub use from_kcd::*;
+
+pub mod to_dbc;
+pub use to_dbc::*;
+
+pub mod to_kcd;
+pub use to_kcd::*;
+
 /// Translation from `OpenCAN` to other formats (e.g. `dbc`).
 pub trait TranslationFromOpencan {
     fn translate(net: &CANNetwork) -> String;
 }
 
-/// Translation from other formats (e.g. `dbc`) to OpenCAN.
+/// How an importer deals with parts of its input that it can't import.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ImportMode {
+    /// Fail on the first problem.
+    #[default]
+    Strict,
 
+    /// Skip anything that can't be imported and report it as a warning.
+    Lenient,
+}
+
+/// Translation from other formats (e.g. `dbc`) to OpenCAN.
 pub trait TranslationToOpencan {
-    fn import_network(input: String, net: &mut CANNetwork);
+    type Error: std::error::Error;
+
+    /// Import `input` into `net`, returning warnings for anything skipped
+    /// in [lenient](ImportMode::Lenient) mode, and for anything dropped in
+    /// either mode.
+    fn import_network(
+        input: String,
+        net: &mut CANNetwork,
+        mode: ImportMode,
+    ) -> Result<Vec<Self::Error>, Self::Error>;
+}
+
+/// Make imported enumerated value names usable in C, e.g. `Not available` to
+/// `NOT_AVAILABLE`.
+///
+/// Names that end up the same are made unique by prefixing their value.
+pub(crate) fn c_enumerated_values(values: Vec<(String, u64)>) -> Vec<(String, u64)> {
+    // unfortunately some people do insane things with their value descriptions.
+    // we are going to normalize these names and prevent collisions.
+    let values: Vec<(String, u64)> = values
+        .into_iter()
+        .map(|(name, value)| {
+            // map naughty characters to _
+            let normalized_name: String = name
+                .to_ascii_uppercase()
+                .chars()
+                .map(|c| match c {
+                    'A'..='Z' | '0'..='9' => c,
+                    _ => '_',
+                })
+                .collect();
+
+            // trim trailing/leading '_'
+            (normalized_name.trim_matches('_').into(), value)
+        })
+        .collect();
+
+    // find duplicate names
+    let mut occurences: HashMap<String, u64> = HashMap::new();
+    for val in &values {
+        *occurences.entry(val.0.clone()).or_insert(0) += 1;
+    }
+
+    // make unique names if there was more than one occurrence
+    values
+        .into_iter()
+        .map(|(name, val)| {
+            if occurences[&name] > 1 {
+                (format!("{val}_{name}"), val)
+            } else {
+                (name, val)
+            }
+        })
+        .collect()
+}
+
+/// Parse an integer written in decimal, hex (`0x`), binary (`0b`) or octal
+/// (`0`), or as a float with no fractional part.
+pub(crate) fn parse_int(text: &str) -> Option<i128> {
+    let text = text.trim();
+    let (negative, digits) = match text.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, text.strip_prefix('+').unwrap_or(text)),
+    };
+
+    let value = if let Some(hex) = digits.strip_prefix("0x").or(digits.strip_prefix("0X")) {
+        i128::from_str_radix(hex, 16).ok()
+    } else if let Some(bin) = digits.strip_prefix("0b").or(digits.strip_prefix("0B")) {
+        i128::from_str_radix(bin, 2).ok()
+    } else if digits.len() > 1
+        && digits.starts_with('0')
+        && digits.bytes().all(|b| b.is_ascii_digit())
+    {
+        i128::from_str_radix(&digits[1..], 8).ok()
+    } else {
+        digits.parse().ok().or_else(|| {
+            let f: f64 = digits.parse().ok()?;
+            (f.fract() == 0.0 && f.abs() < 2f64.powi(100)).then_some(f as i128)
+        })
+    }?;
+
+    Some(if negative { -value } else { value })
+}
+
+/// Reorder signals sorted by start bit so each multiplexor comes before
+/// the signals it multiplexes. Signals that share a multiplexor keep their
+/// relative order.
+pub(crate) fn order_multiplexors_first(signals: Vec<(u32, CANSignal)>) -> Vec<(u32, CANSignal)> {
+    let mut ordered: Vec<(u32, CANSignal)> = Vec::new();
+    let mut pending = signals;
+
+    while !pending.is_empty() {
+        let placed: HashSet<String> = ordered.iter().map(|s| s.1.name.clone()).collect();
+
+        let (ready, rest): (Vec<_>, Vec<_>) = pending.into_iter().partition(|s| {
+            s.1.mux
+                .as_ref()
+                .is_none_or(|m| placed.contains(&m.multiplexor))
+        });
+
+        // Missing multiplexor - leave it to the message builder to complain.
+        if ready.is_empty() {
+            ordered.extend(rest);
+            break;
+        }
+
+        ordered.extend(ready);
+        pending = rest;
+    }
+
+    ordered
 }
@@ -0,0 +1,939 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Write;
+
+use super::from_dbc::{DBC_NAMING_ATTR, DBC_NATIVE_ATTRS};
+use super::TranslationFromOpencan;
+use crate::*;
+
+/// Placeholder node name for DBC messages and signals with no node.
+const DBC_NO_NODE: &str = "Vector__XXX";
+
+/// DBC files mark extended message IDs by setting bit 31.
+const DBC_EXTENDED_ID_FLAG: u32 = 1 << 31;
+
+/// Type and range of a user-defined attribute's `BA_DEF_` definition.
+enum DbcAttributeDef<'a> {
+    String,
+    Int(i64, i64),
+    Float(f64, f64),
+    /// Sorted variants.
+    Enum(BTreeSet<&'a String>),
+}
+
+impl DbcAttributeDef<'_> {
+    /// Definition covering the values of both `self` and `other`.
+    fn unify(self, other: Self) -> Self {
+        use DbcAttributeDef::*;
+
+        match (self, other) {
+            (String, String) => String,
+            (Int(a, b), Int(c, d)) => Int(a.min(c), b.max(d)),
+            (Enum(mut a), Enum(b)) => {
+                a.extend(b);
+                Enum(a)
+            }
+            (a, b) => match (a.float_range(), b.float_range()) {
+                (Some((a, b)), Some((c, d))) => Float(a.min(c), b.max(d)),
+                _ => String,
+            },
+        }
+    }
+
+    fn float_range(&self) -> Option<(f64, f64)> {
+        match *self {
+            Self::Int(min, max) => Some((min as f64, max as f64)),
+            Self::Float(min, max) => Some((min, max)),
+            _ => None,
+        }
+    }
+}
+
+/// Variants of the `VFrameFormat` message attribute, in definition order.
+const DBC_FRAME_FORMATS: [&str; 16] = [
+    "StandardCAN",
+    "ExtendedCAN",
+    "reserved",
+    "reserved",
+    "reserved",
+    "reserved",
+    "reserved",
+    "reserved",
+    "reserved",
+    "reserved",
+    "reserved",
+    "reserved",
+    "reserved",
+    "reserved",
+    "StandardCAN_FD",
+    "ExtendedCAN_FD",
+];
+
+/// Translation to DBC files.
+pub struct DbcExporter<'n> {
+    net: &'n CANNetwork,
+}
+
+impl TranslationFromOpencan for DbcExporter<'_> {
+    fn translate(net: &CANNetwork) -> String {
+        DbcExporter { net }.dump_network()
+    }
+}
+
+impl<'n> DbcExporter<'n> {
+    pub fn new(net: &'n CANNetwork) -> Self {
+        DbcExporter { net }
+    }
+
+    fn dump_network(&self) -> String {
+        let mut out = String::new();
+
+        // header
+        out += "VERSION \"\"\n\n";
+        out += "NS_ :\n";
+        for ns in [
+            "CM_",
+            "BA_DEF_",
+            "BA_",
+            "VAL_",
+            "VAL_TABLE_",
+            "BA_DEF_DEF_",
+            "SIG_VALTYPE_",
+            "SG_MUL_VAL_",
+        ] {
+            writeln!(out, "    {ns}").unwrap();
+        }
+        out += "\nBS_:\n\n";
+
+        // nodes
+        let nodes: Vec<&str> = self.net.iter_nodes().map(|n| n.name.as_str()).collect();
+        writeln!(out, "BU_: {}\n", nodes.join(" ")).unwrap();
+
+        // value tables
+        let mut tables: Vec<_> = self.net.iter_value_tables().collect();
+        tables.sort_by(|a, b| a.name.cmp(&b.name));
+
+        for table in &tables {
+            writeln!(
+                out,
+                "VAL_TABLE_ {}{} ;",
+                table.name,
+                Self::dbc_values(&table.values)
+            )
+            .unwrap();
+        }
+        if !tables.is_empty() {
+            out += "\n";
+        }
+
+        // messages and signals
+        for msg in self.net.iter_messages() {
+            out += &self.dump_message(msg);
+            out += "\n";
+        }
+
+        // comments
+        for node in self.net.iter_nodes() {
+            if let Some(desc) = &node.description {
+                writeln!(out, "CM_ BU_ {} \"{}\";", node.name, Self::dbc_string(desc)).unwrap();
+            }
+        }
+
+        for msg in self.net.iter_messages() {
+            if let Some(desc) = &msg.description {
+                writeln!(
+                    out,
+                    "CM_ BO_ {} \"{}\";",
+                    Self::dbc_id(msg),
+                    Self::dbc_string(desc)
+                )
+                .unwrap();
+            }
+
+            for sigbit in &msg.signals {
+                if let Some(desc) = &sigbit.sig.description {
+                    writeln!(
+                        out,
+                        "CM_ SG_ {} {} \"{}\";",
+                        Self::dbc_id(msg),
+                        sigbit.sig.name,
+                        Self::dbc_string(desc)
+                    )
+                    .unwrap();
+                }
+            }
+        }
+
+        // attribute definitions and defaults
+        let formats: Vec<String> = DBC_FRAME_FORMATS
+            .iter()
+            .map(|f| format!("\"{f}\""))
+            .collect();
+        writeln!(out, "BA_DEF_ BO_ \"GenMsgCycleTime\" INT 0 65535;").unwrap();
+        writeln!(
+            out,
+            "BA_DEF_ BO_ \"VFrameFormat\" ENUM {};",
+            formats.join(",")
+        )
+        .unwrap();
+        writeln!(out, "BA_DEF_ BO_ \"CANFD_BRS\" ENUM \"0\",\"1\";").unwrap();
+
+        let start_values: Vec<_> = self
+            .net
+            .iter_messages()
+            .flat_map(|m| m.signals.iter().map(move |s| (m, &s.sig)))
+            .filter_map(|(m, sig)| Some((Self::dbc_id(m), &sig.name, sig.initial_value?)))
+            .collect();
+        // INT attributes are 32-bit, so wider start values need a FLOAT one
+        let max_start_value = start_values.iter().map(|v| v.2).max().unwrap_or(0);
+        let start_value_type = if max_start_value > i32::MAX as u64 {
+            "FLOAT"
+        } else {
+            "INT"
+        };
+        writeln!(
+            out,
+            "BA_DEF_ SG_ \"GenSigStartValue\" {start_value_type} 0 {max_start_value};"
+        )
+        .unwrap();
+
+        // naming, only when importers can't assume the default
+        let naming = Some(self.net.naming()).filter(|n| **n != CANNaming::default());
+        if naming.is_some() {
+            writeln!(out, "BA_DEF_ \"{DBC_NAMING_ATTR}\" STRING;").unwrap();
+        }
+
+        // user-defined attributes, one definition per object type, each
+        // with a type that holds all of the attribute's values
+        let attributes = self.attribute_values();
+        let definitions = Self::attribute_definitions(&attributes);
+        let mut kinds: BTreeMap<(&str, &str), usize> = BTreeMap::new();
+        for (kind, _, name, _) in &attributes {
+            *kinds.entry((kind, name)).or_default() += 1;
+        }
+
+        // defaults are the smallest value or the first enum variant. Importers
+        // give the default to objects without a value, so it's only written
+        // when every object of the attribute's type has one.
+        let mut defaults = BTreeMap::new();
+
+        for (&(kind, name), &count) in &kinds {
+            let def = &definitions[name];
+            let (ty, default) = match def {
+                DbcAttributeDef::String => ("STRING".into(), "\"\"".into()),
+                DbcAttributeDef::Int(min, max) => (format!("INT {min} {max}"), min.to_string()),
+                DbcAttributeDef::Float(min, max) => (format!("FLOAT {min} {max}"), min.to_string()),
+                DbcAttributeDef::Enum(variants) => {
+                    let variants: Vec<String> = variants
+                        .iter()
+                        .map(|v| format!("\"{}\"", Self::dbc_string(v)))
+                        .collect();
+                    (format!("ENUM {}", variants.join(",")), variants[0].clone())
+                }
+            };
+
+            let default = defaults.entry(name).or_insert(Some(default));
+            if count < self.object_count(kind) {
+                *default = None;
+            }
+
+            let kind = if kind.is_empty() {
+                String::new()
+            } else {
+                format!("{kind} ")
+            };
+            writeln!(out, "BA_DEF_ {kind}\"{name}\" {ty};").unwrap();
+        }
+
+        writeln!(out, "BA_DEF_DEF_ \"GenMsgCycleTime\" 0;").unwrap();
+        writeln!(out, "BA_DEF_DEF_ \"VFrameFormat\" \"StandardCAN\";").unwrap();
+        writeln!(out, "BA_DEF_DEF_ \"CANFD_BRS\" \"0\";").unwrap();
+        writeln!(out, "BA_DEF_DEF_ \"GenSigStartValue\" 0;").unwrap();
+        if naming.is_some() {
+            writeln!(
+                out,
+                "BA_DEF_DEF_ \"{DBC_NAMING_ATTR}\" \"{}\";",
+                CANNaming::default().format()
+            )
+            .unwrap();
+        }
+
+        for (name, default) in defaults {
+            if let Some(default) = default {
+                writeln!(out, "BA_DEF_DEF_ \"{name}\" {default};").unwrap();
+            }
+        }
+
+        // attribute values
+        if let Some(naming) = naming {
+            writeln!(out, "BA_ \"{DBC_NAMING_ATTR}\" \"{}\";", naming.format()).unwrap();
+        }
+
+        for msg in self.net.iter_messages() {
+            let id = Self::dbc_id(msg);
+
+            if let Some(cycletime) = msg.cycletime {
+                writeln!(out, "BA_ \"GenMsgCycleTime\" BO_ {id} {cycletime};").unwrap();
+            }
+
+            let format = match (msg.fd, msg.id.is_extended()) {
+                (false, false) => "StandardCAN",
+                (false, true) => "ExtendedCAN",
+                (true, false) => "StandardCAN_FD",
+                (true, true) => "ExtendedCAN_FD",
+            };
+            let format_idx = DBC_FRAME_FORMATS.iter().position(|f| *f == format).unwrap();
+            writeln!(out, "BA_ \"VFrameFormat\" BO_ {id} {format_idx};").unwrap();
+
+            if msg.brs {
+                writeln!(out, "BA_ \"CANFD_BRS\" BO_ {id} 1;").unwrap();
+            }
+        }
+
+        for (id, signal, value) in start_values {
+            writeln!(out, "BA_ \"GenSigStartValue\" SG_ {id} {signal} {value};").unwrap();
+        }
+
+        for (kind, object, name, value) in &attributes {
+            let value = match (&definitions[name], value) {
+                (DbcAttributeDef::Enum(variants), AttributeValue::Enum(v)) => {
+                    variants.iter().position(|e| *e == v).unwrap().to_string()
+                }
+                (DbcAttributeDef::String, v) => format!("\"{}\"", Self::dbc_string(&v.to_string())),
+                (_, v) => v.to_string(),
+            };
+
+            let object = if kind.is_empty() {
+                String::new()
+            } else {
+                format!("{kind} {object} ")
+            };
+            writeln!(out, "BA_ \"{name}\" {object}{value};").unwrap();
+        }
+
+        // value descriptions
+        for msg in self.net.iter_messages() {
+            for sigbit in &msg.signals {
+                if sigbit.sig.enumerated_values.is_empty() {
+                    continue;
+                }
+
+                writeln!(
+                    out,
+                    "VAL_ {} {}{} ;",
+                    Self::dbc_id(msg),
+                    sigbit.sig.name,
+                    Self::dbc_values(&sigbit.sig.enumerated_values)
+                )
+                .unwrap();
+            }
+        }
+
+        // float value types
+        for msg in self.net.iter_messages() {
+            for sigbit in &msg.signals {
+                let valtype = match sigbit.sig.value_type {
+                    SignalValueType::Integer => continue,
+                    SignalValueType::Float => 1,
+                    SignalValueType::Double => 2,
+                };
+
+                writeln!(
+                    out,
+                    "SIG_VALTYPE_ {} {} : {valtype};",
+                    Self::dbc_id(msg),
+                    sigbit.sig.name
+                )
+                .unwrap();
+            }
+        }
+
+        // extended multiplexing
+        for msg in self.net.iter_messages() {
+            if !Self::needs_extended_mux(msg) {
+                continue;
+            }
+
+            for sigbit in &msg.signals {
+                let Some(mux) = &sigbit.sig.mux else {
+                    continue;
+                };
+
+                let ranges: Vec<String> = mux.values.iter().map(|v| format!("{v}-{v}")).collect();
+
+                writeln!(
+                    out,
+                    "SG_MUL_VAL_ {} {} {} {};",
+                    Self::dbc_id(msg),
+                    sigbit.sig.name,
+                    mux.multiplexor,
+                    ranges.join(", ")
+                )
+                .unwrap();
+            }
+        }
+
+        out
+    }
+
+    fn dump_message(&self, msg: &CANMessage) -> String {
+        let mut out = format!(
+            "BO_ {} {}: {} {}\n",
+            Self::dbc_id(msg),
+            msg.name,
+            msg.length,
+            msg.tx_node().unwrap_or(DBC_NO_NODE)
+        );
+
+        // DBC files indicate rx nodes by signal, not by message.
+        let rx_nodes: Vec<&str> = self
+            .net
+            .iter_nodes()
+            .filter(|n| n.rx_messages.contains_key(&msg.name))
+            .map(|n| n.name.as_str())
+            .collect();
+
+        let rx_nodes = if rx_nodes.is_empty() {
+            DBC_NO_NODE.into()
+        } else {
+            rx_nodes.join(",")
+        };
+
+        for sigbit in &msg.signals {
+            let sig = &sigbit.sig;
+
+            // [0|0] means no range, so fill in a missing limit from what the
+            // signal can represent
+            let (min, max) = match (sig.min, sig.max) {
+                (None, None) => (0.0, 0.0),
+                (min, max) => {
+                    let (lo, hi) = sig.physical_range();
+                    (min.unwrap_or(lo), max.unwrap_or(hi))
+                }
+            };
+
+            writeln!(
+                out,
+                " SG_ {}{} : {}|{}@{}{} ({},{}) [{}|{}] \"{}\" {}",
+                sig.name,
+                Self::mux_indicator(msg, sig),
+                sigbit.start(),
+                sig.width,
+                match sig.byte_order {
+                    ByteOrder::LittleEndian => 1,
+                    ByteOrder::BigEndian => 0,
+                },
+                // floats are always signed
+                if sig.twos_complement || sig.value_type != SignalValueType::Integer {
+                    '-'
+                } else {
+                    '+'
+                },
+                sig.scale.unwrap_or(1.0),
+                sig.offset.unwrap_or(0.0),
+                min,
+                max,
+                Self::dbc_string(sig.unit.as_deref().unwrap_or_default()),
+                rx_nodes
+            )
+            .unwrap();
+        }
+
+        out
+    }
+
+    /// User-defined attribute values in the network, as (object type, object,
+    /// attribute name, value). The object type and object are empty for
+    /// attributes of the network itself. Attributes that are exported as
+    /// message properties are left out.
+    fn attribute_values(&self) -> Vec<(&'static str, String, &'n str, &'n AttributeValue)> {
+        let net = self.net;
+        let mut values = Vec::new();
+
+        let mut push = |kind, object: String, attributes: &'n Attributes| {
+            for (name, value) in attributes {
+                if !DBC_NATIVE_ATTRS.contains(&name.as_str()) {
+                    values.push((kind, object.clone(), name.as_str(), value));
+                }
+            }
+        };
+
+        push("", String::new(), net.attributes());
+
+        for node in net.iter_nodes() {
+            push("BU_", node.name.clone(), &node.attributes);
+        }
+
+        for msg in net.iter_messages() {
+            let id = Self::dbc_id(msg);
+            push("BO_", id.to_string(), &msg.attributes);
+
+            for sigbit in &msg.signals {
+                push(
+                    "SG_",
+                    format!("{id} {}", sigbit.sig.name),
+                    &sigbit.sig.attributes,
+                );
+            }
+        }
+
+        values
+    }
+
+    /// Definitions of user-defined attributes by name. An attribute with
+    /// values of several types is defined as a `FLOAT` if they're all
+    /// numbers, and as a `STRING` otherwise.
+    fn attribute_definitions<'a>(
+        attributes: &[(&str, String, &'a str, &'a AttributeValue)],
+    ) -> BTreeMap<&'a str, DbcAttributeDef<'a>> {
+        let mut definitions: BTreeMap<&str, DbcAttributeDef> = BTreeMap::new();
+
+        for &(_, _, name, value) in attributes {
+            let def = match value {
+                AttributeValue::String(_) => DbcAttributeDef::String,
+                AttributeValue::Int(v) => DbcAttributeDef::Int(*v, *v),
+                AttributeValue::Float(v) => DbcAttributeDef::Float(*v, *v),
+                AttributeValue::Enum(v) => DbcAttributeDef::Enum(BTreeSet::from([v])),
+            };
+
+            let def = match definitions.remove(name) {
+                None => def,
+                Some(prev) => prev.unify(def),
+            };
+            definitions.insert(name, def);
+        }
+
+        // INT attributes are 32-bit
+        for def in definitions.values_mut() {
+            if let DbcAttributeDef::Int(min, max) = *def {
+                if min < i32::MIN.into() || max > i32::MAX.into() {
+                    *def = DbcAttributeDef::Float(min as f64, max as f64);
+                }
+            }
+        }
+
+        definitions
+    }
+
+    /// Number of objects of a `BA_DEF_` object type, e.g. `BO_` for messages.
+    fn object_count(&self, kind: &str) -> usize {
+        match kind {
+            "BU_" => self.net.iter_nodes().count(),
+            "BO_" => self.net.iter_messages().count(),
+            "SG_" => self.net.iter_messages().map(|m| m.signals.len()).sum(),
+            _ => 1,
+        }
+    }
+
+    /// Value descriptions as written after `VAL_` and `VAL_TABLE_`, sorted
+    /// by value.
+    fn dbc_values(values: &bimap::BiMap<String, u64>) -> String {
+        let mut values: Vec<_> = values.iter().collect();
+        values.sort_by_key(|v| v.1);
+
+        values
+            .into_iter()
+            .map(|(name, val)| format!(" {val} \"{}\"", Self::dbc_string(name)))
+            .collect()
+    }
+
+    /// Message ID as written in a DBC file.
+    fn dbc_id(msg: &CANMessage) -> u32 {
+        if msg.id.is_extended() {
+            msg.id.raw() | DBC_EXTENDED_ID_FLAG
+        } else {
+            msg.id.raw()
+        }
+    }
+
+    /// Plain DBC multiplexing only has one multiplexor per message, with one
+    /// value per multiplexed signal. Anything else needs `SG_MUL_VAL_`.
+    fn needs_extended_mux(msg: &CANMessage) -> bool {
+        let mut multiplexors = msg
+            .signals
+            .iter()
+            .filter(|s| msg.is_multiplexor(&s.sig.name));
+
+        multiplexors.next().is_some() && multiplexors.next().is_some()
+            || msg
+                .signals
+                .iter()
+                .any(|s| s.sig.mux.as_ref().is_some_and(|m| m.values.len() > 1))
+    }
+
+    /// Multiplexer indicator for a signal, including the leading space.
+    fn mux_indicator(msg: &CANMessage, sig: &CANSignal) -> String {
+        let muxed = sig.mux.as_ref().map(|m| format!("m{}", m.values[0]));
+        let multiplexor = msg.is_multiplexor(&sig.name).then_some("M");
+
+        match (muxed, multiplexor) {
+            (None, None) => "".into(),
+            (muxed, multiplexor) => format!(
+                " {}{}",
+                muxed.unwrap_or_default(),
+                multiplexor.unwrap_or_default()
+            ),
+        }
+    }
+
+    /// DBC strings can't contain double quotes, so swap them for single quotes.
+    fn dbc_string(s: &str) -> String {
+        s.replace('"', "'")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::*;
+    use crate::translation::{DbcImporter, ImportMode};
+
+    fn test_net() -> CANNetwork {
+        let mut net = CANNetwork::new();
+        net.add_node("TX").unwrap();
+        net.add_node("RX").unwrap();
+
+        let mut states = CANValueTable::new("States").unwrap();
+        states.add_value("IDLE", 0).unwrap();
+        states.add_value("ACTIVE", 1).unwrap();
+        net.insert_value_table(states.clone()).unwrap();
+
+        let msg = CANMessage::builder()
+            .name("Status")
+            .description(Some("Status of TX.".into()))
+            .id(0x10)
+            .cycletime(Some(100))
+            .tx_node("TX")
+            .add_signal(
+                new_sig()
+                    .name("state")
+                    .width(2)
+                    .description(Some("Current \"state\".".into()))
+                    .use_value_table(&states)
+                    .unwrap()
+                    .build()
+                    .unwrap(),
+            )
+            .unwrap()
+            .add_signal(
+                new_sig()
+                    .name("temp")
+                    .width(12)
+                    .twos_complement(true)
+                    .scale(Some(0.5))
+                    .offset(Some(-40.))
+                    .unit(Some("degC".into()))
+                    .min(Some(-40.))
+                    .max(Some(100.))
+                    .initial_value(Some(80))
+                    .attributes(Attributes::from([(
+                        "Accuracy".into(),
+                        AttributeValue::Float(0.25),
+                    )]))
+                    .build()
+                    .unwrap(),
+            )
+            .unwrap()
+            .add_signal_fixed(
+                31,
+                new_sig()
+                    .name("counter")
+                    .width(8)
+                    .byte_order(ByteOrder::BigEndian)
+                    .build()
+                    .unwrap(),
+            )
+            .unwrap()
+            .build()
+            .unwrap();
+        net.insert_msg(msg).unwrap();
+
+        let msg = CANMessage::builder()
+            .name("Data")
+            .id(CANId::Extended(0x1234))
+            .fd(true)
+            .brs(true)
+            .tx_node("RX")
+            .add_signal(new_sig().name("mode").width(2).build().unwrap())
+            .unwrap()
+            .add_signal(
+                new_sig()
+                    .name("a")
+                    .width(16)
+                    .mux(Some(CANSignalMux {
+                        multiplexor: "mode".into(),
+                        values: vec![0, 2],
+                    }))
+                    .build()
+                    .unwrap(),
+            )
+            .unwrap()
+            .add_signal_fixed(
+                24,
+                new_sig()
+                    .name("f")
+                    .width(32)
+                    .value_type(SignalValueType::Float)
+                    .build()
+                    .unwrap(),
+            )
+            .unwrap()
+            .add_signal_fixed(72, basic_sig("b"))
+            .unwrap()
+            .build()
+            .unwrap();
+        net.insert_msg(msg).unwrap();
+
+        net.set_message_rx_by_node("Status", "RX").unwrap();
+        net.set_node_description("TX", Some("Transmitter".into()))
+            .unwrap();
+
+        net.set_attribute("BusType", AttributeValue::String("CAN".into()));
+        net.set_node_attribute("TX", "Domain", AttributeValue::Enum("Body".into()))
+            .unwrap();
+        net.set_node_attribute("RX", "Domain", AttributeValue::Enum("Chassis".into()))
+            .unwrap();
+        net.set_message_attribute("Status", "Priority", AttributeValue::Int(3))
+            .unwrap();
+
+        net
+    }
+
+    #[test]
+    fn dbc_message_lines() {
+        let dbc = DbcExporter::translate(&test_net());
+
+        assert!(dbc.contains("BU_: TX RX\n"));
+        assert!(dbc.contains("BO_ 16 Status: 4 TX\n"));
+        assert!(dbc.contains(" SG_ state : 0|2@1+ (1,0) [0|0] \"\" RX\n"));
+        assert!(dbc.contains(" SG_ temp : 2|12@1- (0.5,-40) [-40|100] \"degC\" RX\n"));
+        assert!(dbc.contains(" SG_ counter : 31|8@0+ (1,0) [0|0] \"\" RX\n"));
+        assert!(dbc.contains("CM_ SG_ 16 state \"Current 'state'.\";\n"));
+        assert!(dbc.contains("CM_ BO_ 16 \"Status of TX.\";\n"));
+        assert!(dbc.contains("CM_ BU_ TX \"Transmitter\";\n"));
+        assert!(dbc.contains("VAL_ 16 state 0 \"IDLE\" 1 \"ACTIVE\" ;\n"));
+        assert!(dbc.contains("VAL_TABLE_ States 0 \"IDLE\" 1 \"ACTIVE\" ;\n"));
+        assert!(dbc.contains("BA_ \"GenMsgCycleTime\" BO_ 16 100;\n"));
+
+        let data_id = 0x1234 | DBC_EXTENDED_ID_FLAG;
+        assert!(dbc.contains(&format!("BO_ {data_id} Data: 12 RX\n")));
+        assert!(dbc.contains(" SG_ mode M : 0|2@1+ (1,0) [0|0] \"\" Vector__XXX\n"));
+        assert!(dbc.contains(&format!("SG_MUL_VAL_ {data_id} a mode 0-0, 2-2;\n")));
+        assert!(dbc.contains(" SG_ f : 24|32@1- (1,0) [0|0] \"\" Vector__XXX\n"));
+        assert!(dbc.contains(&format!("SIG_VALTYPE_ {data_id} f : 1;\n")));
+        assert!(dbc.contains(&format!("BA_ \"VFrameFormat\" BO_ {data_id} 15;\n")));
+        assert!(dbc.contains(&format!("BA_ \"CANFD_BRS\" BO_ {data_id} 1;\n")));
+    }
+
+    #[test]
+    fn dbc_attribute_lines() {
+        let dbc = DbcExporter::translate(&test_net());
+
+        assert!(dbc.contains("BA_DEF_ \"BusType\" STRING;\n"));
+        assert!(dbc.contains("BA_DEF_ BU_ \"Domain\" ENUM \"Body\",\"Chassis\";\n"));
+        assert!(dbc.contains("BA_DEF_ BO_ \"Priority\" INT 3 3;\n"));
+        assert!(dbc.contains("BA_DEF_ SG_ \"Accuracy\" FLOAT 0.25 0.25;\n"));
+        assert!(dbc.contains("BA_DEF_DEF_ \"Domain\" \"Body\";\n"));
+        // not every message has a priority, so the others mustn't get one
+        assert!(!dbc.contains("BA_DEF_DEF_ \"Priority\""));
+
+        assert!(dbc.contains("BA_ \"BusType\" \"CAN\";\n"));
+        assert!(dbc.contains("BA_ \"Domain\" BU_ RX 1;\n"));
+        assert!(dbc.contains("BA_ \"Priority\" BO_ 16 3;\n"));
+        assert!(dbc.contains("BA_ \"Accuracy\" SG_ 16 temp 0.25;\n"));
+        assert!(dbc.contains("BA_DEF_ SG_ \"GenSigStartValue\" INT 0 80;\n"));
+        assert!(dbc.contains("BA_ \"GenSigStartValue\" SG_ 16 temp 80;\n"));
+    }
+
+    #[test]
+    fn dbc_wide_start_value() {
+        let mut net = CANNetwork::new();
+        let msg = CANMessage::builder()
+            .name("M")
+            .id(0x10)
+            .add_signal(
+                new_sig()
+                    .name("serial")
+                    .width(64)
+                    .initial_value(Some(1 << 40))
+                    .build()
+                    .unwrap(),
+            )
+            .unwrap()
+            .build()
+            .unwrap();
+        net.insert_msg(msg).unwrap();
+
+        let dbc = DbcExporter::translate(&net);
+        assert!(dbc.contains("BA_DEF_ SG_ \"GenSigStartValue\" FLOAT 0 1099511627776;\n"));
+
+        let mut imported = CANNetwork::new();
+        DbcImporter::import_network(dbc, &mut imported, ImportMode::Strict).unwrap();
+        let msg = imported.message_by_name("M").unwrap();
+        assert_eq!(msg["serial"].initial_value, Some(1 << 40));
+    }
+
+    /// Attributes with values of different types are defined with a type
+    /// that holds all of them.
+    #[test]
+    fn dbc_mixed_attribute_types() {
+        let mut net = test_net();
+        net.set_message_attribute("Data", "Priority", AttributeValue::Float(2.5))
+            .unwrap();
+        net.set_node_attribute("TX", "Owner", AttributeValue::String("Team".into()))
+            .unwrap();
+        net.set_node_attribute("RX", "Owner", AttributeValue::Int(7))
+            .unwrap();
+
+        let dbc = DbcExporter::translate(&net);
+        assert!(dbc.contains("BA_DEF_ BO_ \"Priority\" FLOAT 2.5 3;\n"));
+        assert!(dbc.contains("BA_ \"Priority\" BO_ 16 3;\n"));
+        assert!(dbc.contains("BA_DEF_ BU_ \"Owner\" STRING;\n"));
+        assert!(dbc.contains("BA_ \"Owner\" BU_ RX \"7\";\n"));
+
+        let mut imported = CANNetwork::new();
+        DbcImporter::import_network(dbc, &mut imported, ImportMode::Strict).unwrap();
+        assert_eq!(
+            imported.message_by_name("Status").unwrap().attributes["Priority"],
+            AttributeValue::Float(3.)
+        );
+        assert_eq!(
+            imported.node_by_name("RX").unwrap().attributes["Owner"],
+            AttributeValue::String("7".into())
+        );
+    }
+
+    /// A signal with only one limit gets the other from its representable
+    /// range, since `[x|0]` would read back as a different range.
+    #[test]
+    fn dbc_one_sided_range() {
+        let mut net = CANNetwork::new();
+        let msg = CANMessage::builder()
+            .name("M")
+            .id(0x10)
+            .add_signal(
+                new_sig()
+                    .name("level")
+                    .width(8)
+                    .scale(Some(0.5))
+                    .max(Some(100.))
+                    .build()
+                    .unwrap(),
+            )
+            .unwrap()
+            .add_signal(
+                new_sig()
+                    .name("trim")
+                    .width(8)
+                    .twos_complement(true)
+                    .min(Some(-10.))
+                    .build()
+                    .unwrap(),
+            )
+            .unwrap()
+            .build()
+            .unwrap();
+        net.insert_msg(msg).unwrap();
+
+        let dbc = DbcExporter::translate(&net);
+        assert!(dbc.contains(" SG_ level : 0|8@1+ (0.5,0) [0|100] \"\" Vector__XXX\n"));
+        assert!(dbc.contains(" SG_ trim : 8|8@1- (1,0) [-10|127] \"\" Vector__XXX\n"));
+
+        let mut imported = CANNetwork::new();
+        DbcImporter::import_network(dbc, &mut imported, ImportMode::Strict).unwrap();
+        let msg = imported.message_by_name("M").unwrap();
+        assert_eq!((msg["level"].min, msg["level"].max), (Some(0.), Some(100.)));
+        assert_eq!((msg["trim"].min, msg["trim"].max), (Some(-10.), Some(127.)));
+    }
+
+    #[test]
+    fn dbc_naming() {
+        // the default naming isn't written out
+        let mut net = test_net();
+        assert!(!DbcExporter::translate(&net).contains("OpenCANNaming"));
+
+        for naming in [
+            CANNaming::unprefixed(),
+            CANNaming::new("ECU_{node}__").unwrap(),
+        ] {
+            net.set_naming(naming.clone());
+
+            let dbc = DbcExporter::translate(&net);
+            assert!(dbc.contains("BA_DEF_ \"OpenCANNaming\" STRING;\n"));
+            assert!(dbc.contains(&format!("BA_ \"OpenCANNaming\" \"{}\";\n", naming.format())));
+
+            let mut imported = CANNetwork::new();
+            DbcImporter::import_network(dbc, &mut imported, ImportMode::Strict).unwrap();
+            assert_eq!(imported.naming(), &naming);
+            assert!(!imported.attributes().contains_key("OpenCANNaming"));
+        }
+    }
+
+    #[test]
+    fn dbc_round_trip() {
+        let net = test_net();
+
+        let mut imported = CANNetwork::new();
+        DbcImporter::import_network(
+            DbcExporter::translate(&net),
+            &mut imported,
+            ImportMode::Strict,
+        )
+        .unwrap();
+
+        for msg in net.iter_messages() {
+            let other = imported.message_by_name(&msg.name).unwrap();
+
+            assert_eq!(msg.id, other.id);
+            assert_eq!(msg.length, other.length);
+            assert_eq!(msg.cycletime, other.cycletime);
+            assert_eq!(msg.tx_node, other.tx_node);
+            assert_eq!((msg.fd, msg.brs), (other.fd, other.brs));
+
+            for sigbit in &msg.signals {
+                let other = other.get_sig(&sigbit.sig.name).unwrap();
+
+                assert_eq!(sigbit.start(), other.start());
+                assert_eq!(sigbit.sig.width, other.sig.width);
+                assert_eq!(sigbit.sig.byte_order, other.sig.byte_order);
+                assert_eq!(sigbit.sig.twos_complement, other.sig.twos_complement);
+                assert_eq!(sigbit.sig.value_type, other.sig.value_type);
+                assert_eq!(sigbit.sig.scale, other.sig.scale);
+                assert_eq!(sigbit.sig.offset, other.sig.offset);
+                assert_eq!(sigbit.sig.unit, other.sig.unit);
+                assert_eq!(
+                    (sigbit.sig.min, sigbit.sig.max),
+                    (other.sig.min, other.sig.max)
+                );
+                assert_eq!(sigbit.sig.mux, other.sig.mux);
+                assert_eq!(sigbit.sig.enumerated_values, other.sig.enumerated_values);
+                assert_eq!(sigbit.sig.value_table, other.sig.value_table);
+                assert_eq!(sigbit.sig.initial_value, other.sig.initial_value);
+                assert_eq!(sigbit.sig.attributes, other.sig.attributes);
+            }
+
+            assert_eq!(msg.attributes, other.attributes);
+            assert_eq!(msg.description, other.description);
+        }
+
+        assert_eq!(
+            imported.value_table_by_name("States"),
+            net.value_table_by_name("States")
+        );
+        assert_eq!(net.attributes(), imported.attributes());
+        for node in net.iter_nodes() {
+            let other = imported.node_by_name(&node.name).unwrap();
+            assert_eq!(node.attributes, other.attributes);
+            assert_eq!(node.description, other.description);
+        }
+
+        assert_eq!(
+            imported
+                .rx_messages_by_node("RX")
+                .unwrap()
+                .iter()
+                .map(|m| &m.name)
+                .collect::<Vec<_>>(),
+            ["Status"]
+        );
+    }
+}
@@ -0,0 +1,439 @@
+use std::fmt::Write;
+
+use super::from_kcd::kcd_big_endian_offset;
+use super::TranslationFromOpencan;
+use crate::*;
+
+/// Name of the single bus written to KCD files.
+const KCD_BUS_NAME: &str = "CAN";
+
+/// Translation to KCD (Kayak) network definitions.
+///
+/// KCD only has one level of multiplexing, so signals multiplexed by a
+/// signal that is itself multiplexed are left out, with a comment in their
+/// place. KCD has no notion of CAN FD; FD messages are written with their
+/// full length.
+pub struct KcdExporter<'n> {
+    net: &'n CANNetwork,
+}
+
+impl TranslationFromOpencan for KcdExporter<'_> {
+    fn translate(net: &CANNetwork) -> String {
+        KcdExporter { net }.dump_network()
+    }
+}
+
+impl<'n> KcdExporter<'n> {
+    pub fn new(net: &'n CANNetwork) -> Self {
+        KcdExporter { net }
+    }
+
+    fn dump_network(&self) -> String {
+        let mut out = String::new();
+
+        // header
+        out += "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n";
+        out += "<NetworkDefinition xmlns=\"http://kayak.2codeornot2code.org/1.0\" \
+                xmlns:xsi=\"http://www.w3.org/2001/XMLSchema-instance\" \
+                xsi:schemaLocation=\"Definition.xsd\">\n";
+        out += "  <Document name=\"opencan\"/>\n";
+
+        // nodes, with IDs counting from 1
+        for node in self.net.iter_nodes() {
+            writeln!(
+                out,
+                "  <Node id=\"{}\" name=\"{}\"/>",
+                self.node_id(&node.name),
+                Self::kcd_string(&node.name)
+            )
+            .unwrap();
+        }
+
+        // bus
+        match self.net.bitrate() {
+            Some(b) => writeln!(out, "  <Bus name=\"{KCD_BUS_NAME}\" baudrate=\"{b}\">").unwrap(),
+            None => writeln!(out, "  <Bus name=\"{KCD_BUS_NAME}\">").unwrap(),
+        }
+
+        for msg in self.net.iter_messages() {
+            out += &self.dump_message(msg);
+        }
+
+        out += "  </Bus>\n";
+        out += "</NetworkDefinition>\n";
+
+        out
+    }
+
+    fn dump_message(&self, msg: &CANMessage) -> String {
+        let mut out = String::new();
+
+        let mut attrs = format!(
+            "id=\"0x{:X}\" name=\"{}\" length=\"{}\"",
+            msg.id.raw(),
+            Self::kcd_string(&msg.name),
+            msg.length
+        );
+        if let Some(cycletime) = msg.cycletime {
+            write!(attrs, " interval=\"{cycletime}\"").unwrap();
+        }
+        if msg.id.is_extended() {
+            attrs += " format=\"extended\"";
+        }
+        writeln!(out, "    <Message {attrs}>").unwrap();
+
+        if let Some(tx) = msg.tx_node() {
+            out += "      <Producer>\n";
+            writeln!(out, "        <NodeRef id=\"{}\"/>", self.node_id(tx)).unwrap();
+            out += "      </Producer>\n";
+        }
+
+        // KCD files indicate consumers by signal, not by message.
+        let consumers: Vec<usize> = self
+            .net
+            .iter_nodes()
+            .filter(|n| n.rx_messages.contains_key(&msg.name))
+            .map(|n| self.node_id(&n.name))
+            .collect();
+
+        for sigbit in &msg.signals {
+            let sig = &sigbit.sig;
+
+            // multiplexed signals go in their multiplexor's mux groups
+            if sig.mux.is_some() {
+                continue;
+            }
+
+            if !msg.is_multiplexor(&sig.name) {
+                out += &Self::dump_signal(sigbit, &consumers, "Signal", "      ");
+                continue;
+            }
+
+            let mut mux = Self::dump_signal(sigbit, &consumers, "Multiplex", "      ");
+            let close = mux.split_off(mux.rfind("      </Multiplex>").unwrap());
+
+            let multiplexed = msg.signals.iter().filter(|s| {
+                s.sig
+                    .mux
+                    .as_ref()
+                    .is_some_and(|m| m.multiplexor == sig.name)
+            });
+
+            let mut values: Vec<u64> = multiplexed
+                .clone()
+                .flat_map(|s| s.sig.mux.as_ref().unwrap().values.clone())
+                .collect();
+            values.sort_unstable();
+            values.dedup();
+
+            for value in values {
+                writeln!(mux, "        <MuxGroup count=\"{value}\">").unwrap();
+
+                for s in multiplexed
+                    .clone()
+                    .filter(|s| s.sig.mux.as_ref().unwrap().values.contains(&value))
+                {
+                    if msg.is_multiplexor(&s.sig.name) {
+                        writeln!(
+                            mux,
+                            "          <!-- Signals multiplexed by `{}` can't be expressed in KCD -->",
+                            Self::kcd_string(&s.sig.name)
+                        )
+                        .unwrap();
+                    }
+
+                    mux += &Self::dump_signal(s, &consumers, "Signal", "          ");
+                }
+
+                mux += "        </MuxGroup>\n";
+            }
+
+            out += &mux;
+            out += &close;
+        }
+
+        out += "    </Message>\n";
+        out
+    }
+
+    /// Write a `Signal` or `Multiplex` element, including its closing tag.
+    fn dump_signal(
+        sigbit: &CANSignalWithPosition,
+        consumers: &[usize],
+        tag: &str,
+        indent: &str,
+    ) -> String {
+        let sig = &sigbit.sig;
+        let mut out = String::new();
+
+        let offset = match sig.byte_order {
+            ByteOrder::LittleEndian => sigbit.start(),
+            ByteOrder::BigEndian => kcd_big_endian_offset(sigbit.start()),
+        };
+
+        let mut attrs = format!(
+            "name=\"{}\" offset=\"{offset}\" length=\"{}\"",
+            Self::kcd_string(&sig.name),
+            sig.width
+        );
+        if sig.byte_order == ByteOrder::BigEndian {
+            attrs += " endianess=\"big\"";
+        }
+        writeln!(out, "{indent}<{tag} {attrs}>").unwrap();
+
+        if let Some(desc) = &sig.description {
+            writeln!(out, "{indent}  <Notes>{}</Notes>", Self::kcd_string(desc)).unwrap();
+        }
+
+        if !consumers.is_empty() {
+            writeln!(out, "{indent}  <Consumer>").unwrap();
+            for id in consumers {
+                writeln!(out, "{indent}    <NodeRef id=\"{id}\"/>").unwrap();
+            }
+            writeln!(out, "{indent}  </Consumer>").unwrap();
+        }
+
+        let mut value = String::new();
+        match (sig.value_type, sig.twos_complement) {
+            (SignalValueType::Float, _) => value += " type=\"single\"",
+            (SignalValueType::Double, _) => value += " type=\"double\"",
+            (SignalValueType::Integer, true) => value += " type=\"signed\"",
+            (SignalValueType::Integer, false) => (),
+        }
+        if let Some(scale) = sig.scale {
+            write!(value, " slope=\"{scale}\"").unwrap();
+        }
+        if let Some(offset) = sig.offset {
+            write!(value, " intercept=\"{offset}\"").unwrap();
+        }
+        if let Some(unit) = &sig.unit {
+            write!(value, " unit=\"{}\"", Self::kcd_string(unit)).unwrap();
+        }
+        if let Some(min) = sig.min {
+            write!(value, " min=\"{min}\"").unwrap();
+        }
+        if let Some(max) = sig.max {
+            write!(value, " max=\"{max}\"").unwrap();
+        }
+        if !value.is_empty() {
+            writeln!(out, "{indent}  <Value{value}/>").unwrap();
+        }
+
+        if !sig.enumerated_values.is_empty() {
+            let mut values: Vec<_> = sig.enumerated_values.iter().collect();
+            values.sort_by_key(|(_, v)| **v);
+
+            writeln!(out, "{indent}  <LabelSet>").unwrap();
+            for (name, value) in values {
+                writeln!(
+                    out,
+                    "{indent}    <Label name=\"{}\" value=\"{value}\"/>",
+                    Self::kcd_string(name)
+                )
+                .unwrap();
+            }
+            writeln!(out, "{indent}  </LabelSet>").unwrap();
+        }
+
+        writeln!(out, "{indent}</{tag}>").unwrap();
+        out
+    }
+
+    /// KCD ID of a node, its position in the network counting from 1.
+    fn node_id(&self, name: &str) -> usize {
+        self.net
+            .iter_nodes()
+            .position(|n| n.name == name)
+            .expect("message nodes should be in the network")
+            + 1
+    }
+
+    /// Escape text for use in XML content and attribute values.
+    fn kcd_string(s: &str) -> String {
+        s.replace('&', "&amp;")
+            .replace('<', "&lt;")
+            .replace('>', "&gt;")
+            .replace('"', "&quot;")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::*;
+    use crate::translation::{ImportMode, KcdImporter};
+
+    fn test_net() -> CANNetwork {
+        let mut net = CANNetwork::new();
+        net.add_node("TX").unwrap();
+        net.add_node("RX").unwrap();
+        net.set_bitrate(250000);
+
+        let msg = CANMessage::builder()
+            .name("Status")
+            .id(0x10)
+            .cycletime(Some(100))
+            .tx_node("TX")
+            .add_signal(
+                new_sig()
+                    .name("state")
+                    .width(2)
+                    .description(Some("Current <state> & mode".into()))
+                    .add_enumerated_value("IDLE", 0)
+                    .unwrap()
+                    .add_enumerated_value("ACTIVE", 1)
+                    .unwrap()
+                    .build()
+                    .unwrap(),
+            )
+            .unwrap()
+            .add_signal(
+                new_sig()
+                    .name("temp")
+                    .width(12)
+                    .twos_complement(true)
+                    .scale(Some(0.5))
+                    .offset(Some(-40.))
+                    .unit(Some("degC".into()))
+                    .min(Some(-40.))
+                    .max(Some(100.))
+                    .build()
+                    .unwrap(),
+            )
+            .unwrap()
+            .add_signal_fixed(
+                31,
+                new_sig()
+                    .name("counter")
+                    .width(8)
+                    .byte_order(ByteOrder::BigEndian)
+                    .build()
+                    .unwrap(),
+            )
+            .unwrap()
+            .build()
+            .unwrap();
+        net.insert_msg(msg).unwrap();
+
+        let msg = CANMessage::builder()
+            .name("Data")
+            .id(CANId::Extended(0x1234))
+            .tx_node("RX")
+            .add_signal(new_sig().name("mode").width(2).build().unwrap())
+            .unwrap()
+            .add_signal(
+                new_sig()
+                    .name("a")
+                    .width(16)
+                    .mux(Some(CANSignalMux {
+                        multiplexor: "mode".into(),
+                        values: vec![0, 2],
+                    }))
+                    .build()
+                    .unwrap(),
+            )
+            .unwrap()
+            .add_signal_fixed(
+                24,
+                new_sig()
+                    .name("f")
+                    .width(32)
+                    .value_type(SignalValueType::Float)
+                    .build()
+                    .unwrap(),
+            )
+            .unwrap()
+            .add_signal_fixed(
+                2,
+                new_sig()
+                    .name("b")
+                    .width(8)
+                    .mux(Some(CANSignalMux {
+                        multiplexor: "mode".into(),
+                        values: vec![1],
+                    }))
+                    .build()
+                    .unwrap(),
+            )
+            .unwrap()
+            .build()
+            .unwrap();
+        net.insert_msg(msg).unwrap();
+
+        net.set_message_rx_by_node("Status", "RX").unwrap();
+        net.set_message_rx_by_node("Data", "TX").unwrap();
+
+        net
+    }
+
+    #[test]
+    fn kcd_message_elements() {
+        let kcd = KcdExporter::translate(&test_net());
+
+        assert!(kcd.contains("  <Node id=\"1\" name=\"TX\"/>\n"));
+        assert!(kcd.contains("  <Bus name=\"CAN\" baudrate=\"250000\">\n"));
+        assert!(kcd
+            .contains("    <Message id=\"0x10\" name=\"Status\" length=\"4\" interval=\"100\">\n"));
+        assert!(kcd.contains("<Notes>Current &lt;state&gt; &amp; mode</Notes>"));
+        assert!(kcd.contains("<Value type=\"signed\" slope=\"0.5\" intercept=\"-40\" unit=\"degC\" min=\"-40\" max=\"100\"/>"));
+        assert!(kcd.contains("<Label name=\"ACTIVE\" value=\"1\"/>"));
+        assert!(
+            kcd.contains("<Signal name=\"counter\" offset=\"24\" length=\"8\" endianess=\"big\">")
+        );
+
+        assert!(kcd.contains("format=\"extended\""));
+        assert!(kcd.contains("      <Multiplex name=\"mode\" offset=\"0\" length=\"2\">\n"));
+        assert!(kcd.contains("        <MuxGroup count=\"2\">\n          <Signal name=\"a\""));
+        assert!(kcd.contains("<Value type=\"single\"/>"));
+    }
+
+    #[test]
+    fn kcd_round_trip() {
+        let net = test_net();
+
+        let mut imported = CANNetwork::new();
+        let warnings = KcdImporter::import_network(
+            KcdExporter::translate(&net),
+            &mut imported,
+            ImportMode::Strict,
+        )
+        .unwrap();
+        assert!(warnings.is_empty());
+        assert_eq!(imported.bitrate(), Some(250000));
+
+        for msg in net.iter_messages() {
+            let other = imported.message_by_name(&msg.name).unwrap();
+
+            assert_eq!(msg.id, other.id);
+            assert_eq!(msg.length, other.length);
+            assert_eq!(msg.cycletime, other.cycletime);
+            assert_eq!(msg.tx_node, other.tx_node);
+
+            for sigbit in &msg.signals {
+                let other = other.get_sig(&sigbit.sig.name).unwrap();
+
+                assert_eq!(sigbit.start(), other.start());
+                assert_eq!(sigbit.sig.width, other.sig.width);
+                assert_eq!(sigbit.sig.byte_order, other.sig.byte_order);
+                assert_eq!(sigbit.sig.twos_complement, other.sig.twos_complement);
+                assert_eq!(sigbit.sig.value_type, other.sig.value_type);
+                assert_eq!(sigbit.sig.scale, other.sig.scale);
+                assert_eq!(sigbit.sig.offset, other.sig.offset);
+                assert_eq!(sigbit.sig.unit, other.sig.unit);
+                assert_eq!(sigbit.sig.description, other.sig.description);
+                assert_eq!(
+                    (sigbit.sig.min, sigbit.sig.max),
+                    (other.sig.min, other.sig.max)
+                );
+                assert_eq!(sigbit.sig.mux, other.sig.mux);
+                assert_eq!(sigbit.sig.enumerated_values, other.sig.enumerated_values);
+            }
+        }
+
+        for (node, rx) in [("RX", "Status"), ("TX", "Data")] {
+            let msgs = imported.rx_messages_by_node(node).unwrap();
+            assert_eq!(msgs.len(), 1);
+            assert_eq!(msgs[0].name, rx);
+        }
+    }
+}
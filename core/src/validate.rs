@@ -0,0 +1,545 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Display;
+
+use thiserror::Error;
+
+use crate::*;
+
+/// C keywords, which can't be used as identifiers in generated code.
+const C_KEYWORDS: [&str; 44] = [
+    "auto",
+    "break",
+    "case",
+    "char",
+    "const",
+    "continue",
+    "default",
+    "do",
+    "double",
+    "else",
+    "enum",
+    "extern",
+    "float",
+    "for",
+    "goto",
+    "if",
+    "inline",
+    "int",
+    "long",
+    "register",
+    "restrict",
+    "return",
+    "short",
+    "signed",
+    "sizeof",
+    "static",
+    "struct",
+    "switch",
+    "typedef",
+    "union",
+    "unsigned",
+    "void",
+    "volatile",
+    "while",
+    "_Alignas",
+    "_Alignof",
+    "_Atomic",
+    "_Bool",
+    "_Complex",
+    "_Generic",
+    "_Imaginary",
+    "_Noreturn",
+    "_Static_assert",
+    "_Thread_local",
+];
+
+/// How serious a [`Finding`] is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    /// Likely a mistake, but the network is usable.
+    Warning,
+
+    /// The network can't be used as-is, e.g. generated code won't compile.
+    Error,
+}
+
+impl Display for Severity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Severity::Warning => write!(f, "warning"),
+            Severity::Error => write!(f, "error"),
+        }
+    }
+}
+
+/// A problem found by [`CANNetwork::validate()`].
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum Finding {
+    #[error(
+        "Enumerated value `{name}` ({value}) of signal `{signal}` does not fit in {width} bits."
+    )]
+    EnumeratedValueOverflow {
+        signal: String,
+        name: String,
+        value: u64,
+        width: u32,
+    },
+
+    #[error("Signal name `{signal}` in message `{message}` is not a valid C identifier.")]
+    InvalidSignalName { message: String, signal: String },
+
+    #[error("Message name `{message}` is not a valid C identifier.")]
+    InvalidMessageName { message: String },
+
+    #[error("Node name `{node}` is not a valid C identifier.")]
+    InvalidNodeName { node: String },
+
+    #[error("Node `{node}` receives its own message `{message}`.")]
+    NodeReceivesOwnMessage { node: String, message: String },
+
+    #[error("Message `{message}` is not received by any node.")]
+    MessageNotReceived { message: String },
+
+    #[error("Template message `{template}` is never instantiated.")]
+    TemplateNotInstantiated { template: String },
+
+    #[error("Attribute name `{attribute}` is not a valid C identifier.")]
+    InvalidAttributeName { attribute: String },
+
+    #[error("Attribute `{attribute}` is used as both {first} and {second}.")]
+    InconsistentAttributeType {
+        attribute: String,
+        first: &'static str,
+        second: &'static str,
+    },
+
+    #[error("On bus `{bus}`: {finding}")]
+    OnBus { bus: String, finding: Box<Finding> },
+
+    #[error("Message `{message}` is routed from bus `{from}` to bus `{to}`, but is missing from bus `{missing}`.")]
+    RoutedMessageMissing {
+        message: String,
+        from: String,
+        to: String,
+        missing: String,
+    },
+
+    #[error("Message `{message}` differs between bus `{from}` and bus `{to}`, where it is routed: {change}")]
+    RoutedMessageChanged {
+        message: String,
+        from: String,
+        to: String,
+        change: Change,
+    },
+
+    #[error("Gateway `{gateway}` does not receive `{message}` on bus `{from}` and transmit it on bus `{to}`.")]
+    GatewayNotRouting {
+        gateway: String,
+        message: String,
+        from: String,
+        to: String,
+    },
+}
+
+impl Finding {
+    /// How serious this finding is.
+    pub fn severity(&self) -> Severity {
+        match self {
+            Self::OnBus { finding, .. } => finding.severity(),
+
+            Self::EnumeratedValueOverflow { .. }
+            | Self::InvalidSignalName { .. }
+            | Self::InvalidMessageName { .. }
+            | Self::InvalidNodeName { .. }
+            | Self::InvalidAttributeName { .. }
+            | Self::RoutedMessageMissing { .. }
+            | Self::RoutedMessageChanged { .. }
+            | Self::GatewayNotRouting { .. } => Severity::Error,
+
+            Self::NodeReceivesOwnMessage { .. }
+            | Self::MessageNotReceived { .. }
+            | Self::TemplateNotInstantiated { .. }
+            | Self::InconsistentAttributeType { .. } => Severity::Warning,
+        }
+    }
+}
+
+/// Whether `name` can be used as an identifier in generated C code.
+fn is_c_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+        && !C_KEYWORDS.contains(&name)
+}
+
+impl CANNetwork {
+    /// Check the whole network for problems that can't be caught while
+    /// building it up piece by piece.
+    ///
+    /// Findings are ordered by where they occur in the network, not by severity.
+    pub fn validate(&self) -> Vec<Finding> {
+        let mut findings = Vec::new();
+
+        for node in self.iter_nodes() {
+            if !is_c_identifier(&node.name) {
+                findings.push(Finding::InvalidNodeName {
+                    node: node.name.clone(),
+                });
+            }
+        }
+
+        let mut templates: Vec<_> = self.iter_template_messages().collect();
+        templates.sort_by(|a, b| a.name.cmp(&b.name));
+
+        for msg in self.iter_messages().chain(templates.iter().copied()) {
+            Self::validate_message(msg, &mut findings);
+        }
+
+        for msg in self.iter_messages() {
+            let mut receivers: Vec<_> = self
+                .iter_nodes()
+                .filter(|n| n.rx_messages.contains_key(&msg.name))
+                .collect();
+            receivers.sort_by(|a, b| a.name.cmp(&b.name));
+
+            if receivers.is_empty() {
+                findings.push(Finding::MessageNotReceived {
+                    message: msg.name.clone(),
+                });
+            }
+
+            for node in receivers {
+                if msg.tx_node() == Some(&node.name) {
+                    findings.push(Finding::NodeReceivesOwnMessage {
+                        node: node.name.clone(),
+                        message: msg.name.clone(),
+                    });
+                }
+            }
+        }
+
+        for template in templates {
+            let used = self.iter_messages().any(
+                |m| matches!(m.kind(), CANMessageKind::FromTemplate(t) if *t == template.name),
+            );
+
+            if !used {
+                findings.push(Finding::TemplateNotInstantiated {
+                    template: template.name.clone(),
+                });
+            }
+        }
+
+        self.validate_attributes(&mut findings);
+
+        findings
+    }
+
+    /// Attribute names end up in generated code, and an attribute must have
+    /// the same type everywhere to be exported to DBC.
+    fn validate_attributes(&self, findings: &mut Vec<Finding>) {
+        let mut attributes: Vec<&Attributes> = vec![self.attributes()];
+        attributes.extend(self.iter_nodes().map(|n| &n.attributes));
+        for msg in self.iter_messages() {
+            attributes.push(&msg.attributes);
+            attributes.extend(msg.signals.iter().map(|s| &s.sig.attributes));
+        }
+
+        let mut types: BTreeMap<&str, &'static str> = BTreeMap::new();
+        let mut reported = BTreeSet::new();
+
+        for (name, value) in attributes.into_iter().flatten() {
+            let Some(&first) = types.get(name.as_str()) else {
+                if !is_c_identifier(name) {
+                    findings.push(Finding::InvalidAttributeName {
+                        attribute: name.clone(),
+                    });
+                }
+
+                types.insert(name, value.type_name());
+                continue;
+            };
+
+            if first != value.type_name() && reported.insert(name) {
+                findings.push(Finding::InconsistentAttributeType {
+                    attribute: name.clone(),
+                    first,
+                    second: value.type_name(),
+                });
+            }
+        }
+    }
+
+    fn validate_message(msg: &CANMessage, findings: &mut Vec<Finding>) {
+        if !is_c_identifier(&msg.name) {
+            findings.push(Finding::InvalidMessageName {
+                message: msg.name.clone(),
+            });
+        }
+
+        for sigbit in &msg.signals {
+            let sig = &sigbit.sig;
+
+            if !is_c_identifier(&sig.name) {
+                findings.push(Finding::InvalidSignalName {
+                    message: msg.name.clone(),
+                    signal: sig.name.clone(),
+                });
+            }
+
+            let mut values: Vec<_> = sig.enumerated_values.iter().collect();
+            values.sort_by_key(|v| v.1);
+
+            for (name, &value) in values {
+                if sig.width < u64::BITS && value >> sig.width != 0 {
+                    findings.push(Finding::EnumeratedValueOverflow {
+                        signal: sig.name.clone(),
+                        name: name.clone(),
+                        value,
+                        width: sig.width,
+                    });
+                }
+            }
+        }
+    }
+}
+
+impl CANSystem {
+    /// Check every bus in the system, and that routed messages still match
+    /// up between the buses they're routed between.
+    ///
+    /// Findings on a single bus are wrapped in [`Finding::OnBus`].
+    pub fn validate(&self) -> Vec<Finding> {
+        let mut findings = Vec::new();
+
+        for (bus, net) in self.iter_buses() {
+            Self::validate_on_bus(bus, net, &mut findings);
+        }
+
+        for route in self.iter_routes() {
+            self.validate_route(route, &mut findings);
+        }
+
+        findings
+    }
+
+    /// Like [`validate()`](Self::validate), but only check bus `bus` and the
+    /// routes onto or off it, e.g. to use that bus on its own.
+    pub fn validate_bus(&self, bus: &str) -> Vec<Finding> {
+        let mut findings = Vec::new();
+
+        if let Some(net) = self.bus_by_name(bus) {
+            Self::validate_on_bus(bus, net, &mut findings);
+        }
+
+        for route in self.iter_routes() {
+            if route.from == bus || route.to == bus {
+                self.validate_route(route, &mut findings);
+            }
+        }
+
+        findings
+    }
+
+    fn validate_on_bus(bus: &str, net: &CANNetwork, findings: &mut Vec<Finding>) {
+        findings.extend(net.validate().into_iter().map(|f| Finding::OnBus {
+            bus: bus.into(),
+            finding: Box::new(f),
+        }));
+    }
+
+    /// Check that a route's message is the same on both buses, and that its
+    /// gateway still routes it.
+    fn validate_route(&self, route: &CANRoute, findings: &mut Vec<Finding>) {
+        let find = |bus: &str| {
+            self.bus_by_name(bus)
+                .and_then(|net| Some((net, net.message_by_name(&route.message)?)))
+        };
+
+        let (Some((from, msg)), Some((to, routed))) = (find(&route.from), find(&route.to)) else {
+            let missing = if find(&route.from).is_none() {
+                &route.from
+            } else {
+                &route.to
+            };
+
+            findings.push(Finding::RoutedMessageMissing {
+                message: route.message.clone(),
+                from: route.from.clone(),
+                to: route.to.clone(),
+                missing: missing.clone(),
+            });
+            return;
+        };
+
+        for change in msg.layout_changes(routed) {
+            findings.push(Finding::RoutedMessageChanged {
+                message: route.message.clone(),
+                from: route.from.clone(),
+                to: route.to.clone(),
+                change,
+            });
+        }
+
+        let receives = msg.tx_node() == Some(&route.gateway)
+            || from
+                .node_by_name(&route.gateway)
+                .is_some_and(|n| n.rx_messages.contains_key(&route.message));
+
+        if !receives
+            || routed.tx_node() != Some(&route.gateway)
+            || to.node_by_name(&route.gateway).is_none()
+        {
+            findings.push(Finding::GatewayNotRouting {
+                gateway: route.gateway.clone(),
+                message: route.message.clone(),
+                from: route.from.clone(),
+                to: route.to.clone(),
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::*;
+
+    #[test]
+    fn c_identifiers() {
+        assert!(is_c_identifier("_abc123"));
+        assert!(!is_c_identifier(""));
+        assert!(!is_c_identifier("1abc"));
+        assert!(!is_c_identifier("a-b"));
+        assert!(!is_c_identifier("int"));
+    }
+
+    #[test]
+    fn validate_findings() {
+        let mut net = CANNetwork::new();
+        net.add_node("A").unwrap();
+        net.add_node("B").unwrap();
+        net.add_node("bad-node").unwrap();
+
+        let msg = CANMessage::builder()
+            .name("Status")
+            .id(0x10)
+            .tx_node("A")
+            .add_signal(
+                new_sig()
+                    .name("mode")
+                    .width(1)
+                    .add_enumerated_value("ON", 0)
+                    .unwrap()
+                    .add_enumerated_value("OFF", 2)
+                    .unwrap()
+                    .build()
+                    .unwrap(),
+            )
+            .unwrap()
+            .add_signal(basic_sig("switch"))
+            .unwrap()
+            .build()
+            .unwrap();
+        net.insert_msg(msg).unwrap();
+        net.set_message_rx_by_node("Status", "A").unwrap();
+        net.set_message_rx_by_node("Status", "B").unwrap();
+
+        let unused = CANMessage::builder()
+            .name("Unused")
+            .id(0x11)
+            .build()
+            .unwrap();
+        net.insert_msg(unused).unwrap();
+
+        let template = CANMessage::template().name("T").build().unwrap();
+        net.insert_template_message(template).unwrap();
+
+        let findings = net.validate();
+        assert_eq!(
+            findings,
+            [
+                Finding::InvalidNodeName {
+                    node: "bad-node".into()
+                },
+                Finding::EnumeratedValueOverflow {
+                    signal: "mode".into(),
+                    name: "OFF".into(),
+                    value: 2,
+                    width: 1
+                },
+                Finding::InvalidSignalName {
+                    message: "Status".into(),
+                    signal: "switch".into()
+                },
+                Finding::NodeReceivesOwnMessage {
+                    node: "A".into(),
+                    message: "Status".into()
+                },
+                Finding::MessageNotReceived {
+                    message: "Unused".into()
+                },
+                Finding::TemplateNotInstantiated {
+                    template: "T".into()
+                },
+            ]
+        );
+
+        assert_eq!(findings[0].severity(), Severity::Error);
+        assert_eq!(findings[3].severity(), Severity::Warning);
+    }
+
+    #[test]
+    fn validate_attributes() {
+        let mut net = CANNetwork::new();
+        net.add_node("A").unwrap();
+
+        let msg = CANMessage::builder()
+            .name("Status")
+            .id(0x10)
+            .tx_node("A")
+            .add_signal(
+                new_sig()
+                    .name("mode")
+                    .width(1)
+                    .attributes(Attributes::from([(
+                        "Level".into(),
+                        AttributeValue::Float(1.5),
+                    )]))
+                    .build()
+                    .unwrap(),
+            )
+            .unwrap()
+            .build()
+            .unwrap();
+        net.insert_msg(msg).unwrap();
+        net.set_message_rx_by_node("Status", "A").unwrap();
+
+        net.set_attribute("bad name", AttributeValue::Int(1));
+        net.set_node_attribute("A", "Level", AttributeValue::Int(2))
+            .unwrap();
+        net.set_message_attribute("Status", "Level", AttributeValue::Int(3))
+            .unwrap();
+
+        assert_eq!(
+            net.validate(),
+            [
+                Finding::NodeReceivesOwnMessage {
+                    node: "A".into(),
+                    message: "Status".into()
+                },
+                Finding::InvalidAttributeName {
+                    attribute: "bad name".into()
+                },
+                Finding::InconsistentAttributeType {
+                    attribute: "Level".into(),
+                    first: "int",
+                    second: "float"
+                },
+            ]
+        );
+    }
+}
@@ -0,0 +1,77 @@
+use serde::{Deserialize, Serialize};
+
+use crate::error::*;
+
+/// Named set of enumerated values shared by signals across the network,
+/// like a DBC `VAL_TABLE_`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CANValueTable {
+    pub name: String,
+
+    /// Bijective (bidirectional) map of value names to raw values.
+    #[serde(serialize_with = "crate::sorted_bimap_by_second")]
+    pub values: bimap::BiMap<String, u64>,
+}
+
+impl CANValueTable {
+    /// Create an empty value table.
+    ///
+    /// The name is used in generated C code, so like message names it may
+    /// contain only ASCII letters, numbers, and underscores.
+    pub fn new(name: &str) -> Result<Self, CANConstructionError> {
+        Self::check_name_validity(name)?;
+
+        Ok(Self {
+            name: name.into(),
+            values: bimap::BiMap::new(),
+        })
+    }
+
+    /// Check validity of value table name - it should not be empty and should
+    /// contain a limited set of characters - `[a-zA-Z0-9_]`.
+    pub(crate) fn check_name_validity(name: &str) -> Result<(), CANConstructionError> {
+        if name.is_empty() {
+            return Err(CANConstructionError::ValueTableNameEmpty);
+        }
+
+        if let Some(c) = name
+            .chars()
+            .find(|c| (!c.is_ascii_alphanumeric()) && c != &'_')
+        {
+            return Err(CANConstructionError::ValueTableNameInvalidChar(
+                name.into(),
+                c,
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Add a value name with a given raw value.
+    pub fn add_value(&mut self, name: &str, val: u64) -> Result<(), CANConstructionError> {
+        if let Some(&v) = self.values.get_by_left(name) {
+            return Err(CANConstructionError::EnumeratedValueNameAlreadyExists(
+                name.into(),
+                v,
+            ));
+        }
+
+        if let Some(n) = self.values.get_by_right(&val) {
+            return Err(CANConstructionError::EnumeratedValueValueAlreadyNamed(
+                n.clone(),
+                val,
+            ));
+        }
+
+        self.values.insert(name.into(), val);
+
+        Ok(())
+    }
+
+    /// Add a value name, choosing the raw value after the highest one so far.
+    pub fn add_value_inferred(&mut self, name: &str) -> Result<(), CANConstructionError> {
+        let val = self.values.right_values().max().map_or(0, |v| v + 1);
+
+        self.add_value(name, val)
+    }
+}
@@ -92,6 +92,8 @@ fn main() -> Result<()> {
         in_file: args.yml,
         dump_json: false,
         dump_python: false,
+        deny_warnings: false,
+        bus: None,
     })
     .unwrap();
 